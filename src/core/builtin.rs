@@ -0,0 +1,218 @@
+//! Functions every script can call without declaring or registering them, the prelude.
+//!
+//! Unlike host functions, builtins are not limited to a fixed signature: `assert_eq` compares
+//! values of any type and `println` takes any number of arguments, so the type checker and the
+//! runtime handle their calls themselves. Functions declared by scripts or registered by the host
+//! take precedence over builtins of the same name.
+
+use derive_more::{Display, Error};
+
+use super::types::Type;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Display)]
+#[display("{}", self.name())]
+pub enum Builtin {
+    /// `assert(condition)`, fails unless the condition is `true`.
+    Assert,
+    /// `assert_eq(left, right)`, fails unless both values are equal.
+    AssertEq,
+    /// `assert_ne(left, right)`, fails if both values are equal.
+    AssertNe,
+    /// `print(format, arguments...)`, writes the formatted text to the output.
+    Print,
+    /// `println(format, arguments...)`, like `print` followed by a line break.
+    Println,
+    /// `eprintln(format, arguments...)`, like `println` but to the error output.
+    Eprintln,
+    /// `format(format, arguments...)`, returns the formatted text.
+    Format,
+    /// `read_line()`, reads a line of input without its line break.
+    ReadLine,
+    /// `read_file(path)`, reads a whole file as text.
+    ReadFile,
+    /// `write_file(path, contents)`, creates or replaces a file.
+    WriteFile,
+}
+
+impl Builtin {
+    pub const ALL: [Builtin; 10] = [
+        Builtin::Assert,
+        Builtin::AssertEq,
+        Builtin::AssertNe,
+        Builtin::Print,
+        Builtin::Println,
+        Builtin::Eprintln,
+        Builtin::Format,
+        Builtin::ReadLine,
+        Builtin::ReadFile,
+        Builtin::WriteFile,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Builtin::ALL
+            .into_iter()
+            .find(|builtin| builtin.name() == name)
+    }
+
+    /// The name scripts call the builtin by.
+    pub fn name(self) -> &'static str {
+        match self {
+            Builtin::Assert => "assert",
+            Builtin::AssertEq => "assert_eq",
+            Builtin::AssertNe => "assert_ne",
+            Builtin::Print => "print",
+            Builtin::Println => "println",
+            Builtin::Eprintln => "eprintln",
+            Builtin::Format => "format",
+            Builtin::ReadLine => "read_line",
+            Builtin::ReadFile => "read_file",
+            Builtin::WriteFile => "write_file",
+        }
+    }
+
+    /// The number of arguments the builtin takes without and with its optional ones.
+    ///
+    /// Every assertion takes a `String` message describing the failure as its last argument,
+    /// formatting builtins take one argument per placeholder after the format string.
+    pub fn arity(self) -> (usize, usize) {
+        match self {
+            Builtin::Assert => (1, 2),
+            Builtin::AssertEq | Builtin::AssertNe => (2, 3),
+            Builtin::Print | Builtin::Println | Builtin::Eprintln | Builtin::Format => {
+                (1, usize::MAX)
+            }
+            Builtin::ReadLine => (0, 0),
+            Builtin::ReadFile => (1, 1),
+            Builtin::WriteFile => (2, 2),
+        }
+    }
+
+    /// The type of the values returned by the builtin.
+    pub fn return_type(self) -> Type {
+        match self {
+            Builtin::Format | Builtin::ReadLine | Builtin::ReadFile => Type::String,
+            _ => Type::Unit,
+        }
+    }
+
+    /// Whether the first argument is a format string, see [`FormatString`].
+    pub fn is_formatting(self) -> bool {
+        matches!(
+            self,
+            Builtin::Print | Builtin::Println | Builtin::Eprintln | Builtin::Format
+        )
+    }
+
+    /// The signature as shown to users, e.g. in the completions of an editor.
+    pub fn signature(self) -> &'static str {
+        match self {
+            Builtin::Assert => "fn assert(condition: bool, message?: String)",
+            Builtin::AssertEq => "fn assert_eq(left: T, right: T, message?: String)",
+            Builtin::AssertNe => "fn assert_ne(left: T, right: T, message?: String)",
+            Builtin::Print => "fn print(format: String, arguments...)",
+            Builtin::Println => "fn println(format: String, arguments...)",
+            Builtin::Eprintln => "fn eprintln(format: String, arguments...)",
+            Builtin::Format => "fn format(format: String, arguments...) -> String",
+            Builtin::ReadLine => "fn read_line() -> String",
+            Builtin::ReadFile => "fn read_file(path: String) -> String",
+            Builtin::WriteFile => "fn write_file(path: String, contents: String)",
+        }
+    }
+}
+
+/// A format string split at its `{}` placeholders, each replaced by the next argument.
+///
+/// `{{` and `}}` stand for literal braces. Only string literals are format strings, a single
+/// argument that is not a literal is written as it is.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FormatString {
+    /// The text around the placeholders, one piece more than there are placeholders.
+    pieces: Vec<String>,
+}
+
+/// A format string that can not be parsed.
+#[derive(Debug, PartialEq, Clone, Display, Error)]
+pub enum FormatError {
+    #[display("unmatched `{{` in format string")]
+    UnclosedPlaceholder,
+    #[display("unmatched `}}` in format string")]
+    UnmatchedBrace,
+    /// Placeholders can not contain names or format options.
+    #[display("invalid placeholder `{{{_0}}}`, only `{{}}` is supported")]
+    InvalidPlaceholder(#[error(not(source))] String),
+}
+
+impl FormatString {
+    pub fn parse(format: &str) -> Result<Self, FormatError> {
+        let mut pieces = vec![String::new()];
+        let mut chars = format.chars();
+        while let Some(char) = chars.next() {
+            match char {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    pieces.last_mut().unwrap().push('{');
+                }
+                '{' => {
+                    let Some(end) = chars.as_str().find('}') else {
+                        return Err(FormatError::UnclosedPlaceholder);
+                    };
+                    let contents = &chars.as_str()[..end];
+                    if !contents.is_empty() {
+                        return Err(FormatError::InvalidPlaceholder(contents.to_string()));
+                    }
+                    chars.next();
+                    pieces.push(String::new());
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    pieces.last_mut().unwrap().push('}');
+                }
+                '}' => return Err(FormatError::UnmatchedBrace),
+                char => pieces.last_mut().unwrap().push(char),
+            }
+        }
+        Ok(FormatString { pieces })
+    }
+
+    /// The number of arguments the format string expects.
+    pub fn placeholders(&self) -> usize {
+        self.pieces.len() - 1
+    }
+
+    /// Replaces the placeholders by the arguments, which must be as many as there are
+    /// placeholders.
+    pub fn format<T: std::fmt::Display>(&self, arguments: &[T]) -> String {
+        debug_assert_eq!(arguments.len(), self.placeholders());
+        let mut text = self.pieces[0].clone();
+        for (argument, piece) in arguments.iter().zip(&self.pieces[1..]) {
+            text.push_str(&argument.to_string());
+            text.push_str(piece);
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_strings_replace_placeholders_and_unescape_braces() {
+        let format = FormatString::parse("{} + {{{}}} = {}").unwrap();
+        assert_eq!(format.placeholders(), 3);
+        assert_eq!(format.format(&[1, 2, 3]), "1 + {2} = 3");
+    }
+
+    #[test]
+    fn invalid_format_strings_are_rejected() {
+        assert_eq!(
+            FormatString::parse("{"),
+            Err(FormatError::UnclosedPlaceholder)
+        );
+        assert_eq!(FormatString::parse("a }"), Err(FormatError::UnmatchedBrace));
+        assert_eq!(
+            FormatString::parse("{name}"),
+            Err(FormatError::InvalidPlaceholder("name".to_string()))
+        );
+    }
+}
@@ -0,0 +1,344 @@
+use std::io::{self, Write};
+
+use derive_more::Display;
+use termcolor::{Color, ColorSpec, WriteColor};
+
+use super::{
+    source_map::{SourceFile, SourceMap},
+    span::Span,
+};
+
+/// How severe a [`Diagnostic`] is, determines the header and the colours used.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Display)]
+pub enum Severity {
+    #[display("error")]
+    Error,
+    #[display("warning")]
+    Warning,
+}
+
+impl Severity {
+    fn color(self) -> Color {
+        match self {
+            Severity::Error => Color::Red,
+            Severity::Warning => Color::Yellow,
+        }
+    }
+}
+
+/// A message attached to a region of the source code.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    /// Primary labels point at the cause of the problem and are underlined with `^`,
+    /// secondary labels add context and are underlined with `-`.
+    pub primary: bool,
+}
+
+/// A problem found in a script, ready to be rendered for the user.
+///
+/// # Example
+/// ```ignore
+/// let diagnostic = Diagnostic::error("mismatched types")
+///     .with_primary_label(span, "expected `u8`, found `String`")
+///     .with_secondary_label(annotation_span, "expected due to this")
+///     .with_help("consider parsing the string");
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    pub fn with_primary_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: true,
+        });
+        self
+    }
+
+    pub fn with_secondary_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: false,
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    /// The span of the first primary label, used for the `--> file:line:column` header.
+    pub fn primary_span(&self) -> Option<Span> {
+        self.labels
+            .iter()
+            .find(|label| label.primary)
+            .or(self.labels.first())
+            .map(|label| label.span)
+    }
+}
+
+/// Conversion of an error type into a renderable [`Diagnostic`].
+pub trait ToDiagnostic {
+    fn to_diagnostic(&self) -> Diagnostic;
+}
+
+/// Tabs are expanded to this many columns when printing source lines.
+const TAB_WIDTH: usize = 4;
+
+/// Multi-line labels show at most this many lines, the ones in between are elided.
+const MAX_LABEL_LINES: usize = 4;
+
+/// # DiagnosticRenderer
+///
+/// Renders [`Diagnostic`]s in the style of `rustc`:
+///
+/// ```text
+/// error: mismatched types
+///  --> example.rscript:3:13
+///   |
+/// 3 | let x: u8 = "five";
+///   |        -- expected due to this
+///   |             ^^^^^^ expected `u8`, found `String`
+/// ```
+///
+/// Colours are only emitted if the writer supports them, so the choice between coloured and
+/// plain output is made through the [`termcolor::ColorChoice`] of the writer.
+pub struct DiagnosticRenderer<'a> {
+    source_map: &'a SourceMap,
+}
+
+impl<'a> DiagnosticRenderer<'a> {
+    pub fn new(source_map: &'a SourceMap) -> Self {
+        DiagnosticRenderer { source_map }
+    }
+
+    /// Number of display columns taken by the first `chars` characters of `text`.
+    fn display_width(text: &str, chars: usize) -> usize {
+        text.chars()
+            .take(chars)
+            .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+            .sum()
+    }
+
+    pub fn render<W>(&self, out: &mut W, diagnostic: &Diagnostic) -> io::Result<()>
+    where
+        W: Write + WriteColor,
+    {
+        let severity_color = diagnostic.severity.color();
+
+        // -- Header --
+        out.set_color(ColorSpec::new().set_fg(Some(severity_color)).set_bold(true))?;
+        write!(out, "{}", diagnostic.severity)?;
+        out.set_color(ColorSpec::new().set_bold(true))?;
+        write!(out, ": {}", diagnostic.message)?;
+        out.reset()?;
+        writeln!(out)?;
+
+        // Labels are drawn against the file of the primary span, labels in other files are skipped
+        let file = diagnostic
+            .primary_span()
+            .and_then(|span| Some((span, self.source_map.get(span.file)?)));
+        let lines = match file {
+            Some((span, file)) => Self::label_lines(file, span, diagnostic),
+            None => Vec::new(),
+        };
+        let gutter_width = lines
+            .iter()
+            .map(|line| line.number)
+            .max()
+            .unwrap_or(1)
+            .to_string()
+            .len();
+        let gutter = " ".repeat(gutter_width);
+
+        // -- Location --
+        if let Some(span) = diagnostic.primary_span() {
+            gutter_theme(out)?;
+            write!(out, "{}--> ", gutter)?;
+            out.reset()?;
+            writeln!(out, "{}", self.source_map.location(span))?;
+        }
+
+        // -- Source Snippet --
+        if !lines.is_empty() {
+            gutter_theme(out)?;
+            write!(out, "{} |", gutter)?;
+            out.reset()?;
+            writeln!(out)?;
+        }
+
+        let mut previous_line = None;
+        for line in &lines {
+            if previous_line.is_some_and(|previous| line.number > previous + 1) {
+                gutter_theme(out)?;
+                write!(out, "...")?;
+                out.reset()?;
+                writeln!(out)?;
+            }
+            previous_line = Some(line.number);
+
+            let text = file.map_or("", |(_, file)| file.line_text(line.number));
+            gutter_theme(out)?;
+            write!(out, "{:>width$} | ", line.number, width = gutter_width)?;
+            out.reset()?;
+            writeln!(out, "{}", text.replace('\t', &" ".repeat(TAB_WIDTH)))?;
+
+            for marker in &line.markers {
+                let start = Self::display_width(text, marker.start_column);
+                let end = Self::display_width(text, marker.end_column).max(start + 1);
+                let (symbol, color) = if marker.primary {
+                    ('^', severity_color)
+                } else {
+                    ('-', Color::Blue)
+                };
+
+                gutter_theme(out)?;
+                write!(out, "{} | ", gutter)?;
+                out.set_color(ColorSpec::new().set_fg(Some(color)).set_bold(true))?;
+                write!(out, "{}", " ".repeat(start))?;
+                write!(out, "{}", symbol.to_string().repeat(end - start))?;
+                if let Some(message) = &marker.message {
+                    write!(out, " {}", message)?;
+                }
+                out.reset()?;
+                writeln!(out)?;
+            }
+        }
+
+        // -- Notes and Help --
+        let has_footer = !diagnostic.notes.is_empty() || !diagnostic.help.is_empty();
+        if !lines.is_empty() && has_footer {
+            gutter_theme(out)?;
+            write!(out, "{} |", gutter)?;
+            out.reset()?;
+            writeln!(out)?;
+        }
+        for (kind, messages) in [("note", &diagnostic.notes), ("help", &diagnostic.help)] {
+            for message in messages {
+                gutter_theme(out)?;
+                write!(out, "{} = ", gutter)?;
+                out.set_color(ColorSpec::new().set_bold(true))?;
+                write!(out, "{}", kind)?;
+                out.reset()?;
+                writeln!(out, ": {}", message)?;
+            }
+        }
+
+        writeln!(out)
+    }
+
+    /// Groups the labels of a diagnostic by the source lines they cover.
+    fn label_lines(file: &SourceFile, primary: Span, diagnostic: &Diagnostic) -> Vec<SnippetLine> {
+        let mut lines: Vec<SnippetLine> = Vec::new();
+
+        let labels = diagnostic
+            .labels
+            .iter()
+            .filter(|label| label.span.file == primary.file);
+        for label in labels {
+            let (start_line, start_column) = file.line_column(label.span.start);
+            let (end_line, end_column) = file.line_column(label.span.end);
+            let line_count = end_line - start_line + 1;
+
+            for number in start_line..=end_line {
+                // Elide the middle of long labels, keeping the first and last lines
+                let skipped = line_count > MAX_LABEL_LINES
+                    && number >= start_line + MAX_LABEL_LINES - 1
+                    && number < end_line;
+                if skipped {
+                    continue;
+                }
+
+                // Continuation lines are underlined from their first non-blank character
+                let first_column = if number == start_line {
+                    start_column - 1
+                } else {
+                    let text = file.line_text(number);
+                    text.chars().count() - text.trim_start().chars().count()
+                };
+                let last_column = if number == end_line {
+                    end_column - 1
+                } else {
+                    file.line_text(number).chars().count()
+                };
+                let marker = Marker {
+                    start_column: first_column,
+                    end_column: last_column,
+                    primary: label.primary,
+                    // The message is shown below the last line of the label
+                    message: (number == end_line && !label.message.is_empty())
+                        .then(|| label.message.clone()),
+                };
+
+                match lines.iter_mut().find(|line| line.number == number) {
+                    Some(line) => line.markers.push(marker),
+                    None => lines.push(SnippetLine {
+                        number,
+                        markers: vec![marker],
+                    }),
+                }
+            }
+        }
+
+        lines.sort_by_key(|line| line.number);
+        for line in &mut lines {
+            line.markers.sort_by_key(|marker| marker.start_column);
+        }
+        lines
+    }
+}
+
+/// A source line shown in a snippet, with the underlines to draw below it.
+struct SnippetLine {
+    number: usize,
+    markers: Vec<Marker>,
+}
+
+/// The part of a label on a single line, columns are 0-based character offsets.
+struct Marker {
+    start_column: usize,
+    end_column: usize,
+    primary: bool,
+    message: Option<String>,
+}
+
+fn gutter_theme<W>(out: &mut W) -> io::Result<()>
+where
+    W: Write + WriteColor,
+{
+    out.set_color(ColorSpec::new().set_fg(Some(Color::Blue)).set_bold(true))
+}
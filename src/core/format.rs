@@ -1,10 +1,9 @@
 use std::io::{self, Write};
 
-use termcolor::{Color, ColorSpec, WriteColor};
+use termcolor::WriteColor;
 
 pub trait Format {
     fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
     where
         W: Write + WriteColor;
 }
-
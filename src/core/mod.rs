@@ -1,3 +1,6 @@
+pub mod builtin;
+pub mod diagnostic;
 pub mod format;
+pub mod source_map;
 pub mod span;
-
+pub mod types;
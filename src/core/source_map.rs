@@ -0,0 +1,139 @@
+use derive_more::Display;
+use serde::Serialize;
+
+use super::span::Span;
+
+/// Identifies a file registered in a [`SourceMap`].
+///
+/// The default id is the one assigned to the first registered file, sources parsed without
+/// registering them use it as well.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default, PartialOrd, Ord, Display, Serialize)]
+#[display("#{_0}")]
+pub struct FileId(u32);
+
+impl FileId {
+    /// The position of the file in its source map, the first registered file has index `0`.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub(crate) fn from_index(index: usize) -> Self {
+        FileId(index as u32)
+    }
+}
+
+/// A source file with a table of line starts for fast offset to line/column lookups.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    name: String,
+    source: String,
+    /// Byte offset of the first character of every line, always starts with `0`.
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    pub fn new(name: impl Into<String>, source: impl Into<String>) -> Self {
+        let source = source.into();
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        SourceFile {
+            name: name.into(),
+            source,
+            line_starts,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Clamps `offset` into the source and onto a character boundary.
+    fn clamp(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+
+    /// Converts a byte offset into a 1-based line and column.
+    ///
+    /// Columns count characters, not bytes. Offsets past the end are clamped.
+    pub fn line_column(&self, offset: usize) -> (usize, usize) {
+        let offset = self.clamp(offset);
+        let line_index = match self.line_starts.binary_search(&offset) {
+            Ok(index) => index,
+            Err(index) => index - 1,
+        };
+        let line_start = self.line_starts[line_index];
+        let column = self.source[line_start..offset].chars().count() + 1;
+        (line_index + 1, column)
+    }
+
+    /// The text of the 1-based line `line`, without the line terminator.
+    pub fn line_text(&self, line: usize) -> &str {
+        let Some(&start) = self.line_starts.get(line.wrapping_sub(1)) else {
+            return "";
+        };
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |next| next - 1);
+        self.source[start..end].trim_end_matches('\r')
+    }
+}
+
+/// # SourceMap
+///
+/// Owns every source file of a program. Each file gets a [`FileId`] which is stored in the
+/// [`Span`]s of everything parsed from it, so errors can be traced back to their file.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a file and returns its id.
+    pub fn add_file(&mut self, name: impl Into<String>, source: impl Into<String>) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        self.files.push(SourceFile::new(name, source));
+        id
+    }
+
+    /// The number of registered files.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn get(&self, file: FileId) -> Option<&SourceFile> {
+        self.files.get(file.0 as usize)
+    }
+
+    /// Formats the start of `span` as `file:line:column`.
+    pub fn location(&self, span: Span) -> String {
+        match self.get(span.file) {
+            Some(file) => {
+                let (line, column) = file.line_column(span.start);
+                format!("{}:{}:{}", file.name, line, column)
+            }
+            None => format!("<unknown file {}>:{}", span.file, span.start),
+        }
+    }
+}
@@ -1,33 +1,44 @@
 use derive_more::Display;
+use serde::Serialize;
 
-#[derive(Debug, PartialEq, Clone, Copy, Default, Display)]
+use super::source_map::FileId;
+
+/// A region of a source file, as byte offsets into the file registered under `file` in the
+/// [`SourceMap`](super::source_map::SourceMap).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default, Display, Serialize)]
 #[display("{start}-{end}")]
 pub struct Span {
+    pub file: FileId,
     pub start: usize,
     pub end: usize,
 }
 
 impl Span {
-    pub fn combine(self, other: Span) -> Span {
+    pub fn new(file: FileId, range: std::ops::Range<usize>) -> Span {
         Span {
-            start: self.start.min(other.start),
-            end: self.end.min(other.end),
+            file,
+            start: range.start,
+            end: range.end,
         }
     }
-}
 
-impl From<std::ops::Range<usize>> for Span {
-    fn from(value: std::ops::Range<usize>) -> Self {
+    /// The smallest span covering both spans, which must belong to the same file.
+    pub fn combine(self, other: Span) -> Span {
+        debug_assert_eq!(
+            self.file, other.file,
+            "cannot combine spans of different files"
+        );
         Span {
-            start: value.start,
-            end: value.end,
+            file: self.file,
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
         }
     }
 }
 
 impl Spanned for Span {
     fn span(&self) -> Span {
-        self.clone()
+        *self
     }
 }
 
@@ -0,0 +1,152 @@
+use derive_more::Display;
+use serde::Serialize;
+
+/// The fixed-width integer types known to the language.
+///
+/// `int` is accepted as an alias for [`IntegerType::I64`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Display, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntegerType {
+    #[display("i8")]
+    I8,
+    #[display("i16")]
+    I16,
+    #[display("i32")]
+    I32,
+    #[display("i64")]
+    I64,
+    #[display("u8")]
+    U8,
+    #[display("u16")]
+    U16,
+    #[display("u32")]
+    U32,
+    #[display("u64")]
+    U64,
+}
+
+impl IntegerType {
+    /// Resolves a type name (or literal suffix) to an [`IntegerType`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "i8" => Some(IntegerType::I8),
+            "i16" => Some(IntegerType::I16),
+            "i32" => Some(IntegerType::I32),
+            "i64" | "int" => Some(IntegerType::I64),
+            "u8" => Some(IntegerType::U8),
+            "u16" => Some(IntegerType::U16),
+            "u32" => Some(IntegerType::U32),
+            "u64" => Some(IntegerType::U64),
+            _ => None,
+        }
+    }
+
+    pub fn is_signed(self) -> bool {
+        matches!(
+            self,
+            IntegerType::I8 | IntegerType::I16 | IntegerType::I32 | IntegerType::I64
+        )
+    }
+
+    pub fn bits(self) -> u32 {
+        match self {
+            IntegerType::I8 | IntegerType::U8 => 8,
+            IntegerType::I16 | IntegerType::U16 => 16,
+            IntegerType::I32 | IntegerType::U32 => 32,
+            IntegerType::I64 | IntegerType::U64 => 64,
+        }
+    }
+
+    /// Smallest value representable by this type.
+    pub fn min(self) -> i128 {
+        if self.is_signed() {
+            -(1i128 << (self.bits() - 1))
+        } else {
+            0
+        }
+    }
+
+    /// Largest value representable by this type.
+    pub fn max(self) -> i128 {
+        if self.is_signed() {
+            (1i128 << (self.bits() - 1)) - 1
+        } else {
+            (1i128 << self.bits()) - 1
+        }
+    }
+
+    /// Whether `value` fits into this type without overflowing.
+    pub fn contains(self, value: i128) -> bool {
+        (self.min()..=self.max()).contains(&value)
+    }
+}
+
+/// The floating point types known to the language.
+///
+/// `float` is accepted as an alias for [`FloatType::F64`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Display, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FloatType {
+    #[display("f32")]
+    F32,
+    #[display("f64")]
+    F64,
+}
+
+impl FloatType {
+    /// Resolves a type name (or literal suffix) to a [`FloatType`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "f32" => Some(FloatType::F32),
+            "f64" | "float" => Some(FloatType::F64),
+            _ => None,
+        }
+    }
+}
+
+/// A resolved type, as used by the type checker and the runtime.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Display)]
+pub enum Type {
+    #[display("{_0}")]
+    Integer(IntegerType),
+    #[display("{_0}")]
+    Float(FloatType),
+    #[display("bool")]
+    Bool,
+    #[display("String")]
+    String,
+    #[display("char")]
+    Char,
+    #[display("()")]
+    Unit,
+    #[display("{_0}")]
+    Struct(String),
+}
+
+impl Type {
+    /// Resolves a builtin type name. User defined types (structs) are not
+    /// known here and yield `None`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(ty) = IntegerType::from_name(name) {
+            return Some(Type::Integer(ty));
+        }
+        if let Some(ty) = FloatType::from_name(name) {
+            return Some(Type::Float(ty));
+        }
+        match name {
+            "bool" => Some(Type::Bool),
+            "String" => Some(Type::String),
+            "char" => Some(Type::Char),
+            "()" => Some(Type::Unit),
+            _ => None,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Type::Integer(_))
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Integer(_) | Type::Float(_))
+    }
+}
@@ -0,0 +1,834 @@
+//! # Formatter
+//!
+//! Pretty-prints a [`Program`] as canonical rscript source.
+//!
+//! The layout of the original source is discarded, except for comments and single blank lines
+//! between statements. Comments are taken from the [`SyntaxTree`] and placed relative to the
+//! statements they belong to:
+//! - comments on their own line are kept in front of the following statement,
+//! - a comment following a statement, a struct field or an opening brace on the same line stays
+//!   at the end of that line,
+//! - comments inside a statement, e.g. between two operands, are moved in front of it.
+//!
+//! Formatting formatted code again does not change it.
+
+use std::ops::Range;
+
+use crate::{
+    core::{source_map::FileId, span::Spanned},
+    parser::{
+        ParserError,
+        ast::{
+            BinaryOp, BlockExpression, Expression, FunctionDeclaration, IfExpression,
+            NamedFieldDeclaration, Program, Statement, StructDeclaration, Visibility,
+        },
+        cst::{SyntaxKind, SyntaxTree},
+    },
+};
+
+/// Options controlling the layout of formatted code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatterConfig {
+    /// Number of spaces per indentation level.
+    pub indent_width: usize,
+    /// Lines longer than this are broken up where possible.
+    pub max_width: usize,
+}
+
+impl Default for FormatterConfig {
+    fn default() -> Self {
+        FormatterConfig {
+            indent_width: 4,
+            max_width: 100,
+        }
+    }
+}
+
+/// Formats `source`, files with syntax errors are left alone and their errors returned.
+pub fn format_source(source: &str, config: &FormatterConfig) -> Result<String, Vec<ParserError>> {
+    trace!("Formatting source");
+    let tree = SyntaxTree::parse(source, FileId::default());
+    let program = tree.lower().into_result()?;
+    let comments = tree
+        .root()
+        .descendant_tokens()
+        .into_iter()
+        .filter(|token| token.kind() == SyntaxKind::Comment)
+        .map(|token| Comment {
+            range: token.text_range(),
+            text: token.text().trim_end().to_string(),
+            emitted: false,
+        })
+        .collect();
+
+    let mut formatter = Formatter {
+        config,
+        source,
+        comments,
+        output: String::new(),
+    };
+    formatter.program(&program);
+    Ok(formatter.output)
+}
+
+struct Comment {
+    range: Range<usize>,
+    text: String,
+    emitted: bool,
+}
+
+/// An entry of a statement list, the final expression of a block and the fields of a struct are
+/// formatted like statements.
+#[derive(Clone, Copy)]
+enum Item<'p> {
+    Statement(&'p Statement),
+    FinalExpression(&'p Expression),
+    Field(&'p NamedFieldDeclaration),
+}
+
+impl Item<'_> {
+    fn range(&self) -> Range<usize> {
+        let span = match self {
+            Item::Statement(statement) => statement.span(),
+            Item::FinalExpression(expression) => expression.span(),
+            Item::Field(field) => field.span,
+        };
+        span.start..span.end
+    }
+}
+
+struct Formatter<'a> {
+    config: &'a FormatterConfig,
+    source: &'a str,
+    comments: Vec<Comment>,
+    output: String,
+}
+
+impl Formatter<'_> {
+    // -- Output --
+
+    fn indent(&mut self, level: usize) {
+        let width = level * self.config.indent_width;
+        self.output.extend(std::iter::repeat_n(' ', width));
+    }
+
+    /// The column the next character is written to.
+    fn column(&self) -> usize {
+        let line_start = self.output.rfind('\n').map_or(0, |index| index + 1);
+        self.output[line_start..].chars().count()
+    }
+
+    fn fits(&self, text: &str, suffix_width: usize) -> bool {
+        self.column() + text.chars().count() + suffix_width <= self.config.max_width
+    }
+
+    /// Whether the source contains an empty line between the two offsets.
+    fn has_blank_line(&self, from: usize, to: usize) -> bool {
+        from < to && self.source[from..to].matches('\n').count() >= 2
+    }
+
+    /// The comments not yet written that start before `offset`.
+    fn pending_comments(&self, offset: usize) -> Vec<usize> {
+        (0..self.comments.len())
+            .filter(|&index| {
+                let comment = &self.comments[index];
+                !comment.emitted && comment.range.start < offset
+            })
+            .collect()
+    }
+
+    /// Whether a comment lies within `range`, comments force blocks onto multiple lines.
+    fn has_comments(&self, range: Range<usize>) -> bool {
+        self.comments
+            .iter()
+            .any(|comment| range.contains(&comment.range.start))
+    }
+
+    /// The offset of the first `{` from `offset` on that is not part of a comment.
+    fn opening_brace(&self, offset: usize) -> usize {
+        self.source[offset..]
+            .match_indices('{')
+            .map(|(index, _)| offset + index)
+            .find(|brace| {
+                !self
+                    .comments
+                    .iter()
+                    .any(|comment| comment.range.contains(brace))
+            })
+            .unwrap_or(offset)
+    }
+
+    /// Writes the comment following `offset` on the same line at the end of the output, returns
+    /// where it ends.
+    fn trailing_comment(&mut self, offset: usize) -> Option<usize> {
+        let index = self.comments.iter().position(|comment| {
+            !comment.emitted
+                && comment.range.start >= offset
+                && !self.source[offset..comment.range.start].contains('\n')
+        })?;
+        self.output.push(' ');
+        self.output.push_str(&self.comments[index].text);
+        self.comments[index].emitted = true;
+        Some(self.comments[index].range.end)
+    }
+
+    /// Writes a comment on its own line.
+    fn comment_line(&mut self, index: usize, level: usize) {
+        self.indent(level);
+        self.output.push_str(&self.comments[index].text);
+        self.output.push('\n');
+        self.comments[index].emitted = true;
+    }
+
+    // -- Statement Lists --
+
+    fn program(&mut self, program: &Program) {
+        let items: Vec<_> = program.statements.iter().map(Item::Statement).collect();
+        self.items(&items, 0, 0, self.source.len());
+    }
+
+    /// Writes one item per line, `start..end` is the region of the source holding them.
+    fn items(&mut self, items: &[Item], level: usize, start: usize, end: usize) {
+        let mut previous_end = start;
+        let mut first = true;
+
+        for item in items {
+            let range = item.range();
+
+            // -- Leading Comments --
+            for index in self.pending_comments(range.start) {
+                if !first && self.has_blank_line(previous_end, self.comments[index].range.start) {
+                    self.output.push('\n');
+                }
+                self.comment_line(index, level);
+                previous_end = self.comments[index].range.end;
+                first = false;
+            }
+            if !first && self.has_blank_line(previous_end, range.start) {
+                self.output.push('\n');
+            }
+
+            // -- Item --
+            // Rendered separately first, comments inside it that could not be placed are written
+            // in front of it
+            let output = std::mem::take(&mut self.output);
+            self.indent(level);
+            match item {
+                Item::Statement(statement) => self.statement(statement, level),
+                Item::FinalExpression(expression) => self.expression(expression, level, 0),
+                Item::Field(field) => {
+                    self.output.push_str(&field.identifier.name);
+                    self.output.push_str(": ");
+                    self.output.push_str(&field.declared_type.name);
+                    self.output.push(',');
+                }
+            }
+            let rendered = std::mem::replace(&mut self.output, output);
+            for index in self.pending_comments(range.end) {
+                self.comment_line(index, level);
+            }
+            self.output.push_str(&rendered);
+
+            // -- Trailing Comment --
+            previous_end = self.trailing_comment(range.end).unwrap_or(range.end);
+            self.output.push('\n');
+            first = false;
+        }
+
+        // -- Remaining Comments --
+        for index in self.pending_comments(end) {
+            if !first && self.has_blank_line(previous_end, self.comments[index].range.start) {
+                self.output.push('\n');
+            }
+            self.comment_line(index, level);
+            previous_end = self.comments[index].range.end;
+            first = false;
+        }
+    }
+
+    // -- Statements --
+
+    /// Writes a statement starting at the current position, without a trailing newline.
+    fn statement(&mut self, statement: &Statement, level: usize) {
+        match statement {
+            Statement::VariableDeclaration(node) => {
+                self.output.push_str("let ");
+                self.output.push_str(&node.identifier.name);
+                if let Some(declared_type) = &node.declared_type {
+                    self.output.push_str(": ");
+                    self.output.push_str(&declared_type.name);
+                }
+                self.output.push_str(" = ");
+                self.expression(&node.initializer, level, 1);
+                self.output.push(';');
+            }
+            Statement::FunctionDeclaration(node) => self.function_declaration(node, level),
+            Statement::StructDeclaration(node) => self.struct_declaration(node, level),
+            Statement::UseDeclaration(node) => {
+                let path: Vec<_> = node
+                    .path
+                    .iter()
+                    .map(|segment| segment.name.as_str())
+                    .collect();
+                self.output.push_str("use ");
+                self.output.push_str(&path.join("::"));
+                self.output.push(';');
+            }
+            Statement::ExpressionStatement(node) => {
+                self.expression(&node.expression, level, 1);
+                // Block-like expressions do not need a `;`, but removing it would turn the last
+                // statement of a block into its final expression
+                let has_semicolon = node.span.end > node.expression.span().end;
+                if has_semicolon || !is_block_like(&node.expression) {
+                    self.output.push(';');
+                }
+            }
+            Statement::ReturnStatement(node) => {
+                self.output.push_str("return");
+                if let Some(value) = &node.value {
+                    self.output.push(' ');
+                    self.expression(value, level, 1);
+                }
+                self.output.push(';');
+            }
+            Statement::BreakStatement(node) => {
+                self.output.push_str("break");
+                if let Some(value) = &node.value {
+                    self.output.push(' ');
+                    self.expression(value, level, 1);
+                }
+                self.output.push(';');
+            }
+            // Programs with errors are never formatted, keep the source just in case
+            Statement::Error(node) => self
+                .output
+                .push_str(&self.source[node.span.start..node.span.end]),
+        }
+    }
+
+    fn function_declaration(&mut self, node: &FunctionDeclaration, level: usize) {
+        let parameters: Vec<_> = node
+            .parameters
+            .iter()
+            .map(|parameter| {
+                format!(
+                    "{}: {}",
+                    parameter.identifier.name, parameter.declared_type.name
+                )
+            })
+            .collect();
+        // Functions without `->` have the return type `()`
+        let return_type = if node.return_type.name == "()" {
+            String::new()
+        } else {
+            format!(" -> {}", node.return_type.name)
+        };
+
+        for attribute in &node.attributes {
+            self.output.push_str("#[");
+            self.output.push_str(&attribute.name.name);
+            self.output.push_str("]\n");
+            self.indent(level);
+        }
+        self.visibility(node.visibility);
+        self.output.push_str("fn ");
+        self.output.push_str(&node.identifier.name);
+        let signature = format!("({}){}", parameters.join(", "), return_type);
+        if self.fits(&signature, 2) || parameters.is_empty() {
+            self.output.push_str(&signature);
+        } else {
+            self.output.push_str("(\n");
+            for parameter in &parameters {
+                self.indent(level + 1);
+                self.output.push_str(parameter);
+                self.output.push_str(",\n");
+            }
+            self.indent(level);
+            self.output.push(')');
+            self.output.push_str(&return_type);
+        }
+
+        self.output.push(' ');
+        let items: Vec<_> = node.body.iter().map(Item::Statement).collect();
+        let body_start = self.opening_brace(node.return_type.span.end);
+        self.braced(&items, level, body_start..node.span.end);
+    }
+
+    fn struct_declaration(&mut self, node: &StructDeclaration, level: usize) {
+        self.visibility(node.visibility());
+        self.output.push_str("struct ");
+        match node {
+            StructDeclaration::NamedStruct {
+                identifier,
+                fields,
+                span,
+                ..
+            } => {
+                self.output.push_str(&identifier.name);
+                self.output.push(' ');
+                let items: Vec<_> = fields.iter().map(Item::Field).collect();
+                let body_start = self.opening_brace(identifier.span.end);
+                self.braced(&items, level, body_start..span.end);
+            }
+            StructDeclaration::TupleStruct {
+                identifier, fields, ..
+            } => {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|field| field.declared_type.name.as_str())
+                    .collect();
+                self.output.push_str(&identifier.name);
+                self.output.push('(');
+                self.output.push_str(&fields.join(", "));
+                self.output.push_str(");");
+            }
+            StructDeclaration::UnitStruct { identifier, .. } => {
+                self.output.push_str(&identifier.name);
+                self.output.push(';');
+            }
+        }
+    }
+
+    fn visibility(&mut self, visibility: Visibility) {
+        if visibility == Visibility::Public {
+            self.output.push_str("pub ");
+        }
+    }
+
+    /// Writes `{`, the items on separate lines and `}`, `range` starts at the `{`.
+    fn braced(&mut self, items: &[Item], level: usize, range: Range<usize>) {
+        if items.is_empty() && !self.has_comments(range.clone()) {
+            self.output.push_str("{}");
+            return;
+        }
+        self.output.push('{');
+        let start = self
+            .trailing_comment(range.start + 1)
+            .unwrap_or(range.start);
+        self.output.push('\n');
+        self.items(items, level + 1, start, range.end);
+        self.indent(level);
+        self.output.push('}');
+    }
+
+    // -- Expressions --
+
+    /// Writes an expression, breaking it up if it does not fit into the line.
+    ///
+    /// `suffix_width` is the number of characters that follow the expression on the same line.
+    fn expression(&mut self, expression: &Expression, level: usize, suffix_width: usize) {
+        if let Some(flat) = self.flat(expression)
+            && self.fits(&flat, suffix_width)
+        {
+            self.output.push_str(&flat);
+            return;
+        }
+
+        match expression {
+            Expression::FunctionCall(node) if !node.arguments.is_empty() => {
+                self.output.push_str(&node.function_name.name);
+                self.broken_arguments(&node.arguments, level);
+            }
+            Expression::MethodCall(node) => {
+                self.receiver(&node.receiver, level);
+                self.output.push('.');
+                self.output.push_str(&node.method.name);
+                if node.arguments.is_empty() {
+                    self.output.push_str("()");
+                } else {
+                    self.broken_arguments(&node.arguments, level);
+                }
+            }
+            Expression::FieldAccess(node) => {
+                self.receiver(&node.object, level);
+                self.output.push('.');
+                self.output.push_str(&node.field.name);
+            }
+            Expression::BinaryOp(node) => self.broken_binary(node, level, suffix_width),
+            Expression::UnaryOp(node) => {
+                self.output.push_str(node.operator.symbol());
+                if matches!(*node.operand, Expression::BinaryOp(_)) {
+                    self.output.push('(');
+                    self.expression(&node.operand, level, suffix_width + 1);
+                    self.output.push(')');
+                } else {
+                    self.expression(&node.operand, level, suffix_width);
+                }
+            }
+            Expression::BlockExpression(node) => self.block(node, level),
+            Expression::IfExpression(node) => self.if_expression(node, level),
+            // Everything else is always flat
+            _ => {
+                let flat = self.flat(expression).unwrap_or_default();
+                self.output.push_str(&flat);
+            }
+        }
+    }
+
+    /// Writes `(`, the arguments on separate lines with trailing commas and `)`.
+    fn broken_arguments(&mut self, arguments: &[Expression], level: usize) {
+        self.output.push_str("(\n");
+        for argument in arguments {
+            self.indent(level + 1);
+            self.expression(argument, level + 1, 1);
+            self.output.push_str(",\n");
+        }
+        self.indent(level);
+        self.output.push(')');
+    }
+
+    /// Writes the object of a field access or method call, in parentheses if it is an operation.
+    fn receiver(&mut self, receiver: &Expression, level: usize) {
+        if is_operation(receiver) {
+            self.output.push('(');
+            self.expression(receiver, level, 1);
+            self.output.push(')');
+        } else {
+            self.expression(receiver, level, 0);
+        }
+    }
+
+    /// Writes a chain of operators of the same precedence with one operator per line.
+    fn broken_binary(&mut self, node: &BinaryOp, level: usize, suffix_width: usize) {
+        let precedence = node.operator.precedence();
+        let mut chain = vec![(&node.operator, &*node.right)];
+        let mut first = &*node.left;
+        while let Expression::BinaryOp(left) = first {
+            if left.operator.precedence() != precedence {
+                break;
+            }
+            chain.push((&left.operator, &*left.right));
+            first = &*left.left;
+        }
+        chain.reverse();
+
+        self.operand(first, precedence, false, level, 0);
+        for (operator, operand) in chain {
+            self.output.push('\n');
+            self.indent(level + 1);
+            self.output.push_str(operator.symbol());
+            self.output.push(' ');
+            self.operand(operand, precedence, true, level + 1, suffix_width);
+        }
+    }
+
+    /// Writes the operand of a binary operator, in parentheses if it binds less tightly.
+    fn operand(
+        &mut self,
+        operand: &Expression,
+        precedence: u8,
+        is_right: bool,
+        level: usize,
+        suffix_width: usize,
+    ) {
+        if needs_parentheses(operand, precedence, is_right) {
+            self.output.push('(');
+            self.expression(operand, level, suffix_width + 1);
+            self.output.push(')');
+        } else {
+            self.expression(operand, level, suffix_width);
+        }
+    }
+
+    fn block(&mut self, node: &BlockExpression, level: usize) {
+        let mut items: Vec<_> = node.statements.iter().map(Item::Statement).collect();
+        items.extend(node.final_expression.as_deref().map(Item::FinalExpression));
+        self.braced(&items, level, node.span.start..node.span.end);
+    }
+
+    fn if_expression(&mut self, node: &IfExpression, level: usize) {
+        self.output.push_str("if ");
+        self.expression(&node.condition, level, 2);
+        self.output.push(' ');
+        self.block(&node.then_branch, level);
+        if let Some(else_branch) = &node.else_branch {
+            self.output.push_str(" else ");
+            match else_if(else_branch) {
+                Some(nested) => self.if_expression(nested, level),
+                None => self.block(else_branch, level),
+            }
+        }
+    }
+
+    /// The expression on a single line, `None` if it has to span multiple lines.
+    fn flat(&self, expression: &Expression) -> Option<String> {
+        let flat = match expression {
+            Expression::Identifier(node) => node.name.clone(),
+            // Literals are kept as written, including their base, separators and escapes
+            Expression::IntegerLiteral(node) => self.text(node.span.start..node.span.end),
+            Expression::FloatLiteral(node) => self.text(node.span.start..node.span.end),
+            Expression::StringLiteral(node) => self.text(node.span.start..node.span.end),
+            Expression::CharLiteral(node) => self.text(node.span.start..node.span.end),
+            Expression::BooleanLiteral(node) => node.value.to_string(),
+            Expression::UnaryOp(node) => {
+                let operand = self.flat(&node.operand)?;
+                if matches!(*node.operand, Expression::BinaryOp(_)) {
+                    format!("{}({})", node.operator.symbol(), operand)
+                } else {
+                    format!("{}{}", node.operator.symbol(), operand)
+                }
+            }
+            Expression::BinaryOp(node) => {
+                let precedence = node.operator.precedence();
+                let left = self.flat_operand(&node.left, precedence, false)?;
+                let right = self.flat_operand(&node.right, precedence, true)?;
+                format!("{} {} {}", left, node.operator.symbol(), right)
+            }
+            Expression::FunctionCall(node) => {
+                let arguments = node
+                    .arguments
+                    .iter()
+                    .map(|argument| self.flat(argument))
+                    .collect::<Option<Vec<_>>>()?;
+                format!("{}({})", node.function_name.name, arguments.join(", "))
+            }
+            Expression::FieldAccess(node) => {
+                format!("{}.{}", self.flat_receiver(&node.object)?, node.field.name)
+            }
+            Expression::MethodCall(node) => {
+                let arguments = node
+                    .arguments
+                    .iter()
+                    .map(|argument| self.flat(argument))
+                    .collect::<Option<Vec<_>>>()?;
+                format!(
+                    "{}.{}({})",
+                    self.flat_receiver(&node.receiver)?,
+                    node.method.name,
+                    arguments.join(", ")
+                )
+            }
+            Expression::BlockExpression(node) => self.flat_block(node)?,
+            Expression::IfExpression(node) => self.flat_if(node)?,
+        };
+        Some(flat)
+    }
+
+    fn flat_operand(&self, operand: &Expression, precedence: u8, is_right: bool) -> Option<String> {
+        let flat = self.flat(operand)?;
+        if needs_parentheses(operand, precedence, is_right) {
+            Some(format!("({})", flat))
+        } else {
+            Some(flat)
+        }
+    }
+
+    fn flat_receiver(&self, receiver: &Expression) -> Option<String> {
+        let flat = self.flat(receiver)?;
+        if is_operation(receiver) {
+            Some(format!("({})", flat))
+        } else {
+            Some(flat)
+        }
+    }
+
+    /// Only blocks without statements and comments fit on a single line.
+    fn flat_block(&self, node: &BlockExpression) -> Option<String> {
+        if !node.statements.is_empty() || self.has_comments(node.span.start..node.span.end) {
+            return None;
+        }
+        match &node.final_expression {
+            Some(expression) => Some(format!("{{ {} }}", self.flat(expression)?)),
+            None => Some("{}".to_string()),
+        }
+    }
+
+    fn flat_if(&self, node: &IfExpression) -> Option<String> {
+        let mut flat = format!(
+            "if {} {}",
+            self.flat(&node.condition)?,
+            self.flat_block(&node.then_branch)?
+        );
+        if let Some(else_branch) = &node.else_branch {
+            let branch = match else_if(else_branch) {
+                Some(nested) => self.flat_if(nested)?,
+                None => self.flat_block(else_branch)?,
+            };
+            flat.push_str(" else ");
+            flat.push_str(&branch);
+        }
+        Some(flat)
+    }
+
+    fn text(&self, range: Range<usize>) -> String {
+        self.source[range].to_string()
+    }
+}
+
+/// The nested if of an `else if`, which the parser represents as an else block containing only
+/// the nested if and sharing its span.
+fn else_if(else_branch: &BlockExpression) -> Option<&IfExpression> {
+    match else_branch.final_expression.as_deref() {
+        Some(Expression::IfExpression(nested))
+            if else_branch.statements.is_empty() && nested.span == else_branch.span =>
+        {
+            Some(nested)
+        }
+        _ => None,
+    }
+}
+
+/// Operators are left associative, so operands on the right need parentheses already if their
+/// precedence is equal.
+fn needs_parentheses(operand: &Expression, precedence: u8, is_right: bool) -> bool {
+    match operand {
+        Expression::BinaryOp(node) if is_right => node.operator.precedence() <= precedence,
+        Expression::BinaryOp(node) => node.operator.precedence() < precedence,
+        _ => false,
+    }
+}
+
+/// Field accesses and method calls bind tighter than any operator.
+fn is_operation(expression: &Expression) -> bool {
+    matches!(expression, Expression::BinaryOp(_) | Expression::UnaryOp(_))
+}
+
+/// Block-like expressions may be used as statements without a trailing `;`.
+fn is_block_like(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::BlockExpression(_) | Expression::IfExpression(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(source: &str) -> String {
+        format_source(source, &FormatterConfig::default()).expect("source should parse")
+    }
+
+    /// Formats `source` twice and checks that the second pass changes nothing.
+    fn assert_idempotent(source: &str) -> String {
+        let once = format(source);
+        let twice = format(&once);
+        assert_eq!(once, twice, "formatting is not idempotent for:\n{source}");
+        once
+    }
+
+    #[test]
+    fn formats_examples_idempotently() {
+        assert_idempotent(include_str!("../../example.rscript"));
+        assert_idempotent(include_str!("../../simple.rscript"));
+    }
+
+    #[test]
+    fn normalizes_layout() {
+        let formatted = assert_idempotent("let   x:u8=1+2*3;fn f(a:int,b:int)->int{return a;}");
+        assert_eq!(
+            formatted,
+            "let x: u8 = 1 + 2 * 3;\nfn f(a: int, b: int) -> int {\n    return a;\n}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_required_parentheses_only() {
+        let formatted = assert_idempotent("let x = ((1 + 2)) * (3 * 4) - (5 - 6) + -(7 + 8);");
+        assert_eq!(
+            formatted,
+            "let x = (1 + 2) * (3 * 4) - (5 - 6) + -(7 + 8);\n"
+        );
+
+        let formatted = assert_idempotent("let y = ((a + b)).c.d( 1 ) - (e.f);");
+        assert_eq!(formatted, "let y = (a + b).c.d(1) - e.f;\n");
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let source = "\
+// leading
+let x = 1; // trailing
+
+
+// after blank lines
+fn f() { // opening
+    let y = 2 + // inside
+        3;
+    // before closing
+}
+// at the end
+";
+        let formatted = assert_idempotent(source);
+        assert_eq!(
+            formatted,
+            "\
+// leading
+let x = 1; // trailing
+
+// after blank lines
+fn f() { // opening
+    // inside
+    let y = 2 + 3;
+    // before closing
+}
+// at the end
+"
+        );
+    }
+
+    #[test]
+    fn breaks_long_lines() {
+        let config = FormatterConfig {
+            indent_width: 2,
+            max_width: 30,
+        };
+        let source = "let total = compute(first_argument, second_argument) + another_value * 2;";
+        let once = format_source(source, &config).unwrap();
+        let twice = format_source(&once, &config).unwrap();
+        assert_eq!(once, twice);
+        assert_eq!(
+            once,
+            "let total = compute(\n  first_argument,\n  second_argument,\n)\n  + another_value * 2;\n"
+        );
+    }
+
+    #[test]
+    fn formats_blocks_and_if_expressions() {
+        let source = "fn max(a: int, b: int) -> int { if a > b { return a; } else if a == b { return a; } else { return b; } }\nlet k = if true { 1 } else { 2 };\nlet z = { let q = 1; q };";
+        let formatted = assert_idempotent(source);
+        assert_eq!(
+            formatted,
+            "\
+fn max(a: int, b: int) -> int {
+    if a > b {
+        return a;
+    } else if a == b {
+        return a;
+    } else {
+        return b;
+    }
+}
+let k = if true { 1 } else { 2 };
+let z = {
+    let q = 1;
+    q
+};
+"
+        );
+    }
+
+    #[test]
+    fn keeps_semicolons_that_change_meaning() {
+        let formatted = assert_idempotent("let x = { if true { 1 } else { 2 }; 3 };");
+        assert_eq!(
+            formatted,
+            "let x = {\n    if true { 1 } else { 2 };\n    3\n};\n"
+        );
+    }
+
+    #[test]
+    fn formats_imports_and_visibility() {
+        let formatted =
+            assert_idempotent("use math :: vec::Vector2D;pub struct Point{x:i64}pub fn f(){}");
+        assert_eq!(
+            formatted,
+            "use math::vec::Vector2D;\npub struct Point {\n    x: i64,\n}\npub fn f() {}\n"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_source() {
+        assert!(format_source("let x = ;", &FormatterConfig::default()).is_err());
+    }
+}
@@ -0,0 +1,488 @@
+//! # Syntax Highlighting
+//!
+//! Classifies every token of a script by the role it plays, using the position of the token in
+//! the [concrete syntax tree](crate::parser::cst): an identifier is a type in `x: Point`, a
+//! function in `fn area(...)` and `area(...)`, a field in `point.x` and a variable otherwise.
+//! The tree is lossless, so comments and scripts with syntax errors are highlighted as well.
+//!
+//! The classified source can be written to a terminal with [`write_highlighted`] or turned into
+//! HTML with [`highlight_html`], styled by the stylesheet of a [`Theme`].
+//!
+//! ```
+//! use rscript::highlight::{HighlightKind, highlight};
+//!
+//! let kinds: Vec<_> = highlight("fn f(x: u8)")
+//!     .into_iter()
+//!     .filter_map(|highlight| highlight.kind)
+//!     .collect();
+//! assert_eq!(kinds[..4], [
+//!     HighlightKind::Keyword,
+//!     HighlightKind::Function,
+//!     HighlightKind::Punctuation,
+//!     HighlightKind::Variable,
+//! ]);
+//! ```
+
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+    ops::Range,
+};
+
+use derive_more::Display;
+use termcolor::{Color, ColorSpec, WriteColor};
+
+use crate::{
+    core::source_map::FileId,
+    parser::cst::{SyntaxKind, SyntaxToken, SyntaxTree},
+};
+
+/// The role of a token, which decides its style.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Display)]
+pub enum HighlightKind {
+    #[display("keyword")]
+    Keyword,
+    /// Numbers and booleans.
+    #[display("literal")]
+    Literal,
+    /// String and character literals.
+    #[display("string")]
+    String,
+    #[display("operator")]
+    Operator,
+    /// Delimiters, separators and other punctuation.
+    #[display("punctuation")]
+    Punctuation,
+    /// Type names, including the names of declared structs.
+    #[display("type")]
+    Type,
+    /// Names of declared and called functions and methods.
+    #[display("function")]
+    Function,
+    #[display("field")]
+    Field,
+    /// Names of variables, parameters and modules.
+    #[display("variable")]
+    Variable,
+    #[display("comment")]
+    Comment,
+    /// Text the lexer could not make sense of.
+    #[display("error")]
+    Error,
+}
+
+/// A region of the source and its role, `None` for whitespace.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Highlight {
+    pub kind: Option<HighlightKind>,
+    pub range: Range<usize>,
+}
+
+/// Classifies every token of `source`. The ranges of the highlights cover the whole source, in
+/// order.
+pub fn highlight(source: &str) -> Vec<Highlight> {
+    trace!("Highlighting source");
+    let tree = SyntaxTree::parse(source, FileId::default());
+    tree.root()
+        .descendant_tokens()
+        .iter()
+        .map(|token| Highlight {
+            kind: classify(token),
+            range: token.text_range(),
+        })
+        .collect()
+}
+
+fn classify(token: &SyntaxToken) -> Option<HighlightKind> {
+    use SyntaxKind::*;
+
+    let kind = match token.kind() {
+        Whitespace => return None,
+        Comment => HighlightKind::Comment,
+        ErrorToken => HighlightKind::Error,
+        True | False | IntegerLiteral | FloatLiteral => HighlightKind::Literal,
+        String | Char => HighlightKind::String,
+        kind if kind.is_keyword() => HighlightKind::Keyword,
+        Plus | Minus | Star | Slash | Percent | Assign | Equals | NotEquals | LessThan
+        | GreaterThan | LessThanOrEqual | GreaterThanOrEqual | And | Or | Bang | Ampersand
+        | Pipe | Caret | ShiftLeft | ShiftRight | RightArrow => HighlightKind::Operator,
+        Identifier => classify_identifier(token),
+        _ => HighlightKind::Punctuation,
+    };
+    Some(kind)
+}
+
+fn classify_identifier(token: &SyntaxToken) -> HighlightKind {
+    let parent = token.parent();
+    match parent.kind() {
+        SyntaxKind::TypeAnnotation
+        | SyntaxKind::ReturnType
+        | SyntaxKind::TupleField
+        | SyntaxKind::StructDeclaration => HighlightKind::Type,
+        SyntaxKind::FunctionDeclaration
+        | SyntaxKind::CallExpression
+        | SyntaxKind::MethodCallExpression => HighlightKind::Function,
+        SyntaxKind::FieldExpression => HighlightKind::Field,
+        // Attributes are a fixed set of names, like keywords
+        SyntaxKind::Attribute => HighlightKind::Keyword,
+        // `name: Type`, the name comes before the colon
+        SyntaxKind::Parameter | SyntaxKind::NamedField => {
+            let after_colon = parent
+                .tokens()
+                .take_while(|sibling| sibling.text_range() != token.text_range())
+                .any(|sibling| sibling.kind() == SyntaxKind::Colon);
+            match (after_colon, parent.kind()) {
+                (true, _) => HighlightKind::Type,
+                (false, SyntaxKind::NamedField) => HighlightKind::Field,
+                (false, _) => HighlightKind::Variable,
+            }
+        }
+        // The last segment of a path names an imported function or struct
+        SyntaxKind::UseDeclaration => {
+            let last = parent
+                .tokens()
+                .filter(|sibling| sibling.kind() == SyntaxKind::Identifier)
+                .last()
+                .is_some_and(|last| last.text_range() == token.text_range());
+            if last {
+                HighlightKind::Type
+            } else {
+                HighlightKind::Variable
+            }
+        }
+        _ => HighlightKind::Variable,
+    }
+}
+
+// -- Themes --
+
+/// # Theme
+///
+/// The style of every [`HighlightKind`], as terminal colours. Themes are plain values, so a
+/// theme can be configured by changing the styles of a predefined one:
+///
+/// ```
+/// use rscript::highlight::Theme;
+/// use termcolor::{Color, ColorSpec};
+///
+/// let mut theme = Theme::light();
+/// theme.comment = ColorSpec::new().set_fg(Some(Color::Green)).clone();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub keyword: ColorSpec,
+    pub literal: ColorSpec,
+    pub string: ColorSpec,
+    pub operator: ColorSpec,
+    pub punctuation: ColorSpec,
+    pub type_name: ColorSpec,
+    pub function: ColorSpec,
+    pub field: ColorSpec,
+    pub variable: ColorSpec,
+    pub comment: ColorSpec,
+    pub error: ColorSpec,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+    /// A theme for dark backgrounds.
+    pub fn dark() -> Self {
+        Theme {
+            keyword: style(Color::Rgb(198, 120, 221)).set_bold(true).clone(),
+            literal: style(Color::Rgb(209, 154, 102)),
+            string: style(Color::Rgb(152, 195, 121)),
+            operator: style(Color::Rgb(86, 182, 194)),
+            punctuation: ColorSpec::new(),
+            type_name: style(Color::Rgb(229, 192, 123)),
+            function: style(Color::Rgb(97, 175, 239)),
+            field: style(Color::Rgb(224, 108, 117)),
+            variable: ColorSpec::new(),
+            comment: style(Color::Rgb(92, 99, 112)).set_italic(true).clone(),
+            error: style(Color::Red).set_underline(true).clone(),
+        }
+    }
+
+    /// A theme for light backgrounds.
+    pub fn light() -> Self {
+        Theme {
+            keyword: style(Color::Rgb(166, 38, 164)).set_bold(true).clone(),
+            literal: style(Color::Rgb(152, 104, 1)),
+            string: style(Color::Rgb(80, 161, 79)),
+            operator: style(Color::Rgb(1, 132, 188)),
+            punctuation: ColorSpec::new(),
+            type_name: style(Color::Rgb(193, 132, 1)),
+            function: style(Color::Rgb(64, 120, 242)),
+            field: style(Color::Rgb(228, 86, 73)),
+            variable: ColorSpec::new(),
+            comment: style(Color::Rgb(160, 161, 167)).set_italic(true).clone(),
+            error: style(Color::Red).set_underline(true).clone(),
+        }
+    }
+
+    pub fn style(&self, kind: HighlightKind) -> &ColorSpec {
+        match kind {
+            HighlightKind::Keyword => &self.keyword,
+            HighlightKind::Literal => &self.literal,
+            HighlightKind::String => &self.string,
+            HighlightKind::Operator => &self.operator,
+            HighlightKind::Punctuation => &self.punctuation,
+            HighlightKind::Type => &self.type_name,
+            HighlightKind::Function => &self.function,
+            HighlightKind::Field => &self.field,
+            HighlightKind::Variable => &self.variable,
+            HighlightKind::Comment => &self.comment,
+            HighlightKind::Error => &self.error,
+        }
+    }
+
+    /// A stylesheet for the output of [`highlight_html`], with a rule per styled kind.
+    pub fn css(&self) -> String {
+        const KINDS: [HighlightKind; 11] = [
+            HighlightKind::Keyword,
+            HighlightKind::Literal,
+            HighlightKind::String,
+            HighlightKind::Operator,
+            HighlightKind::Punctuation,
+            HighlightKind::Type,
+            HighlightKind::Function,
+            HighlightKind::Field,
+            HighlightKind::Variable,
+            HighlightKind::Comment,
+            HighlightKind::Error,
+        ];
+
+        let mut css = String::new();
+        for kind in KINDS {
+            let spec = self.style(kind);
+            let mut declarations = Vec::new();
+            if let Some(color) = spec.fg() {
+                declarations.push(format!("color: {}", css_color(color)));
+            }
+            if let Some(color) = spec.bg() {
+                declarations.push(format!("background-color: {}", css_color(color)));
+            }
+            if spec.bold() {
+                declarations.push("font-weight: bold".to_string());
+            }
+            if spec.italic() {
+                declarations.push("font-style: italic".to_string());
+            }
+            if spec.underline() {
+                declarations.push("text-decoration: underline".to_string());
+            }
+            if !declarations.is_empty() {
+                let _ = writeln!(
+                    css,
+                    ".rscript .rs-{kind} {{ {}; }}",
+                    declarations.join("; ")
+                );
+            }
+        }
+        css
+    }
+}
+
+fn style(color: Color) -> ColorSpec {
+    ColorSpec::new().set_fg(Some(color)).clone()
+}
+
+fn css_color(color: &Color) -> String {
+    match *color {
+        Color::Black => "black".to_string(),
+        Color::Blue => "blue".to_string(),
+        Color::Green => "green".to_string(),
+        Color::Red => "red".to_string(),
+        Color::Cyan => "cyan".to_string(),
+        Color::Magenta => "magenta".to_string(),
+        Color::Yellow => "yellow".to_string(),
+        Color::White => "white".to_string(),
+        Color::Rgb(red, green, blue) => format!("#{red:02x}{green:02x}{blue:02x}"),
+        Color::Ansi256(index) => {
+            let (red, green, blue) = ansi256_rgb(index);
+            format!("#{red:02x}{green:02x}{blue:02x}")
+        }
+        _ => "inherit".to_string(),
+    }
+}
+
+/// The colour of an entry of the 256 colour palette of terminals.
+fn ansi256_rgb(index: u8) -> (u8, u8, u8) {
+    const BASIC: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (128, 0, 0),
+        (0, 128, 0),
+        (128, 128, 0),
+        (0, 0, 128),
+        (128, 0, 128),
+        (0, 128, 128),
+        (192, 192, 192),
+        (128, 128, 128),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (0, 0, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    match index {
+        0..16 => BASIC[index as usize],
+        16..232 => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let index = index - 16;
+            (level(index / 36), level(index / 6 % 6), level(index % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+// -- Output --
+
+/// Writes `source` to `out` in the colours of `theme`. Writers without colour support receive
+/// the source unchanged.
+pub fn write_highlighted<W>(out: &mut W, source: &str, theme: &Theme) -> io::Result<()>
+where
+    W: Write + WriteColor,
+{
+    for highlight in highlight(source) {
+        let text = &source[highlight.range];
+        match highlight.kind {
+            Some(kind) => {
+                out.set_color(theme.style(kind))?;
+                write!(out, "{text}")?;
+                out.reset()?;
+            }
+            None => write!(out, "{text}")?,
+        }
+    }
+    Ok(())
+}
+
+/// Renders `source` as a `<pre class="rscript">` element, every token but whitespace wrapped in
+/// a `<span>` whose class is named after its kind, e.g. `rs-keyword`. See [`Theme::css`] for
+/// a matching stylesheet.
+pub fn highlight_html(source: &str) -> String {
+    let mut html = String::from("<pre class=\"rscript\"><code>");
+    for highlight in highlight(source) {
+        let text = escape_html(&source[highlight.range]);
+        match highlight.kind {
+            Some(kind) => {
+                let _ = write!(html, "<span class=\"rs-{kind}\">{text}</span>");
+            }
+            None => html.push_str(&text),
+        }
+    }
+    html.push_str("</code></pre>");
+    html
+}
+
+/// Escapes the characters with a meaning in HTML.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The highlighted tokens of `source` with their text, whitespace left out.
+    fn tokens(source: &str) -> Vec<(HighlightKind, &str)> {
+        highlight(source)
+            .into_iter()
+            .filter_map(|highlight| Some((highlight.kind?, &source[highlight.range])))
+            .collect()
+    }
+
+    #[test]
+    fn covers_the_whole_source() {
+        let source = include_str!("../../example.rscript");
+        let highlights = highlight(source);
+        let mut end = 0;
+        for highlight in &highlights {
+            assert_eq!(highlight.range.start, end);
+            end = highlight.range.end;
+        }
+        assert_eq!(end, source.len());
+    }
+
+    #[test]
+    fn classifies_identifiers_by_their_role() {
+        use HighlightKind::*;
+
+        let source =
+            "struct Point { x: i64 }\nfn norm(p: Point) -> i64 { return p.x + abs(p.y()); }";
+        let identifiers: Vec<_> = tokens(source)
+            .into_iter()
+            .filter(|(kind, _)| !matches!(kind, Keyword | Punctuation | Operator))
+            .collect();
+        assert_eq!(
+            identifiers,
+            vec![
+                (Type, "Point"),
+                (Field, "x"),
+                (Type, "i64"),
+                (Function, "norm"),
+                (Variable, "p"),
+                (Type, "Point"),
+                (Type, "i64"),
+                (Variable, "p"),
+                (Field, "x"),
+                (Function, "abs"),
+                (Variable, "p"),
+                (Function, "y"),
+            ]
+        );
+    }
+
+    #[test]
+    fn highlights_comments_literals_and_invalid_tokens() {
+        use HighlightKind::*;
+
+        assert_eq!(
+            tokens("let s = \"a\" @ 1.5; // done"),
+            vec![
+                (Keyword, "let"),
+                (Variable, "s"),
+                (Operator, "="),
+                (String, "\"a\""),
+                (Error, "@"),
+                (Literal, "1.5"),
+                (Punctuation, ";"),
+                (Comment, "// done"),
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_escaped_html() {
+        assert_eq!(
+            highlight_html("1 < 2"),
+            "<pre class=\"rscript\"><code><span class=\"rs-literal\">1</span> \
+             <span class=\"rs-operator\">&lt;</span> <span class=\"rs-literal\">2</span>\
+             </code></pre>"
+        );
+        assert!(
+            Theme::dark()
+                .css()
+                .contains(".rscript .rs-keyword { color: #c678dd; font-weight: bold; }")
+        );
+    }
+}
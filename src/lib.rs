@@ -0,0 +1,43 @@
+//! # rscript
+//!
+//! A small, statically typed scripting language with a Rust-like syntax, meant to be embedded
+//! into Rust applications.
+//!
+//! The [`Runtime`] is the entry point: it parses, type checks and executes scripts, keeps their
+//! declarations between calls and lets the host exchange [`Value`]s with them.
+//!
+//! ```
+//! use rscript::{Runtime, Value};
+//!
+//! let mut runtime = Runtime::new();
+//! runtime.set_global("base", Value::I64(40));
+//! runtime.execute("fn add(a: i64, b: i64) -> i64 { return a + b; }")?;
+//!
+//! assert_eq!(runtime.evaluate("add(base, 2)")?, Some(Value::I64(42)));
+//! assert_eq!(runtime.call("add", vec![Value::I64(1), Value::I64(2)])?, Value::I64(3));
+//! # Ok::<(), rscript::RuntimeError>(())
+//! ```
+//!
+//! Errors carry spans into the [`SourceMap`] of the runtime and can be rendered with a
+//! [`DiagnosticRenderer`](core::diagnostic::DiagnosticRenderer).
+
+#[macro_use]
+extern crate log;
+
+pub mod core;
+pub mod formatter;
+pub mod highlight;
+pub mod module;
+pub mod optimizer;
+pub mod parser;
+pub mod resolve;
+pub mod runtime;
+pub mod typeck;
+
+pub use crate::{
+    core::{source_map::SourceMap, span::Span, types::Type},
+    parser::{ParseResult, Parser, ParserError, ast},
+    resolve::{Resolution, Resolver},
+    runtime::{Engine, Runtime, RuntimeError, Value},
+    typeck::{TypeChecker, TypeError},
+};
@@ -0,0 +1,733 @@
+//! What the server knows about an open document: its diagnostics, the symbols of its names and
+//! the types of its expressions, along with the conversions between byte offsets and LSP
+//! positions.
+//!
+//! Documents saved as files are analyzed together with the modules they import, loaded from the
+//! directory of the document the way `rscript run` loads them.
+
+use std::{collections::HashMap, fmt::Write, path::PathBuf};
+
+use serde_json::{Value, json};
+
+use rscript::{
+    Parser, Resolution, Runtime, SourceMap, Span, TypeChecker,
+    ast::{
+        BlockExpression, Expression, FunctionDeclaration, Program, Statement, StructDeclaration,
+        Visibility,
+    },
+    core::{
+        builtin::Builtin,
+        diagnostic::{Diagnostic, Severity, ToDiagnostic},
+        source_map::FileId,
+        span::Spanned,
+        types::Type,
+    },
+    module::load_imports,
+    resolve::{SymbolId, SymbolKind},
+};
+
+/// The keywords offered by completion, reserved words without a meaning yet are left out.
+const KEYWORDS: &[&str] = &[
+    "let", "fn", "struct", "pub", "use", "if", "else", "return", "true", "false",
+];
+
+// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#symbolKind
+const SYMBOL_FIELD: u32 = 8;
+const SYMBOL_FUNCTION: u32 = 12;
+const SYMBOL_VARIABLE: u32 = 13;
+const SYMBOL_STRUCT: u32 = 23;
+
+// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#completionItemKind
+const COMPLETION_METHOD: u32 = 2;
+const COMPLETION_FUNCTION: u32 = 3;
+const COMPLETION_FIELD: u32 = 5;
+const COMPLETION_VARIABLE: u32 = 6;
+const COMPLETION_KEYWORD: u32 = 14;
+const COMPLETION_STRUCT: u32 = 22;
+
+pub struct Analysis {
+    pub text: String,
+    /// The byte offset of the start of every line.
+    line_starts: Vec<usize>,
+    file: FileId,
+    /// The document and the modules it imports.
+    source_map: SourceMap,
+    /// The document merged with the modules it imports, see [`load_imports`].
+    program: Program,
+    /// The qualified names of the imported items, by the names the document imports them as.
+    imports: HashMap<String, String>,
+    resolution: Resolution,
+    /// The checker after checking the program, it knows the members of every type.
+    checker: TypeChecker,
+    pub diagnostics: Vec<Diagnostic>,
+    types: Types,
+}
+
+impl Analysis {
+    /// Parses, resolves and type checks `text`. Parsing recovers from errors, so a document
+    /// being edited still has symbols and types up to the first type error.
+    pub fn new(uri: &str, text: String) -> Self {
+        trace!("Analyzing `{uri}`");
+        let mut source_map = SourceMap::new();
+        let file = source_map.add_file(uri, text.as_str());
+        let parsed = Parser::with_file(&text, file).parse();
+        let mut diagnostics: Vec<_> = parsed
+            .errors
+            .iter()
+            .map(ToDiagnostic::to_diagnostic)
+            .collect();
+
+        let imports = imports(&parsed.program);
+        let mut program = match uri_to_path(uri) {
+            Some(path) => match load_imports(&path, parsed.program.clone(), &mut source_map) {
+                Ok(program) => program,
+                Err(error) => {
+                    diagnostics.extend(error.diagnostics());
+                    parsed.program
+                }
+            },
+            None => parsed.program,
+        };
+
+        // Scripts see the standard library of the runtime executing them
+        let runtime = Runtime::new();
+        let resolution = runtime.resolver().resolve_program(&program);
+        let mut checker = runtime.type_checker().clone();
+        let type_error = checker.check_program(&mut program).err();
+
+        // Type errors mostly repeat syntax errors and unresolved names
+        if diagnostics.is_empty()
+            && resolution.errors.is_empty()
+            && let Some(error) = type_error
+        {
+            diagnostics.push(error.to_diagnostic());
+        }
+        diagnostics.extend(resolution.diagnostics());
+
+        let mut types = Types::default();
+        types.statements(&program.statements, &resolution);
+
+        let line_starts = line_starts(&text);
+        Analysis {
+            text,
+            line_starts,
+            file,
+            source_map,
+            program,
+            imports,
+            resolution,
+            checker,
+            diagnostics,
+            types,
+        }
+    }
+
+    // -- Positions --
+
+    /// Converts a byte offset into an LSP position, whose character counts UTF-16 code units.
+    pub fn position(&self, offset: usize) -> Value {
+        position(&self.text, &self.line_starts, offset)
+    }
+
+    /// Converts an LSP position into a byte offset, positions past the end of a line are
+    /// clamped to its end.
+    pub fn offset(&self, line: usize, character: usize) -> usize {
+        let Some(&start) = self.line_starts.get(line) else {
+            return self.text.len();
+        };
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.text.len(), |next| next - 1);
+        let mut units = 0;
+        for (index, char) in self.text[start..end].char_indices() {
+            if units >= character {
+                return start + index;
+            }
+            units += char.len_utf16();
+        }
+        end
+    }
+
+    pub fn range(&self, span: Span) -> Value {
+        json!({ "start": self.position(span.start), "end": self.position(span.end) })
+    }
+
+    /// The LSP location of `span`, which is in the document or in a module it imports.
+    pub fn location(&self, uri: &str, span: Span) -> Value {
+        if span.file == self.file {
+            return json!({ "uri": uri, "range": self.range(span) });
+        }
+        let file = self
+            .source_map
+            .get(span.file)
+            .expect("spans refer to loaded files");
+        let text = file.source();
+        let line_starts = line_starts(text);
+        let range = json!({
+            "start": position(text, &line_starts, span.start),
+            "end": position(text, &line_starts, span.end),
+        });
+        json!({ "uri": path_to_uri(file.name()), "range": range })
+    }
+
+    /// The range covering the whole document.
+    pub fn full_range(&self) -> Value {
+        self.range(Span::new(self.file, 0..self.text.len()))
+    }
+
+    // -- Features --
+
+    pub fn diagnostics(&self, uri: &str) -> Vec<Value> {
+        self.diagnostics
+            .iter()
+            .map(|diagnostic| {
+                // Errors in imported modules are shown at the start of the document
+                let primary = diagnostic
+                    .primary_span()
+                    .filter(|span| span.file == self.file);
+                let range = self.range(primary.unwrap_or_default());
+                let severity = match diagnostic.severity {
+                    Severity::Error => 1,
+                    Severity::Warning => 2,
+                };
+                let mut message = diagnostic.message.clone();
+                if let Some(span) = diagnostic.primary_span()
+                    && span.file != self.file
+                {
+                    let _ = write!(message, "\nnote: in {}", self.source_map.location(span));
+                }
+                for note in &diagnostic.notes {
+                    message.push_str(&format!("\nnote: {note}"));
+                }
+                for help in &diagnostic.help {
+                    message.push_str(&format!("\nhelp: {help}"));
+                }
+                let related: Vec<_> = diagnostic
+                    .labels
+                    .iter()
+                    .filter(|label| !label.primary && label.span.file == self.file)
+                    .map(|label| {
+                        json!({
+                            "location": { "uri": uri, "range": self.range(label.span) },
+                            "message": label.message,
+                        })
+                    })
+                    .collect();
+                json!({
+                    "range": range,
+                    "severity": severity,
+                    "source": "rscript",
+                    "message": message,
+                    "relatedInformation": related,
+                })
+            })
+            .collect()
+    }
+
+    /// Describes the symbol at `offset`, or the type of the innermost expression around it.
+    pub fn hover(&self, offset: usize) -> Option<Value> {
+        let (contents, span) = match self.resolution.symbol_at(self.file, offset) {
+            Some(id) => {
+                let span = self
+                    .use_at(id, offset)
+                    .unwrap_or(self.resolution.symbol(id).span);
+                (self.describe(id), span)
+            }
+            None => {
+                let (span, ty) = self
+                    .types
+                    .expressions
+                    .iter()
+                    .filter(|(span, _)| {
+                        span.file == self.file && span.start <= offset && offset <= span.end
+                    })
+                    .min_by_key(|(span, _)| span.end - span.start)?;
+                (ty.clone(), *span)
+            }
+        };
+        Some(json!({
+            "contents": { "kind": "markdown", "value": format!("```rscript\n{contents}\n```") },
+            "range": self.range(span),
+        }))
+    }
+
+    /// The span of the declaration of the symbol at `offset`, which may be in an imported module.
+    pub fn definition(&self, offset: usize) -> Option<Span> {
+        let id = self.resolution.symbol_at(self.file, offset)?;
+        Some(self.resolution.symbol(id).span)
+    }
+
+    /// The spans of the uses of the symbol at `offset`, after its declaration if requested.
+    pub fn references(&self, offset: usize, include_declaration: bool) -> Vec<Span> {
+        let Some(id) = self.resolution.symbol_at(self.file, offset) else {
+            return Vec::new();
+        };
+        let mut spans = self.resolution.references(id);
+        if include_declaration {
+            spans.insert(0, self.resolution.symbol(id).span);
+        }
+        spans
+    }
+
+    /// The functions, structs and global variables of the document, nested functions and fields
+    /// as children of their declarations.
+    pub fn document_symbols(&self) -> Vec<Value> {
+        self.program
+            .statements
+            .iter()
+            .filter(|statement| statement.span().file == self.file)
+            .filter_map(|statement| self.document_symbol(statement, true))
+            .collect()
+    }
+
+    fn document_symbol(&self, statement: &Statement, top_level: bool) -> Option<Value> {
+        let symbol = |name: &str, kind, span, selection, detail: Option<&String>, children| {
+            json!({
+                "name": name,
+                "kind": kind,
+                "detail": detail,
+                "range": self.range(span),
+                "selectionRange": self.range(selection),
+                "children": children,
+            })
+        };
+        match statement {
+            Statement::FunctionDeclaration(declaration) => {
+                let identifier = &declaration.identifier;
+                let children: Vec<_> = declaration
+                    .body
+                    .iter()
+                    .filter_map(|statement| self.document_symbol(statement, false))
+                    .collect();
+                Some(symbol(
+                    &identifier.name,
+                    SYMBOL_FUNCTION,
+                    declaration.span,
+                    identifier.span,
+                    self.types.signatures.get(&identifier.span),
+                    children,
+                ))
+            }
+            Statement::StructDeclaration(declaration) => {
+                let identifier = declaration.identifier();
+                let children = match declaration {
+                    StructDeclaration::NamedStruct { fields, .. } => fields
+                        .iter()
+                        .map(|field| {
+                            symbol(
+                                &field.identifier.name,
+                                SYMBOL_FIELD,
+                                field.span,
+                                field.identifier.span,
+                                Some(&field.declared_type.name),
+                                Vec::new(),
+                            )
+                        })
+                        .collect(),
+                    StructDeclaration::TupleStruct { .. }
+                    | StructDeclaration::UnitStruct { .. } => Vec::new(),
+                };
+                Some(symbol(
+                    &identifier.name,
+                    SYMBOL_STRUCT,
+                    declaration.span(),
+                    identifier.span,
+                    None,
+                    children,
+                ))
+            }
+            Statement::VariableDeclaration(declaration) if top_level => {
+                let identifier = &declaration.identifier;
+                Some(symbol(
+                    &identifier.name,
+                    SYMBOL_VARIABLE,
+                    declaration.span,
+                    identifier.span,
+                    self.types.variables.get(&identifier.span),
+                    Vec::new(),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// The fields of the value before a `.` at `offset`, or the names declared before `offset`
+    /// and the keywords otherwise.
+    pub fn completions(&self, offset: usize) -> Vec<Value> {
+        let offset = offset.min(self.text.len());
+        let is_identifier = |char: char| char.is_alphanumeric() || char == '_';
+        let before = &self.text[..offset];
+        let word_start = before.trim_end_matches(is_identifier).len();
+
+        if let Some(receiver) = before[..word_start].strip_suffix('.') {
+            let name = &receiver[receiver.trim_end_matches(is_identifier).len()..];
+            return self.field_completions(name, offset);
+        }
+
+        // Imported items are offered by the names the document imports them as
+        let imported: HashMap<&str, &str> = self
+            .imports
+            .iter()
+            .map(|(name, qualified)| (qualified.as_str(), name.as_str()))
+            .collect();
+        let mut items: HashMap<&str, Value> = HashMap::new();
+        for (id, symbol) in self.resolution.symbols() {
+            let label = if symbol.span.file == self.file {
+                symbol.name.as_str()
+            } else if let Some(name) = imported.get(symbol.name.as_str()) {
+                name
+            } else {
+                continue;
+            };
+            let declared_before = symbol.span.file != self.file || symbol.span.end <= word_start;
+            let (kind, detail) = match symbol.kind {
+                SymbolKind::Function => (COMPLETION_FUNCTION, self.describe(id)),
+                SymbolKind::Struct => (COMPLETION_STRUCT, self.describe(id)),
+                SymbolKind::Global | SymbolKind::Local | SymbolKind::Parameter
+                    if declared_before =>
+                {
+                    (COMPLETION_VARIABLE, self.describe(id))
+                }
+                _ => continue,
+            };
+            // Later declarations shadow earlier ones of the same name
+            items.insert(
+                label,
+                json!({ "label": label, "kind": kind, "detail": detail }),
+            );
+        }
+        // Declared functions shadow builtins of the same name
+        for builtin in Builtin::ALL {
+            items.entry(builtin.name()).or_insert_with(|| {
+                json!({
+                    "label": builtin.name(),
+                    "kind": COMPLETION_FUNCTION,
+                    "detail": builtin.signature(),
+                })
+            });
+        }
+        for keyword in KEYWORDS {
+            items
+                .entry(keyword)
+                .or_insert_with(|| json!({ "label": keyword, "kind": COMPLETION_KEYWORD }));
+        }
+
+        let mut items: Vec<_> = items.into_values().collect();
+        items.sort_by(|a, b| a["label"].as_str().cmp(&b["label"].as_str()));
+        items
+    }
+
+    /// The fields and methods the type checker knows for the type of the last variable named
+    /// `name` declared before `offset`.
+    fn field_completions(&self, name: &str, offset: usize) -> Vec<Value> {
+        let ty = self
+            .resolution
+            .symbols()
+            .filter(|(_, symbol)| {
+                symbol.name == name && symbol.span.file == self.file && symbol.span.end <= offset
+            })
+            .filter_map(|(_, symbol)| self.types.variables.get(&symbol.span))
+            .last();
+        let Some(members) = ty.and_then(|ty| {
+            let ty = Type::from_name(ty).unwrap_or_else(|| Type::Struct(ty.clone()));
+            self.checker.type_members(&ty)
+        }) else {
+            return Vec::new();
+        };
+
+        let fields = members.fields.iter().map(|(field, ty)| {
+            json!({ "label": field, "kind": COMPLETION_FIELD, "detail": ty.to_string() })
+        });
+        let methods = members.methods.iter().map(|(method, signature)| {
+            let parameters: Vec<_> = signature.parameters.iter().map(Type::to_string).collect();
+            let mut detail = format!("fn {method}({})", parameters.join(", "));
+            if signature.return_type != Type::Unit {
+                let _ = write!(detail, " -> {}", signature.return_type);
+            }
+            json!({ "label": method, "kind": COMPLETION_METHOD, "detail": detail })
+        });
+        let mut items: Vec<_> = fields.chain(methods).collect();
+        items.sort_by(|a, b| a["label"].as_str().cmp(&b["label"].as_str()));
+        items
+    }
+
+    /// The span of the use of a symbol around `offset`, if `offset` is not in its declaration.
+    fn use_at(&self, id: SymbolId, offset: usize) -> Option<Span> {
+        self.resolution
+            .references(id)
+            .into_iter()
+            .find(|span| span.start <= offset && offset <= span.end)
+    }
+
+    /// Renders the declaration of a symbol the way it is written in source code.
+    fn describe(&self, id: SymbolId) -> String {
+        let symbol = self.resolution.symbol(id);
+        let ty = self.types.variables.get(&symbol.span);
+        match (symbol.kind, ty) {
+            (SymbolKind::Global | SymbolKind::Local, Some(ty)) => {
+                format!("let {}: {ty}", symbol.name)
+            }
+            (SymbolKind::Global | SymbolKind::Local, None) => format!("let {}", symbol.name),
+            (SymbolKind::Parameter, Some(ty)) => format!("{}: {ty}", symbol.name),
+            (SymbolKind::Parameter, None) => symbol.name.clone(),
+            (SymbolKind::Function | SymbolKind::Struct, _) => self
+                .types
+                .signatures
+                .get(&symbol.span)
+                .cloned()
+                .unwrap_or_else(|| symbol.name.clone()),
+        }
+    }
+}
+
+/// The types found by the type checker, collected from the checked program.
+#[derive(Default)]
+struct Types {
+    /// The types of variables and parameters, by the span of their name.
+    variables: HashMap<Span, String>,
+    /// The declarations of functions and structs, by the span of their name.
+    signatures: HashMap<Span, String>,
+    /// Every expression with a known type.
+    expressions: Vec<(Span, String)>,
+}
+
+impl Types {
+    fn statements(&mut self, statements: &[Statement], resolution: &Resolution) {
+        for statement in statements {
+            match statement {
+                Statement::VariableDeclaration(declaration) => {
+                    let initializer = self.expression(&declaration.initializer, resolution);
+                    let ty = match &declaration.declared_type {
+                        Some(declared_type) => Some(declared_type.name.clone()),
+                        None => initializer,
+                    };
+                    if let Some(ty) = ty {
+                        self.variables.insert(declaration.identifier.span, ty);
+                    }
+                }
+                Statement::FunctionDeclaration(declaration) => {
+                    self.function(declaration, resolution)
+                }
+                Statement::StructDeclaration(declaration) => self.struct_declaration(declaration),
+                Statement::ExpressionStatement(statement) => {
+                    self.expression(&statement.expression, resolution);
+                }
+                Statement::ReturnStatement(statement) => {
+                    if let Some(value) = &statement.value {
+                        self.expression(value, resolution);
+                    }
+                }
+                Statement::BreakStatement(statement) => {
+                    if let Some(value) = &statement.value {
+                        self.expression(value, resolution);
+                    }
+                }
+                Statement::UseDeclaration(_) | Statement::Error(_) => {}
+            }
+        }
+    }
+
+    fn function(&mut self, declaration: &FunctionDeclaration, resolution: &Resolution) {
+        let parameters: Vec<_> = declaration
+            .parameters
+            .iter()
+            .map(|parameter| {
+                let ty = &parameter.declared_type.name;
+                self.variables.insert(parameter.identifier.span, ty.clone());
+                format!("{}: {ty}", parameter.identifier.name)
+            })
+            .collect();
+        let mut signature = format!(
+            "{}fn {}({})",
+            visibility(declaration.visibility),
+            declaration.identifier.name,
+            parameters.join(", ")
+        );
+        if declaration.return_type.name != "()" {
+            signature.push_str(&format!(" -> {}", declaration.return_type.name));
+        }
+        self.signatures
+            .insert(declaration.identifier.span, signature);
+        self.statements(&declaration.body, resolution);
+    }
+
+    fn struct_declaration(&mut self, declaration: &StructDeclaration) {
+        let identifier = declaration.identifier();
+        let prefix = format!(
+            "{}struct {}",
+            visibility(declaration.visibility()),
+            identifier.name
+        );
+        let signature = match declaration {
+            StructDeclaration::NamedStruct { fields, .. } => {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|field| format!("{}: {}", field.identifier.name, field.declared_type.name))
+                    .collect();
+                format!("{prefix} {{ {} }}", fields.join(", "))
+            }
+            StructDeclaration::TupleStruct { fields, .. } => {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|field| field.declared_type.name.as_str())
+                    .collect();
+                format!("{prefix}({})", fields.join(", "))
+            }
+            StructDeclaration::UnitStruct { .. } => prefix,
+        };
+        self.signatures.insert(identifier.span, signature);
+    }
+
+    /// Records the type of `expression` and of the expressions it contains, returns its type.
+    fn expression(&mut self, expression: &Expression, resolution: &Resolution) -> Option<String> {
+        let inferred = match expression {
+            Expression::BinaryOp(node) => {
+                self.expression(&node.left, resolution);
+                self.expression(&node.right, resolution);
+                node.inferred_type.as_ref()
+            }
+            Expression::UnaryOp(node) => {
+                self.expression(&node.operand, resolution);
+                node.inferred_type.as_ref()
+            }
+            Expression::FunctionCall(node) => {
+                for argument in &node.arguments {
+                    self.expression(argument, resolution);
+                }
+                node.inferred_type.as_ref()
+            }
+            Expression::FieldAccess(node) => {
+                self.expression(&node.object, resolution);
+                node.inferred_type.as_ref()
+            }
+            Expression::MethodCall(node) => {
+                self.expression(&node.receiver, resolution);
+                for argument in &node.arguments {
+                    self.expression(argument, resolution);
+                }
+                node.inferred_type.as_ref()
+            }
+            Expression::BlockExpression(node) => {
+                self.block(node, resolution);
+                node.inferred_type.as_ref()
+            }
+            Expression::IfExpression(node) => {
+                self.expression(&node.condition, resolution);
+                self.block(&node.then_branch, resolution);
+                if let Some(else_branch) = &node.else_branch {
+                    self.block(else_branch, resolution);
+                }
+                node.inferred_type.as_ref()
+            }
+            Expression::IntegerLiteral(node) => node.inferred_type.as_ref(),
+            Expression::FloatLiteral(node) => node.inferred_type.as_ref(),
+            Expression::Identifier(_)
+            | Expression::StringLiteral(_)
+            | Expression::CharLiteral(_)
+            | Expression::BooleanLiteral(_) => None,
+        };
+
+        let ty = match expression {
+            Expression::StringLiteral(_) => Some("String".to_string()),
+            Expression::CharLiteral(_) => Some("char".to_string()),
+            Expression::BooleanLiteral(_) => Some("bool".to_string()),
+            Expression::Identifier(identifier) => resolution
+                .resolve(identifier.span)
+                .and_then(|id| self.variables.get(&resolution.symbol(id).span))
+                .cloned(),
+            _ => inferred.map(|identifier| identifier.name.clone()),
+        }?;
+        self.expressions.push((expression.span(), ty.clone()));
+        Some(ty)
+    }
+
+    fn block(&mut self, block: &BlockExpression, resolution: &Resolution) {
+        self.statements(&block.statements, resolution);
+        if let Some(expression) = &block.final_expression {
+            self.expression(expression, resolution);
+        }
+    }
+}
+
+/// The qualified names of the items `program` imports, by the names it imports them as.
+fn imports(program: &Program) -> HashMap<String, String> {
+    program
+        .statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::UseDeclaration(declaration) => {
+                let mut qualified = String::new();
+                for segment in declaration.module() {
+                    qualified.push_str(&segment.name);
+                    qualified.push_str("::");
+                }
+                qualified.push_str(&declaration.item().name);
+                Some((declaration.item().name.clone(), qualified))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The byte offset of the start of every line of `text`.
+fn line_starts(text: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+        .collect()
+}
+
+/// Converts a byte offset into `text` into an LSP position.
+fn position(text: &str, line_starts: &[usize], offset: usize) -> Value {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let line = match line_starts.binary_search(&offset) {
+        Ok(line) => line,
+        Err(line) => line - 1,
+    };
+    let start = line_starts[line];
+    let character = text[start..offset].encode_utf16().count();
+    json!({ "line": line, "character": character })
+}
+
+/// The path of a `file://` URI, documents with other schemes are not files.
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut path = Vec::with_capacity(encoded.len());
+    let mut index = 0;
+    while index < encoded.len() {
+        let byte = match encoded[index] {
+            b'%' => {
+                let hex = std::str::from_utf8(encoded.get(index + 1..index + 3)?).ok()?;
+                index += 2;
+                u8::from_str_radix(hex, 16).ok()?
+            }
+            byte => byte,
+        };
+        path.push(byte);
+        index += 1;
+    }
+    String::from_utf8(path).ok().map(PathBuf::from)
+}
+
+/// The `file://` URI of an absolute path, percent-encoding everything but unreserved characters.
+fn path_to_uri(path: &str) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            uri.push(char::from(byte));
+        } else {
+            let _ = write!(uri, "%{byte:02X}");
+        }
+    }
+    uri
+}
+
+fn visibility(visibility: Visibility) -> &'static str {
+    match visibility {
+        Visibility::Public => "pub ",
+        Visibility::Private => "",
+    }
+}
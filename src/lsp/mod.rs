@@ -0,0 +1,322 @@
+//! # Language Server
+//!
+//! Speaks the Language Server Protocol over stdio, so editors can show the diagnostics of
+//! scripts while they are edited and navigate them. Every open document is analyzed whenever it
+//! changes, together with the modules it imports as saved on disk, see [`Analysis`].
+//!
+//! Supported requests:
+//! - `textDocument/hover`: the declaration of a name or the type of an expression,
+//! - `textDocument/definition` and `textDocument/references`, from name resolution, also into
+//!   imported modules,
+//! - `textDocument/documentSymbol`: functions, structs and global variables,
+//! - `textDocument/completion`: names in scope, keywords and members after a `.`,
+//! - `textDocument/formatting`: the [formatter](rscript::formatter).
+//!
+//! Diagnostics are published after every change. Documents are synchronized in full.
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use serde_json::{Value, json};
+
+use rscript::formatter::{FormatterConfig, format_source};
+
+use self::{
+    analysis::Analysis,
+    transport::{Body, MAX_CONTENT_LENGTH, read_message, write_message},
+};
+
+mod analysis;
+mod transport;
+
+// https://www.jsonrpc.org/specification#error_object
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+/// The largest indentation `textDocument/formatting` accepts as `tabSize`.
+const MAX_TAB_SIZE: u64 = 16;
+
+/// The error of a request, sent to the client in the response.
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+impl ResponseError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        ResponseError {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn invalid_params(message: impl Into<String>) -> Self {
+        ResponseError::new(INVALID_PARAMS, message)
+    }
+}
+
+pub struct Server<R, W> {
+    reader: R,
+    writer: W,
+    /// The open documents by URI.
+    documents: HashMap<String, Analysis>,
+    initialized: bool,
+    shut_down: bool,
+}
+
+impl<R: BufRead, W: Write> Server<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        Server {
+            reader,
+            writer,
+            documents: HashMap::new(),
+            initialized: false,
+            shut_down: false,
+        }
+    }
+
+    /// Serves the client until it sends `exit` or closes the stream. Returns whether the client
+    /// asked the server to shut down before.
+    pub fn run(&mut self) -> io::Result<bool> {
+        info!("Language server started");
+        while let Some(body) = read_message(&mut self.reader)? {
+            let body = match body {
+                Body::Complete(body) => body,
+                // The id of the request is in the skipped body
+                Body::TooLarge(length) => {
+                    self.respond(
+                        Value::Null,
+                        Err(ResponseError::new(
+                            INVALID_REQUEST,
+                            format!(
+                                "message of {length} bytes exceeds the maximum of \
+                                 {MAX_CONTENT_LENGTH} bytes"
+                            ),
+                        )),
+                    )?;
+                    continue;
+                }
+            };
+            let message: Value = match serde_json::from_slice(&body) {
+                Ok(message) => message,
+                Err(error) => {
+                    self.respond(
+                        Value::Null,
+                        Err(ResponseError::new(PARSE_ERROR, error.to_string())),
+                    )?;
+                    continue;
+                }
+            };
+            let Some(method) = message["method"].as_str() else {
+                // Responses to requests of the server, which never sends any
+                continue;
+            };
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+            match message.get("id").cloned() {
+                Some(id) => {
+                    trace!("Request `{method}` ({id})");
+                    let result = self.request(method, &params);
+                    self.respond(id, result)?;
+                }
+                None if method == "exit" => break,
+                None => {
+                    trace!("Notification `{method}`");
+                    self.notification(method, &params)?;
+                }
+            }
+        }
+        info!("Language server stopped");
+        Ok(self.shut_down)
+    }
+
+    fn respond(&mut self, id: Value, result: Result<Value, ResponseError>) -> io::Result<()> {
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": error.code, "message": error.message },
+            }),
+        };
+        write_message(&mut self.writer, &response)
+    }
+
+    fn notify(&mut self, method: &str, params: Value) -> io::Result<()> {
+        let notification = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        write_message(&mut self.writer, &notification)
+    }
+
+    // -- Requests --
+
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, ResponseError> {
+        if self.shut_down {
+            return Err(ResponseError::new(
+                INVALID_REQUEST,
+                "the server was shut down",
+            ));
+        }
+        if method == "initialize" {
+            self.initialized = true;
+            return Ok(initialize_result());
+        }
+        if !self.initialized {
+            return Err(ResponseError::new(
+                SERVER_NOT_INITIALIZED,
+                "the server was not initialized",
+            ));
+        }
+
+        match method {
+            "shutdown" => {
+                self.shut_down = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => {
+                let (document, offset) = self.position(params)?;
+                Ok(document.hover(offset).unwrap_or(Value::Null))
+            }
+            "textDocument/definition" => {
+                let uri = document_uri(params)?;
+                let (document, offset) = self.position(params)?;
+                Ok(document
+                    .definition(offset)
+                    .map_or(Value::Null, |span| document.location(uri, span)))
+            }
+            "textDocument/references" => {
+                let uri = document_uri(params)?;
+                let (document, offset) = self.position(params)?;
+                let include_declaration = params["context"]["includeDeclaration"]
+                    .as_bool()
+                    .unwrap_or(false);
+                let locations: Vec<_> = document
+                    .references(offset, include_declaration)
+                    .into_iter()
+                    .map(|span| document.location(uri, span))
+                    .collect();
+                Ok(json!(locations))
+            }
+            "textDocument/documentSymbol" => {
+                let document = self.document(params)?;
+                Ok(json!(document.document_symbols()))
+            }
+            "textDocument/completion" => {
+                let (document, offset) = self.position(params)?;
+                Ok(json!(document.completions(offset)))
+            }
+            "textDocument/formatting" => {
+                let document = self.document(params)?;
+                let mut config = FormatterConfig::default();
+                if let Some(tab_size) = params["options"]["tabSize"].as_u64() {
+                    if tab_size > MAX_TAB_SIZE {
+                        return Err(ResponseError::invalid_params(format!(
+                            "tab size {tab_size} exceeds the maximum of {MAX_TAB_SIZE}"
+                        )));
+                    }
+                    config.indent_width = tab_size as usize;
+                }
+                // Documents with syntax errors cannot be formatted, their errors are reported
+                // as diagnostics already
+                let Ok(formatted) = format_source(&document.text, &config) else {
+                    return Ok(Value::Null);
+                };
+                if formatted == document.text {
+                    return Ok(json!([]));
+                }
+                Ok(json!([{ "range": document.full_range(), "newText": formatted }]))
+            }
+            _ => Err(ResponseError::new(
+                METHOD_NOT_FOUND,
+                format!("unsupported method `{method}`"),
+            )),
+        }
+    }
+
+    /// The open document a request refers to.
+    fn document(&self, params: &Value) -> Result<&Analysis, ResponseError> {
+        let uri = document_uri(params)?;
+        self.documents
+            .get(uri)
+            .ok_or_else(|| ResponseError::invalid_params(format!("document `{uri}` is not open")))
+    }
+
+    /// The open document a request refers to and the byte offset of its position.
+    fn position(&self, params: &Value) -> Result<(&Analysis, usize), ResponseError> {
+        let document = self.document(params)?;
+        let position = &params["position"];
+        let (Some(line), Some(character)) =
+            (position["line"].as_u64(), position["character"].as_u64())
+        else {
+            return Err(ResponseError::invalid_params("missing position"));
+        };
+        Ok((document, document.offset(line as usize, character as usize)))
+    }
+
+    // -- Notifications --
+
+    fn notification(&mut self, method: &str, params: &Value) -> io::Result<()> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.update(uri, text.to_string())
+            }
+            "textDocument/didChange" => {
+                // Full synchronization, the last change holds the whole text
+                let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                else {
+                    return Ok(());
+                };
+                self.update(uri, text.to_string())
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                self.notify(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                )
+            }
+            // `initialized`, `$/cancelRequest`, `workspace/didChangeConfiguration`, ...
+            _ => Ok(()),
+        }
+    }
+
+    fn update(&mut self, uri: &str, text: String) -> io::Result<()> {
+        let analysis = Analysis::new(uri, text);
+        let diagnostics = analysis.diagnostics(uri);
+        self.documents.insert(uri.to_string(), analysis);
+        self.notify(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics }),
+        )
+    }
+}
+
+fn initialize_result() -> Value {
+    json!({
+        "capabilities": {
+            "textDocumentSync": { "openClose": true, "change": 1 },
+            "hoverProvider": true,
+            "definitionProvider": true,
+            "referencesProvider": true,
+            "documentSymbolProvider": true,
+            "completionProvider": { "triggerCharacters": ["."] },
+            "documentFormattingProvider": true,
+        },
+        "serverInfo": { "name": "rscript", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+fn document_uri(params: &Value) -> Result<&str, ResponseError> {
+    params["textDocument"]["uri"]
+        .as_str()
+        .ok_or_else(|| ResponseError::invalid_params("missing document URI"))
+}
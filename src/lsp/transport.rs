@@ -0,0 +1,68 @@
+//! The base protocol of LSP: every message is a JSON-RPC object preceded by a `Content-Length`
+//! header and an empty line.
+
+use std::io::{self, BufRead, Read, Write};
+
+use serde_json::Value;
+
+/// The largest message body read, in bytes. Longer messages are skipped, so a client cannot make
+/// the server allocate whatever a `Content-Length` header claims.
+pub const MAX_CONTENT_LENGTH: usize = 16 << 20;
+
+/// The body of a message as read from the stream.
+pub enum Body {
+    Complete(Vec<u8>),
+    /// A body longer than [`MAX_CONTENT_LENGTH`] was skipped, with its length.
+    TooLarge(usize),
+}
+
+/// Reads the body of the next message, `None` once the client closed the stream.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Body>> {
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        // Other headers, i.e. `Content-Type`, only have one valid value
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            let length = value.trim().parse().map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid content length `{}`", value.trim()),
+                )
+            })?;
+            content_length = Some(length);
+        }
+    }
+
+    let length = content_length.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "missing `Content-Length` header",
+        )
+    })?;
+    if length > MAX_CONTENT_LENGTH {
+        let skipped = io::copy(&mut reader.take(length as u64), &mut io::sink())?;
+        if skipped < length as u64 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        return Ok(Some(Body::TooLarge(length)));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(Body::Complete(body)))
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
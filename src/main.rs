@@ -1,39 +1,716 @@
-#![allow(unused)]
-
-use std::{env::args, fs};
+use std::{
+    fs,
+    io::{self, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    thread,
+};
 extern crate pretty_env_logger;
 #[macro_use]
 extern crate log;
-use core::format::Format;
+use clap::{Parser as _, Subcommand, ValueEnum};
+use logos::Logos;
+use rscript::{
+    Engine, Parser, Runtime, SourceMap, Span,
+    core::{
+        diagnostic::{Diagnostic, DiagnosticRenderer, Severity, ToDiagnostic},
+        format::Format,
+    },
+    formatter::{FormatterConfig, format_source},
+    highlight::{Theme, escape_html, highlight_html, write_highlighted},
+    optimizer::OptimizerConfig,
+    parser::{ParseResult, lexer::Token, sexpr::program_to_sexpr},
+    runtime::vm::{BytecodeFile, disassemble},
+};
 use termcolor::{ColorChoice, StandardStream};
 
-use crate::{parser::Parser, runtime::Runtime};
+use crate::{lsp::Server, repl::Repl};
+
+mod lsp;
+mod repl;
 
-mod core;
-mod parser;
-mod runtime;
+/// Runs and inspects rscript programs.
+#[derive(clap::Parser)]
+#[command(name = "rscript", version)]
+struct Cli {
+    /// When to use colours in the output.
+    #[arg(long, value_enum, default_value_t = ColorMode::Auto, global = true)]
+    color: ColorMode,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Executes a script or a compiled `.rsc` file, a `main` function returning an integer in
+    /// `0..=255` sets the exit code.
+    Run {
+        /// The script to execute, `-` reads it from stdin. Modules it imports are loaded from
+        /// the files next to it, or from the working directory for stdin.
+        file: String,
+        /// How to execute the script, compiled files always run on the VM.
+        #[arg(long, value_enum, default_value_t = EngineMode::Tree)]
+        engine: EngineMode,
+        /// Executes the script as written, without optimizing it.
+        #[arg(long)]
+        no_optimize: bool,
+    },
+    /// Compiles a script to a bytecode file that can be run without parsing it again.
+    Compile {
+        /// The script to compile, `-` reads it from stdin.
+        file: String,
+        /// Where to write the bytecode, defaults to the script path with an `.rsc` extension.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Compiles the script as written, without optimizing it.
+        #[arg(long)]
+        no_optimize: bool,
+    },
+    /// Prints the bytecode of a script or a compiled `.rsc` file.
+    Disasm {
+        /// The script or bytecode file to disassemble, `-` reads a script from stdin.
+        file: String,
+        /// Compiles scripts as written, without optimizing it.
+        #[arg(long)]
+        no_optimize: bool,
+    },
+    /// Starts an interactive session.
+    Repl,
+    /// Starts a language server speaking LSP over stdio.
+    Lsp,
+    /// Parses, resolves the names of and type checks a script without executing it.
+    Check {
+        /// The script to check, `-` reads it from stdin.
+        file: String,
+    },
+    /// Prints the abstract syntax tree of a script.
+    Ast {
+        /// The script to parse, `-` reads it from stdin.
+        file: String,
+        /// How to print the tree.
+        #[arg(long, value_enum, default_value_t = AstFormat::Tree)]
+        format: AstFormat,
+        /// Type checks the script first, so expressions carry their inferred types.
+        #[arg(long)]
+        typed: bool,
+    },
+    /// Prints a script with syntax highlighting.
+    Highlight {
+        /// The script to highlight, `-` reads it from stdin.
+        file: String,
+        /// Prints an HTML page instead of coloured text.
+        #[arg(long)]
+        html: bool,
+        /// The colours to use.
+        #[arg(long, value_enum, default_value_t = ThemeMode::Dark)]
+        theme: ThemeMode,
+    },
+    /// Prints the tokens of a script.
+    Tokens {
+        /// The script to tokenize, `-` reads it from stdin.
+        file: String,
+    },
+    /// Formats scripts in place.
+    Fmt {
+        /// Only reports the files that are not formatted and fails if there are any.
+        #[arg(long)]
+        check: bool,
+        /// Number of spaces per indentation level.
+        #[arg(long, default_value_t = FormatterConfig::default().indent_width)]
+        indent_width: usize,
+        /// Lines longer than this are broken up where possible.
+        #[arg(long, default_value_t = FormatterConfig::default().max_width)]
+        max_width: usize,
+        /// The scripts to format, `-` formats stdin to stdout.
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+    /// Runs the `#[test]` functions of scripts, each after executing its script in a fresh
+    /// runtime, and reports which of them failed.
+    Test {
+        /// The scripts to test, directories are searched for `.rscript` files.
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Only runs the tests whose name contains this text.
+        #[arg(short, long)]
+        filter: Option<String>,
+        /// How to execute the scripts.
+        #[arg(long, value_enum, default_value_t = EngineMode::Tree)]
+        engine: EngineMode,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ColorMode {
+    /// Colours if the output is a terminal.
+    Auto,
+    /// Always use colours.
+    Always,
+    /// Never use colours.
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum EngineMode {
+    /// Walk the syntax tree.
+    Tree,
+    /// Compile to bytecode and run it on the virtual machine.
+    Vm,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum AstFormat {
+    /// An indented, coloured tree for reading.
+    Tree,
+    /// JSON with every node, span and inferred type.
+    Json,
+    /// S-expressions without spans, for diffing.
+    Sexpr,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ThemeMode {
+    /// Colours for dark backgrounds.
+    Dark,
+    /// Colours for light backgrounds.
+    Light,
+}
+
+impl From<ThemeMode> for Theme {
+    fn from(mode: ThemeMode) -> Self {
+        match mode {
+            ThemeMode::Dark => Theme::dark(),
+            ThemeMode::Light => Theme::light(),
+        }
+    }
+}
+
+impl From<EngineMode> for Engine {
+    fn from(mode: EngineMode) -> Self {
+        match mode {
+            EngineMode::Tree => Engine::TreeWalker,
+            EngineMode::Vm => Engine::Vm,
+        }
+    }
+}
 
-fn main() -> anyhow::Result<()> {
+impl ColorMode {
+    /// `ColorChoice::Auto` does not check whether the stream is a terminal, so that is done here.
+    fn choice(self, is_terminal: bool) -> ColorChoice {
+        match self {
+            ColorMode::Auto if is_terminal => ColorChoice::Auto,
+            ColorMode::Auto | ColorMode::Never => ColorChoice::Never,
+            ColorMode::Always => ColorChoice::Always,
+        }
+    }
+
+    fn stdout(self) -> StandardStream {
+        StandardStream::stdout(self.choice(io::stdout().is_terminal()))
+    }
+
+    fn stderr(self) -> StandardStream {
+        StandardStream::stderr(self.choice(io::stderr().is_terminal()))
+    }
+}
+
+/// The stack of the thread running the command, large enough for scripts to reach the default
+/// maximum call depth of the runtime in debug builds.
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+fn main() -> anyhow::Result<ExitCode> {
     pretty_env_logger::init();
+    let cli = Cli::parse();
+    let command = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(|| execute(cli))?;
+    command
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+fn execute(cli: Cli) -> anyhow::Result<ExitCode> {
+    let color = cli.color;
+
+    let result = match cli.command {
+        Command::Run {
+            file,
+            engine,
+            no_optimize,
+        } => run_command(&file, engine.into(), optimizer(no_optimize), color),
+        Command::Repl => {
+            Repl::new(color.stdout(), color.stderr())?.run()?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Lsp => lsp_command(),
+        Command::Compile {
+            file,
+            output,
+            no_optimize,
+        } => compile_command(&file, output, optimizer(no_optimize), color),
+        Command::Disasm { file, no_optimize } => {
+            disasm_command(&file, optimizer(no_optimize), color)
+        }
+        Command::Check { file } => check_command(&file, color),
+        Command::Ast {
+            file,
+            format,
+            typed,
+        } => ast_command(&file, format, typed, color),
+        Command::Highlight { file, html, theme } => {
+            highlight_command(&file, html, &theme.into(), color)
+        }
+        Command::Tokens { file } => tokens_command(&file, color),
+        Command::Fmt {
+            check,
+            indent_width,
+            max_width,
+            files,
+        } => {
+            let config = FormatterConfig {
+                indent_width,
+                max_width,
+            };
+            fmt_command(&files, check, &config, color)
+        }
+        Command::Test {
+            paths,
+            filter,
+            engine,
+        } => test_command(&paths, filter.as_deref(), engine.into(), color),
+    };
+    // A reader that stops early, like `rscript ast script.rscript | head`, is not an error
+    match result {
+        Err(error)
+            if error
+                .downcast_ref::<io::Error>()
+                .is_some_and(|error| error.kind() == io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(ExitCode::SUCCESS)
+        }
+        result => result,
+    }
+}
+
+/// Writes the output of a command to stdout, unlike `print!` returning the errors of closed
+/// pipes instead of panicking.
+fn write_stdout(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(text.as_bytes())?;
+    stdout.flush()
+}
 
-    let input_file_path = args()
-        .skip(1)
-        .next()
-        .ok_or(anyhow::anyhow!("No input file provided"))?;
+/// Reads a script, `-` reads from stdin. Returns the name to use in diagnostics and the source.
+///
+/// The name is also the path modules are imported relative to, `<stdin>` has no directory, so
+/// every command looks up the modules of a script read from stdin in the working directory.
+fn read_source(path: &str) -> anyhow::Result<(String, String)> {
+    if path == "-" {
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source)?;
+        Ok(("<stdin>".to_string(), source))
+    } else {
+        let source = fs::read_to_string(path)
+            .map_err(|error| anyhow::anyhow!("Failed to read {}: {}", path, error))?;
+        info!("Successfully read input file: {}", path);
+        Ok((path.to_string(), source))
+    }
+}
 
-    let source = fs::read_to_string(&input_file_path)?;
-    info!("Successfully read input file: {}", input_file_path);
+/// Compiled files are recognized by their extension, everything else is read as source code.
+fn is_bytecode(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension == "rsc")
+}
 
-    println!("---SOURCE---");
-    print!("{}", source);
-    println!("---ENDING---");
+fn read_bytecode(path: &str) -> anyhow::Result<BytecodeFile> {
+    let bytes =
+        fs::read(path).map_err(|error| anyhow::anyhow!("Failed to read {}: {}", path, error))?;
+    let bytecode = BytecodeFile::from_bytes(&bytes)
+        .map_err(|error| anyhow::anyhow!("Failed to load {}: {}", path, error))?;
+    info!("Successfully loaded bytecode file: {}", path);
+    Ok(bytecode)
+}
 
-    let mut runtime = Runtime::new();
+/// Renders diagnostics to stderr and returns the exit code for failure.
+fn report(
+    source_map: &SourceMap,
+    diagnostics: &[Diagnostic],
+    color: ColorMode,
+) -> anyhow::Result<ExitCode> {
+    let renderer = DiagnosticRenderer::new(source_map);
+    let mut stderr = color.stderr();
+    for diagnostic in diagnostics {
+        renderer.render(&mut stderr, diagnostic)?;
+    }
+    // Warnings alone do not fail a command
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        Ok(ExitCode::FAILURE)
+    } else {
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// The command line runs a single script, so every optimization is safe to enable.
+fn optimizer(no_optimize: bool) -> OptimizerConfig {
+    if no_optimize {
+        OptimizerConfig::none()
+    } else {
+        OptimizerConfig::all()
+    }
+}
+
+fn run_command(
+    path: &str,
+    engine: Engine,
+    optimizer: OptimizerConfig,
+    color: ColorMode,
+) -> anyhow::Result<ExitCode> {
+    let mut runtime = Runtime::with_engine(engine);
+    runtime.set_optimizer(optimizer);
     info!("Created a new runtime instance");
 
-    runtime.execute(&source);
+    let result = if is_bytecode(path) {
+        let bytecode = read_bytecode(path)?;
+        runtime.execute_bytecode(&bytecode)
+    } else {
+        let (name, source) = read_source(path)?;
+        runtime.execute_file(Path::new(&name), &source)
+    };
+    let result = result.and_then(|()| runtime.run_main());
+    match result {
+        // Operating systems truncate exit codes to a byte, other codes would be misreported
+        Ok(Some(value)) => match value.as_i128().map(u8::try_from) {
+            Some(Ok(code)) => Ok(ExitCode::from(code)),
+            Some(Err(_)) => {
+                let diagnostic = Diagnostic::error(format!(
+                    "`main` returned {value}, which is not a valid exit code"
+                ))
+                .with_help("exit codes range from 0 to 255");
+                report(runtime.source_map(), &[diagnostic], color)
+            }
+            None => Ok(ExitCode::SUCCESS),
+        },
+        Ok(None) => Ok(ExitCode::SUCCESS),
+        Err(error) => report(runtime.source_map(), &error.diagnostics(), color),
+    }
+}
+
+fn compile_command(
+    path: &str,
+    output: Option<PathBuf>,
+    optimizer: OptimizerConfig,
+    color: ColorMode,
+) -> anyhow::Result<ExitCode> {
+    let (name, source) = read_source(path)?;
+    let mut runtime = Runtime::new();
+    runtime.set_optimizer(optimizer);
+    let result = runtime.compile_file(Path::new(&name), &source);
+    let bytecode = match result {
+        Ok(bytecode) => bytecode,
+        Err(error) => return report(runtime.source_map(), &error.diagnostics(), color),
+    };
+
+    let output = output.unwrap_or_else(|| match path {
+        "-" => PathBuf::from("stdin.rsc"),
+        path => Path::new(path).with_extension("rsc"),
+    });
+    fs::write(&output, bytecode.to_bytes())
+        .map_err(|error| anyhow::anyhow!("Failed to write {}: {}", output.display(), error))?;
+    info!("Wrote bytecode to {}", output.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn disasm_command(
+    path: &str,
+    optimizer: OptimizerConfig,
+    color: ColorMode,
+) -> anyhow::Result<ExitCode> {
+    let (bytecode, source_map) = if is_bytecode(path) {
+        let bytecode = read_bytecode(path)?;
+        // The spans of a decoded file refer to the files of a source map by index
+        let mut source_map = SourceMap::new();
+        source_map.add_file(bytecode.name.as_str(), bytecode.source.as_str());
+        for (name, source) in &bytecode.modules {
+            source_map.add_file(name.as_str(), source.as_str());
+        }
+        (bytecode, source_map)
+    } else {
+        let (name, source) = read_source(path)?;
+        let mut runtime = Runtime::new();
+        runtime.set_optimizer(optimizer);
+        let result = runtime.compile_file(Path::new(&name), &source);
+        match result {
+            Ok(bytecode) => (bytecode, runtime.source_map().clone()),
+            Err(error) => return report(runtime.source_map(), &error.diagnostics(), color),
+        }
+    };
+
+    write_stdout(&disassemble(&bytecode.chunk, &bytecode.name, &source_map))?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn lsp_command() -> anyhow::Result<ExitCode> {
+    let mut server = Server::new(io::stdin().lock(), io::stdout().lock());
+    // Exiting without a shutdown request is an error, see the `exit` notification
+    if server.run()? {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn check_command(path: &str, color: ColorMode) -> anyhow::Result<ExitCode> {
+    let (name, source) = read_source(path)?;
+    // Scripts are checked against the standard library of the runtime that would execute them
+    let mut runtime = Runtime::new();
+    let mut program = match runtime.load_file(Path::new(&name), &source) {
+        Ok(program) => program,
+        Err(error) => return report(runtime.source_map(), &error.diagnostics(), color),
+    };
+    let resolution = runtime.resolver().resolve_program(&program);
+    let mut diagnostics = resolution.diagnostics();
+    // Type errors mostly repeat unresolved names
+    if resolution.errors.is_empty()
+        && let Err(error) = runtime.type_checker().clone().check_program(&mut program)
+    {
+        diagnostics.insert(0, error.to_diagnostic());
+    }
+    report(runtime.source_map(), &diagnostics, color)
+}
+
+fn ast_command(
+    path: &str,
+    format: AstFormat,
+    typed: bool,
+    color: ColorMode,
+) -> anyhow::Result<ExitCode> {
+    let (name, source) = read_source(path)?;
+    let mut source_map = SourceMap::new();
+    let file = source_map.add_file(name.as_str(), source.as_str());
+
+    let ParseResult {
+        mut program,
+        errors,
+    } = Parser::with_file(&source, file).parse();
+    let mut diagnostics: Vec<_> = errors.iter().map(ToDiagnostic::to_diagnostic).collect();
+    // The tree is printed up to the first type error, with the types inferred so far, together
+    // with the modules the script imports
+    if typed && diagnostics.is_empty() {
+        let mut runtime = Runtime::new();
+        program = match runtime.load_file(Path::new(&name), &source) {
+            Ok(program) => program,
+            Err(error) => return report(runtime.source_map(), &error.diagnostics(), color),
+        };
+        source_map = runtime.source_map().clone();
+        if let Err(error) = runtime.type_checker().clone().check_program(&mut program) {
+            diagnostics.push(error.to_diagnostic());
+        }
+    }
+
+    match format {
+        AstFormat::Tree => program.format(&mut color.stdout(), 4, 0)?,
+        AstFormat::Json => write_stdout(&(serde_json::to_string_pretty(&program)? + "\n"))?,
+        AstFormat::Sexpr => write_stdout(&program_to_sexpr(&program))?,
+    }
+    if diagnostics.is_empty() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        report(&source_map, &diagnostics, color)
+    }
+}
+
+fn highlight_command(
+    path: &str,
+    html: bool,
+    theme: &Theme,
+    color: ColorMode,
+) -> anyhow::Result<ExitCode> {
+    let (name, source) = read_source(path)?;
+    if html {
+        write_stdout(&format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+            escape_html(&name),
+            theme.css(),
+            highlight_html(&source)
+        ))?;
+    } else {
+        write_highlighted(&mut color.stdout(), &source, theme)?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn tokens_command(path: &str, color: ColorMode) -> anyhow::Result<ExitCode> {
+    let (name, source) = read_source(path)?;
+    let mut source_map = SourceMap::new();
+    let file = source_map.add_file(name, source.as_str());
+    let source_file = source_map.get(file).expect("file was just added");
+
+    let mut diagnostics = Vec::new();
+    let mut stdout = io::stdout().lock();
+    let mut lexer = Token::lexer(&source);
+    while let Some(token) = lexer.next() {
+        let span = lexer.span();
+        let (line, column) = source_file.line_column(span.start);
+        match token {
+            Ok(token) => writeln!(
+                stdout,
+                "{}:{}\t{:?}\t{:?}",
+                line,
+                column,
+                token,
+                lexer.slice()
+            )?,
+            Err(error) => {
+                writeln!(stdout, "{}:{}\tError\t{:?}", line, column, lexer.slice())?;
+                diagnostics.push(
+                    Diagnostic::error(error.to_string())
+                        .with_primary_label(Span::new(file, span), "invalid token"),
+                );
+            }
+        }
+    }
+
+    if diagnostics.is_empty() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        report(&source_map, &diagnostics, color)
+    }
+}
+
+/// Rewrites the files in place, with `check` only reports the files that are not formatted.
+fn fmt_command(
+    paths: &[String],
+    check: bool,
+    config: &FormatterConfig,
+    color: ColorMode,
+) -> anyhow::Result<ExitCode> {
+    let mut exit_code = ExitCode::SUCCESS;
+    for path in paths {
+        let (name, source) = read_source(path)?;
+        let formatted = match format_source(&source, config) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                let mut source_map = SourceMap::new();
+                source_map.add_file(name, source);
+                let diagnostics: Vec<_> = errors.iter().map(ToDiagnostic::to_diagnostic).collect();
+                exit_code = report(&source_map, &diagnostics, color)?;
+                continue;
+            }
+        };
+
+        if check {
+            if formatted != source {
+                eprintln!("{} is not formatted", name);
+                exit_code = ExitCode::FAILURE;
+            }
+        } else if path == "-" {
+            write_stdout(&formatted)?;
+        } else if formatted != source {
+            fs::write(path, formatted)?;
+            info!("Formatted {}", path);
+        }
+    }
+    Ok(exit_code)
+}
+
+/// Expands directories to the scripts in them, sorted so tests run in a stable order.
+fn script_paths(paths: &[String]) -> anyhow::Result<Vec<PathBuf>> {
+    fn visit(directory: &Path, scripts: &mut Vec<PathBuf>) -> io::Result<()> {
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                visit(&path, scripts)?;
+            } else if path
+                .extension()
+                .is_some_and(|extension| extension == "rscript")
+            {
+                scripts.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut scripts = Vec::new();
+    for path in paths.iter().map(PathBuf::from) {
+        if path.is_dir() {
+            let mut found = Vec::new();
+            visit(&path, &mut found)
+                .map_err(|error| anyhow::anyhow!("Failed to read {}: {}", path.display(), error))?;
+            found.sort();
+            scripts.extend(found);
+        } else {
+            scripts.push(path);
+        }
+    }
+    Ok(scripts)
+}
+
+/// Runs every test function of the scripts whose name contains `filter`, each script in a runtime
+/// of its own.
+fn test_command(
+    paths: &[String],
+    filter: Option<&str>,
+    engine: Engine,
+    color: ColorMode,
+) -> anyhow::Result<ExitCode> {
+    let (mut passed, mut failed, mut filtered_out) = (0, 0, 0);
+    let mut load_failed = false;
+    for path in script_paths(paths)? {
+        let (name, source) = read_source(&path.to_string_lossy())?;
+        let mut runtime = Runtime::with_engine(engine);
+        if let Err(error) = runtime.execute_file(Path::new(&name), &source) {
+            writeln!(io::stderr(), "error: could not load the tests of {}", name)?;
+            report(runtime.source_map(), &error.diagnostics(), color)?;
+            load_failed = true;
+            continue;
+        }
 
-    dbg!(runtime);
+        let tests: Vec<_> = runtime
+            .tests()
+            .iter()
+            .filter(|test| filter.is_none_or(|filter| test.contains(filter)))
+            .cloned()
+            .collect();
+        filtered_out += runtime.tests().len() - tests.len();
+        if tests.is_empty() {
+            continue;
+        }
+        write_stdout(&format!("running {} tests in {}\n", tests.len(), name))?;
+        for test in tests {
+            // Every test runs in a runtime of its own, so tests cannot see what others changed
+            let mut runtime = Runtime::with_engine(engine);
+            let result = runtime
+                .execute_file(Path::new(&name), &source)
+                .and_then(|()| runtime.call(&test, Vec::new()));
+            match result {
+                Ok(_) => {
+                    write_stdout(&format!("test {} ... ok\n", test))?;
+                    passed += 1;
+                }
+                Err(error) => {
+                    write_stdout(&format!("test {} ... FAILED\n", test))?;
+                    report(runtime.source_map(), &error.diagnostics(), color)?;
+                    failed += 1;
+                }
+            }
+        }
+    }
 
-    Ok(())
+    let success = failed == 0 && !load_failed;
+    write_stdout(&format!(
+        "test result: {}. {} passed; {} failed; {} filtered out\n",
+        if success { "ok" } else { "FAILED" },
+        passed,
+        failed,
+        filtered_out
+    ))?;
+    Ok(if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
 }
@@ -0,0 +1,373 @@
+//! # Modules
+//!
+//! Scripts executed from a file can import the public functions and structs of other files with
+//! `use` declarations. All but the last segment of the path name the file of the module, relative
+//! to the directory of the entry script, the last one names the imported item:
+//!
+//! ```text
+//! use math::vec::Vector2D; // `pub struct Vector2D` declared in `math/vec.rscript`
+//! ```
+//!
+//! The imported modules are merged into a single [`Program`] with the entry script, after the
+//! modules they depend on. Every item of a module is renamed to its full path, e.g.
+//! `math::vec::Vector2D`, and every use of it in the importing files along with it. Modules can
+//! therefore declare items of the same name, and private items cannot be named outside of their
+//! module, as `::` is not part of any identifier written in source code.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use derive_more::{Display, Error, From};
+
+use self::qualify::{Declarations, Qualify};
+use crate::{
+    core::{
+        diagnostic::{Diagnostic, ToDiagnostic},
+        source_map::SourceMap,
+        span::Span,
+    },
+    parser::{
+        Parser, ParserError,
+        ast::{Program, Statement, UseDeclaration, Visibility},
+    },
+};
+
+mod qualify;
+
+/// The extension of the file of a module.
+pub const MODULE_EXTENSION: &str = "rscript";
+
+#[derive(Debug, PartialEq, Clone, Display, Error, From)]
+pub enum ModuleError {
+    #[display("{}", display_parser_errors(_0))]
+    ParserErrors(#[error(not(source))] Vec<ParserError>),
+    #[display("unresolved module `{module}`")]
+    #[from(ignore)]
+    UnresolvedModule {
+        module: String,
+        path: PathBuf,
+        /// Why the file of the module could not be read.
+        reason: String,
+        span: Span,
+    },
+    #[display("cannot find `{name}` in module `{module}`")]
+    #[from(ignore)]
+    UnresolvedImport {
+        name: String,
+        module: String,
+        span: Span,
+    },
+    #[display("`{name}` is private to module `{module}`")]
+    #[from(ignore)]
+    PrivateItem {
+        name: String,
+        module: String,
+        span: Span,
+        declaration: Span,
+    },
+    #[display("cyclic import of module `{module}`")]
+    #[from(ignore)]
+    CyclicImport {
+        module: String,
+        /// The importing files, from the module imported again to the import closing the cycle.
+        cycle: Vec<String>,
+        span: Span,
+    },
+    #[display("the name `{name}` is defined multiple times")]
+    #[from(ignore)]
+    NameConflict {
+        name: String,
+        span: Span,
+        previous: Span,
+    },
+}
+
+impl ModuleError {
+    /// Converts the error into diagnostics, one per parser error or a single one otherwise.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let diagnostic = Diagnostic::error(self.to_string());
+        let diagnostic = match self {
+            ModuleError::ParserErrors(errors) => {
+                return errors.iter().map(ToDiagnostic::to_diagnostic).collect();
+            }
+            ModuleError::UnresolvedModule {
+                path, reason, span, ..
+            } => diagnostic
+                .with_primary_label(*span, format!("cannot read `{}`: {reason}", path.display())),
+            ModuleError::UnresolvedImport { span, .. } => {
+                diagnostic.with_primary_label(*span, "not found in the module")
+            }
+            ModuleError::PrivateItem {
+                span, declaration, ..
+            } => diagnostic
+                .with_primary_label(*span, "private item")
+                .with_secondary_label(*declaration, "declared here")
+                .with_help("declare the item with `pub` to import it from other modules"),
+            ModuleError::CyclicImport { cycle, span, .. } => diagnostic
+                .with_primary_label(*span, "imported again here")
+                .with_note(format!(
+                    "the import cycle is {}",
+                    cycle
+                        .iter()
+                        .map(|module| format!("`{module}`"))
+                        .collect::<Vec<_>>()
+                        .join(" -> ")
+                )),
+            ModuleError::NameConflict {
+                name,
+                span,
+                previous,
+            } => diagnostic
+                .with_primary_label(*span, format!("`{name}` redefined here"))
+                .with_secondary_label(*previous, format!("previous definition of `{name}`")),
+        };
+        vec![diagnostic]
+    }
+}
+
+fn display_parser_errors(errors: &[ParserError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses the script at `path` and every module it imports, directly or not, into a single
+/// program. `source` is the content of the script, the files of the modules are read from disk.
+///
+/// Every parsed file is added to `source_map`, so the spans of the program and of errors can be
+/// rendered.
+pub fn load_program(
+    path: &Path,
+    source: &str,
+    source_map: &mut SourceMap,
+) -> Result<Program, ModuleError> {
+    let file = source_map.add_file(path.display().to_string(), source);
+    let program = Parser::with_file(source, file).parse().into_result()?;
+    load_imports(path, program, source_map)
+}
+
+/// Like [`load_program`], for a script already parsed from the file at `path`, e.g. with the
+/// errors the parser recovered from, which are kept in the merged program.
+pub fn load_imports(
+    path: &Path,
+    program: Program,
+    source_map: &mut SourceMap,
+) -> Result<Program, ModuleError> {
+    trace!("Loading script `{}` and its modules", path.display());
+    let name = path.display().to_string();
+    let mut loader = ModuleLoader {
+        root: path.parent().unwrap_or(Path::new("")).to_path_buf(),
+        source_map,
+        modules: HashMap::new(),
+        loading: Vec::new(),
+        statements: Vec::new(),
+    };
+    let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    loader.load(key, name, None, program.statements)?;
+    Ok(Program {
+        statements: loader.statements,
+        span: program.span,
+    })
+}
+
+struct ModuleLoader<'a> {
+    /// The directory module paths are relative to.
+    root: PathBuf,
+    source_map: &'a mut SourceMap,
+    /// The items of the modules loaded so far, by file.
+    modules: HashMap<PathBuf, Rc<Module>>,
+    /// The files whose imports are being loaded with their names, the entry script first.
+    loading: Vec<(PathBuf, String)>,
+    /// The statements of the loaded files, every file after the modules it imports.
+    statements: Vec<Statement>,
+}
+
+/// The items a module declares at its top level, by the name they are imported with.
+struct Module {
+    name: String,
+    functions: HashMap<String, Item>,
+    structs: HashMap<String, Item>,
+}
+
+struct Item {
+    /// The name of the item in the merged program.
+    qualified: String,
+    visibility: Visibility,
+    /// The span of the name in the declaration.
+    span: Span,
+}
+
+impl ModuleLoader<'_> {
+    /// Loads the imports of a file and adds its statements, with its items renamed to their
+    /// qualified names, to the merged program.
+    ///
+    /// `module` is the path of the module, `None` for the entry script, whose items keep their
+    /// names so the host can find them.
+    fn load(
+        &mut self,
+        key: PathBuf,
+        name: String,
+        module: Option<&str>,
+        mut statements: Vec<Statement>,
+    ) -> Result<Rc<Module>, ModuleError> {
+        let qualify = |name: &str| match module {
+            Some(module) => format!("{module}::{name}"),
+            None => name.to_string(),
+        };
+
+        // -- Own Declarations --
+        let declarations = Declarations::collect(&statements);
+        let mut renames = Qualify::default();
+        if module.is_some() {
+            for (names, renamed) in [
+                (&declarations.functions, &mut renames.functions),
+                (&declarations.structs, &mut renames.structs),
+                (&declarations.globals, &mut renames.globals),
+            ] {
+                renamed.extend(names.keys().map(|name| (name.clone(), qualify(name))));
+            }
+        }
+
+        // Only the items at the top level can be imported
+        let mut module = Module {
+            name: name.clone(),
+            functions: HashMap::new(),
+            structs: HashMap::new(),
+        };
+        for statement in &statements {
+            let (items, identifier, visibility) = match statement {
+                Statement::FunctionDeclaration(declaration) => (
+                    &mut module.functions,
+                    &declaration.identifier,
+                    declaration.visibility,
+                ),
+                Statement::StructDeclaration(declaration) => (
+                    &mut module.structs,
+                    declaration.identifier(),
+                    declaration.visibility(),
+                ),
+                _ => continue,
+            };
+            let item = Item {
+                qualified: qualify(&identifier.name),
+                visibility,
+                span: identifier.span,
+            };
+            items.insert(identifier.name.clone(), item);
+        }
+
+        // -- Imports --
+        self.loading.push((key.clone(), name.clone()));
+        let mut imported: HashMap<String, Span> = HashMap::new();
+        let mut body = Vec::with_capacity(statements.len());
+        for statement in statements.drain(..) {
+            let Statement::UseDeclaration(declaration) = statement else {
+                body.push(statement);
+                continue;
+            };
+            let dependency = self.import(&declaration)?;
+            let item = declaration.item();
+
+            let previous = imported
+                .get(&item.name)
+                .or_else(|| declarations.functions.get(&item.name))
+                .or_else(|| declarations.structs.get(&item.name));
+            if let Some(previous) = previous {
+                return Err(ModuleError::NameConflict {
+                    name: item.name.clone(),
+                    span: item.span,
+                    previous: *previous,
+                });
+            }
+
+            // A function and a struct of the same name are imported together
+            let mut found = false;
+            for (items, renamed) in [
+                (&dependency.functions, &mut renames.functions),
+                (&dependency.structs, &mut renames.structs),
+            ] {
+                let Some(declared) = items.get(&item.name) else {
+                    continue;
+                };
+                if declared.visibility == Visibility::Private {
+                    return Err(ModuleError::PrivateItem {
+                        name: item.name.clone(),
+                        module: dependency.name.clone(),
+                        span: item.span,
+                        declaration: declared.span,
+                    });
+                }
+                renamed.insert(item.name.clone(), declared.qualified.clone());
+                found = true;
+            }
+            if !found {
+                return Err(ModuleError::UnresolvedImport {
+                    name: item.name.clone(),
+                    module: dependency.name.clone(),
+                    span: item.span,
+                });
+            }
+            imported.insert(item.name.clone(), item.span);
+        }
+        self.loading.pop();
+
+        renames.statements(&mut body);
+        self.statements.extend(body);
+
+        let module = Rc::new(module);
+        self.modules.insert(key, module.clone());
+        Ok(module)
+    }
+
+    /// Loads the module an import refers to, unless it was loaded before.
+    fn import(&mut self, declaration: &UseDeclaration) -> Result<Rc<Module>, ModuleError> {
+        let segments = declaration.module();
+        let module: Vec<_> = segments
+            .iter()
+            .map(|segment| segment.name.as_str())
+            .collect();
+        let module = module.join("::");
+        let span = segments[0].span.combine(segments[segments.len() - 1].span);
+
+        let mut path = self.root.clone();
+        path.extend(segments.iter().map(|segment| &segment.name));
+        path.set_extension(MODULE_EXTENSION);
+        let unresolved = |error: std::io::Error| ModuleError::UnresolvedModule {
+            module: module.clone(),
+            path: path.clone(),
+            reason: error.to_string(),
+            span,
+        };
+        let key = fs::canonicalize(&path).map_err(unresolved)?;
+
+        if let Some(index) = self.loading.iter().position(|(loading, _)| *loading == key) {
+            let mut cycle: Vec<_> = self.loading[index..]
+                .iter()
+                .map(|(_, name)| name.clone())
+                .collect();
+            cycle.push(module.clone());
+            return Err(ModuleError::CyclicImport {
+                module,
+                cycle,
+                span,
+            });
+        }
+        if let Some(loaded) = self.modules.get(&key) {
+            return Ok(loaded.clone());
+        }
+
+        trace!("Loading module `{}` from `{}`", module, path.display());
+        let source = fs::read_to_string(&path).map_err(unresolved)?;
+        let file = self
+            .source_map
+            .add_file(path.display().to_string(), source.as_str());
+        let program = Parser::with_file(&source, file).parse().into_result()?;
+        self.load(key, module.clone(), Some(&module), program.statements)
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    core::span::Span,
+    parser::ast::{
+        BlockExpression, Expression, FunctionDeclaration, Identifier, Statement, StructDeclaration,
+    },
+};
+
+/// The names a file declares, with the spans of their declarations.
+///
+/// Structs are global wherever they are declared, so nested ones are included. Functions and
+/// variables are only global at the top level.
+#[derive(Default)]
+pub(super) struct Declarations {
+    pub(super) functions: HashMap<String, Span>,
+    pub(super) structs: HashMap<String, Span>,
+    pub(super) globals: HashMap<String, Span>,
+}
+
+impl Declarations {
+    pub(super) fn collect(statements: &[Statement]) -> Self {
+        let mut declarations = Declarations::default();
+        for statement in statements {
+            let (names, identifier) = match statement {
+                Statement::VariableDeclaration(declaration) => {
+                    (&mut declarations.globals, &declaration.identifier)
+                }
+                Statement::FunctionDeclaration(declaration) => {
+                    (&mut declarations.functions, &declaration.identifier)
+                }
+                _ => continue,
+            };
+            names.insert(identifier.name.clone(), identifier.span);
+        }
+        declarations.statements(statements);
+        declarations
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            match statement {
+                Statement::VariableDeclaration(declaration) => {
+                    self.expression(&declaration.initializer)
+                }
+                Statement::FunctionDeclaration(declaration) => self.statements(&declaration.body),
+                Statement::StructDeclaration(declaration) => {
+                    let identifier = declaration.identifier();
+                    self.structs
+                        .insert(identifier.name.clone(), identifier.span);
+                }
+                Statement::ExpressionStatement(statement) => self.expression(&statement.expression),
+                Statement::ReturnStatement(statement) => {
+                    if let Some(value) = &statement.value {
+                        self.expression(value);
+                    }
+                }
+                Statement::BreakStatement(statement) => {
+                    if let Some(value) = &statement.value {
+                        self.expression(value);
+                    }
+                }
+                Statement::UseDeclaration(_) | Statement::Error(_) => {}
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::BinaryOp(node) => {
+                self.expression(&node.left);
+                self.expression(&node.right);
+            }
+            Expression::UnaryOp(node) => self.expression(&node.operand),
+            Expression::FunctionCall(node) => node
+                .arguments
+                .iter()
+                .for_each(|argument| self.expression(argument)),
+            Expression::FieldAccess(node) => self.expression(&node.object),
+            Expression::MethodCall(node) => {
+                self.expression(&node.receiver);
+                node.arguments
+                    .iter()
+                    .for_each(|argument| self.expression(argument));
+            }
+            Expression::BlockExpression(node) => self.block(node),
+            Expression::IfExpression(node) => {
+                self.expression(&node.condition);
+                self.block(&node.then_branch);
+                if let Some(else_branch) = &node.else_branch {
+                    self.block(else_branch);
+                }
+            }
+            Expression::Identifier(_)
+            | Expression::IntegerLiteral(_)
+            | Expression::FloatLiteral(_)
+            | Expression::StringLiteral(_)
+            | Expression::CharLiteral(_)
+            | Expression::BooleanLiteral(_) => {}
+        }
+    }
+
+    fn block(&mut self, block: &BlockExpression) {
+        self.statements(&block.statements);
+        if let Some(expression) = &block.final_expression {
+            self.expression(expression);
+        }
+    }
+}
+
+/// Renames the declarations and uses of functions, structs and global variables of a file to
+/// the names they have in the merged program.
+///
+/// Names missing from the maps are left alone, they refer to items of the entry script or the
+/// host. Local variables and nested functions shadowing a global are left alone as well.
+#[derive(Default)]
+pub(super) struct Qualify {
+    pub(super) functions: HashMap<String, String>,
+    pub(super) structs: HashMap<String, String>,
+    pub(super) globals: HashMap<String, String>,
+    /// Local variables in scope at the current position, innermost scope last.
+    scopes: Vec<HashSet<String>>,
+    /// Nested functions in scope at the current position, innermost scope last. Function bodies
+    /// see the ones around their declaration.
+    nested: Vec<HashSet<String>>,
+}
+
+impl Qualify {
+    pub(super) fn statements(&mut self, statements: &mut [Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::VariableDeclaration(declaration) => {
+                if let Some(declared_type) = &mut declaration.declared_type {
+                    rename(declared_type, &self.structs);
+                }
+                self.expression(&mut declaration.initializer);
+                // Declared after the initializer, which still sees a shadowed variable
+                match self.scopes.last_mut() {
+                    Some(scope) => {
+                        scope.insert(declaration.identifier.name.clone());
+                    }
+                    None => rename(&mut declaration.identifier, &self.globals),
+                }
+            }
+            Statement::FunctionDeclaration(declaration) => self.function(declaration),
+            Statement::StructDeclaration(declaration) => self.struct_declaration(declaration),
+            Statement::ExpressionStatement(statement) => self.expression(&mut statement.expression),
+            Statement::ReturnStatement(statement) => {
+                if let Some(value) = &mut statement.value {
+                    self.expression(value);
+                }
+            }
+            Statement::BreakStatement(statement) => {
+                if let Some(value) = &mut statement.value {
+                    self.expression(value);
+                }
+            }
+            // Imports below the top level are reported by the type checker
+            Statement::UseDeclaration(_) | Statement::Error(_) => {}
+        }
+    }
+
+    fn function(&mut self, declaration: &mut FunctionDeclaration) {
+        if self.nested.is_empty() {
+            rename(&mut declaration.identifier, &self.functions);
+        }
+        for parameter in &mut declaration.parameters {
+            rename(&mut parameter.declared_type, &self.structs);
+        }
+        rename(&mut declaration.return_type, &self.structs);
+
+        // Functions only see globals and their own parameters, not the locals around them
+        let parameters = declaration
+            .parameters
+            .iter()
+            .map(|parameter| parameter.identifier.name.clone())
+            .collect();
+        let enclosing = std::mem::replace(&mut self.scopes, vec![parameters]);
+        self.nested.push(nested_functions(&declaration.body));
+        self.statements(&mut declaration.body);
+        self.nested.pop();
+        self.scopes = enclosing;
+    }
+
+    fn struct_declaration(&mut self, declaration: &mut StructDeclaration) {
+        match declaration {
+            StructDeclaration::NamedStruct {
+                identifier, fields, ..
+            } => {
+                rename(identifier, &self.structs);
+                for field in fields {
+                    rename(&mut field.declared_type, &self.structs);
+                }
+            }
+            StructDeclaration::TupleStruct {
+                identifier, fields, ..
+            } => {
+                rename(identifier, &self.structs);
+                for field in fields {
+                    rename(&mut field.declared_type, &self.structs);
+                }
+            }
+            StructDeclaration::UnitStruct { identifier, .. } => {
+                rename(identifier, &self.structs);
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &mut Expression) {
+        match expression {
+            Expression::BinaryOp(node) => {
+                self.expression(&mut node.left);
+                self.expression(&mut node.right);
+            }
+            Expression::UnaryOp(node) => self.expression(&mut node.operand),
+            Expression::FunctionCall(node) => {
+                let nested = self
+                    .nested
+                    .iter()
+                    .any(|scope| scope.contains(&node.function_name.name));
+                if !nested {
+                    rename(&mut node.function_name, &self.functions);
+                }
+                for argument in &mut node.arguments {
+                    self.expression(argument);
+                }
+            }
+            Expression::FieldAccess(node) => self.expression(&mut node.object),
+            Expression::MethodCall(node) => {
+                self.expression(&mut node.receiver);
+                for argument in &mut node.arguments {
+                    self.expression(argument);
+                }
+            }
+            Expression::BlockExpression(node) => self.block(node),
+            Expression::IfExpression(node) => {
+                self.expression(&mut node.condition);
+                self.block(&mut node.then_branch);
+                if let Some(else_branch) = &mut node.else_branch {
+                    self.block(else_branch);
+                }
+            }
+            Expression::Identifier(identifier) => {
+                let local = self
+                    .scopes
+                    .iter()
+                    .any(|scope| scope.contains(&identifier.name));
+                if !local {
+                    rename(identifier, &self.globals);
+                }
+            }
+            Expression::IntegerLiteral(_)
+            | Expression::FloatLiteral(_)
+            | Expression::StringLiteral(_)
+            | Expression::CharLiteral(_)
+            | Expression::BooleanLiteral(_) => {}
+        }
+    }
+
+    fn block(&mut self, block: &mut BlockExpression) {
+        self.scopes.push(HashSet::new());
+        self.nested.push(nested_functions(&block.statements));
+        self.statements(&mut block.statements);
+        if let Some(expression) = &mut block.final_expression {
+            self.expression(expression);
+        }
+        self.nested.pop();
+        self.scopes.pop();
+    }
+}
+
+/// The names of the functions a statement list declares.
+fn nested_functions(statements: &[Statement]) -> HashSet<String> {
+    statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::FunctionDeclaration(declaration) => {
+                Some(declaration.identifier.name.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn rename(identifier: &mut Identifier, names: &HashMap<String, String>) {
+    if let Some(name) = names.get(&identifier.name) {
+        identifier.name = name.clone();
+    }
+}
@@ -0,0 +1,32 @@
+use super::{Pass, walk_expression};
+use crate::parser::ast::{BlockExpression, Expression};
+
+/// Replaces `if` expressions whose condition is a boolean literal by the branch that is taken.
+///
+/// The branch stays a block, so the variables declared in it keep their scope.
+pub(super) struct RemoveDeadBranches;
+
+impl Pass for RemoveDeadBranches {
+    fn expression(&mut self, expression: &mut Expression) {
+        walk_expression(self, expression);
+
+        let Expression::IfExpression(node) = expression else {
+            return;
+        };
+        let Expression::BooleanLiteral(condition) = node.condition.as_ref() else {
+            return;
+        };
+        trace!("Removing dead branch of the `if` at {}", node.span);
+        let taken = if condition.value {
+            node.then_branch.clone()
+        } else {
+            node.else_branch.clone().unwrap_or_else(|| BlockExpression {
+                statements: Vec::new(),
+                final_expression: None,
+                inferred_type: node.inferred_type.clone(),
+                span: node.span,
+            })
+        };
+        *expression = Expression::BlockExpression(taken);
+    }
+}
@@ -0,0 +1,130 @@
+use super::{Pass, walk_expression};
+use crate::{
+    core::span::{Span, Spanned},
+    parser::ast::{
+        BinaryOperator, BooleanLiteral, Expression, FloatLiteral, Identifier, IntegerLiteral,
+        UnaryOp, UnaryOperator,
+    },
+    runtime::{Value, float_literal_value, integer_literal_value},
+};
+
+/// Evaluates operations whose operands are literals, bottom up so nested operations collapse
+/// into a single literal.
+///
+/// Operations that fail, like an overflowing addition, are left alone to fail at runtime.
+pub(super) struct FoldConstants;
+
+impl Pass for FoldConstants {
+    fn expression(&mut self, expression: &mut Expression) {
+        walk_expression(self, expression);
+        if let Some(folded) = fold(expression) {
+            trace!("Folded constant expression at {}", folded.span());
+            *expression = folded;
+        }
+    }
+}
+
+fn fold(expression: &Expression) -> Option<Expression> {
+    match expression {
+        Expression::BinaryOp(node) => match node.operator {
+            // Only the left operand needs to be known to short circuit
+            BinaryOperator::And | BinaryOperator::Or => {
+                let Value::Bool(left) = constant(&node.left)? else {
+                    return None;
+                };
+                match (&node.operator, left) {
+                    (BinaryOperator::And, false) | (BinaryOperator::Or, true) => {
+                        literal(Value::Bool(left), node.span)
+                    }
+                    _ => Some(node.right.as_ref().clone()),
+                }
+            }
+            _ => {
+                let left = constant(&node.left)?;
+                let right = constant(&node.right)?;
+                let value = left.binary_operation(&node.operator, &right).ok()?;
+                literal(value, node.span)
+            }
+        },
+        Expression::UnaryOp(node) => {
+            // Negated integer literals are how negative integers are written, nothing to fold
+            if let (UnaryOperator::Negate, Expression::IntegerLiteral(_)) =
+                (&node.operator, node.operand.as_ref())
+            {
+                return None;
+            }
+            let operand = constant(&node.operand)?;
+            let value = match node.operator {
+                UnaryOperator::Negate => operand.negate(),
+                UnaryOperator::Not => operand.not(),
+            };
+            literal(value.ok()?, node.span)
+        }
+        _ => None,
+    }
+}
+
+/// The value of a literal, including negated integer literals.
+fn constant(expression: &Expression) -> Option<Value> {
+    match expression {
+        Expression::IntegerLiteral(literal) => Some(integer_literal_value(literal, false)),
+        Expression::UnaryOp(UnaryOp {
+            operator: UnaryOperator::Negate,
+            operand,
+            ..
+        }) => match operand.as_ref() {
+            Expression::IntegerLiteral(literal) => Some(integer_literal_value(literal, true)),
+            _ => None,
+        },
+        Expression::FloatLiteral(literal) => Some(float_literal_value(literal)),
+        Expression::BooleanLiteral(literal) => Some(Value::Bool(literal.value)),
+        _ => None,
+    }
+}
+
+/// The literal evaluating to `value`, typed like the checker would have typed it.
+fn literal(value: Value, span: Span) -> Option<Expression> {
+    let ty = Some(Identifier {
+        name: value.ty().to_string(),
+        span,
+    });
+    let expression = match value {
+        Value::F32(float) => FloatLiteral {
+            value: float.into(),
+            suffix: None,
+            inferred_type: ty,
+            span,
+        }
+        .into(),
+        Value::F64(float) => FloatLiteral {
+            value: float,
+            suffix: None,
+            inferred_type: ty,
+            span,
+        }
+        .into(),
+        Value::Bool(value) => BooleanLiteral { value, span }.into(),
+        value => {
+            let integer = value.as_i128()?;
+            let magnitude = IntegerLiteral {
+                value: integer.unsigned_abs() as u64,
+                suffix: None,
+                inferred_type: ty.clone(),
+                span,
+            }
+            .into();
+            if integer < 0 {
+                UnaryOp {
+                    operator: UnaryOperator::Negate,
+                    operand: Box::new(magnitude),
+                    span,
+                    inferred_type: ty,
+                }
+                .into()
+            } else {
+                magnitude
+            }
+        }
+    };
+    Some(expression)
+}
@@ -0,0 +1,254 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{Pass, walk_block, walk_expression, walk_statement};
+use crate::{
+    core::span::Spanned,
+    parser::ast::{
+        BlockExpression, Expression, FunctionDeclaration, Statement, UnaryOp, UnaryOperator,
+    },
+};
+
+/// Functions whose body is larger than this many expression nodes are not inlined.
+const MAX_INLINED_SIZE: usize = 12;
+
+/// Replaces calls to tiny functions, whose body is a single `return` of a simple expression, by
+/// that expression with the parameters replaced by the arguments.
+///
+/// Only calls whose arguments are literals or variables are inlined, so no argument is evaluated
+/// in a different order or a different number of times than in the call.
+pub(super) struct InlineCalls {
+    /// Inlinable functions declared at the top level of the program, by name.
+    candidates: HashMap<String, FunctionDeclaration>,
+    /// Local variables in scope at the current position, innermost scope last. The globals a
+    /// function body refers to must not be shadowed by them at the call site.
+    scopes: Vec<HashSet<String>>,
+}
+
+impl InlineCalls {
+    pub(super) fn new(statements: &[Statement]) -> Self {
+        let mut nested = NestedFunctions::default();
+        nested.statements(&mut statements.to_vec());
+
+        let candidates = statements
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::FunctionDeclaration(declaration) => Some(declaration),
+                _ => None,
+            })
+            // Calls refer to a nested function of the same name where it is in scope, which may be
+            // the case at the call site or, for the calls in the body, after inlining
+            .filter(|declaration| !nested.names.contains(&declaration.identifier.name))
+            .filter(|declaration| {
+                inlined_body(declaration).is_some_and(|body| !calls_any(body, &nested.names))
+            })
+            .map(|declaration| (declaration.identifier.name.clone(), declaration.clone()))
+            .collect();
+
+        InlineCalls {
+            candidates,
+            scopes: Vec::new(),
+        }
+    }
+
+    /// The body of the function called by `expression` with the arguments substituted, if the
+    /// call can be inlined here.
+    fn inline(&self, expression: &Expression) -> Option<Expression> {
+        let Expression::FunctionCall(call) = expression else {
+            return None;
+        };
+        let function = self.candidates.get(&call.function_name.name)?;
+        if !call.arguments.iter().all(is_trivial) {
+            return None;
+        }
+
+        let mut body = inlined_body(function)?.clone();
+        let arguments: HashMap<_, _> = function
+            .parameters
+            .iter()
+            .map(|parameter| parameter.identifier.name.as_str())
+            .zip(&call.arguments)
+            .collect();
+        let mut free = Vec::new();
+        substitute(&mut body, &arguments, &mut free);
+        if free.iter().any(|name| self.is_local(name)) {
+            return None;
+        }
+        Some(body)
+    }
+
+    fn is_local(&self, name: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.contains(name))
+    }
+}
+
+impl Pass for InlineCalls {
+    fn statement(&mut self, statement: &mut Statement) {
+        walk_statement(self, statement);
+        // Declared after the initializer, which cannot refer to the variable itself
+        if let (Statement::VariableDeclaration(declaration), Some(scope)) =
+            (statement, self.scopes.last_mut())
+        {
+            scope.insert(declaration.identifier.name.clone());
+        }
+    }
+
+    fn function(&mut self, declaration: &mut FunctionDeclaration) {
+        // Functions only see globals and their own parameters, not the locals around them
+        let parameters = declaration
+            .parameters
+            .iter()
+            .map(|parameter| parameter.identifier.name.clone())
+            .collect();
+        let enclosing = std::mem::replace(&mut self.scopes, vec![parameters]);
+        self.statements(&mut declaration.body);
+        self.scopes = enclosing;
+    }
+
+    fn block(&mut self, block: &mut BlockExpression) {
+        self.scopes.push(HashSet::new());
+        walk_block(self, block);
+        self.scopes.pop();
+    }
+
+    fn expression(&mut self, expression: &mut Expression) {
+        walk_expression(self, expression);
+        if let Some(inlined) = self.inline(expression) {
+            trace!("Inlined a call at {}", expression.span());
+            *expression = inlined;
+        }
+    }
+}
+
+/// Collects the names of the functions declared below the top level.
+#[derive(Default)]
+struct NestedFunctions {
+    names: HashSet<String>,
+    /// Whether the current position is inside a function body or a block.
+    nested: bool,
+}
+
+impl Pass for NestedFunctions {
+    fn function(&mut self, declaration: &mut FunctionDeclaration) {
+        if self.nested {
+            self.names.insert(declaration.identifier.name.clone());
+        }
+        let enclosing = std::mem::replace(&mut self.nested, true);
+        self.statements(&mut declaration.body);
+        self.nested = enclosing;
+    }
+
+    fn block(&mut self, block: &mut BlockExpression) {
+        let enclosing = std::mem::replace(&mut self.nested, true);
+        walk_block(self, block);
+        self.nested = enclosing;
+    }
+}
+
+/// The returned expression of a function consisting of a single small `return`.
+fn inlined_body(function: &FunctionDeclaration) -> Option<&Expression> {
+    let [Statement::ReturnStatement(statement)] = function.body.as_slice() else {
+        return None;
+    };
+    let body = statement.value.as_ref()?;
+    let size = simple_size(body)?;
+    (size <= MAX_INLINED_SIZE).then_some(body)
+}
+
+/// The number of nodes of an expression without blocks, which could declare variables capturing
+/// the substituted arguments.
+fn simple_size(expression: &Expression) -> Option<usize> {
+    let children: Vec<&Expression> = match expression {
+        Expression::BinaryOp(node) => vec![&node.left, &node.right],
+        Expression::UnaryOp(node) => vec![&node.operand],
+        Expression::FunctionCall(node) => node.arguments.iter().collect(),
+        Expression::FieldAccess(node) => vec![&node.object],
+        Expression::MethodCall(node) => std::iter::once(node.receiver.as_ref())
+            .chain(&node.arguments)
+            .collect(),
+        Expression::BlockExpression(_) | Expression::IfExpression(_) => return None,
+        Expression::Identifier(_)
+        | Expression::IntegerLiteral(_)
+        | Expression::FloatLiteral(_)
+        | Expression::StringLiteral(_)
+        | Expression::CharLiteral(_)
+        | Expression::BooleanLiteral(_) => Vec::new(),
+    };
+    children
+        .into_iter()
+        .try_fold(1, |size, child| Some(size + simple_size(child)?))
+}
+
+/// Whether a simple expression calls a function named like one of `names`.
+fn calls_any(expression: &Expression, names: &HashSet<String>) -> bool {
+    match expression {
+        Expression::FunctionCall(node) => {
+            names.contains(&node.function_name.name)
+                || node
+                    .arguments
+                    .iter()
+                    .any(|argument| calls_any(argument, names))
+        }
+        Expression::BinaryOp(node) => calls_any(&node.left, names) || calls_any(&node.right, names),
+        Expression::UnaryOp(node) => calls_any(&node.operand, names),
+        Expression::FieldAccess(node) => calls_any(&node.object, names),
+        Expression::MethodCall(node) => {
+            calls_any(&node.receiver, names)
+                || node
+                    .arguments
+                    .iter()
+                    .any(|argument| calls_any(argument, names))
+        }
+        _ => false,
+    }
+}
+
+/// Arguments that can be evaluated anywhere, any number of times, with the same result.
+fn is_trivial(expression: &Expression) -> bool {
+    match expression {
+        Expression::Identifier(_)
+        | Expression::IntegerLiteral(_)
+        | Expression::FloatLiteral(_)
+        | Expression::StringLiteral(_)
+        | Expression::CharLiteral(_)
+        | Expression::BooleanLiteral(_) => true,
+        Expression::UnaryOp(UnaryOp {
+            operator: UnaryOperator::Negate,
+            operand,
+            ..
+        }) => matches!(operand.as_ref(), Expression::IntegerLiteral(_)),
+        _ => false,
+    }
+}
+
+/// Replaces the parameters in a simple expression by the arguments, collecting the names of the
+/// other variables it refers to, which are globals.
+fn substitute(
+    expression: &mut Expression,
+    arguments: &HashMap<&str, &Expression>,
+    free: &mut Vec<String>,
+) {
+    match expression {
+        Expression::Identifier(identifier) => match arguments.get(identifier.name.as_str()) {
+            Some(argument) => *expression = (*argument).clone(),
+            None => free.push(identifier.name.clone()),
+        },
+        Expression::BinaryOp(node) => {
+            substitute(&mut node.left, arguments, free);
+            substitute(&mut node.right, arguments, free);
+        }
+        Expression::UnaryOp(node) => substitute(&mut node.operand, arguments, free),
+        Expression::FunctionCall(node) => {
+            for argument in &mut node.arguments {
+                substitute(argument, arguments, free);
+            }
+        }
+        Expression::FieldAccess(node) => substitute(&mut node.object, arguments, free),
+        Expression::MethodCall(node) => {
+            substitute(&mut node.receiver, arguments, free);
+            for argument in &mut node.arguments {
+                substitute(argument, arguments, free);
+            }
+        }
+        _ => {}
+    }
+}
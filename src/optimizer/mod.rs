@@ -0,0 +1,185 @@
+//! # Optimizer
+//!
+//! Rewrites type checked programs into cheaper but equivalent ones before they are executed.
+//! Every pass relies on the types inferred by the [`TypeChecker`](crate::TypeChecker) and keeps
+//! the spans of the code it rewrites, so errors raised by optimized code point at the same
+//! source as without optimizations.
+
+use crate::parser::ast::{BlockExpression, Expression, FunctionDeclaration, Program, Statement};
+
+mod branches;
+mod fold;
+mod inline;
+mod unreachable;
+
+/// The passes to run, all of them can be toggled independently.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct OptimizerConfig {
+    /// Evaluates operations on integer, float and boolean literals at compile time.
+    pub constant_folding: bool,
+    /// Replaces `if` expressions with a constant condition by the branch that is taken.
+    pub dead_branches: bool,
+    /// Removes the statements following a `return`.
+    pub unreachable_code: bool,
+    /// Replaces calls to functions that only return a small expression by that expression.
+    ///
+    /// Inlined calls keep the body the function had when the program was optimized, so this is
+    /// off by default: a runtime executing several scripts lets later ones redeclare functions.
+    pub inlining: bool,
+}
+
+impl OptimizerConfig {
+    /// Every pass enabled.
+    pub fn all() -> Self {
+        OptimizerConfig {
+            inlining: true,
+            ..Self::default()
+        }
+    }
+
+    /// Every pass disabled, programs are executed as written.
+    pub fn none() -> Self {
+        OptimizerConfig {
+            constant_folding: false,
+            dead_branches: false,
+            unreachable_code: false,
+            inlining: false,
+        }
+    }
+}
+
+impl Default for OptimizerConfig {
+    fn default() -> Self {
+        OptimizerConfig {
+            constant_folding: true,
+            dead_branches: true,
+            unreachable_code: true,
+            inlining: false,
+        }
+    }
+}
+
+/// Runs the enabled passes over a type checked program.
+pub fn optimize_program(program: &mut Program, config: &OptimizerConfig) {
+    trace!("Optimizing program with {:?}", config);
+    // Inlining first exposes the arguments of inlined calls to constant folding, which in turn
+    // turns conditions into literals for dead branch elimination
+    if config.inlining {
+        inline::InlineCalls::new(&program.statements).statements(&mut program.statements);
+    }
+    if config.constant_folding {
+        fold::FoldConstants.statements(&mut program.statements);
+    }
+    if config.dead_branches {
+        branches::RemoveDeadBranches.statements(&mut program.statements);
+    }
+    if config.unreachable_code {
+        unreachable::RemoveUnreachableCode.statements(&mut program.statements);
+    }
+}
+
+/// Runs the enabled passes over a type checked expression. There are no function declarations
+/// to inline outside of a program.
+pub fn optimize_expression(expression: &mut Expression, config: &OptimizerConfig) {
+    if config.constant_folding {
+        fold::FoldConstants.expression(expression);
+    }
+    if config.dead_branches {
+        branches::RemoveDeadBranches.expression(expression);
+    }
+    if config.unreachable_code {
+        unreachable::RemoveUnreachableCode.expression(expression);
+    }
+}
+
+// -- Traversal --
+
+/// A rewrite of the AST. Every method visits the children of its node by default, passes
+/// override the ones for the nodes they rewrite.
+trait Pass {
+    fn statements(&mut self, statements: &mut Vec<Statement>) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &mut Statement) {
+        walk_statement(self, statement);
+    }
+
+    fn function(&mut self, declaration: &mut FunctionDeclaration) {
+        self.statements(&mut declaration.body);
+    }
+
+    fn block(&mut self, block: &mut BlockExpression) {
+        walk_block(self, block);
+    }
+
+    fn expression(&mut self, expression: &mut Expression) {
+        walk_expression(self, expression);
+    }
+}
+
+fn walk_statement<P: Pass + ?Sized>(pass: &mut P, statement: &mut Statement) {
+    match statement {
+        Statement::VariableDeclaration(declaration) => {
+            pass.expression(&mut declaration.initializer)
+        }
+        Statement::FunctionDeclaration(declaration) => pass.function(declaration),
+        Statement::ExpressionStatement(statement) => pass.expression(&mut statement.expression),
+        Statement::ReturnStatement(statement) => {
+            if let Some(value) = &mut statement.value {
+                pass.expression(value);
+            }
+        }
+        Statement::BreakStatement(statement) => {
+            if let Some(value) = &mut statement.value {
+                pass.expression(value);
+            }
+        }
+        Statement::StructDeclaration(_) | Statement::UseDeclaration(_) | Statement::Error(_) => {}
+    }
+}
+
+fn walk_block<P: Pass + ?Sized>(pass: &mut P, block: &mut BlockExpression) {
+    pass.statements(&mut block.statements);
+    if let Some(expression) = &mut block.final_expression {
+        pass.expression(expression);
+    }
+}
+
+fn walk_expression<P: Pass + ?Sized>(pass: &mut P, expression: &mut Expression) {
+    match expression {
+        Expression::BinaryOp(node) => {
+            pass.expression(&mut node.left);
+            pass.expression(&mut node.right);
+        }
+        Expression::UnaryOp(node) => pass.expression(&mut node.operand),
+        Expression::FunctionCall(node) => {
+            for argument in &mut node.arguments {
+                pass.expression(argument);
+            }
+        }
+        Expression::FieldAccess(node) => pass.expression(&mut node.object),
+        Expression::MethodCall(node) => {
+            pass.expression(&mut node.receiver);
+            for argument in &mut node.arguments {
+                pass.expression(argument);
+            }
+        }
+        Expression::BlockExpression(node) => pass.block(node),
+        Expression::IfExpression(node) => {
+            pass.expression(&mut node.condition);
+            pass.block(&mut node.then_branch);
+            if let Some(else_branch) = &mut node.else_branch {
+                pass.block(else_branch);
+            }
+        }
+        Expression::Identifier(_)
+        | Expression::IntegerLiteral(_)
+        | Expression::FloatLiteral(_)
+        | Expression::StringLiteral(_)
+        | Expression::CharLiteral(_)
+        | Expression::BooleanLiteral(_) => {}
+    }
+}
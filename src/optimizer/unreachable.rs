@@ -0,0 +1,51 @@
+use super::{Pass, walk_block};
+use crate::{
+    core::span::Spanned,
+    parser::ast::{BlockExpression, Statement},
+};
+
+/// Removes the statements following a `return` in the same statement list, and the final
+/// expression of a block that returns before reaching it.
+///
+/// Function and struct declarations are kept, they are declared before any statement of their
+/// list runs and can be used by the statements before the `return`.
+pub(super) struct RemoveUnreachableCode;
+
+impl Pass for RemoveUnreachableCode {
+    fn statements(&mut self, statements: &mut Vec<Statement>) {
+        if let Some(index) = statements
+            .iter()
+            .position(|statement| matches!(statement, Statement::ReturnStatement(_)))
+        {
+            let unreachable = statements.split_off(index + 1);
+            trace!(
+                "Removing code after the `return` at {}",
+                statements[index].span()
+            );
+            statements.extend(unreachable.into_iter().filter(|statement| {
+                matches!(
+                    statement,
+                    Statement::FunctionDeclaration(_) | Statement::StructDeclaration(_)
+                )
+            }));
+        }
+
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn block(&mut self, block: &mut BlockExpression) {
+        walk_block(self, block);
+        let returns = block
+            .statements
+            .iter()
+            .any(|statement| matches!(statement, Statement::ReturnStatement(_)));
+        if returns && block.final_expression.take().is_some() {
+            trace!(
+                "Removed unreachable final expression of the block at {}",
+                block.span
+            );
+        }
+    }
+}
@@ -1,17 +1,26 @@
 use derive_more::{Display, From};
+use serde::Serialize;
 
-use crate::core::span::{Span, Spanned};
+use crate::core::{
+    span::{Span, Spanned},
+    types::{FloatType, IntegerType},
+};
 
-#[derive(Debug, PartialEq, Clone, From)]
+#[derive(Debug, PartialEq, Clone, From, Serialize)]
+#[serde(tag = "kind")]
 pub enum Expression {
     BinaryOp(BinaryOp),
+    UnaryOp(UnaryOp),
     FunctionCall(FunctionCall),
+    FieldAccess(FieldAccess),
+    MethodCall(MethodCall),
     BlockExpression(BlockExpression),
     IfExpression(IfExpression),
     Identifier(Identifier),
     IntegerLiteral(IntegerLiteral),
     FloatLiteral(FloatLiteral),
     StringLiteral(StringLiteral),
+    CharLiteral(CharLiteral),
     BooleanLiteral(BooleanLiteral),
 }
 
@@ -20,49 +29,68 @@ impl Spanned for Expression {
     fn span(&self) -> Span {
         match self {
             Expression::BinaryOp(node) => node.span,
+            Expression::UnaryOp(node) => node.span,
             Expression::FunctionCall(node) => node.span,
+            Expression::FieldAccess(node) => node.span,
+            Expression::MethodCall(node) => node.span,
             Expression::BlockExpression(node) => node.span,
             Expression::IfExpression(node) => node.span,
             Expression::Identifier(node) => node.span,
             Expression::IntegerLiteral(node) => node.span,
             Expression::FloatLiteral(node) => node.span,
             Expression::StringLiteral(node) => node.span,
+            Expression::CharLiteral(node) => node.span,
             Expression::BooleanLiteral(node) => node.span,
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct IntegerLiteral {
-    pub value: i64,
+    /// Magnitude of the literal, negative numbers are expressed through [`UnaryOperator::Negate`].
+    pub value: u64,
+    /// Explicit type suffix, e.g. `u8` in `255u8`.
+    pub suffix: Option<IntegerType>,
+    /// The suffix if present, otherwise chosen by the type checker from the surrounding context.
+    pub inferred_type: Option<Identifier>,
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct FloatLiteral {
     pub value: f64,
+    /// Explicit type suffix, e.g. `f32` in `1.0f32`.
+    pub suffix: Option<FloatType>,
+    /// The suffix if present, otherwise chosen by the type checker from the surrounding context.
+    pub inferred_type: Option<Identifier>,
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct StringLiteral {
     pub value: String,
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct CharLiteral {
+    pub value: char,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct BooleanLiteral {
     pub value: bool,
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Identifier {
     pub name: String,
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct BinaryOp {
     pub operator: BinaryOperator,
     pub left: Box<Expression>,
@@ -71,23 +99,101 @@ pub struct BinaryOp {
     pub inferred_type: Option<Identifier>,
 }
 
-#[derive(Debug, PartialEq, Clone, Display)]
+#[derive(Debug, PartialEq, Clone, Display, Serialize)]
 pub enum BinaryOperator {
     Add,
     Subtract,
     Multiply,
     Divide,
+    Remainder,
     Equals,
     NotEquals,
     LessThan,
     GreaterThan,
+    LessThanOrEqual,
+    GreaterThanOrEqual,
     And,
     Or,
-    // TODO: More operators, for example:
-    // bit operators: << & | ^
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    ShiftLeft,
+    ShiftRight,
+}
+
+impl BinaryOperator {
+    /// The operator as written in source code.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Remainder => "%",
+            BinaryOperator::Equals => "==",
+            BinaryOperator::NotEquals => "!=",
+            BinaryOperator::LessThan => "<",
+            BinaryOperator::GreaterThan => ">",
+            BinaryOperator::LessThanOrEqual => "<=",
+            BinaryOperator::GreaterThanOrEqual => ">=",
+            BinaryOperator::And => "&&",
+            BinaryOperator::Or => "||",
+            BinaryOperator::BitwiseAnd => "&",
+            BinaryOperator::BitwiseOr => "|",
+            BinaryOperator::BitwiseXor => "^",
+            BinaryOperator::ShiftLeft => "<<",
+            BinaryOperator::ShiftRight => ">>",
+        }
+    }
+
+    /// How tightly the operator binds, higher binds tighter. All binary operators are left
+    /// associative.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Or => 1,
+            BinaryOperator::And => 2,
+            BinaryOperator::Equals | BinaryOperator::NotEquals => 3,
+            BinaryOperator::LessThan
+            | BinaryOperator::GreaterThan
+            | BinaryOperator::LessThanOrEqual
+            | BinaryOperator::GreaterThanOrEqual => 4,
+            BinaryOperator::BitwiseOr => 5,
+            BinaryOperator::BitwiseXor => 6,
+            BinaryOperator::BitwiseAnd => 7,
+            BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => 8,
+            BinaryOperator::Add | BinaryOperator::Subtract => 9,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Remainder => 10,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct UnaryOp {
+    pub operator: UnaryOperator,
+    pub operand: Box<Expression>,
+    pub span: Span,
+    pub inferred_type: Option<Identifier>,
+}
+
+#[derive(Debug, PartialEq, Clone, Display, Serialize)]
+pub enum UnaryOperator {
+    /// `-`
+    Negate,
+    /// `!`, logical not for `bool` and bitwise not for integers
+    Not,
+}
+
+impl UnaryOperator {
+    /// The operator as written in source code.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            UnaryOperator::Negate => "-",
+            UnaryOperator::Not => "!",
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct FunctionCall {
     pub function_name: Identifier,
     pub arguments: Vec<Expression>,
@@ -95,7 +201,26 @@ pub struct FunctionCall {
     pub inferred_type: Option<Identifier>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// `object.field`
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct FieldAccess {
+    pub object: Box<Expression>,
+    pub field: Identifier,
+    pub span: Span,
+    pub inferred_type: Option<Identifier>,
+}
+
+/// `receiver.method(arguments)`
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct MethodCall {
+    pub receiver: Box<Expression>,
+    pub method: Identifier,
+    pub arguments: Vec<Expression>,
+    pub span: Span,
+    pub inferred_type: Option<Identifier>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct BlockExpression {
     pub statements: Vec<Statement>,
     pub final_expression: Option<Box<Expression>>,
@@ -103,7 +228,7 @@ pub struct BlockExpression {
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct IfExpression {
     pub condition: Box<Expression>,
     pub then_branch: BlockExpression,
@@ -113,14 +238,18 @@ pub struct IfExpression {
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone, From)]
+#[derive(Debug, PartialEq, Clone, From, Serialize)]
+#[serde(tag = "kind")]
 pub enum Statement {
     VariableDeclaration(VariableDeclaration),
     FunctionDeclaration(FunctionDeclaration),
     StructDeclaration(StructDeclaration),
+    UseDeclaration(UseDeclaration),
     ExpressionStatement(ExpressionStatement),
     ReturnStatement(ReturnStatement),
     BreakStatement(BreakStatement),
+    /// Placeholder for a statement the parser could not make sense of.
+    Error(ErrorStatement),
 }
 
 impl Spanned for Statement {
@@ -133,23 +262,38 @@ impl Spanned for Statement {
                 StructDeclaration::TupleStruct { span, .. } => span,
                 StructDeclaration::UnitStruct { span, .. } => span,
             },
+            Statement::UseDeclaration(node) => node.span,
             Statement::ExpressionStatement(node) => node.span,
             Statement::ReturnStatement(node) => node.span,
             Statement::BreakStatement(node) => node.span,
+            Statement::Error(node) => node.span,
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct VariableDeclaration {
     pub identifier: Identifier,
+    /// Optional type annotation, e.g. `u8` in `let x: u8 = 5;`
+    pub declared_type: Option<Identifier>,
     pub initializer: Expression,
-    // TODO: Add type annotation
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// Whether an item can be imported by other modules.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    #[default]
+    Private,
+    /// `pub`
+    Public,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct FunctionDeclaration {
+    pub attributes: Vec<Attribute>,
+    pub visibility: Visibility,
     pub identifier: Identifier,
     pub parameters: Vec<Parameter>,
     pub return_type: Identifier,
@@ -157,31 +301,73 @@ pub struct FunctionDeclaration {
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+impl FunctionDeclaration {
+    /// Whether the function is marked with `#[test]`.
+    pub fn is_test(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|attribute| attribute.name.name == TEST_ATTRIBUTE)
+    }
+}
+
+/// The name of the attribute marking test functions.
+pub const TEST_ATTRIBUTE: &str = "test";
+
+/// `#[test]` in front of a function declaration.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Attribute {
+    pub name: Identifier,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Parameter {
     pub identifier: Identifier,
     pub declared_type: Identifier,
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[allow(clippy::enum_variant_names)]
+#[serde(tag = "kind")]
 pub enum StructDeclaration {
     NamedStruct {
+        visibility: Visibility,
         identifier: Identifier,
         fields: Vec<NamedFieldDeclaration>,
         span: Span,
     },
     TupleStruct {
+        visibility: Visibility,
         identifier: Identifier,
         fields: Vec<TupleFieldDeclaration>,
         span: Span,
     },
     UnitStruct {
+        visibility: Visibility,
         identifier: Identifier,
         span: Span,
     },
 }
 
+impl StructDeclaration {
+    pub fn identifier(&self) -> &Identifier {
+        match self {
+            StructDeclaration::NamedStruct { identifier, .. }
+            | StructDeclaration::TupleStruct { identifier, .. }
+            | StructDeclaration::UnitStruct { identifier, .. } => identifier,
+        }
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        match self {
+            StructDeclaration::NamedStruct { visibility, .. }
+            | StructDeclaration::TupleStruct { visibility, .. }
+            | StructDeclaration::UnitStruct { visibility, .. } => *visibility,
+        }
+    }
+}
+
 impl Spanned for StructDeclaration {
     fn span(&self) -> Span {
         match self {
@@ -192,38 +378,64 @@ impl Spanned for StructDeclaration {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct NamedFieldDeclaration {
     pub identifier: Identifier,
     pub declared_type: Identifier,
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct TupleFieldDeclaration {
     pub declared_type: Identifier,
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// `use math::vec::Vector2D;`
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct UseDeclaration {
+    /// The path of the module followed by the name of the imported item, always at least two
+    /// segments.
+    pub path: Vec<Identifier>,
+    pub span: Span,
+}
+
+impl UseDeclaration {
+    /// The segments naming the module the item is imported from.
+    pub fn module(&self) -> &[Identifier] {
+        &self.path[..self.path.len() - 1]
+    }
+
+    /// The name of the imported item.
+    pub fn item(&self) -> &Identifier {
+        &self.path[self.path.len() - 1]
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct ExpressionStatement {
     pub expression: Expression,
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct ReturnStatement {
     pub value: Option<Expression>,
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct BreakStatement {
     pub value: Option<Expression>,
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct ErrorStatement {
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Program {
     pub statements: Vec<Statement>,
     pub span: Span,
@@ -0,0 +1,178 @@
+use std::{fmt, rc::Rc};
+
+use super::kind::SyntaxKind;
+
+/// An immutable leaf of the green tree, owning its text.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GreenToken {
+    kind: SyntaxKind,
+    text: String,
+}
+
+impl GreenToken {
+    pub fn new(kind: SyntaxKind, text: impl Into<String>) -> Self {
+        GreenToken {
+            kind,
+            text: text.into(),
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn width(&self) -> usize {
+        self.text.len()
+    }
+}
+
+/// An immutable inner node of the green tree.
+///
+/// Green nodes only know their width, not their position, so identical subtrees can be shared
+/// between trees, e.g. between the trees before and after an edit.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+    width: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+        let width = children.iter().map(GreenElement::width).sum();
+        GreenNode {
+            kind,
+            width,
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    /// Length of the source text covered by the node in bytes.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+}
+
+/// Writes the exact source text the node was built from.
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => write!(f, "{}", node)?,
+                GreenElement::Token(token) => write!(f, "{}", token.text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            GreenElement::Node(node) => node.kind,
+            GreenElement::Token(token) => token.kind,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.width,
+            GreenElement::Token(token) => token.width(),
+        }
+    }
+}
+
+/// A position in a [`GreenNodeBuilder`] to which a node can be started later on.
+///
+/// Used for left-recursive constructs like binary expressions, where the node kind is only
+/// known after the left operand has been parsed.
+#[derive(Debug, Clone, Copy)]
+pub struct Checkpoint(usize);
+
+/// Builds a [`GreenNode`] from a flat sequence of start, token and finish events.
+#[derive(Debug, Default)]
+pub struct GreenNodeBuilder {
+    /// Kinds of the nodes currently open and the index of their first child in `children`.
+    parents: Vec<(SyntaxKind, usize)>,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNodeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start_node(&mut self, kind: SyntaxKind) {
+        self.parents.push((kind, self.children.len()));
+    }
+
+    pub fn token(&mut self, kind: SyntaxKind, text: &str) {
+        self.children
+            .push(GreenElement::Token(Rc::new(GreenToken::new(kind, text))));
+    }
+
+    pub fn finish_node(&mut self) {
+        let (kind, first_child) = self.parents.pop().expect("no node to finish");
+        let children = self.children.split_off(first_child);
+        self.children
+            .push(GreenElement::Node(Rc::new(GreenNode::new(kind, children))));
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.children.len())
+    }
+
+    /// Starts a node containing everything added since `checkpoint`.
+    pub fn start_node_at(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        let Checkpoint(first_child) = checkpoint;
+        debug_assert!(
+            first_child <= self.children.len(),
+            "checkpoint is no longer valid"
+        );
+        if let Some(&(_, parent_first_child)) = self.parents.last() {
+            debug_assert!(
+                first_child >= parent_first_child,
+                "checkpoint lies before the current node"
+            );
+        }
+        self.parents.push((kind, first_child));
+    }
+
+    /// The kind of the element added last.
+    pub fn last_kind(&self) -> Option<SyntaxKind> {
+        self.children.last().map(GreenElement::kind)
+    }
+
+    /// Number of nodes currently open.
+    pub fn depth(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// Returns the root node, every started node must have been finished.
+    pub fn finish(mut self) -> GreenNode {
+        assert!(self.parents.is_empty(), "unfinished nodes");
+        assert_eq!(self.children.len(), 1, "expected exactly one root node");
+        match self.children.pop() {
+            Some(GreenElement::Node(node)) => Rc::unwrap_or_clone(node),
+            _ => panic!("the root must be a node"),
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use derive_more::Display;
+
+use crate::parser::lexer::Token;
+
+/// The kind of a token or node in the concrete syntax tree.
+///
+/// Token kinds mirror the [`Token`]s of the lexer without their payload, plus the trivia the
+/// lexer skips. Node kinds describe the syntactic constructs built from them.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Display)]
+pub enum SyntaxKind {
+    // -- Trivia --
+    /// Spaces, tabs and newlines.
+    Whitespace,
+    /// A `//` line comment, without the line terminator.
+    Comment,
+    /// Text the lexer could not turn into a token.
+    ErrorToken,
+
+    // -- Keywords --
+    True,
+    False,
+    Let,
+    Mut,
+    Type,
+    Struct,
+    Fn,
+    While,
+    Loop,
+    For,
+    If,
+    Else,
+    Return,
+    Pub,
+    Use,
+
+    // -- Operators --
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Assign,
+    Equals,
+    NotEquals,
+    LessThan,
+    GreaterThan,
+    LessThanOrEqual,
+    GreaterThanOrEqual,
+    And,
+    Or,
+    Bang,
+    Ampersand,
+    Pipe,
+    Caret,
+    ShiftLeft,
+    ShiftRight,
+
+    // -- Delimiters --
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Semicolon,
+    Colon,
+    Comma,
+    Period,
+    RightArrow,
+    PathSeparator,
+    Hash,
+
+    // -- Identifier and Literals --
+    Identifier,
+    IntegerLiteral,
+    FloatLiteral,
+    String,
+    Char,
+
+    // -- Nodes --
+    /// The root node, spans the whole source including leading and trailing trivia.
+    Program,
+    /// `let x: T = value;`
+    VariableDeclaration,
+    /// `: T` of a variable declaration.
+    TypeAnnotation,
+    /// `fn name(parameters) -> T { body }`, optionally preceded by attributes and `pub`
+    FunctionDeclaration,
+    /// `#[name]` of a function declaration.
+    Attribute,
+    /// `(a: T, b: U)`
+    ParameterList,
+    /// `a: T`
+    Parameter,
+    /// `-> T`
+    ReturnType,
+    /// `{ statements }` of a function, unlike a block it has no final expression.
+    FunctionBody,
+    /// `struct Name { ... }`, `struct Name(...);` or `struct Name;`, optionally preceded by `pub`
+    StructDeclaration,
+    /// `{ a: T, b: U }`
+    NamedFieldList,
+    /// `a: T`
+    NamedField,
+    /// `(T, U)`
+    TupleFieldList,
+    /// `T`
+    TupleField,
+    /// `use module::path::Item;`
+    UseDeclaration,
+    /// An expression followed by `;`, which may be omitted after block-like expressions.
+    ExpressionStatement,
+    /// `return value;`
+    ReturnStatement,
+    /// A statement the parser could not make sense of, including the tokens skipped while
+    /// recovering.
+    ErrorStatement,
+    /// Tokens that do not belong to any statement, e.g. a stray `}`.
+    Error,
+    /// `left op right`
+    BinaryExpression,
+    /// `op operand`
+    UnaryExpression,
+    /// `(expression)`
+    ParenExpression,
+    /// A single literal token.
+    Literal,
+    /// An identifier used as an expression.
+    NameReference,
+    /// `name(arguments)`
+    CallExpression,
+    /// `(a, b)` of a call.
+    ArgumentList,
+    /// `object.field`
+    FieldExpression,
+    /// `receiver.method(arguments)`
+    MethodCallExpression,
+    /// `{ statements final_expression }`
+    BlockExpression,
+    /// `if condition { ... } else ...`
+    IfExpression,
+    /// `else { ... }` or `else if ...`
+    ElseBranch,
+}
+
+impl SyntaxKind {
+    /// Whitespace and comments, which carry no meaning for the program.
+    pub fn is_trivia(self) -> bool {
+        matches!(self, SyntaxKind::Whitespace | SyntaxKind::Comment)
+    }
+
+    pub fn is_keyword(self) -> bool {
+        matches!(
+            self,
+            SyntaxKind::True
+                | SyntaxKind::False
+                | SyntaxKind::Let
+                | SyntaxKind::Mut
+                | SyntaxKind::Type
+                | SyntaxKind::Struct
+                | SyntaxKind::Fn
+                | SyntaxKind::While
+                | SyntaxKind::Loop
+                | SyntaxKind::For
+                | SyntaxKind::If
+                | SyntaxKind::Else
+                | SyntaxKind::Return
+                | SyntaxKind::Pub
+                | SyntaxKind::Use
+        )
+    }
+}
+
+impl From<&Token> for SyntaxKind {
+    fn from(token: &Token) -> Self {
+        match token {
+            Token::True => SyntaxKind::True,
+            Token::False => SyntaxKind::False,
+            Token::Let => SyntaxKind::Let,
+            Token::Mut => SyntaxKind::Mut,
+            Token::Type => SyntaxKind::Type,
+            Token::Struct => SyntaxKind::Struct,
+            Token::Fn => SyntaxKind::Fn,
+            Token::While => SyntaxKind::While,
+            Token::Loop => SyntaxKind::Loop,
+            Token::For => SyntaxKind::For,
+            Token::If => SyntaxKind::If,
+            Token::Else => SyntaxKind::Else,
+            Token::Return => SyntaxKind::Return,
+            Token::Pub => SyntaxKind::Pub,
+            Token::Use => SyntaxKind::Use,
+            Token::Plus => SyntaxKind::Plus,
+            Token::Minus => SyntaxKind::Minus,
+            Token::Star => SyntaxKind::Star,
+            Token::Slash => SyntaxKind::Slash,
+            Token::Percent => SyntaxKind::Percent,
+            Token::Assign => SyntaxKind::Assign,
+            Token::Equals => SyntaxKind::Equals,
+            Token::NotEquals => SyntaxKind::NotEquals,
+            Token::LessThan => SyntaxKind::LessThan,
+            Token::GreaterThan => SyntaxKind::GreaterThan,
+            Token::LessThanOrEqual => SyntaxKind::LessThanOrEqual,
+            Token::GreaterThanOrEqual => SyntaxKind::GreaterThanOrEqual,
+            Token::And => SyntaxKind::And,
+            Token::Or => SyntaxKind::Or,
+            Token::Bang => SyntaxKind::Bang,
+            Token::Ampersand => SyntaxKind::Ampersand,
+            Token::Pipe => SyntaxKind::Pipe,
+            Token::Caret => SyntaxKind::Caret,
+            Token::ShiftLeft => SyntaxKind::ShiftLeft,
+            Token::ShiftRight => SyntaxKind::ShiftRight,
+            Token::LParen => SyntaxKind::LParen,
+            Token::RParen => SyntaxKind::RParen,
+            Token::LBrace => SyntaxKind::LBrace,
+            Token::RBrace => SyntaxKind::RBrace,
+            Token::LBracket => SyntaxKind::LBracket,
+            Token::RBracket => SyntaxKind::RBracket,
+            Token::Semicolon => SyntaxKind::Semicolon,
+            Token::Colon => SyntaxKind::Colon,
+            Token::Comma => SyntaxKind::Comma,
+            Token::Period => SyntaxKind::Period,
+            Token::RightArrow => SyntaxKind::RightArrow,
+            Token::PathSeparator => SyntaxKind::PathSeparator,
+            Token::Hash => SyntaxKind::Hash,
+            Token::Identifier(_) => SyntaxKind::Identifier,
+            Token::IntegerLiteral(_) => SyntaxKind::IntegerLiteral,
+            Token::FloatLiteral(_) => SyntaxKind::FloatLiteral,
+            Token::String(_) => SyntaxKind::String,
+            Token::Char(_) => SyntaxKind::Char,
+        }
+    }
+}
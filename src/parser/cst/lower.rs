@@ -0,0 +1,436 @@
+use logos::Logos;
+
+use super::{
+    kind::SyntaxKind,
+    syntax::{SyntaxNode, SyntaxToken},
+};
+use crate::{
+    core::{
+        source_map::FileId,
+        span::{Span, Spanned},
+    },
+    parser::{
+        ast::{
+            Attribute, BinaryOp, BlockExpression, BooleanLiteral, CharLiteral, ErrorStatement,
+            Expression, ExpressionStatement, FieldAccess, FloatLiteral, FunctionCall,
+            FunctionDeclaration, Identifier, IfExpression, IntegerLiteral, MethodCall,
+            NamedFieldDeclaration, Parameter, Program, ReturnStatement, Statement, StringLiteral,
+            StructDeclaration, TupleFieldDeclaration, UnaryOp, UnaryOperator, UseDeclaration,
+            VariableDeclaration, Visibility,
+        },
+        binary_operator,
+        lexer::{FloatToken, IntegerToken, Token},
+    },
+};
+
+/// Lowers a concrete syntax tree into the [`ast`](crate::parser::ast).
+///
+/// Trivia is dropped and parentheses are removed, the result including its spans equals what
+/// [`Parser`](crate::parser::Parser) produces for the same source. Statements that are
+/// incomplete are lowered to [`Statement::Error`].
+pub(super) struct Lowering {
+    file: FileId,
+}
+
+impl Lowering {
+    pub(super) fn new(file: FileId) -> Self {
+        Lowering { file }
+    }
+
+    fn span(&self, node: &SyntaxNode) -> Span {
+        Span::new(self.file, node.text_range())
+    }
+
+    fn identifier(&self, token: &SyntaxToken) -> Identifier {
+        Identifier {
+            name: token.text().to_string(),
+            span: Span::new(self.file, token.text_range()),
+        }
+    }
+
+    pub(super) fn program(&self, root: &SyntaxNode) -> Program {
+        let statements = root
+            .children()
+            .filter(|node| node.kind() != SyntaxKind::Error)
+            .map(|node| self.statement(&node))
+            .collect();
+        Program {
+            statements,
+            span: self.span(root),
+        }
+    }
+
+    // -- Statements --
+
+    fn statement(&self, node: &SyntaxNode) -> Statement {
+        let statement = match node.kind() {
+            SyntaxKind::VariableDeclaration => self.variable_declaration(node).map(Into::into),
+            SyntaxKind::FunctionDeclaration => self.function_declaration(node).map(Into::into),
+            SyntaxKind::StructDeclaration => self.struct_declaration(node).map(Into::into),
+            SyntaxKind::UseDeclaration => Some(
+                UseDeclaration {
+                    path: node
+                        .tokens()
+                        .filter(|token| token.kind() == SyntaxKind::Identifier)
+                        .map(|token| self.identifier(&token))
+                        .collect(),
+                    span: self.span(node),
+                }
+                .into(),
+            ),
+            SyntaxKind::ReturnStatement => Some(
+                ReturnStatement {
+                    value: self.child_expression(node),
+                    span: self.span(node),
+                }
+                .into(),
+            ),
+            SyntaxKind::ExpressionStatement => self.child_expression(node).map(|expression| {
+                let span = match node.child_token(SyntaxKind::Semicolon) {
+                    Some(semicolon) => expression
+                        .span()
+                        .combine(Span::new(self.file, semicolon.text_range())),
+                    None => expression.span(),
+                };
+                ExpressionStatement { expression, span }.into()
+            }),
+            _ => None,
+        };
+        // Malformed statements may end with the trivia consumed before the parser gave up
+        statement.unwrap_or_else(|| {
+            ErrorStatement {
+                span: Span::new(self.file, node.trimmed_range()),
+            }
+            .into()
+        })
+    }
+
+    fn variable_declaration(&self, node: &SyntaxNode) -> Option<VariableDeclaration> {
+        let declared_type = match node.child(SyntaxKind::TypeAnnotation) {
+            Some(annotation) => {
+                Some(self.identifier(&annotation.child_token(SyntaxKind::Identifier)?))
+            }
+            None => None,
+        };
+        Some(VariableDeclaration {
+            identifier: self.identifier(&node.child_token(SyntaxKind::Identifier)?),
+            declared_type,
+            initializer: self.child_expression(node)?,
+            span: self.span(node),
+        })
+    }
+
+    fn function_declaration(&self, node: &SyntaxNode) -> Option<FunctionDeclaration> {
+        let parameter_list = node.child(SyntaxKind::ParameterList)?;
+        let parameters = parameter_list
+            .children()
+            .map(|parameter| {
+                let mut names = parameter
+                    .tokens()
+                    .filter(|token| token.kind() == SyntaxKind::Identifier);
+                Some(Parameter {
+                    identifier: self.identifier(&names.next()?),
+                    declared_type: self.identifier(&names.next()?),
+                    span: self.span(&parameter),
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        // Functions without `->` return the unit type `()`
+        let return_type = match node.child(SyntaxKind::ReturnType) {
+            Some(return_type) => self.identifier(&return_type.child_token(SyntaxKind::Identifier)?),
+            None => Identifier {
+                name: "()".to_string(),
+                span: Span::new(
+                    self.file,
+                    parameter_list.child_token(SyntaxKind::RParen)?.text_range(),
+                ),
+            },
+        };
+
+        let body = node
+            .child(SyntaxKind::FunctionBody)?
+            .children()
+            .map(|statement| self.statement(&statement))
+            .collect();
+
+        let attributes = node
+            .children()
+            .filter(|child| child.kind() == SyntaxKind::Attribute)
+            .map(|attribute| {
+                Some(Attribute {
+                    name: self.identifier(&attribute.child_token(SyntaxKind::Identifier)?),
+                    span: self.span(&attribute),
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        Some(FunctionDeclaration {
+            attributes,
+            visibility: visibility(node),
+            identifier: self.identifier(&node.child_token(SyntaxKind::Identifier)?),
+            parameters,
+            return_type,
+            body,
+            span: self.span(node),
+        })
+    }
+
+    fn struct_declaration(&self, node: &SyntaxNode) -> Option<StructDeclaration> {
+        let visibility = visibility(node);
+        let identifier = self.identifier(&node.child_token(SyntaxKind::Identifier)?);
+        let span = self.span(node);
+
+        if let Some(fields) = node.child(SyntaxKind::TupleFieldList) {
+            let fields = fields
+                .children()
+                .map(|field| {
+                    Some(TupleFieldDeclaration {
+                        declared_type: self.identifier(&field.child_token(SyntaxKind::Identifier)?),
+                        span: self.span(&field),
+                    })
+                })
+                .collect::<Option<_>>()?;
+            Some(StructDeclaration::TupleStruct {
+                visibility,
+                identifier,
+                fields,
+                span,
+            })
+        } else if let Some(fields) = node.child(SyntaxKind::NamedFieldList) {
+            let fields = fields
+                .children()
+                .map(|field| {
+                    let mut names = field
+                        .tokens()
+                        .filter(|token| token.kind() == SyntaxKind::Identifier);
+                    Some(NamedFieldDeclaration {
+                        identifier: self.identifier(&names.next()?),
+                        declared_type: self.identifier(&names.next()?),
+                        span: self.span(&field),
+                    })
+                })
+                .collect::<Option<_>>()?;
+            Some(StructDeclaration::NamedStruct {
+                visibility,
+                identifier,
+                fields,
+                span,
+            })
+        } else {
+            Some(StructDeclaration::UnitStruct {
+                visibility,
+                identifier,
+                span,
+            })
+        }
+    }
+
+    // -- Expressions --
+
+    /// Lowers the first child of `node` that is an expression.
+    fn child_expression(&self, node: &SyntaxNode) -> Option<Expression> {
+        let child = node.children().find(|child| is_expression(child.kind()))?;
+        self.expression(&child)
+    }
+
+    fn expression(&self, node: &SyntaxNode) -> Option<Expression> {
+        let span = self.span(node);
+        let expression = match node.kind() {
+            SyntaxKind::Literal => self.literal(&node.tokens().next()?)?,
+            SyntaxKind::NameReference => self
+                .identifier(&node.child_token(SyntaxKind::Identifier)?)
+                .into(),
+            SyntaxKind::ParenExpression => self.child_expression(node)?,
+            SyntaxKind::UnaryExpression => {
+                let operator = match node.tokens().next()?.kind() {
+                    SyntaxKind::Minus => UnaryOperator::Negate,
+                    SyntaxKind::Bang => UnaryOperator::Not,
+                    _ => return None,
+                };
+                let operator_span = Span::new(self.file, node.tokens().next()?.text_range());
+                let operand = self.child_expression(node)?;
+                UnaryOp {
+                    operator,
+                    span: operator_span.combine(operand.span()),
+                    operand: Box::new(operand),
+                    inferred_type: None,
+                }
+                .into()
+            }
+            SyntaxKind::BinaryExpression => {
+                let mut operands = node.children().filter(|child| is_expression(child.kind()));
+                let left = self.expression(&operands.next()?)?;
+                let right = self.expression(&operands.next()?)?;
+                let (operator, _) =
+                    lex(node.tokens().next()?.text()).and_then(|token| binary_operator(&token))?;
+                BinaryOp {
+                    operator,
+                    span: left.span().combine(right.span()),
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    inferred_type: None,
+                }
+                .into()
+            }
+            SyntaxKind::CallExpression => {
+                let arguments = node
+                    .child(SyntaxKind::ArgumentList)?
+                    .children()
+                    .map(|argument| self.expression(&argument))
+                    .collect::<Option<_>>()?;
+                FunctionCall {
+                    function_name: self.identifier(&node.child_token(SyntaxKind::Identifier)?),
+                    arguments,
+                    span,
+                    inferred_type: None,
+                }
+                .into()
+            }
+            // Like binary operations, the span starts at the object even if it is parenthesized
+            SyntaxKind::FieldExpression => {
+                let object = self.child_expression(node)?;
+                let field = self.identifier(&node.child_token(SyntaxKind::Identifier)?);
+                FieldAccess {
+                    span: object.span().combine(field.span),
+                    object: Box::new(object),
+                    field,
+                    inferred_type: None,
+                }
+                .into()
+            }
+            SyntaxKind::MethodCallExpression => {
+                let receiver = self.child_expression(node)?;
+                let argument_list = node.child(SyntaxKind::ArgumentList)?;
+                let arguments = argument_list
+                    .children()
+                    .map(|argument| self.expression(&argument))
+                    .collect::<Option<_>>()?;
+                let end = argument_list.child_token(SyntaxKind::RParen)?;
+                MethodCall {
+                    span: receiver
+                        .span()
+                        .combine(Span::new(self.file, end.text_range())),
+                    receiver: Box::new(receiver),
+                    method: self.identifier(&node.child_token(SyntaxKind::Identifier)?),
+                    arguments,
+                    inferred_type: None,
+                }
+                .into()
+            }
+            SyntaxKind::BlockExpression => self.block_expression(node)?.into(),
+            SyntaxKind::IfExpression => self.if_expression(node)?.into(),
+            _ => return None,
+        };
+        Some(expression)
+    }
+
+    fn literal(&self, token: &SyntaxToken) -> Option<Expression> {
+        let span = Span::new(self.file, token.text_range());
+        let literal = match lex(token.text())? {
+            Token::IntegerLiteral(IntegerToken { value, suffix }) => IntegerLiteral {
+                value,
+                suffix,
+                inferred_type: None,
+                span,
+            }
+            .into(),
+            Token::FloatLiteral(FloatToken { value, suffix }) => FloatLiteral {
+                value,
+                suffix,
+                inferred_type: None,
+                span,
+            }
+            .into(),
+            Token::String(value) => StringLiteral { value, span }.into(),
+            Token::Char(value) => CharLiteral { value, span }.into(),
+            Token::True => BooleanLiteral { value: true, span }.into(),
+            Token::False => BooleanLiteral { value: false, span }.into(),
+            _ => return None,
+        };
+        Some(literal)
+    }
+
+    fn block_expression(&self, node: &SyntaxNode) -> Option<BlockExpression> {
+        let mut statements = Vec::new();
+        let mut final_expression = None;
+        for child in node.children() {
+            if is_expression(child.kind()) {
+                final_expression = Some(Box::new(self.expression(&child)?));
+            } else {
+                statements.push(self.statement(&child));
+            }
+        }
+        Some(BlockExpression {
+            statements,
+            final_expression,
+            inferred_type: None,
+            span: self.span(node),
+        })
+    }
+
+    fn if_expression(&self, node: &SyntaxNode) -> Option<IfExpression> {
+        let mut children = node.children();
+        let condition = self.expression(&children.next()?)?;
+        let then_branch = self.block_expression(&children.next()?)?;
+
+        let else_branch = match children.next() {
+            Some(else_branch) => {
+                let branch = else_branch.children().next()?;
+                match branch.kind() {
+                    SyntaxKind::BlockExpression => Some(self.block_expression(&branch)?),
+                    // `else if` is sugar for an else block containing only the nested if
+                    SyntaxKind::IfExpression => {
+                        let nested = self.if_expression(&branch)?;
+                        let span = nested.span;
+                        Some(BlockExpression {
+                            statements: Vec::new(),
+                            final_expression: Some(Box::new(nested.into())),
+                            inferred_type: None,
+                            span,
+                        })
+                    }
+                    _ => return None,
+                }
+            }
+            None => None,
+        };
+
+        Some(IfExpression {
+            condition: Box::new(condition),
+            then_branch,
+            else_branch,
+            inferred_type: None,
+            span: self.span(node),
+        })
+    }
+}
+
+fn is_expression(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::BinaryExpression
+            | SyntaxKind::UnaryExpression
+            | SyntaxKind::ParenExpression
+            | SyntaxKind::Literal
+            | SyntaxKind::NameReference
+            | SyntaxKind::CallExpression
+            | SyntaxKind::FieldExpression
+            | SyntaxKind::MethodCallExpression
+            | SyntaxKind::BlockExpression
+            | SyntaxKind::IfExpression
+    )
+}
+
+/// Lexes the text of a single token to recover its payload.
+fn lex(text: &str) -> Option<Token> {
+    Token::lexer(text).next()?.ok()
+}
+
+fn visibility(node: &SyntaxNode) -> Visibility {
+    if node.child_token(SyntaxKind::Pub).is_some() {
+        Visibility::Public
+    } else {
+        Visibility::Private
+    }
+}
@@ -0,0 +1,166 @@
+//! # Concrete Syntax Tree
+//!
+//! A lossless representation of the source code, keeping every token including whitespace,
+//! comments and invalid tokens, so the exact source text can be reproduced from it. Tools like
+//! formatters and refactorings work on this tree, the interpreter works on the
+//! [`ast`](super::ast) lowered from it.
+//!
+//! The tree is split in two layers:
+//! - the green tree ([`GreenNode`], [`GreenToken`]) is immutable, position independent and
+//!   shareable, so unchanged subtrees can be reused when reparsing after an edit.
+//! - the red tree ([`SyntaxNode`], [`SyntaxToken`]) is built on demand on top of it and adds
+//!   absolute positions and parent pointers.
+//!
+//! # Example
+//! ```
+//! use rscript::{Parser, core::source_map::FileId, parser::cst::SyntaxTree};
+//!
+//! let tree = SyntaxTree::parse("let x = 1; // one", FileId::default());
+//! assert_eq!(tree.root().text(), "let x = 1; // one");
+//! assert_eq!(tree.lower(), Parser::new("let x = 1; // one").parse());
+//! ```
+
+use std::rc::Rc;
+
+pub use self::{
+    green::{GreenElement, GreenNode, GreenNodeBuilder, GreenToken},
+    kind::SyntaxKind,
+    parser::{RawToken, tokenize},
+    syntax::{SyntaxElement, SyntaxNode, SyntaxToken},
+};
+use self::{lower::Lowering, parser::CstParser};
+use super::{ParseResult, ParserError};
+use crate::core::source_map::FileId;
+
+mod green;
+mod kind;
+mod lower;
+mod parser;
+mod syntax;
+
+/// The concrete syntax tree of a source file, together with the errors found while parsing it.
+#[derive(Debug, Clone)]
+pub struct SyntaxTree {
+    green: Rc<GreenNode>,
+    file: FileId,
+    errors: Vec<ParserError>,
+}
+
+impl SyntaxTree {
+    /// Parses `source`, recovering from errors the same way [`Parser`](super::Parser) does.
+    pub fn parse(source: &str, file: FileId) -> Self {
+        let (builder, errors) = CstParser::new(source, file).parse();
+        SyntaxTree {
+            green: Rc::new(builder.finish()),
+            file,
+            errors,
+        }
+    }
+
+    pub fn root(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green.clone())
+    }
+
+    pub fn file(&self) -> FileId {
+        self.file
+    }
+
+    pub fn errors(&self) -> &[ParserError] {
+        &self.errors
+    }
+
+    /// Lowers the tree into an abstract syntax tree.
+    pub fn lower(&self) -> ParseResult {
+        ParseResult {
+            program: Lowering::new(self.file).program(&self.root()),
+            errors: self.errors.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::parser::Parser;
+
+    /// Checks that the tree of `source` reproduces it and lowers to what the parser produces.
+    fn assert_consistent(name: &str, source: &str) {
+        let tree = SyntaxTree::parse(source, FileId::default());
+        assert_eq!(tree.root().text(), source, "the tree of {name} is lossy");
+        assert_eq!(
+            tree.lower(),
+            Parser::new(source).parse(),
+            "the tree of {name} lowers differently"
+        );
+    }
+
+    #[test]
+    fn trees_of_valid_sources_match_the_parser() {
+        let sources = [
+            "",
+            "let x = 1; // one",
+            "// leading\npub struct Point { x: i64, y: i64 }\nstruct Pair(u8, u8);\nstruct Unit;",
+            "fn f(a: i64, b: i64) -> i64 {\n    let c = -a * (b + 2) % 3;\n    return if c >= 0 { c } else { a.abs() };\n}",
+            "#[test]\nfn t() { assert_eq(1 << 2, 4, \"shift\"); }",
+            "use math::vec::Vector2D;\nlet s = \"a\\n\".len() + 'c'.to_string().len();",
+            "let x = 1.5e3f32; let y = !true || false && 0x1F != 31u8;",
+        ];
+        for source in sources {
+            let tree = SyntaxTree::parse(source, FileId::default());
+            assert_eq!(tree.errors(), [], "`{source}` has errors");
+            assert_consistent(source, source);
+        }
+    }
+
+    #[test]
+    fn trees_of_broken_sources_match_the_parser() {
+        let sources = [
+            "let = 1;",
+            "let x = ;\nlet y = 2;",
+            "fn f( { return 1; }\nfn g() -> i64 { return 2; }",
+            "struct { x: i64 }\nlet ok = 1;",
+            "let x = 1 +;",
+            "let s = \"unterminated",
+            "let @ = 3; let z = (1 + 2;",
+            "fn f() -> i64 { let x = 1 }",
+            "#[",
+            "}}} let after = 1;",
+        ];
+        for source in sources {
+            let tree = SyntaxTree::parse(source, FileId::default());
+            assert!(
+                !tree.errors().is_empty(),
+                "`{source}` parsed without errors"
+            );
+            assert_consistent(source, source);
+        }
+    }
+
+    #[test]
+    fn trees_of_the_test_scripts_match_the_parser() {
+        let root = concat!(env!("CARGO_MANIFEST_DIR"), "/tests");
+        for directory in [
+            "scripts",
+            "golden/lexer",
+            "golden/parser",
+            "golden/format",
+            "modules",
+        ] {
+            let mut entries: Vec<_> = fs::read_dir(format!("{root}/{directory}"))
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension == "rscript")
+                })
+                .collect();
+            entries.sort();
+            for path in entries {
+                let source = fs::read_to_string(&path).unwrap();
+                assert_consistent(&path.display().to_string(), &source);
+            }
+        }
+    }
+}
@@ -0,0 +1,758 @@
+use std::ops::Range;
+
+use logos::Logos;
+
+use super::{
+    green::{Checkpoint, GreenNodeBuilder},
+    kind::SyntaxKind,
+};
+use crate::{
+    core::{source_map::FileId, span::Span},
+    parser::{
+        MAX_NESTING, ParserError, binary_operator,
+        lexer::{self, Token},
+        starts_statement,
+    },
+};
+
+/// A token of the source, trivia and invalid tokens included.
+#[derive(Debug, Clone)]
+pub struct RawToken {
+    pub kind: SyntaxKind,
+    pub range: Range<usize>,
+    /// The lexed token, `None` for trivia.
+    pub token: Option<lexer::Result>,
+}
+
+/// Splits the source into tokens without losing a single byte.
+///
+/// The lexer skips whitespace and comments, they are recovered from the gaps between the tokens
+/// it produces.
+pub fn tokenize(source: &str) -> Vec<RawToken> {
+    let mut tokens = Vec::new();
+    let mut lexer = Token::lexer(source);
+    let mut end = 0;
+    while let Some(token) = lexer.next() {
+        let range = lexer.span();
+        push_trivia(source, end..range.start, &mut tokens);
+        let kind = match &token {
+            Ok(token) => SyntaxKind::from(token),
+            Err(_) => SyntaxKind::ErrorToken,
+        };
+        end = range.end;
+        tokens.push(RawToken {
+            kind,
+            range,
+            token: Some(token),
+        });
+    }
+    push_trivia(source, end..source.len(), &mut tokens);
+    tokens
+}
+
+/// Splits a gap between two tokens into whitespace and comments.
+fn push_trivia(source: &str, range: Range<usize>, tokens: &mut Vec<RawToken>) {
+    let mut start = range.start;
+    while start < range.end {
+        let rest = &source[start..range.end];
+        let (kind, length) = if rest.starts_with("//") {
+            (SyntaxKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else {
+            let length = rest
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len());
+            (SyntaxKind::Whitespace, length)
+        };
+        tokens.push(RawToken {
+            kind,
+            range: start..start + length,
+            token: None,
+        });
+        start += length;
+    }
+}
+
+/// Builds the concrete syntax tree of a source.
+///
+/// Follows the same grammar and error recovery as [`Parser`](crate::parser::Parser), but keeps
+/// every token including trivia. Trivia between two nodes is attached to their parent, so nodes
+/// start and end with significant tokens.
+pub(super) struct CstParser<'a> {
+    source: &'a str,
+    file: FileId,
+    tokens: Vec<RawToken>,
+    /// Index of the next token not yet added to the tree.
+    position: usize,
+    /// Number of significant tokens consumed so far, used to guarantee progress during error
+    /// recovery.
+    consumed: usize,
+    /// Index of the first token whose validity was not reported yet. Like the parser, invalid
+    /// tokens are reported once the significant token before them is consumed.
+    reported: usize,
+    /// Number of statements and expressions enclosing the one being parsed.
+    depth: usize,
+    builder: GreenNodeBuilder,
+    errors: Vec<ParserError>,
+}
+
+type Result<T = ()> = std::result::Result<T, ParserError>;
+
+impl<'a> CstParser<'a> {
+    pub(super) fn new(source: &'a str, file: FileId) -> Self {
+        CstParser {
+            source,
+            file,
+            tokens: tokenize(source),
+            position: 0,
+            consumed: 0,
+            reported: 0,
+            depth: 0,
+            builder: GreenNodeBuilder::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub(super) fn parse(mut self) -> (GreenNodeBuilder, Vec<ParserError>) {
+        trace!("Parsing concrete syntax tree");
+        self.builder.start_node(SyntaxKind::Program);
+        self.report_invalid_tokens();
+        while self.peek().is_some() {
+            let is_error = self.statement_or_recover() == SyntaxKind::ErrorStatement;
+            if self.peek() == Some(&Token::RBrace) {
+                if !is_error {
+                    self.errors.push(ParserError::UnexpectedToken {
+                        expected: "statement".to_string(),
+                        found: Some(Token::RBrace),
+                        span: self.current_span(),
+                    });
+                }
+                self.start_node(SyntaxKind::Error);
+                self.bump();
+                self.builder.finish_node();
+            }
+        }
+        self.flush_trivia();
+        self.builder.finish_node();
+        (self.builder, self.errors)
+    }
+
+    // -- Tokens --
+
+    /// Index of the next significant token.
+    fn next_significant(&self) -> Option<usize> {
+        (self.position..self.tokens.len()).find(|&index| {
+            let kind = self.tokens[index].kind;
+            !kind.is_trivia() && kind != SyntaxKind::ErrorToken
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        let index = self.next_significant()?;
+        self.tokens[index].token.as_ref()?.as_ref().ok()
+    }
+
+    fn current_span(&self) -> Span {
+        let range = match self.next_significant() {
+            Some(index) => self.tokens[index].range.clone(),
+            None => self.source.len()..self.source.len(),
+        };
+        Span::new(self.file, range)
+    }
+
+    /// Adds trivia and invalid tokens in front of the next significant token to the tree.
+    fn flush_trivia(&mut self) {
+        while let Some(token) = self.tokens.get(self.position) {
+            if !token.kind.is_trivia() && token.kind != SyntaxKind::ErrorToken {
+                break;
+            }
+            let text = &self.source[token.range.clone()];
+            self.builder.token(token.kind, text);
+            self.position += 1;
+        }
+    }
+
+    /// Reports the invalid tokens in front of the next significant token.
+    fn report_invalid_tokens(&mut self) {
+        let end = self.next_significant().unwrap_or(self.tokens.len());
+        for token in &self.tokens[self.reported.max(self.position)..end] {
+            if let Some(Err(error)) = &token.token {
+                self.errors.push(ParserError::LexerError {
+                    error: error.clone(),
+                    span: Span::new(self.file, token.range.clone()),
+                });
+            }
+        }
+        self.reported = self.reported.max(end);
+    }
+
+    /// Adds the next significant token to the tree.
+    fn bump(&mut self) {
+        self.flush_trivia();
+        if let Some(token) = self.tokens.get(self.position) {
+            let text = &self.source[token.range.clone()];
+            self.builder.token(token.kind, text);
+            self.position += 1;
+            self.consumed += 1;
+        }
+        self.report_invalid_tokens();
+    }
+
+    fn expect(&mut self, expected: Token) -> Result {
+        if self.peek() == Some(&expected) {
+            self.bump();
+            Ok(())
+        } else {
+            Err(self.unexpected(expected.to_string()))
+        }
+    }
+
+    fn expect_identifier(&mut self) -> Result {
+        if let Some(Token::Identifier(_)) = self.peek() {
+            self.bump();
+            Ok(())
+        } else {
+            Err(self.unexpected("identifier"))
+        }
+    }
+
+    fn unexpected(&self, expected: impl Into<String>) -> ParserError {
+        ParserError::UnexpectedToken {
+            expected: expected.into(),
+            found: self.peek().cloned(),
+            span: self.current_span(),
+        }
+    }
+
+    // -- Nodes --
+
+    fn start_node(&mut self, kind: SyntaxKind) {
+        self.flush_trivia();
+        self.builder.start_node(kind);
+    }
+
+    fn checkpoint(&mut self) -> Checkpoint {
+        self.flush_trivia();
+        self.builder.checkpoint()
+    }
+
+    /// Wraps a single token into a node.
+    fn token_node(&mut self, kind: SyntaxKind) {
+        self.start_node(kind);
+        self.bump();
+        self.builder.finish_node();
+    }
+
+    // -- Error Recovery --
+
+    /// Parses a statement, wrapping it into an error node if it is malformed.
+    ///
+    /// Returns the kind of the node that was added.
+    fn statement_or_recover(&mut self) -> SyntaxKind {
+        let checkpoint = self.checkpoint();
+        let depth = self.builder.depth();
+        let start = self.consumed;
+        match self.statement() {
+            Ok(()) => self.last_kind(),
+            Err(error) => {
+                self.recover(error, checkpoint, depth, start);
+                SyntaxKind::ErrorStatement
+            }
+        }
+    }
+
+    /// Records `error`, closes the nodes left open by the failed statement, skips to the next
+    /// statement boundary and wraps everything since `checkpoint` into an error node.
+    fn recover(&mut self, error: ParserError, checkpoint: Checkpoint, depth: usize, start: usize) {
+        trace!("Recovering from error: {}", error);
+        self.errors.push(error);
+        while self.builder.depth() > depth {
+            self.builder.finish_node();
+        }
+        self.synchronize();
+        if self.consumed == start && self.peek().is_some() {
+            self.bump();
+        }
+        self.builder
+            .start_node_at(checkpoint, SyntaxKind::ErrorStatement);
+        self.builder.finish_node();
+    }
+
+    /// Mirrors [`Parser::synchronize`](crate::parser::Parser).
+    fn synchronize(&mut self) {
+        let mut depth = 0usize;
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::Semicolon if depth == 0 => {
+                    self.bump();
+                    return;
+                }
+                Token::LBrace => depth += 1,
+                Token::RBrace if depth == 0 => return,
+                Token::RBrace => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.bump();
+                        return;
+                    }
+                }
+                Token::Let
+                | Token::Hash
+                | Token::Pub
+                | Token::Fn
+                | Token::Struct
+                | Token::Use
+                | Token::Return
+                | Token::If
+                | Token::While
+                | Token::Loop
+                | Token::For
+                    if depth == 0 =>
+                {
+                    return;
+                }
+                _ => {}
+            }
+            self.bump();
+        }
+    }
+
+    /// The kind of the node finished last.
+    fn last_kind(&self) -> SyntaxKind {
+        self.builder.last_kind().unwrap_or(SyntaxKind::Error)
+    }
+
+    // -- Statements --
+
+    /// Runs `parse` one nesting level deeper, failing at the same depth as the parser.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth == MAX_NESTING {
+            return Err(ParserError::NestingTooDeep {
+                span: self.current_span(),
+            });
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn statement(&mut self) -> Result {
+        self.nested(|parser| match parser.peek() {
+            Some(Token::Let) => parser.variable_declaration(),
+            Some(Token::Hash) | Some(Token::Pub) | Some(Token::Fn) | Some(Token::Struct) => {
+                parser.item()
+            }
+            Some(Token::Use) => parser.use_declaration(),
+            Some(Token::Return) => parser.return_statement(),
+            Some(_) => parser.expression_statement(),
+            None => Err(parser.unexpected("statement")),
+        })
+    }
+
+    fn variable_declaration(&mut self) -> Result {
+        self.start_node(SyntaxKind::VariableDeclaration);
+        self.expect(Token::Let)?;
+        self.expect_identifier()?;
+        if self.peek() == Some(&Token::Colon) {
+            self.start_node(SyntaxKind::TypeAnnotation);
+            self.bump();
+            self.expect_identifier()?;
+            self.builder.finish_node();
+        }
+        self.expect(Token::Assign)?;
+        self.expression()?;
+        self.expect(Token::Semicolon)?;
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    /// A function or struct declaration, optionally preceded by `pub` and for functions by
+    /// attributes. Both belong to the node of the declaration.
+    fn item(&mut self) -> Result {
+        let checkpoint = self.checkpoint();
+        let mut has_attributes = false;
+        while self.peek() == Some(&Token::Hash) {
+            self.attribute()?;
+            has_attributes = true;
+        }
+        if self.peek() == Some(&Token::Pub) {
+            self.bump();
+        }
+        match self.peek() {
+            Some(Token::Fn) => self.function_declaration(checkpoint),
+            Some(Token::Struct) if !has_attributes => self.struct_declaration(checkpoint),
+            _ if has_attributes => Err(self.unexpected("`fn`")),
+            _ => Err(self.unexpected("`fn` or `struct`")),
+        }
+    }
+
+    fn attribute(&mut self) -> Result {
+        self.start_node(SyntaxKind::Attribute);
+        self.expect(Token::Hash)?;
+        self.expect(Token::LBracket)?;
+        self.expect_identifier()?;
+        self.expect(Token::RBracket)?;
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    fn function_declaration(&mut self, checkpoint: Checkpoint) -> Result {
+        self.builder
+            .start_node_at(checkpoint, SyntaxKind::FunctionDeclaration);
+        self.expect(Token::Fn)?;
+        self.expect_identifier()?;
+
+        // -- Parameters --
+        self.start_node(SyntaxKind::ParameterList);
+        self.expect(Token::LParen)?;
+        while let Some(Token::Identifier(_)) = self.peek() {
+            self.start_node(SyntaxKind::Parameter);
+            self.bump();
+            self.expect(Token::Colon)?;
+            self.expect_identifier()?;
+            self.builder.finish_node();
+            if self.peek() == Some(&Token::Comma) {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        self.expect(Token::RParen)?;
+        self.builder.finish_node();
+
+        // -- Return Type --
+        if self.peek() == Some(&Token::RightArrow) {
+            self.start_node(SyntaxKind::ReturnType);
+            self.bump();
+            self.expect_identifier()?;
+            self.builder.finish_node();
+        }
+
+        // -- Body --
+        self.start_node(SyntaxKind::FunctionBody);
+        self.expect(Token::LBrace)?;
+        while !matches!(self.peek(), Some(Token::RBrace) | None) {
+            self.statement_or_recover();
+        }
+        self.expect(Token::RBrace)?;
+        self.builder.finish_node();
+
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    fn struct_declaration(&mut self, checkpoint: Checkpoint) -> Result {
+        self.builder
+            .start_node_at(checkpoint, SyntaxKind::StructDeclaration);
+        self.expect(Token::Struct)?;
+        self.expect_identifier()?;
+        match self.peek() {
+            // -- Tuple Fields --
+            Some(Token::LParen) => {
+                self.start_node(SyntaxKind::TupleFieldList);
+                self.bump();
+                while let Some(Token::Identifier(_)) = self.peek() {
+                    self.token_node(SyntaxKind::TupleField);
+                    if self.peek() == Some(&Token::Comma) {
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                self.expect(Token::RParen)?;
+                self.builder.finish_node();
+                self.expect(Token::Semicolon)?;
+            }
+            // -- Named Fields --
+            Some(Token::LBrace) => {
+                self.start_node(SyntaxKind::NamedFieldList);
+                self.bump();
+                while let Some(Token::Identifier(_)) = self.peek() {
+                    self.start_node(SyntaxKind::NamedField);
+                    self.bump();
+                    self.expect(Token::Colon)?;
+                    self.expect_identifier()?;
+                    self.builder.finish_node();
+                    if self.peek() == Some(&Token::Comma) {
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                self.expect(Token::RBrace)?;
+                self.builder.finish_node();
+            }
+            // -- Unit Struct --
+            Some(Token::Semicolon) => self.bump(),
+            Some(_) => return Err(self.unexpected("`(` or `{` or `;`")),
+            None => {
+                return Err(ParserError::UnexpectedEof {
+                    span: self.current_span(),
+                });
+            }
+        }
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    fn use_declaration(&mut self) -> Result {
+        self.start_node(SyntaxKind::UseDeclaration);
+        self.expect(Token::Use)?;
+        self.expect_identifier()?;
+        self.expect(Token::PathSeparator)?;
+        self.expect_identifier()?;
+        while self.peek() == Some(&Token::PathSeparator) {
+            self.bump();
+            self.expect_identifier()?;
+        }
+        self.expect(Token::Semicolon)?;
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    fn return_statement(&mut self) -> Result {
+        self.start_node(SyntaxKind::ReturnStatement);
+        self.expect(Token::Return)?;
+        if self.peek() != Some(&Token::Semicolon) {
+            self.expression()?;
+        }
+        self.expect(Token::Semicolon)?;
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    fn expression_statement(&mut self) -> Result {
+        self.start_node(SyntaxKind::ExpressionStatement);
+        let kind = self.expression()?;
+        if !is_block_like(kind) || self.peek() == Some(&Token::Semicolon) {
+            self.expect(Token::Semicolon)?;
+        }
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    // -- Expressions --
+
+    /// Parses an expression and returns the kind of its node.
+    fn expression(&mut self) -> Result<SyntaxKind> {
+        self.nested(|parser| parser.binary_expression(0))
+    }
+
+    /// Mirrors the precedence climbing of [`Parser`](crate::parser::Parser).
+    fn binary_expression(&mut self, min_precedence: u8) -> Result<SyntaxKind> {
+        let checkpoint = self.checkpoint();
+        let mut kind = self.unary_expression()?;
+
+        while let Some((_, precedence)) = self.peek().and_then(binary_operator) {
+            if precedence <= min_precedence {
+                break;
+            }
+            self.builder
+                .start_node_at(checkpoint, SyntaxKind::BinaryExpression);
+            self.bump();
+            self.binary_expression(precedence)?;
+            self.builder.finish_node();
+            kind = SyntaxKind::BinaryExpression;
+        }
+
+        Ok(kind)
+    }
+
+    fn unary_expression(&mut self) -> Result<SyntaxKind> {
+        if !matches!(self.peek(), Some(Token::Minus) | Some(Token::Bang)) {
+            return self.postfix_expression();
+        }
+        self.start_node(SyntaxKind::UnaryExpression);
+        self.bump();
+        self.nested(CstParser::unary_expression)?;
+        self.builder.finish_node();
+        Ok(SyntaxKind::UnaryExpression)
+    }
+
+    fn postfix_expression(&mut self) -> Result<SyntaxKind> {
+        let checkpoint = self.checkpoint();
+        let mut kind = self.primary_expression()?;
+        while self.peek() == Some(&Token::Period) {
+            self.bump();
+            self.expect_identifier()?;
+            kind = if self.peek() == Some(&Token::LParen) {
+                SyntaxKind::MethodCallExpression
+            } else {
+                SyntaxKind::FieldExpression
+            };
+            self.builder.start_node_at(checkpoint, kind);
+            if kind == SyntaxKind::MethodCallExpression {
+                self.argument_list()?;
+            }
+            self.builder.finish_node();
+        }
+        Ok(kind)
+    }
+
+    fn primary_expression(&mut self) -> Result<SyntaxKind> {
+        match self.peek() {
+            Some(
+                Token::IntegerLiteral(_)
+                | Token::FloatLiteral(_)
+                | Token::String(_)
+                | Token::Char(_)
+                | Token::True
+                | Token::False,
+            ) => {
+                self.token_node(SyntaxKind::Literal);
+                Ok(SyntaxKind::Literal)
+            }
+            Some(Token::Identifier(_)) => {
+                let checkpoint = self.checkpoint();
+                self.bump();
+                if self.peek() == Some(&Token::LParen) {
+                    self.builder
+                        .start_node_at(checkpoint, SyntaxKind::CallExpression);
+                    self.argument_list()?;
+                    self.builder.finish_node();
+                    Ok(SyntaxKind::CallExpression)
+                } else {
+                    self.builder
+                        .start_node_at(checkpoint, SyntaxKind::NameReference);
+                    self.builder.finish_node();
+                    Ok(SyntaxKind::NameReference)
+                }
+            }
+            Some(Token::LParen) => {
+                self.start_node(SyntaxKind::ParenExpression);
+                self.bump();
+                // The parser discards parentheses, so `({ ... })` is block-like as well
+                let kind = self.expression()?;
+                self.expect(Token::RParen)?;
+                self.builder.finish_node();
+                Ok(kind)
+            }
+            Some(Token::LBrace) => {
+                self.block_expression()?;
+                Ok(SyntaxKind::BlockExpression)
+            }
+            Some(Token::If) => {
+                self.if_expression()?;
+                Ok(SyntaxKind::IfExpression)
+            }
+            Some(_) => Err(self.unexpected("expression")),
+            None => Err(ParserError::UnexpectedEof {
+                span: self.current_span(),
+            }),
+        }
+    }
+
+    fn argument_list(&mut self) -> Result {
+        self.start_node(SyntaxKind::ArgumentList);
+        self.expect(Token::LParen)?;
+        while self.peek() != Some(&Token::RParen) {
+            self.expression()?;
+            if self.peek() == Some(&Token::Comma) {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        self.expect(Token::RParen)?;
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    fn block_expression(&mut self) -> Result {
+        self.start_node(SyntaxKind::BlockExpression);
+        self.expect(Token::LBrace)?;
+        while !matches!(self.peek(), Some(Token::RBrace) | None) {
+            let checkpoint = self.checkpoint();
+            let depth = self.builder.depth();
+            let start = self.consumed;
+            if let Err(error) = self.block_item(checkpoint) {
+                self.recover(error, checkpoint, depth, start);
+            }
+        }
+        self.expect(Token::RBrace)?;
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    /// Parses a statement or the final expression of a block.
+    fn block_item(&mut self, checkpoint: Checkpoint) -> Result {
+        if starts_statement(self.peek()) {
+            return self.statement();
+        }
+
+        let kind = self.expression()?;
+        match self.peek() {
+            // The final expression is a direct child of the block
+            Some(Token::RBrace) => Ok(()),
+            Some(Token::Semicolon) => {
+                self.builder
+                    .start_node_at(checkpoint, SyntaxKind::ExpressionStatement);
+                self.bump();
+                self.builder.finish_node();
+                Ok(())
+            }
+            _ if is_block_like(kind) => {
+                self.builder
+                    .start_node_at(checkpoint, SyntaxKind::ExpressionStatement);
+                self.builder.finish_node();
+                Ok(())
+            }
+            _ => Err(self.unexpected("`;` or `}`")),
+        }
+    }
+
+    fn if_expression(&mut self) -> Result {
+        self.start_node(SyntaxKind::IfExpression);
+        self.expect(Token::If)?;
+        self.expression()?;
+        self.block_expression()?;
+        if self.peek() == Some(&Token::Else) {
+            self.start_node(SyntaxKind::ElseBranch);
+            self.bump();
+            if self.peek() == Some(&Token::If) {
+                self.if_expression()?;
+            } else {
+                self.block_expression()?;
+            }
+            self.builder.finish_node();
+        }
+        self.builder.finish_node();
+        Ok(())
+    }
+}
+
+/// Block-like expressions may be used as statements without a trailing `;`.
+fn is_block_like(kind: SyntaxKind) -> bool {
+    matches!(kind, SyntaxKind::BlockExpression | SyntaxKind::IfExpression)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_cover_the_source_without_gaps() {
+        let source = "let x = 1; // one\n  // two\n @ \"text\"\n";
+        let tokens = tokenize(source);
+        let mut end = 0;
+        for token in &tokens {
+            assert_eq!(token.range.start, end, "gap before {token:?}");
+            end = token.range.end;
+        }
+        assert_eq!(end, source.len());
+
+        let kinds: Vec<_> = tokens
+            .iter()
+            .filter(|token| !token.kind.is_trivia())
+            .map(|token| token.kind)
+            .collect();
+        assert!(kinds.contains(&SyntaxKind::ErrorToken), "{kinds:?}");
+        assert_eq!(
+            tokens
+                .iter()
+                .filter(|token| token.kind == SyntaxKind::Comment)
+                .count(),
+            2
+        );
+    }
+}
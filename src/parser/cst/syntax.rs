@@ -0,0 +1,223 @@
+use std::{fmt, ops::Range, rc::Rc};
+
+use super::{
+    green::{GreenElement, GreenNode, GreenToken},
+    kind::SyntaxKind,
+};
+
+/// A node of the red tree: a [`GreenNode`] together with its absolute position and parent.
+///
+/// Red nodes are created on demand while walking the tree and are cheap to clone.
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green: Rc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    /// Byte offset of the node in the source.
+    offset: usize,
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: Rc<GreenNode>) -> Self {
+        SyntaxNode(Rc::new(NodeData {
+            green,
+            parent: None,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind()
+    }
+
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn parent(&self) -> Option<&SyntaxNode> {
+        self.0.parent.as_ref()
+    }
+
+    /// The byte range of the node in the source, including any trivia it contains.
+    pub fn text_range(&self) -> Range<usize> {
+        self.0.offset..self.0.offset + self.0.green.width()
+    }
+
+    /// The exact source text of the node.
+    pub fn text(&self) -> String {
+        self.0.green.to_string()
+    }
+
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> + '_ {
+        let mut offset = self.0.offset;
+        self.0.green.children().iter().map(move |child| {
+            let child_offset = offset;
+            offset += child.width();
+            match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                    green: green.clone(),
+                    parent: Some(self.clone()),
+                    offset: child_offset,
+                }))),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: green.clone(),
+                    parent: self.clone(),
+                    offset: child_offset,
+                }),
+            }
+        })
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> + '_ {
+        self.children_with_tokens()
+            .filter_map(SyntaxElement::into_node)
+    }
+
+    /// The direct child tokens, without trivia and invalid tokens.
+    pub fn tokens(&self) -> impl Iterator<Item = SyntaxToken> + '_ {
+        self.children_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .filter(|token| !token.kind().is_trivia() && token.kind() != SyntaxKind::ErrorToken)
+    }
+
+    /// The first direct child node of the given kind.
+    pub fn child(&self, kind: SyntaxKind) -> Option<SyntaxNode> {
+        self.children().find(|node| node.kind() == kind)
+    }
+
+    /// The first direct child token of the given kind.
+    pub fn child_token(&self, kind: SyntaxKind) -> Option<SyntaxToken> {
+        self.tokens().find(|token| token.kind() == kind)
+    }
+
+    /// All nodes and tokens below this node in source order, the node itself included.
+    pub fn descendants_with_tokens(&self) -> Vec<SyntaxElement> {
+        let mut elements = vec![SyntaxElement::Node(self.clone())];
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => elements.extend(node.descendants_with_tokens()),
+                token => elements.push(token),
+            }
+        }
+        elements
+    }
+
+    /// All tokens below this node in source order, trivia included.
+    pub fn descendant_tokens(&self) -> Vec<SyntaxToken> {
+        self.descendants_with_tokens()
+            .into_iter()
+            .filter_map(SyntaxElement::into_token)
+            .collect()
+    }
+
+    /// The byte range of the node without leading and trailing trivia and invalid tokens.
+    pub fn trimmed_range(&self) -> Range<usize> {
+        let tokens = self.descendant_tokens();
+        let mut significant = tokens
+            .iter()
+            .filter(|token| !token.kind().is_trivia() && token.kind() != SyntaxKind::ErrorToken);
+        match (significant.next(), significant.next_back()) {
+            (Some(first), Some(last)) => first.text_range().start..last.text_range().end,
+            (Some(only), None) => only.text_range(),
+            _ => {
+                let start = self.text_range().start;
+                start..start
+            }
+        }
+    }
+}
+
+/// Prints the tree with one element per line, e.g. `Literal@4..6`.
+impl fmt::Debug for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+impl SyntaxNode {
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        writeln!(f, "{:indent$}{}@{:?}", "", self.kind(), self.text_range())?;
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => node.fmt_indented(f, indent + 2)?,
+                SyntaxElement::Token(token) => writeln!(f, "{:1$}{token:?}", "", indent + 2)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A token of the red tree: a [`GreenToken`] together with its absolute position and parent.
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    parent: SyntaxNode,
+    offset: usize,
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind()
+    }
+
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    pub fn text_range(&self) -> Range<usize> {
+        self.offset..self.offset + self.green.width()
+    }
+
+    pub fn parent(&self) -> &SyntaxNode {
+        &self.parent
+    }
+}
+
+impl fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}@{:?} {:?}",
+            self.kind(),
+            self.text_range(),
+            self.text()
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            SyntaxElement::Node(node) => node.kind(),
+            SyntaxElement::Token(token) => token.kind(),
+        }
+    }
+
+    pub fn text_range(&self) -> Range<usize> {
+        match self {
+            SyntaxElement::Node(node) => node.text_range(),
+            SyntaxElement::Token(token) => token.text_range(),
+        }
+    }
+
+    pub fn into_node(self) -> Option<SyntaxNode> {
+        match self {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        }
+    }
+
+    pub fn into_token(self) -> Option<SyntaxToken> {
+        match self {
+            SyntaxElement::Node(_) => None,
+            SyntaxElement::Token(token) => Some(token),
+        }
+    }
+}
@@ -2,15 +2,17 @@ use std::io::{self, Write};
 
 use termcolor::{Color, ColorSpec, WriteColor};
 
-use crate::Format;
+use crate::core::format::Format;
 
 use super::ast::{
-    BinaryOp, BooleanLiteral, Expression, FloatLiteral, FunctionDeclaration, Identifier,
-    IntegerLiteral, NamedFieldDeclaration, Parameter, Program, Statement, StringLiteral,
-    StructDeclaration, TupleFieldDeclaration, VariableDeclaration,
+    Attribute, BinaryOp, BlockExpression, BooleanLiteral, BreakStatement, CharLiteral,
+    ErrorStatement, Expression, ExpressionStatement, FieldAccess, FloatLiteral, FunctionCall,
+    FunctionDeclaration, Identifier, IfExpression, IntegerLiteral, MethodCall,
+    NamedFieldDeclaration, Parameter, Program, ReturnStatement, Statement, StringLiteral,
+    StructDeclaration, TupleFieldDeclaration, UnaryOp, UseDeclaration, VariableDeclaration,
+    Visibility,
 };
 
-
 fn bracket_theme<W>(stdout: &mut W) -> io::Result<()>
 where
     W: Write + WriteColor,
@@ -68,6 +70,18 @@ where
     )
 }
 
+/// Marks public items, private ones are the default and left unmarked.
+fn visibility_property<W>(stdout: &mut W, visibility: Visibility) -> io::Result<()>
+where
+    W: Write + WriteColor,
+{
+    if visibility == Visibility::Public {
+        property_theme(stdout)?;
+        write!(stdout, " visibility = pub")?;
+    }
+    Ok(())
+}
+
 impl Format for Program {
     fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
     where
@@ -82,7 +96,7 @@ impl Format for Program {
         span_theme(stdout)?;
         write!(stdout, " {}", self.span)?;
         bracket_theme(stdout)?;
-        write!(stdout, "]\n")?;
+        writeln!(stdout, "]")?;
         stdout.reset()?;
         for statement in &self.statements {
             statement.format(stdout, indent, level + 1)?;
@@ -96,16 +110,16 @@ impl Format for Statement {
     where
         W: Write + WriteColor,
     {
-        let prefix = " ".repeat(indent * level);
         match self {
-            Statement::VariableDeclaration(v) => v.format(stdout, indent, level)?,
-            Statement::FunctionDeclaration(v) => v.format(stdout, indent, level)?,
-            Statement::StructDeclaration(v) => v.format(stdout, indent, level)?,
-            Statement::ExpressionStatement(v) => todo!(),
-            Statement::ReturnStatement(v) => todo!(),
-            Statement::BreakStatement(v) => todo!(),
-        };
-        Ok(())
+            Statement::VariableDeclaration(v) => v.format(stdout, indent, level),
+            Statement::FunctionDeclaration(v) => v.format(stdout, indent, level),
+            Statement::StructDeclaration(v) => v.format(stdout, indent, level),
+            Statement::UseDeclaration(v) => v.format(stdout, indent, level),
+            Statement::ExpressionStatement(v) => v.format(stdout, indent, level),
+            Statement::ReturnStatement(v) => v.format(stdout, indent, level),
+            Statement::BreakStatement(v) => v.format(stdout, indent, level),
+            Statement::Error(v) => v.format(stdout, indent, level),
+        }
     }
 }
 
@@ -123,10 +137,13 @@ impl Format for VariableDeclaration {
         span_theme(stdout)?;
         write!(stdout, " {}", self.span)?;
         bracket_theme(stdout)?;
-        write!(stdout, "]\n")?;
+        writeln!(stdout, "]")?;
         stdout.reset()?;
         // Print the variable declaration details
         self.identifier.format(stdout, indent, level + 1)?;
+        if let Some(declared_type) = &self.declared_type {
+            declared_type.format(stdout, indent, level + 1)?;
+        }
         self.initializer.format(stdout, indent, level + 1)?;
         Ok(())
     }
@@ -138,7 +155,6 @@ impl Format for FunctionDeclaration {
         W: Write + WriteColor,
     {
         let prefix = " ".repeat(indent * level);
-        let prefix_plus = " ".repeat(indent * (level + 1));
         write!(stdout, "{}", prefix)?;
         bracket_theme(stdout)?;
         write!(stdout, "[")?;
@@ -146,19 +162,46 @@ impl Format for FunctionDeclaration {
         write!(stdout, "FunctionDeclaration")?;
         span_theme(stdout)?;
         write!(stdout, " {}", self.span)?;
+        visibility_property(stdout, self.visibility)?;
         bracket_theme(stdout)?;
-        write!(stdout, "]\n")?;
+        writeln!(stdout, "]")?;
         // Print the function declaration details
-        // e.g., identifier, parameters, return type
+        // e.g., attributes, identifier, parameters, return type
+        for attribute in &self.attributes {
+            attribute.format(stdout, indent, level + 1)?;
+        }
         self.identifier.format(stdout, indent, level + 1)?;
         for parameter in &self.parameters {
             parameter.format(stdout, indent, level + 1)?;
         }
         self.return_type.format(stdout, indent, level + 1)?;
+        for statement in &self.body {
+            statement.format(stdout, indent, level + 1)?;
+        }
         Ok(())
     }
 }
 
+impl Format for Attribute {
+    fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
+    where
+        W: Write + WriteColor,
+    {
+        let prefix = " ".repeat(indent * level);
+        write!(stdout, "{}", prefix)?;
+        bracket_theme(stdout)?;
+        write!(stdout, "[")?;
+        node_theme(stdout)?;
+        write!(stdout, "Attribute")?;
+        span_theme(stdout)?;
+        write!(stdout, " {}", self.span)?;
+        bracket_theme(stdout)?;
+        writeln!(stdout, "]")?;
+        stdout.reset()?;
+        self.name.format(stdout, indent, level + 1)
+    }
+}
+
 impl Format for Parameter {
     fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
     where
@@ -173,7 +216,7 @@ impl Format for Parameter {
         span_theme(stdout)?;
         write!(stdout, " {}", self.span)?;
         bracket_theme(stdout)?;
-        write!(stdout, "]\n")?;
+        writeln!(stdout, "]")?;
         stdout.reset()?;
         // Print the parameter details
         self.identifier.format(stdout, indent, level + 1)?;
@@ -190,6 +233,7 @@ impl Format for StructDeclaration {
         let prefix = " ".repeat(indent * level);
         match self {
             StructDeclaration::NamedStruct {
+                visibility,
                 identifier,
                 fields,
                 span,
@@ -201,8 +245,9 @@ impl Format for StructDeclaration {
                 write!(stdout, "StructDeclaration::NamedStruct")?;
                 span_theme(stdout)?;
                 write!(stdout, " {}", span)?;
+                visibility_property(stdout, *visibility)?;
                 bracket_theme(stdout)?;
-                write!(stdout, "]\n")?;
+                writeln!(stdout, "]")?;
                 stdout.reset()?;
                 identifier.format(stdout, indent, level + 1)?;
                 for field in fields {
@@ -210,6 +255,7 @@ impl Format for StructDeclaration {
                 }
             }
             StructDeclaration::TupleStruct {
+                visibility,
                 identifier,
                 fields,
                 span,
@@ -221,15 +267,20 @@ impl Format for StructDeclaration {
                 write!(stdout, "StructDeclaration::TupleStruct")?;
                 span_theme(stdout)?;
                 write!(stdout, " {}", span)?;
+                visibility_property(stdout, *visibility)?;
                 bracket_theme(stdout)?;
-                write!(stdout, "]\n")?;
+                writeln!(stdout, "]")?;
                 stdout.reset()?;
                 identifier.format(stdout, indent, level + 1)?;
                 for field in fields {
                     field.format(stdout, indent, level + 1)?;
                 }
             }
-            StructDeclaration::UnitStruct { identifier, span } => {
+            StructDeclaration::UnitStruct {
+                visibility,
+                identifier,
+                span,
+            } => {
                 write!(stdout, "{}", prefix)?;
                 bracket_theme(stdout)?;
                 write!(stdout, "[")?;
@@ -237,8 +288,9 @@ impl Format for StructDeclaration {
                 write!(stdout, "StructDeclaration::UnitStruct")?;
                 span_theme(stdout)?;
                 write!(stdout, " {}", span)?;
+                visibility_property(stdout, *visibility)?;
                 bracket_theme(stdout)?;
-                write!(stdout, "]\n")?;
+                writeln!(stdout, "]")?;
                 stdout.reset()?;
                 identifier.format(stdout, indent, level + 1)?;
             }
@@ -261,7 +313,7 @@ impl Format for NamedFieldDeclaration {
         span_theme(stdout)?;
         write!(stdout, " {}", self.span)?;
         bracket_theme(stdout)?;
-        write!(stdout, "]\n")?;
+        writeln!(stdout, "]")?;
         stdout.reset()?;
         // Print the field declaration details
         self.identifier.format(stdout, indent, level + 1)?;
@@ -285,12 +337,31 @@ impl Format for TupleFieldDeclaration {
         span_theme(stdout)?;
         write!(stdout, " {}", self.span)?;
         bracket_theme(stdout)?;
-        write!(stdout, "]\n")?;
+        writeln!(stdout, "]")?;
         self.declared_type.format(stdout, indent, level + 1)?;
         stdout.reset()
     }
 }
 
+impl Format for ErrorStatement {
+    fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
+    where
+        W: Write + WriteColor,
+    {
+        let prefix = " ".repeat(indent * level);
+        write!(stdout, "{}", prefix)?;
+        bracket_theme(stdout)?;
+        write!(stdout, "[")?;
+        node_theme(stdout)?;
+        write!(stdout, "ErrorStatement")?;
+        span_theme(stdout)?;
+        write!(stdout, " {}", self.span)?;
+        bracket_theme(stdout)?;
+        writeln!(stdout, "]")?;
+        stdout.reset()
+    }
+}
+
 impl Format for Identifier {
     fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
     where
@@ -307,7 +378,7 @@ impl Format for Identifier {
         property_theme(stdout)?;
         write!(stdout, "name = \"{}\"", self.name)?;
         bracket_theme(stdout)?;
-        write!(stdout, "]\n")?;
+        writeln!(stdout, "]")?;
         stdout.reset()
     }
 }
@@ -319,15 +390,18 @@ impl Format for Expression {
     {
         match self {
             Expression::BinaryOp(v) => v.format(stdout, indent, level),
-            //Expression::FunctionCall(v) => v.format(stdout, indent, level),
-            //Expression::BlockExpression(v) => v.format(stdout, indent, level),
-            //Expression::IfExpression(v) => v.format(stdout, indent, level),
+            Expression::UnaryOp(v) => v.format(stdout, indent, level),
+            Expression::FunctionCall(v) => v.format(stdout, indent, level),
+            Expression::FieldAccess(v) => v.format(stdout, indent, level),
+            Expression::MethodCall(v) => v.format(stdout, indent, level),
+            Expression::BlockExpression(v) => v.format(stdout, indent, level),
+            Expression::IfExpression(v) => v.format(stdout, indent, level),
             Expression::Identifier(v) => v.format(stdout, indent, level),
             Expression::IntegerLiteral(v) => v.format(stdout, indent, level),
             Expression::FloatLiteral(v) => v.format(stdout, indent, level),
-            //Expression::StringLiteral(v) => v.format(stdout, indent, level),
+            Expression::StringLiteral(v) => v.format(stdout, indent, level),
+            Expression::CharLiteral(v) => v.format(stdout, indent, level),
             Expression::BooleanLiteral(v) => v.format(stdout, indent, level),
-            other => todo!("Implement formatting for {:?}", other),
         }
     }
 }
@@ -348,16 +422,245 @@ impl Format for BinaryOp {
         property_theme(stdout)?;
         write!(stdout, " operator = \"{}\"", self.operator)?;
         bracket_theme(stdout)?;
-        write!(stdout, "]\n")?;
+        writeln!(stdout, "]")?;
         stdout.reset()?;
 
         // Print the left and right expressions
         self.left.format(stdout, indent, level + 1)?;
         self.right.format(stdout, indent, level + 1)?;
 
-        // Print the operator
+        Ok(())
+    }
+}
+
+impl Format for UnaryOp {
+    fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
+    where
+        W: Write + WriteColor,
+    {
+        let prefix = " ".repeat(indent * level);
+        write!(stdout, "{}", prefix)?;
+        bracket_theme(stdout)?;
+        write!(stdout, "[")?;
+        node_theme(stdout)?;
+        write!(stdout, "UnaryOp")?;
+        span_theme(stdout)?;
+        write!(stdout, " {}", self.span)?;
         property_theme(stdout)?;
+        write!(stdout, " operator = \"{}\"", self.operator)?;
+        bracket_theme(stdout)?;
+        writeln!(stdout, "]")?;
+        stdout.reset()?;
+        self.operand.format(stdout, indent, level + 1)
+    }
+}
+
+impl Format for FunctionCall {
+    fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
+    where
+        W: Write + WriteColor,
+    {
+        let prefix = " ".repeat(indent * level);
+        write!(stdout, "{}", prefix)?;
+        bracket_theme(stdout)?;
+        write!(stdout, "[")?;
+        node_theme(stdout)?;
+        write!(stdout, "FunctionCall")?;
+        span_theme(stdout)?;
+        write!(stdout, " {}", self.span)?;
+        bracket_theme(stdout)?;
+        writeln!(stdout, "]")?;
+        stdout.reset()?;
+        self.function_name.format(stdout, indent, level + 1)?;
+        for argument in &self.arguments {
+            argument.format(stdout, indent, level + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Format for FieldAccess {
+    fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
+    where
+        W: Write + WriteColor,
+    {
+        let prefix = " ".repeat(indent * level);
+        write!(stdout, "{}", prefix)?;
+        bracket_theme(stdout)?;
+        write!(stdout, "[")?;
+        node_theme(stdout)?;
+        write!(stdout, "FieldAccess")?;
+        span_theme(stdout)?;
+        write!(stdout, " {}", self.span)?;
+        bracket_theme(stdout)?;
+        writeln!(stdout, "]")?;
+        stdout.reset()?;
+        self.object.format(stdout, indent, level + 1)?;
+        self.field.format(stdout, indent, level + 1)
+    }
+}
 
+impl Format for MethodCall {
+    fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
+    where
+        W: Write + WriteColor,
+    {
+        let prefix = " ".repeat(indent * level);
+        write!(stdout, "{}", prefix)?;
+        bracket_theme(stdout)?;
+        write!(stdout, "[")?;
+        node_theme(stdout)?;
+        write!(stdout, "MethodCall")?;
+        span_theme(stdout)?;
+        write!(stdout, " {}", self.span)?;
+        bracket_theme(stdout)?;
+        writeln!(stdout, "]")?;
+        stdout.reset()?;
+        self.receiver.format(stdout, indent, level + 1)?;
+        self.method.format(stdout, indent, level + 1)?;
+        for argument in &self.arguments {
+            argument.format(stdout, indent, level + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Format for BlockExpression {
+    fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
+    where
+        W: Write + WriteColor,
+    {
+        let prefix = " ".repeat(indent * level);
+        write!(stdout, "{}", prefix)?;
+        bracket_theme(stdout)?;
+        write!(stdout, "[")?;
+        node_theme(stdout)?;
+        write!(stdout, "BlockExpression")?;
+        span_theme(stdout)?;
+        write!(stdout, " {}", self.span)?;
+        bracket_theme(stdout)?;
+        writeln!(stdout, "]")?;
+        stdout.reset()?;
+        for statement in &self.statements {
+            statement.format(stdout, indent, level + 1)?;
+        }
+        if let Some(final_expression) = &self.final_expression {
+            final_expression.format(stdout, indent, level + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Format for IfExpression {
+    fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
+    where
+        W: Write + WriteColor,
+    {
+        let prefix = " ".repeat(indent * level);
+        write!(stdout, "{}", prefix)?;
+        bracket_theme(stdout)?;
+        write!(stdout, "[")?;
+        node_theme(stdout)?;
+        write!(stdout, "IfExpression")?;
+        span_theme(stdout)?;
+        write!(stdout, " {}", self.span)?;
+        bracket_theme(stdout)?;
+        writeln!(stdout, "]")?;
+        stdout.reset()?;
+        self.condition.format(stdout, indent, level + 1)?;
+        self.then_branch.format(stdout, indent, level + 1)?;
+        if let Some(else_branch) = &self.else_branch {
+            else_branch.format(stdout, indent, level + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Format for ExpressionStatement {
+    fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
+    where
+        W: Write + WriteColor,
+    {
+        let prefix = " ".repeat(indent * level);
+        write!(stdout, "{}", prefix)?;
+        bracket_theme(stdout)?;
+        write!(stdout, "[")?;
+        node_theme(stdout)?;
+        write!(stdout, "ExpressionStatement")?;
+        span_theme(stdout)?;
+        write!(stdout, " {}", self.span)?;
+        bracket_theme(stdout)?;
+        writeln!(stdout, "]")?;
+        stdout.reset()?;
+        self.expression.format(stdout, indent, level + 1)
+    }
+}
+
+impl Format for ReturnStatement {
+    fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
+    where
+        W: Write + WriteColor,
+    {
+        let prefix = " ".repeat(indent * level);
+        write!(stdout, "{}", prefix)?;
+        bracket_theme(stdout)?;
+        write!(stdout, "[")?;
+        node_theme(stdout)?;
+        write!(stdout, "ReturnStatement")?;
+        span_theme(stdout)?;
+        write!(stdout, " {}", self.span)?;
+        bracket_theme(stdout)?;
+        writeln!(stdout, "]")?;
+        stdout.reset()?;
+        if let Some(value) = &self.value {
+            value.format(stdout, indent, level + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Format for UseDeclaration {
+    fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
+    where
+        W: Write + WriteColor,
+    {
+        let prefix = " ".repeat(indent * level);
+        write!(stdout, "{}", prefix)?;
+        bracket_theme(stdout)?;
+        write!(stdout, "[")?;
+        node_theme(stdout)?;
+        write!(stdout, "UseDeclaration")?;
+        span_theme(stdout)?;
+        write!(stdout, " {}", self.span)?;
+        bracket_theme(stdout)?;
+        writeln!(stdout, "]")?;
+        stdout.reset()?;
+        for segment in &self.path {
+            segment.format(stdout, indent, level + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Format for BreakStatement {
+    fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
+    where
+        W: Write + WriteColor,
+    {
+        let prefix = " ".repeat(indent * level);
+        write!(stdout, "{}", prefix)?;
+        bracket_theme(stdout)?;
+        write!(stdout, "[")?;
+        node_theme(stdout)?;
+        write!(stdout, "BreakStatement")?;
+        span_theme(stdout)?;
+        write!(stdout, " {}", self.span)?;
+        bracket_theme(stdout)?;
+        writeln!(stdout, "]")?;
+        stdout.reset()?;
+        if let Some(value) = &self.value {
+            value.format(stdout, indent, level + 1)?;
+        }
         Ok(())
     }
 }
@@ -377,8 +680,11 @@ impl Format for IntegerLiteral {
         write!(stdout, " {}", self.span)?;
         property_theme(stdout)?;
         write!(stdout, " value = {}", self.value)?;
+        if let Some(inferred_type) = &self.inferred_type {
+            write!(stdout, " type = {}", inferred_type.name)?;
+        }
         bracket_theme(stdout)?;
-        write!(stdout, "]\n")?;
+        writeln!(stdout, "]")?;
         stdout.reset()
     }
 }
@@ -398,8 +704,11 @@ impl Format for FloatLiteral {
         write!(stdout, " {}", self.span)?;
         property_theme(stdout)?;
         write!(stdout, " value = {}", self.value)?;
+        if let Some(inferred_type) = &self.inferred_type {
+            write!(stdout, " type = {}", inferred_type.name)?;
+        }
         bracket_theme(stdout)?;
-        write!(stdout, "]\n")?;
+        writeln!(stdout, "]")?;
         stdout.reset()
     }
 }
@@ -420,7 +729,28 @@ impl Format for StringLiteral {
         property_theme(stdout)?;
         write!(stdout, " value = \"{}\"", self.value)?;
         bracket_theme(stdout)?;
-        write!(stdout, "]\n")?;
+        writeln!(stdout, "]")?;
+        stdout.reset()
+    }
+}
+
+impl Format for CharLiteral {
+    fn format<W>(&self, stdout: &mut W, indent: usize, level: usize) -> io::Result<()>
+    where
+        W: Write + WriteColor,
+    {
+        let prefix = " ".repeat(indent * level);
+        write!(stdout, "{}", prefix)?;
+        bracket_theme(stdout)?;
+        write!(stdout, "[")?;
+        node_theme(stdout)?;
+        write!(stdout, "CharLiteral")?;
+        span_theme(stdout)?;
+        write!(stdout, " {}", self.span)?;
+        property_theme(stdout)?;
+        write!(stdout, " value = {:?}", self.value)?;
+        bracket_theme(stdout)?;
+        writeln!(stdout, "]")?;
         stdout.reset()
     }
 }
@@ -441,7 +771,7 @@ impl Format for BooleanLiteral {
         property_theme(stdout)?;
         write!(stdout, " value = {}", self.value)?;
         bracket_theme(stdout)?;
-        write!(stdout, "]\n")?;
+        writeln!(stdout, "]")?;
         stdout.reset()
     }
 }
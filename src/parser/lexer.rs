@@ -3,6 +3,8 @@ use std::num::{ParseFloatError, ParseIntError};
 use derive_more::{Display, Error, From};
 use logos::Logos;
 
+use crate::core::types::{FloatType, IntegerType};
+
 pub type Result = std::result::Result<Token, LexerError>;
 
 #[derive(Debug, Clone, PartialEq, Default, From, Display, Error)]
@@ -11,13 +13,20 @@ pub enum LexerError {
     ParseIntError(ParseIntError),
     #[display("{_0}")]
     ParseFloatError(ParseFloatError),
+    #[display("invalid escape sequence")]
+    InvalidEscape,
+    #[display("character literal must contain exactly one character")]
+    InvalidCharLiteral,
     #[default]
+    #[display("invalid token")]
     Other,
 }
 
 #[derive(Logos, Debug, PartialEq, Clone)]
-// skip whitespace
+// skip whitespace and line comments, the concrete syntax tree recovers them from the gaps
+// between tokens
 #[logos(skip r"[ \t\n\f]+")]
+#[logos(skip r"//[^\n]*")]
 #[logos(error = LexerError)]
 pub enum Token {
     // -- Keywords --
@@ -47,6 +56,10 @@ pub enum Token {
     Else,
     #[token("return")]
     Return,
+    #[token("pub")]
+    Pub,
+    #[token("use")]
+    Use,
 
     // -- Operators --
     #[token("+")]
@@ -61,6 +74,9 @@ pub enum Token {
     #[token("/")]
     /// `/`
     Slash,
+    #[token("%")]
+    /// `%`
+    Percent,
     #[token("=")]
     /// `=`
     Assign,
@@ -76,12 +92,36 @@ pub enum Token {
     #[token(">")]
     /// `>`
     GreaterThan,
+    #[token("<=")]
+    /// `<=`
+    LessThanOrEqual,
+    #[token(">=")]
+    /// `>=`
+    GreaterThanOrEqual,
     #[token("&&")]
     /// `&&`
     And,
     #[token("||")]
     /// `||`
     Or,
+    #[token("!")]
+    /// `!`
+    Bang,
+    #[token("&")]
+    /// `&`
+    Ampersand,
+    #[token("|")]
+    /// `|`
+    Pipe,
+    #[token("^")]
+    /// `^`
+    Caret,
+    #[token("<<")]
+    /// `<<`
+    ShiftLeft,
+    #[token(">>")]
+    /// `>>`
+    ShiftRight,
 
     // -- Delimiters --
     /// `(`
@@ -117,31 +157,208 @@ pub enum Token {
     #[token("->")]
     /// `->`
     RightArrow,
+    #[token("::")]
+    /// `::`
+    PathSeparator,
+    #[token("#")]
+    /// `#`, starts an attribute
+    Hash,
 
     // -- Identifier --
     #[regex("([a-zA-Z_][a-zA-Z0-9_]*)", |lex| lex.slice().to_string())]
     Identifier(String),
 
     // -- Literals --
-    #[regex("[0-9]+", |lex| lex.slice().parse::<i64>())]
-    IntegerLiteral(i64),
+    #[regex("[0-9][0-9_]*(i8|i16|i32|i64|u8|u16|u32|u64)?", |lex| parse_integer_literal(lex.slice(), 10))]
+    #[regex("0x[0-9a-fA-F_]+(i8|i16|i32|i64|u8|u16|u32|u64)?", |lex| parse_integer_literal(&lex.slice()[2..], 16))]
+    #[regex("0o[0-7_]+(i8|i16|i32|i64|u8|u16|u32|u64)?", |lex| parse_integer_literal(&lex.slice()[2..], 8))]
+    #[regex("0b[01_]+(i8|i16|i32|i64|u8|u16|u32|u64)?", |lex| parse_integer_literal(&lex.slice()[2..], 2))]
+    IntegerLiteral(IntegerToken),
 
-    #[regex("[0-9]+\\.[0-9]+", |lex| lex.slice().parse::<f64>())]
-    FloatLiteral(f64),
+    #[regex("[0-9][0-9_]*\\.[0-9][0-9_]*([eE][+-]?[0-9_]+)?(f32|f64)?", |lex| parse_float_literal(lex.slice()))]
+    #[regex("[0-9][0-9_]*[eE][+-]?[0-9_]+(f32|f64)?", |lex| parse_float_literal(lex.slice()))]
+    #[regex("[0-9][0-9_]*(f32|f64)", |lex| parse_float_literal(lex.slice()))]
+    FloatLiteral(FloatToken),
 
     // RegExp:
     // Anything inside quotes:
     // | Except for " and \
     // OR
     // | \ followed by a single character
-    // TODO: Maybe the heavy-lifting should be done in [`parse_string_literal`] for better error
-    // handling and more tolerant/flexible string matching. I.e. still matching incorrect escape
-    // sequences but showing an error for them. (Incorrect escape sequence => still a String)
+    // Escape sequences are validated by [`parse_string_literal`], so incorrect ones are still
+    // matched as a string but reported as a [`LexerError::InvalidEscape`].
     #[regex("\"([^\"\\\\]|\\\\.)*\"", |lex| parse_string_literal(lex.slice()))]
     String(String),
+
+    // RegExp:
+    // Anything inside single quotes:
+    // | Except for ' and \
+    // OR
+    // | \ followed by a single character
+    // The number of characters is checked by [`parse_char_literal`].
+    #[regex("'([^'\\\\]|\\\\.)*'", |lex| parse_char_literal(lex.slice()))]
+    Char(char),
+}
+
+/// Describes the token the way it is written in source code, for use in error messages.
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Token::True => "true",
+            Token::False => "false",
+            Token::Let => "let",
+            Token::Mut => "mut",
+            Token::Type => "type",
+            Token::Struct => "struct",
+            Token::Fn => "fn",
+            Token::While => "while",
+            Token::Loop => "loop",
+            Token::For => "for",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::Return => "return",
+            Token::Pub => "pub",
+            Token::Use => "use",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::Assign => "=",
+            Token::Equals => "==",
+            Token::NotEquals => "!=",
+            Token::LessThan => "<",
+            Token::GreaterThan => ">",
+            Token::LessThanOrEqual => "<=",
+            Token::GreaterThanOrEqual => ">=",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::Bang => "!",
+            Token::Ampersand => "&",
+            Token::Pipe => "|",
+            Token::Caret => "^",
+            Token::ShiftLeft => "<<",
+            Token::ShiftRight => ">>",
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::LBracket => "[",
+            Token::RBracket => "]",
+            Token::Semicolon => ";",
+            Token::Colon => ":",
+            Token::Comma => ",",
+            Token::Period => ".",
+            Token::RightArrow => "->",
+            Token::PathSeparator => "::",
+            Token::Hash => "#",
+            Token::Identifier(name) => return write!(f, "identifier `{}`", name),
+            Token::IntegerLiteral(_) => return write!(f, "integer literal"),
+            Token::FloatLiteral(_) => return write!(f, "float literal"),
+            Token::String(_) => return write!(f, "string literal"),
+            Token::Char(_) => return write!(f, "char literal"),
+        };
+        write!(f, "`{}`", symbol)
+    }
+}
+
+/// Payload of [`Token::IntegerLiteral`].
+///
+/// The value is kept unsigned, negative numbers are produced by the unary `-` operator.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct IntegerToken {
+    pub value: u64,
+    pub suffix: Option<IntegerType>,
+}
+
+/// Payload of [`Token::FloatLiteral`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FloatToken {
+    pub value: f64,
+    pub suffix: Option<FloatType>,
+}
+
+/// Splits a trailing type suffix (e.g. `u8`, `f32`) off a numeric literal.
+///
+/// Only called on slices the lexer regexes have already validated, so the suffix (if any) is
+/// guaranteed to start at the first `i`, `u` or `f` character. Hexadecimal digits never contain
+/// `i` or `u`, and hexadecimal literals cannot carry a float suffix.
+fn split_suffix<'s>(digits: &'s str, suffix_start: &[char]) -> (&'s str, Option<&'s str>) {
+    match digits.find(suffix_start) {
+        Some(index) => (&digits[..index], Some(&digits[index..])),
+        None => (digits, None),
+    }
+}
+
+fn parse_integer_literal(
+    lexed_slice: &str,
+    radix: u32,
+) -> std::result::Result<IntegerToken, LexerError> {
+    let (digits, suffix) = split_suffix(lexed_slice, &['i', 'u']);
+    let digits = digits.replace('_', "");
+    let value = u64::from_str_radix(&digits, radix)?;
+    let suffix = suffix.and_then(IntegerType::from_name);
+    Ok(IntegerToken { value, suffix })
+}
+
+fn parse_float_literal(lexed_slice: &str) -> std::result::Result<FloatToken, LexerError> {
+    let (digits, suffix) = split_suffix(lexed_slice, &['f']);
+    let digits = digits.replace('_', "");
+    let value = digits.parse::<f64>()?;
+    let suffix = suffix.and_then(FloatType::from_name);
+    Ok(FloatToken { value, suffix })
+}
+
+/// Replaces the escape sequences in the contents of a string or char literal.
+///
+/// Supported are `\n`, `\r`, `\t`, `\\`, `\0`, `\'`, `\"` and unicode escapes like `\u{1F980}`.
+fn unescape(contents: &str) -> std::result::Result<String, LexerError> {
+    let mut unescaped = String::with_capacity(contents.len());
+    let mut chars = contents.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        let escaped = match chars.next().ok_or(LexerError::InvalidEscape)? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            '\\' => '\\',
+            '\'' => '\'',
+            '"' => '"',
+            'u' => {
+                let rest = chars.as_str();
+                let code = rest
+                    .strip_prefix('{')
+                    .and_then(|rest| rest.split_once('}'))
+                    .map(|(code, _)| code)
+                    .ok_or(LexerError::InvalidEscape)?;
+                // Skip `{`, the hex digits and `}`
+                chars.nth(code.len() + 1);
+                u32::from_str_radix(code, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or(LexerError::InvalidEscape)?
+            }
+            _ => return Err(LexerError::InvalidEscape),
+        };
+        unescaped.push(escaped);
+    }
+    Ok(unescaped)
+}
+
+fn parse_string_literal(lexed_slice: &str) -> std::result::Result<String, LexerError> {
+    // Strip the surrounding quotes
+    unescape(&lexed_slice[1..lexed_slice.len() - 1])
 }
 
-fn parse_string_literal(lexed_slice: &str) -> Option<String> {
-    // TODO: Parse escape sequences into real string
-    return Some(lexed_slice.to_string());
+fn parse_char_literal(lexed_slice: &str) -> std::result::Result<char, LexerError> {
+    let unescaped = unescape(&lexed_slice[1..lexed_slice.len() - 1])?;
+    let mut chars = unescaped.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(LexerError::InvalidCharLiteral),
+    }
 }
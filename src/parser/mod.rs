@@ -1,46 +1,147 @@
-use derive_more::{Display, Error, From};
-use logos::{Lexer, Logos};
+use derive_more::{Display, Error};
+use logos::Lexer;
 
 use self::{
     ast::{
-        BinaryOp, BinaryOperator, Expression, FloatLiteral, FunctionDeclaration, Identifier,
-        IntegerLiteral, NamedFieldDeclaration, Parameter, Program, ReturnStatement, Statement,
-        StructDeclaration, TupleFieldDeclaration, VariableDeclaration,
+        Attribute, BinaryOp, BinaryOperator, BlockExpression, BooleanLiteral, CharLiteral,
+        ErrorStatement, Expression, ExpressionStatement, FieldAccess, FloatLiteral, FunctionCall,
+        FunctionDeclaration, Identifier, IfExpression, IntegerLiteral, MethodCall,
+        NamedFieldDeclaration, Parameter, Program, ReturnStatement, Statement, StringLiteral,
+        StructDeclaration, TupleFieldDeclaration, UnaryOp, UnaryOperator, UseDeclaration,
+        VariableDeclaration, Visibility,
     },
-    lexer::{LexerError, Token},
+    lexer::{FloatToken, IntegerToken, LexerError, Token},
+};
+use crate::core::{
+    diagnostic::{Diagnostic, ToDiagnostic},
+    source_map::FileId,
+    span::{Span, Spanned},
 };
-use crate::core::span::{Span, Spanned};
 
 pub mod ast;
-pub mod lexer;
+pub mod cst;
 pub mod format;
+pub mod lexer;
+pub mod sexpr;
 
-#[derive(Debug, From, PartialEq, Display, Error)]
+#[derive(Debug, PartialEq, Clone, Display, Error)]
 pub enum ParserError {
-    #[display("{_0}")]
-    LexerError(LexerError),
-    #[display(
-        "unexpected token, expected: {}, found: {:?}, span: {:?}",
-        expected,
-        found,
-        span
-    )]
+    #[display("{error}")]
+    LexerError { error: LexerError, span: Span },
+    #[display("expected {expected}, found {}", describe_found(found))]
     UnexpectedToken {
         expected: String,
         found: Option<Token>,
         span: Span,
     },
     #[display("unexpected end of file")]
-    UnexpectedEof,
+    UnexpectedEof { span: Span },
+    #[display("code is nested more than {MAX_NESTING} levels deep")]
+    NestingTooDeep { span: Span },
+}
+
+/// How deeply statements and expressions may be nested, the parsers and every pass over the
+/// syntax tree recurse into each level.
+pub const MAX_NESTING: usize = 64;
+
+fn describe_found(found: &Option<Token>) -> String {
+    match found {
+        Some(token) => token.to_string(),
+        None => "end of file".to_string(),
+    }
+}
+
+impl Spanned for ParserError {
+    fn span(&self) -> Span {
+        match self {
+            ParserError::LexerError { span, .. }
+            | ParserError::UnexpectedToken { span, .. }
+            | ParserError::UnexpectedEof { span }
+            | ParserError::NestingTooDeep { span } => *span,
+        }
+    }
+}
+
+impl ToDiagnostic for ParserError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string());
+        match self {
+            ParserError::LexerError { span, .. } => {
+                diagnostic.with_primary_label(*span, "invalid token")
+            }
+            ParserError::UnexpectedToken {
+                expected,
+                found: Some(Token::Identifier(_)),
+                span,
+            } if expected.as_str() == "`:`" => diagnostic
+                .with_primary_label(*span, "expected `:`")
+                .with_help("types are written after a colon, e.g. `x: int`"),
+            ParserError::UnexpectedToken { expected, span, .. } => {
+                diagnostic.with_primary_label(*span, format!("expected {expected}"))
+            }
+            ParserError::UnexpectedEof { span } => {
+                diagnostic.with_primary_label(*span, "the file ends here")
+            }
+            ParserError::NestingTooDeep { span } => diagnostic
+                .with_primary_label(*span, "nested too deeply")
+                .with_help("move the inner part into a variable or a function"),
+        }
+    }
+}
+
+/// The outcome of [`Parser::parse`].
+///
+/// The parser recovers from errors at statement boundaries, so `program` is always
+/// available. Statements that could not be parsed are replaced by
+/// [`Statement::Error`] nodes and the reasons are collected in `errors`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseResult {
+    pub program: Program,
+    pub errors: Vec<ParserError>,
+}
+
+impl ParseResult {
+    /// Returns the program if it was parsed without any errors.
+    pub fn into_result(self) -> Result<Program, Vec<ParserError>> {
+        if self.errors.is_empty() {
+            Ok(self.program)
+        } else {
+            Err(self.errors)
+        }
+    }
+}
+
+/// An entry of a block: either a statement or the trailing expression producing its value.
+enum BlockItem {
+    Statement(Statement),
+    FinalExpression(Expression),
 }
 
 pub struct Parser<'a> {
     lexer: Lexer<'a, Token>,
+    /// The file every produced [`Span`] refers to.
+    file: FileId,
     current: Option<(Token, Span)>,
+    /// Span of the most recently consumed token.
+    previous_span: Span,
+    /// Number of tokens consumed so far, used to guarantee progress during error recovery.
+    position: usize,
+    /// Number of statements and expressions enclosing the one being parsed.
+    depth: usize,
+    errors: Vec<ParserError>,
 }
 
 impl<'a> Parser<'a> {
+    /// Creates a parser for a source that is not registered in a [`SourceMap`], its spans refer
+    /// to the default [`FileId`].
+    ///
+    /// [`SourceMap`]: crate::core::source_map::SourceMap
     pub fn new(input: &'a str) -> Self {
+        Self::with_file(input, FileId::default())
+    }
+
+    /// Creates a parser whose spans refer to `file`.
+    pub fn with_file(input: &'a str, file: FileId) -> Self {
         trace!("Creating new lexer");
         let lexer = Lexer::new(input);
         for token in lexer.clone() {
@@ -49,21 +150,35 @@ impl<'a> Parser<'a> {
         trace!("Creating new parser");
         Parser {
             lexer,
+            file,
             current: None,
+            previous_span: Span::new(file, 0..0),
+            position: 0,
+            depth: 0,
+            errors: Vec::new(),
         }
     }
 
     /// Advances the [`Parser`] to the next [`Token`] and updates the `current` value.
-    fn advance(&mut self) -> Result<(), LexerError> {
-        if let Some(token) = self.lexer.next() {
-            let token = token?;
-            let span = self.current_span();
-            self.current = Some((token, span));
-        } else {
-            self.current = None;
+    ///
+    /// Invalid tokens are reported as [`ParserError::LexerError`] and skipped.
+    fn advance(&mut self) {
+        if let Some((_, span)) = &self.current {
+            self.previous_span = *span;
+            self.position += 1;
         }
 
-        Ok(())
+        self.current = loop {
+            match self.lexer.next() {
+                Some(Ok(token)) => break Some((token, self.current_span())),
+                Some(Err(error)) => {
+                    let span = self.current_span();
+                    trace!("Skipping invalid token at {}: {}", span, error);
+                    self.errors.push(ParserError::LexerError { error, span });
+                }
+                None => break None,
+            }
+        };
     }
 
     fn peek(&self) -> Option<&Token> {
@@ -73,18 +188,18 @@ impl<'a> Parser<'a> {
     fn consume(&mut self, expected: Token) -> Result<Span, ParserError> {
         if let Some((token, span)) = self.current.clone() {
             if token == expected {
-                self.advance()?;
-                Ok(span.clone())
+                self.advance();
+                Ok(span)
             } else {
                 Err(ParserError::UnexpectedToken {
-                    expected: format!("{:?}", expected),
+                    expected: expected.to_string(),
                     found: Some(token),
-                    span: span,
+                    span,
                 })
             }
         } else {
             Err(ParserError::UnexpectedToken {
-                expected: format!("{:?}", expected),
+                expected: expected.to_string(),
                 found: None,
                 span: self.current_span(),
             })
@@ -93,7 +208,7 @@ impl<'a> Parser<'a> {
 
     fn consume_identifier(&mut self) -> Result<Identifier, ParserError> {
         if let Some((Token::Identifier(name), span)) = self.current.as_ref().cloned() {
-            self.advance()?;
+            self.advance();
             Ok(Identifier { name, span })
         } else {
             Err(ParserError::UnexpectedToken {
@@ -104,116 +219,331 @@ impl<'a> Parser<'a> {
         }
     }
 
-    pub fn parse(mut self) -> Result<Program, ParserError> {
+    /// Parses the whole input, collecting every error instead of stopping at the first one.
+    pub fn parse(mut self) -> ParseResult {
         trace!("Parsing program");
         let program_start_span = self.current_span().start;
         let mut statements = Vec::new();
-        self.advance()?;
+        self.advance();
         while self.peek().is_some() {
-            statements.push(self.parse_statement()?);
+            let statement = self.parse_statement_or_recover();
+            // A stray `}` cannot close anything at the top level. If recovery stopped in front of
+            // it, it belongs to the malformed statement and was already reported.
+            if self.peek() == Some(&Token::RBrace) {
+                if !matches!(statement, Statement::Error(_)) {
+                    self.errors.push(ParserError::UnexpectedToken {
+                        expected: "statement".to_string(),
+                        found: Some(Token::RBrace),
+                        span: self.current_span(),
+                    });
+                }
+                self.advance();
+            }
+            statements.push(statement);
         }
 
         let program_end_span = self.current_span().end;
 
-        trace!("Successfully parsed program");
-        Ok(Program {
-            statements,
-            span: Span {
-                start: program_start_span,
-                end: program_end_span,
+        trace!("Parsed program with {} error(s)", self.errors.len());
+        ParseResult {
+            program: Program {
+                statements,
+                span: Span {
+                    file: self.file,
+                    start: program_start_span,
+                    end: program_end_span,
+                },
             },
-        })
+            errors: self.errors,
+        }
+    }
+
+    /// Parses the whole input as a single expression, without a trailing `;`.
+    pub fn parse_single_expression(mut self) -> Result<Expression, Vec<ParserError>> {
+        trace!("Parsing single expression");
+        self.advance();
+        let result = self
+            .parse_expression()
+            .and_then(|expression| match self.current.clone() {
+                None => Ok(expression),
+                Some((token, span)) => Err(ParserError::UnexpectedToken {
+                    expected: "end of input".to_string(),
+                    found: Some(token),
+                    span,
+                }),
+            });
+        match result {
+            Ok(expression) if self.errors.is_empty() => Ok(expression),
+            Ok(_) => Err(self.errors),
+            Err(error) => {
+                self.errors.push(error);
+                Err(self.errors)
+            }
+        }
+    }
+
+    /// Parses a statement, replacing it by an error node if it is malformed.
+    fn parse_statement_or_recover(&mut self) -> Statement {
+        let start_span = self.current_span();
+        let start_position = self.position;
+        match self.parse_statement() {
+            Ok(statement) => statement,
+            Err(error) => self.recover(error, start_span, start_position),
+        }
+    }
+
+    /// Records `error`, skips to the next statement boundary and returns an error node spanning
+    /// the skipped tokens.
+    fn recover(
+        &mut self,
+        error: ParserError,
+        start_span: Span,
+        start_position: usize,
+    ) -> Statement {
+        trace!("Recovering from error: {}", error);
+        self.errors.push(error);
+        self.synchronize();
+
+        // Always make progress, otherwise the same token would fail over and over again
+        if self.position == start_position && self.peek().is_some() {
+            self.advance();
+        }
+
+        let span = if self.position == start_position {
+            start_span
+        } else {
+            start_span.combine(self.previous_span)
+        };
+        ErrorStatement { span }.into()
+    }
+
+    /// Skips tokens until a statement boundary is reached.
+    ///
+    /// Stops after a `;`, before a `}` closing the enclosing block and before a keyword that
+    /// starts a statement. Nested `{ ... }` are skipped as a whole.
+    fn synchronize(&mut self) {
+        let mut depth = 0usize;
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::Semicolon if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                Token::LBrace => depth += 1,
+                Token::RBrace if depth == 0 => return,
+                Token::RBrace => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.advance();
+                        return;
+                    }
+                }
+                Token::Let
+                | Token::Hash
+                | Token::Pub
+                | Token::Fn
+                | Token::Struct
+                | Token::Use
+                | Token::Return
+                | Token::If
+                | Token::While
+                | Token::Loop
+                | Token::For
+                    if depth == 0 =>
+                {
+                    return;
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    /// Runs `parse` one nesting level deeper, failing on code nested more than [`MAX_NESTING`]
+    /// levels deep instead of overflowing the stack.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParserError>,
+    ) -> Result<T, ParserError> {
+        if self.depth == MAX_NESTING {
+            return Err(ParserError::NestingTooDeep {
+                span: self.current_span(),
+            });
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
     }
 
     fn parse_statement(&mut self) -> Result<Statement, ParserError> {
         trace!("Parsing statement");
-        match self.peek() {
-            Some(Token::Let) => self.parse_variable_declaration().map(Into::into),
-            Some(Token::Fn) => self.parse_function_declaration().map(Into::into),
-            Some(Token::Struct) => self.parse_struct_declaration().map(Into::into),
-            Some(Token::Return) => self.parse_return_statement().map(Into::into),
-            Some(other) => todo!("parse: {:#?}", other),
+        self.nested(|parser| match parser.peek() {
+            Some(Token::Let) => parser.parse_variable_declaration().map(Into::into),
+            Some(Token::Hash) | Some(Token::Pub) | Some(Token::Fn) | Some(Token::Struct) => {
+                parser.parse_item()
+            }
+            Some(Token::Use) => parser.parse_use_declaration().map(Into::into),
+            Some(Token::Return) => parser.parse_return_statement().map(Into::into),
+            Some(_) => parser.parse_expression_statement().map(Into::into),
             None => Err(ParserError::UnexpectedToken {
                 expected: "statement".to_string(),
                 found: None,
-                span: self.current_span(),
+                span: parser.current_span(),
             }),
-        }
+        })
     }
 
     fn current_span(&self) -> Span {
-        self.lexer.span().into()
+        Span::new(self.file, self.lexer.span())
     }
 
     fn parse_variable_declaration(&mut self) -> Result<VariableDeclaration, ParserError> {
         trace!("Parsing variable declaration");
         let start_span = self.consume(Token::Let)?.start;
         let identifier = self.consume_identifier()?;
-        // TODO: Parse Type if Token::Colon
+        let declared_type = if self.peek() == Some(&Token::Colon) {
+            self.advance();
+            Some(self.consume_identifier()?)
+        } else {
+            None
+        };
         let _ = self.consume(Token::Assign)?;
         let initializer = self.parse_expression()?;
         let end_span = self.consume(Token::Semicolon)?.end;
         Ok(VariableDeclaration {
             identifier,
+            declared_type,
             initializer,
             span: Span {
+                file: self.file,
                 start: start_span,
                 end: end_span,
             },
         })
     }
 
-    fn parse_function_declaration(&mut self) -> Result<FunctionDeclaration, ParserError> {
+    /// Parses a function or struct declaration, optionally preceded by `pub`. Functions may be
+    /// preceded by attributes as well.
+    fn parse_item(&mut self) -> Result<Statement, ParserError> {
+        let start = self.current_span().start;
+        let attributes = self.parse_attributes()?;
+        let visibility = if self.peek() == Some(&Token::Pub) {
+            self.advance();
+            Visibility::Public
+        } else {
+            Visibility::Private
+        };
+        match self.peek() {
+            Some(Token::Fn) => self
+                .parse_function_declaration(attributes, visibility, start)
+                .map(Into::into),
+            Some(Token::Struct) if attributes.is_empty() => self
+                .parse_struct_declaration(visibility, start)
+                .map(Into::into),
+            found => Err(ParserError::UnexpectedToken {
+                expected: if attributes.is_empty() {
+                    "`fn` or `struct`".to_string()
+                } else {
+                    "`fn`".to_string()
+                },
+                found: found.cloned(),
+                span: self.current_span(),
+            }),
+        }
+    }
+
+    /// Parses any number of `#[name]` attributes.
+    fn parse_attributes(&mut self) -> Result<Vec<Attribute>, ParserError> {
+        let mut attributes = Vec::new();
+        while self.peek() == Some(&Token::Hash) {
+            trace!("Parsing attribute");
+            let start = self.consume(Token::Hash)?.start;
+            self.consume(Token::LBracket)?;
+            let name = self.consume_identifier()?;
+            let end = self.consume(Token::RBracket)?.end;
+            attributes.push(Attribute {
+                name,
+                span: Span {
+                    file: self.file,
+                    start,
+                    end,
+                },
+            });
+        }
+        Ok(attributes)
+    }
+
+    /// Parses a function declaration starting with `fn` or the attribute or `pub` at
+    /// `start_span`.
+    fn parse_function_declaration(
+        &mut self,
+        attributes: Vec<Attribute>,
+        visibility: Visibility,
+        start_span: usize,
+    ) -> Result<FunctionDeclaration, ParserError> {
         trace!("Parsing function declaration");
-        let start_span = self.consume(Token::Fn)?.start;
+        self.consume(Token::Fn)?;
         let identifier = self.consume_identifier()?;
         let _ = self.consume(Token::LParen)?;
 
         // -- Parse Parameters --
         let mut parameters = Vec::new();
-        loop {
-            if let Some(Token::Identifier(name)) = self.peek() {
-                let identifier = self.consume_identifier()?;
-                let _ = self.consume(Token::Colon)?;
-                let declared_type = self.consume_identifier()?;
-                let span = Span {
-                    start: identifier.span.start,
-                    end: declared_type.span.end,
-                };
-                parameters.push(Parameter {
-                    identifier,
-                    declared_type,
-                    span,
-                });
+        while let Some(Token::Identifier(_)) = self.peek() {
+            let identifier = self.consume_identifier()?;
+            let _ = self.consume(Token::Colon)?;
+            let declared_type = self.consume_identifier()?;
+            let span = Span {
+                file: self.file,
+                start: identifier.span.start,
+                end: declared_type.span.end,
+            };
+            parameters.push(Parameter {
+                identifier,
+                declared_type,
+                span,
+            });
+            if self.peek() == Some(&Token::Comma) {
+                self.advance();
             } else {
                 break;
             }
         }
 
-        let _ = self.consume(Token::RParen)?;
-
-        let _ = self.consume(Token::RightArrow)?;
+        let close_paren_span = self.consume(Token::RParen)?;
 
         // -- Parse Return Type --
-        let return_type = self.consume_identifier()?;
+        // Functions without `->` return the unit type `()`
+        let return_type = if self.peek() == Some(&Token::RightArrow) {
+            self.advance();
+            self.consume_identifier()?
+        } else {
+            Identifier {
+                name: "()".to_string(),
+                span: close_paren_span,
+            }
+        };
 
         let _ = self.consume(Token::LBrace)?;
 
         // -- Parse Body --
         let mut body = Vec::new();
 
-        while self.peek() != Some(&Token::RBrace) {
-            body.push(self.parse_statement()?);
+        while !matches!(self.peek(), Some(Token::RBrace) | None) {
+            body.push(self.parse_statement_or_recover());
         }
 
         let end_span = self.consume(Token::RBrace)?.end;
 
         let span = Span {
+            file: self.file,
             start: start_span,
             end: end_span,
         };
 
         Ok(FunctionDeclaration {
+            attributes,
+            visibility,
             identifier,
             parameters,
             return_type,
@@ -222,46 +552,38 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_struct_declaration(&mut self) -> Result<StructDeclaration, ParserError> {
+    /// Parses a struct declaration starting with `struct` or the `pub` at `start_span`.
+    fn parse_struct_declaration(
+        &mut self,
+        visibility: Visibility,
+        start_span: usize,
+    ) -> Result<StructDeclaration, ParserError> {
         trace!("Parsing struct declaration");
-        let start_span = self.consume(Token::Struct)?.start;
+        self.consume(Token::Struct)?;
 
         // -- Parse Identifier --
         let identifier = self.consume_identifier()?;
 
         // -- Parse Fields --
-        match self.current.as_ref().cloned() {
+        match self.current.clone() {
             // -- Tuple Fields --
             Some((Token::LParen, _)) => {
                 trace!("Matched tuple struct");
+                self.advance();
                 let mut fields = Vec::new();
-                loop {
-                    self.advance()?;
-                    match self.current.as_ref().cloned() {
-                        // -- End of Fields --
-                        Some((Token::RParen, _)) => break,
-                        // -- Comma -> Next Field --
-                        Some((Token::Comma, _)) => {
-                            trace!("Found comma, expecting next field");
-                            continue;
-                        }
-                        // -- Next Field --
-                        Some((Token::Identifier(name), span)) => {
-                            let declared_type = Identifier { name, span };
-                            trace!("Found tuple field: {:?}", declared_type);
-                            fields.push(TupleFieldDeclaration {
-                                declared_type,
-                                span,
-                            });
-                        }
-                        Some((other, span)) => {
-                            return Err(ParserError::UnexpectedToken {
-                                expected: "`)` or identifier".to_string(),
-                                found: Some(other),
-                                span,
-                            });
-                        }
-                        None => return Err(ParserError::UnexpectedEof),
+                while let Some(Token::Identifier(_)) = self.peek() {
+                    let declared_type = self.consume_identifier()?;
+                    trace!("Found tuple field: {:?}", declared_type);
+                    let span = declared_type.span;
+                    fields.push(TupleFieldDeclaration {
+                        declared_type,
+                        span,
+                    });
+                    // -- Comma -> Next Field --
+                    if self.peek() == Some(&Token::Comma) {
+                        self.advance();
+                    } else {
+                        break;
                     }
                 }
 
@@ -270,11 +592,13 @@ impl<'a> Parser<'a> {
                 let end_span = self.consume(Token::Semicolon)?.end;
 
                 let span = Span {
+                    file: self.file,
                     start: start_span,
                     end: end_span,
                 };
 
                 Ok(StructDeclaration::TupleStruct {
+                    visibility,
                     identifier,
                     fields,
                     span,
@@ -283,42 +607,36 @@ impl<'a> Parser<'a> {
             // -- Named Fields --
             Some((Token::LBrace, _)) => {
                 trace!("Matched named fields struct");
+                self.advance();
                 let mut fields = Vec::new();
-                let end_span = loop {
-                    self.advance()?;
-                    match self.current.as_ref().cloned() {
-                        // -- End of Fields --
-                        Some((Token::RBrace, span)) => break span.end,
-                        // -- Next Field --
-                        Some((Token::Identifier(_), span)) => {
-                            let identifier = self.consume_identifier()?;
-                            self.consume(Token::Colon)?;
-                            let declared_type = self.consume_identifier()?;
-                            fields.push(NamedFieldDeclaration {
-                                identifier,
-                                declared_type,
-                                span,
-                            });
-                        }
-                        Some((other, span)) => {
-                            return Err(ParserError::UnexpectedToken {
-                                expected: "`)` or identifier".to_string(),
-                                found: Some(other),
-                                span,
-                            });
-                        }
-                        None => return Err(ParserError::UnexpectedEof),
+                while let Some(Token::Identifier(_)) = self.peek() {
+                    let identifier = self.consume_identifier()?;
+                    self.consume(Token::Colon)?;
+                    let declared_type = self.consume_identifier()?;
+                    let span = identifier.span.combine(declared_type.span);
+                    fields.push(NamedFieldDeclaration {
+                        identifier,
+                        declared_type,
+                        span,
+                    });
+                    // -- Comma -> Next Field --
+                    if self.peek() == Some(&Token::Comma) {
+                        self.advance();
+                    } else {
+                        break;
                     }
-                };
+                }
 
-                self.consume(Token::RBrace)?;
+                let end_span = self.consume(Token::RBrace)?.end;
 
                 let span = Span {
+                    file: self.file,
                     start: start_span,
                     end: end_span,
                 };
 
                 Ok(StructDeclaration::NamedStruct {
+                    visibility,
                     identifier,
                     fields,
                     span,
@@ -330,32 +648,60 @@ impl<'a> Parser<'a> {
                 let end_span = span.end;
 
                 let span = Span {
+                    file: self.file,
                     start: start_span,
                     end: end_span,
                 };
 
-                self.advance()?;
-                Ok(StructDeclaration::UnitStruct { identifier, span })
+                self.advance();
+                Ok(StructDeclaration::UnitStruct {
+                    visibility,
+                    identifier,
+                    span,
+                })
             }
             Some((other, span)) => Err(ParserError::UnexpectedToken {
                 expected: "`(` or `{` or `;`".to_string(),
                 found: Some(other),
-                span: span,
+                span,
+            }),
+            None => Err(ParserError::UnexpectedEof {
+                span: self.current_span(),
             }),
-            None => Err(ParserError::UnexpectedEof),
         }
     }
 
+    fn parse_use_declaration(&mut self) -> Result<UseDeclaration, ParserError> {
+        trace!("Parsing use declaration");
+        let start_span = self.consume(Token::Use)?;
+
+        // -- Parse Path --
+        // A module followed by at least one more segment naming the item
+        let mut path = vec![self.consume_identifier()?];
+        self.consume(Token::PathSeparator)?;
+        path.push(self.consume_identifier()?);
+        while self.peek() == Some(&Token::PathSeparator) {
+            self.advance();
+            path.push(self.consume_identifier()?);
+        }
+
+        let end_span = self.consume(Token::Semicolon)?;
+        Ok(UseDeclaration {
+            path,
+            span: start_span.combine(end_span),
+        })
+    }
+
     fn parse_return_statement(&mut self) -> Result<ReturnStatement, ParserError> {
         trace!("Parsing return statement");
         let span = self.consume(Token::Return)?;
 
         if self.peek() == Some(&Token::Semicolon) {
+            let span = span.combine(self.consume(Token::Semicolon)?);
             Ok(ReturnStatement { value: None, span })
         } else {
             let expression = self.parse_expression()?;
-            let span = span.combine(expression.span());
-            let _ = self.consume(Token::Semicolon)?;
+            let span = span.combine(self.consume(Token::Semicolon)?);
 
             Ok(ReturnStatement {
                 value: Some(expression),
@@ -364,76 +710,358 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn parse_expression_statement(&mut self) -> Result<ExpressionStatement, ParserError> {
+        trace!("Parsing expression statement");
+        let expression = self.parse_expression()?;
+        let span = if is_block_like(&expression) && self.peek() != Some(&Token::Semicolon) {
+            expression.span()
+        } else {
+            expression.span().combine(self.consume(Token::Semicolon)?)
+        };
+        Ok(ExpressionStatement { expression, span })
+    }
+
     fn parse_expression(&mut self) -> Result<Expression, ParserError> {
         trace!("Parsing expression");
-        let first: Expression = match self.peek() {
-            Some(&Token::IntegerLiteral(_)) => self.parse_integer_literal().map(Into::into)?,
-            Some(&Token::FloatLiteral(_)) => self.parse_float_literal().map(Into::into)?,
-            Some(&Token::Identifier(_)) => self.consume_identifier().map(Into::into)?,
-            other => todo!("got expression: {:?}", other),
+        self.nested(|parser| parser.parse_binary_expression(0))
+    }
+
+    /// Parses a chain of binary operations using precedence climbing.
+    ///
+    /// Only operators binding tighter than `min_precedence` are consumed, all binary operators
+    /// are left associative.
+    fn parse_binary_expression(&mut self, min_precedence: u8) -> Result<Expression, ParserError> {
+        let mut left = self.parse_unary_expression()?;
+
+        while let Some((operator, precedence)) = self.peek().and_then(binary_operator) {
+            if precedence <= min_precedence {
+                break;
+            }
+            trace!("Parsing binary operation: {}", operator);
+            self.advance();
+            let right = self.parse_binary_expression(precedence)?;
+            let span = left.span().combine(right.span());
+            left = BinaryOp {
+                operator,
+                left: Box::new(left),
+                right: Box::new(right),
+                span,
+                inferred_type: None,
+            }
+            .into();
+        }
+
+        Ok(left)
+    }
+
+    fn parse_unary_expression(&mut self) -> Result<Expression, ParserError> {
+        let operator = match self.peek() {
+            Some(Token::Minus) => UnaryOperator::Negate,
+            Some(Token::Bang) => UnaryOperator::Not,
+            _ => return self.parse_postfix_expression(),
         };
+        trace!("Parsing unary operation: {}", operator);
+        let start_span = self.current_span();
+        self.advance();
+        let operand = self.nested(Parser::parse_unary_expression)?;
+        let span = start_span.combine(operand.span());
+        Ok(UnaryOp {
+            operator,
+            operand: Box::new(operand),
+            span,
+            inferred_type: None,
+        }
+        .into())
+    }
 
-        let is_binary_op = matches!(
-            self.current.as_ref().map(|v| &v.0),
-            Some(Token::Plus) | Some(Token::Minus) | Some(Token::Star) | Some(Token::Slash)
-        );
-
-        if is_binary_op {
-            trace!("Parsing binary operation");
-            match self.current.as_ref() {
-                Some((Token::Plus, _)) => {
-                    trace!("Parsing plus operation");
-                    let _ = self.consume(Token::Plus)?;
-                    let second = self.parse_expression()?;
-                    let span = first.span().combine(second.span());
-                    return Ok(BinaryOp {
-                        operator: BinaryOperator::Add,
-                        left: Box::new(first),
-                        right: Box::new(second),
-                        span,
-                        inferred_type: None,
-                    }
-                    .into());
+    /// Parses field accesses and method calls, which bind tighter than any operator.
+    fn parse_postfix_expression(&mut self) -> Result<Expression, ParserError> {
+        let mut expression = self.parse_primary_expression()?;
+        while self.peek() == Some(&Token::Period) {
+            self.advance();
+            let name = self.consume_identifier()?;
+            expression = if self.peek() == Some(&Token::LParen) {
+                trace!("Parsing method call: {}", name.name);
+                let (arguments, end_span) = self.parse_arguments()?;
+                MethodCall {
+                    span: expression.span().combine(end_span),
+                    receiver: Box::new(expression),
+                    method: name,
+                    arguments,
+                    inferred_type: None,
                 }
-                Some((Token::Star, _)) => {
-                    trace!("Parsing star operation");
-                    let _ = self.consume(Token::Star)?;
-                    let second = self.parse_expression()?;
-                    let span = first.span().combine(second.span());
-                    return Ok(BinaryOp {
-                        operator: BinaryOperator::Multiply,
-                        left: Box::new(first),
-                        right: Box::new(second),
-                        span,
-                        inferred_type: None,
-                    }
-                    .into());
+                .into()
+            } else {
+                trace!("Parsing field access: {}", name.name);
+                FieldAccess {
+                    span: expression.span().combine(name.span),
+                    object: Box::new(expression),
+                    field: name,
+                    inferred_type: None,
                 }
-                other => todo!("binary operation {:?} not implemented yet", other),
+                .into()
+            };
+        }
+        Ok(expression)
+    }
+
+    fn parse_primary_expression(&mut self) -> Result<Expression, ParserError> {
+        match self.current.clone() {
+            Some((Token::IntegerLiteral(_), _)) => self.parse_integer_literal().map(Into::into),
+            Some((Token::FloatLiteral(_), _)) => self.parse_float_literal().map(Into::into),
+            Some((Token::String(value), span)) => {
+                self.advance();
+                Ok(StringLiteral { value, span }.into())
+            }
+            Some((Token::Char(value), span)) => {
+                self.advance();
+                Ok(CharLiteral { value, span }.into())
+            }
+            Some((Token::True, span)) => {
+                self.advance();
+                Ok(BooleanLiteral { value: true, span }.into())
+            }
+            Some((Token::False, span)) => {
+                self.advance();
+                Ok(BooleanLiteral { value: false, span }.into())
+            }
+            Some((Token::Identifier(_), _)) => {
+                let identifier = self.consume_identifier()?;
+                if self.peek() == Some(&Token::LParen) {
+                    self.parse_function_call(identifier).map(Into::into)
+                } else {
+                    Ok(identifier.into())
+                }
+            }
+            Some((Token::LParen, _)) => {
+                self.advance();
+                let expression = self.parse_expression()?;
+                self.consume(Token::RParen)?;
+                Ok(expression)
             }
+            Some((Token::LBrace, _)) => self.parse_block_expression().map(Into::into),
+            Some((Token::If, _)) => self.parse_if_expression().map(Into::into),
+            Some((other, span)) => Err(ParserError::UnexpectedToken {
+                expected: "expression".to_string(),
+                found: Some(other),
+                span,
+            }),
+            None => Err(ParserError::UnexpectedEof {
+                span: self.current_span(),
+            }),
         }
-        Ok(first)
+    }
+
+    fn parse_function_call(
+        &mut self,
+        function_name: Identifier,
+    ) -> Result<FunctionCall, ParserError> {
+        trace!("Parsing function call");
+        let (arguments, end_span) = self.parse_arguments()?;
+        let span = function_name.span.combine(end_span);
+        Ok(FunctionCall {
+            function_name,
+            arguments,
+            span,
+            inferred_type: None,
+        })
+    }
+
+    /// Parses `(a, b, ...)` and returns the arguments and the span of the closing parenthesis.
+    fn parse_arguments(&mut self) -> Result<(Vec<Expression>, Span), ParserError> {
+        self.consume(Token::LParen)?;
+        let mut arguments = Vec::new();
+        while self.peek() != Some(&Token::RParen) {
+            arguments.push(self.parse_expression()?);
+            if self.peek() == Some(&Token::Comma) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        let end_span = self.consume(Token::RParen)?;
+        Ok((arguments, end_span))
+    }
+
+    fn parse_block_expression(&mut self) -> Result<BlockExpression, ParserError> {
+        trace!("Parsing block expression");
+        let start_span = self.consume(Token::LBrace)?;
+        let mut statements = Vec::new();
+        let mut final_expression = None;
+
+        while !matches!(self.peek(), Some(Token::RBrace) | None) {
+            let start_span = self.current_span();
+            let start_position = self.position;
+            match self.parse_block_item() {
+                Ok(BlockItem::Statement(statement)) => statements.push(statement),
+                Ok(BlockItem::FinalExpression(expression)) => {
+                    final_expression = Some(Box::new(expression));
+                }
+                Err(error) => statements.push(self.recover(error, start_span, start_position)),
+            }
+        }
+
+        let end_span = self.consume(Token::RBrace)?;
+        Ok(BlockExpression {
+            statements,
+            final_expression,
+            inferred_type: None,
+            span: start_span.combine(end_span),
+        })
+    }
+
+    fn parse_block_item(&mut self) -> Result<BlockItem, ParserError> {
+        if starts_statement(self.peek()) {
+            return self.parse_statement().map(BlockItem::Statement);
+        }
+
+        let expression = self.parse_expression()?;
+        match self.peek() {
+            Some(Token::RBrace) => Ok(BlockItem::FinalExpression(expression)),
+            Some(Token::Semicolon) => {
+                let span = expression.span().combine(self.consume(Token::Semicolon)?);
+                Ok(BlockItem::Statement(
+                    ExpressionStatement { expression, span }.into(),
+                ))
+            }
+            _ if is_block_like(&expression) => {
+                let span = expression.span();
+                Ok(BlockItem::Statement(
+                    ExpressionStatement { expression, span }.into(),
+                ))
+            }
+            _ => Err(ParserError::UnexpectedToken {
+                expected: "`;` or `}`".to_string(),
+                found: self.peek().cloned(),
+                span: self.current_span(),
+            }),
+        }
+    }
+
+    fn parse_if_expression(&mut self) -> Result<IfExpression, ParserError> {
+        trace!("Parsing if expression");
+        let start_span = self.consume(Token::If)?;
+        let condition = self.parse_expression()?;
+        let then_branch = self.parse_block_expression()?;
+
+        let else_branch = if self.peek() == Some(&Token::Else) {
+            self.advance();
+            if self.peek() == Some(&Token::If) {
+                // `else if` is sugar for an else block containing only the nested if
+                let nested = self.parse_if_expression()?;
+                let span = nested.span;
+                Some(BlockExpression {
+                    statements: Vec::new(),
+                    final_expression: Some(Box::new(nested.into())),
+                    inferred_type: None,
+                    span,
+                })
+            } else {
+                Some(self.parse_block_expression()?)
+            }
+        } else {
+            None
+        };
+
+        let end_span = else_branch
+            .as_ref()
+            .map_or(then_branch.span, |branch| branch.span);
+
+        Ok(IfExpression {
+            condition: Box::new(condition),
+            then_branch,
+            else_branch,
+            inferred_type: None,
+            span: start_span.combine(end_span),
+        })
     }
 
     fn parse_integer_literal(&mut self) -> Result<IntegerLiteral, ParserError> {
         trace!("Parsing integer literal");
-        match self.current.as_ref() {
-            Some(&(Token::IntegerLiteral(value), span)) => {
-                self.advance()?;
-                Ok(IntegerLiteral { value, span })
+        match self.current.clone() {
+            Some((Token::IntegerLiteral(IntegerToken { value, suffix }), span)) => {
+                self.advance();
+                Ok(IntegerLiteral {
+                    value,
+                    suffix,
+                    inferred_type: None,
+                    span,
+                })
             }
-            other => todo!("unexpected token for integer literal: {:?}", other),
+            other => Err(ParserError::UnexpectedToken {
+                expected: "integer literal".to_string(),
+                found: other.map(|(token, _)| token),
+                span: self.current_span(),
+            }),
         }
     }
 
     fn parse_float_literal(&mut self) -> Result<FloatLiteral, ParserError> {
         trace!("Parsing float literal");
-        match self.current.as_ref() {
-            Some(&(Token::FloatLiteral(value), span)) => {
-                self.advance()?;
-                Ok(FloatLiteral { value, span })
+        match self.current.clone() {
+            Some((Token::FloatLiteral(FloatToken { value, suffix }), span)) => {
+                self.advance();
+                Ok(FloatLiteral {
+                    value,
+                    suffix,
+                    inferred_type: None,
+                    span,
+                })
             }
-            other => todo!("unexpected token for float literal: {:?}", other),
+            other => Err(ParserError::UnexpectedToken {
+                expected: "float literal".to_string(),
+                found: other.map(|(token, _)| token),
+                span: self.current_span(),
+            }),
         }
     }
 }
+
+/// Maps a token to the binary operator it represents and its precedence.
+fn binary_operator(token: &Token) -> Option<(BinaryOperator, u8)> {
+    let operator = match token {
+        Token::Or => BinaryOperator::Or,
+        Token::And => BinaryOperator::And,
+        Token::Equals => BinaryOperator::Equals,
+        Token::NotEquals => BinaryOperator::NotEquals,
+        Token::LessThan => BinaryOperator::LessThan,
+        Token::GreaterThan => BinaryOperator::GreaterThan,
+        Token::LessThanOrEqual => BinaryOperator::LessThanOrEqual,
+        Token::GreaterThanOrEqual => BinaryOperator::GreaterThanOrEqual,
+        Token::Pipe => BinaryOperator::BitwiseOr,
+        Token::Caret => BinaryOperator::BitwiseXor,
+        Token::Ampersand => BinaryOperator::BitwiseAnd,
+        Token::ShiftLeft => BinaryOperator::ShiftLeft,
+        Token::ShiftRight => BinaryOperator::ShiftRight,
+        Token::Plus => BinaryOperator::Add,
+        Token::Minus => BinaryOperator::Subtract,
+        Token::Star => BinaryOperator::Multiply,
+        Token::Slash => BinaryOperator::Divide,
+        Token::Percent => BinaryOperator::Remainder,
+        _ => return None,
+    };
+    let precedence = operator.precedence();
+    Some((operator, precedence))
+}
+
+/// Whether the token starts a statement that cannot be an expression statement.
+fn starts_statement(token: Option<&Token>) -> bool {
+    matches!(
+        token,
+        Some(Token::Let)
+            | Some(Token::Hash)
+            | Some(Token::Pub)
+            | Some(Token::Fn)
+            | Some(Token::Struct)
+            | Some(Token::Use)
+            | Some(Token::Return)
+    )
+}
+
+/// Block-like expressions may be used as statements without a trailing `;`.
+fn is_block_like(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::BlockExpression(_) | Expression::IfExpression(_)
+    )
+}
@@ -0,0 +1,275 @@
+//! Prints the [`ast`](super::ast) as S-expressions, a compact text form that is easy to diff
+//! and to read back with any Lisp reader:
+//!
+//! ```text
+//! (program
+//!   (fn add ((a i64) (b i64)) i64
+//!     (return (+ a b)))
+//!   (let x (call add 1 2)))
+//! ```
+//!
+//! Spans are left out. Attributes are written as keywords in front of the item, e.g.
+//! `(:test fn adds () () ...)`. Types inferred by the type checker are shown as
+//! `(the T expression)`, so the same program prints without them before it is checked.
+
+use std::fmt::Write;
+
+use super::ast::{
+    BlockExpression, Expression, FunctionDeclaration, Identifier, Program, Statement,
+    StructDeclaration, Visibility,
+};
+
+/// The number of spaces nested statements are indented by.
+const INDENT: usize = 2;
+
+/// Prints `program` as an S-expression, one statement per line.
+pub fn program_to_sexpr(program: &Program) -> String {
+    let mut printer = Printer::default();
+    printer.output.push_str("(program");
+    printer.statements(&program.statements, INDENT);
+    printer.output.push_str(")\n");
+    printer.output
+}
+
+/// Prints a single expression as an S-expression on one line.
+pub fn expression_to_sexpr(expression: &Expression) -> String {
+    let mut printer = Printer::default();
+    printer.expression(expression, 0);
+    printer.output
+}
+
+#[derive(Default)]
+struct Printer {
+    output: String,
+}
+
+impl Printer {
+    /// Prints every statement on a new line, indented by `indent`.
+    fn statements(&mut self, statements: &[Statement], indent: usize) {
+        for statement in statements {
+            let _ = write!(self.output, "\n{:indent$}", "");
+            self.statement(statement, indent);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement, indent: usize) {
+        match statement {
+            Statement::VariableDeclaration(declaration) => {
+                let _ = write!(self.output, "(let {}", declaration.identifier.name);
+                if let Some(declared_type) = &declaration.declared_type {
+                    let _ = write!(self.output, " :type {}", declared_type.name);
+                }
+                self.output.push(' ');
+                self.expression(&declaration.initializer, indent);
+                self.output.push(')');
+            }
+            Statement::FunctionDeclaration(declaration) => self.function(declaration, indent),
+            Statement::StructDeclaration(declaration) => self.struct_declaration(declaration),
+            Statement::UseDeclaration(declaration) => {
+                let path: Vec<_> = declaration
+                    .path
+                    .iter()
+                    .map(|segment| segment.name.as_str())
+                    .collect();
+                let _ = write!(self.output, "(use {})", path.join("::"));
+            }
+            Statement::ExpressionStatement(statement) => {
+                self.output.push_str("(expr ");
+                self.expression(&statement.expression, indent);
+                self.output.push(')');
+            }
+            Statement::ReturnStatement(statement) => {
+                self.keyword_with_value("return", statement.value.as_ref(), indent)
+            }
+            Statement::BreakStatement(statement) => {
+                self.keyword_with_value("break", statement.value.as_ref(), indent)
+            }
+            Statement::Error(_) => self.output.push_str("(error)"),
+        }
+    }
+
+    fn keyword_with_value(&mut self, keyword: &str, value: Option<&Expression>, indent: usize) {
+        let _ = write!(self.output, "({keyword}");
+        if let Some(value) = value {
+            self.output.push(' ');
+            self.expression(value, indent);
+        }
+        self.output.push(')');
+    }
+
+    fn function(&mut self, declaration: &FunctionDeclaration, indent: usize) {
+        self.output.push('(');
+        for attribute in &declaration.attributes {
+            let _ = write!(self.output, ":{} ", attribute.name.name);
+        }
+        self.visibility(declaration.visibility);
+        let parameters: Vec<_> = declaration
+            .parameters
+            .iter()
+            .map(|parameter| {
+                format!(
+                    "({} {})",
+                    parameter.identifier.name, parameter.declared_type.name
+                )
+            })
+            .collect();
+        let _ = write!(
+            self.output,
+            "fn {} ({}) {}",
+            declaration.identifier.name,
+            parameters.join(" "),
+            declaration.return_type.name
+        );
+        self.statements(&declaration.body, indent + INDENT);
+        self.output.push(')');
+    }
+
+    fn struct_declaration(&mut self, declaration: &StructDeclaration) {
+        self.output.push('(');
+        self.visibility(declaration.visibility());
+        let _ = write!(self.output, "struct {}", declaration.identifier().name);
+        match declaration {
+            StructDeclaration::NamedStruct { fields, .. } => {
+                for field in fields {
+                    let _ = write!(
+                        self.output,
+                        " ({} {})",
+                        field.identifier.name, field.declared_type.name
+                    );
+                }
+            }
+            StructDeclaration::TupleStruct { fields, .. } => {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|field| field.declared_type.name.as_str())
+                    .collect();
+                let _ = write!(self.output, " ({})", fields.join(" "));
+            }
+            StructDeclaration::UnitStruct { .. } => {}
+        }
+        self.output.push(')');
+    }
+
+    fn visibility(&mut self, visibility: Visibility) {
+        if visibility == Visibility::Public {
+            self.output.push_str("pub ");
+        }
+    }
+
+    // -- Expressions --
+
+    /// Prints an expression, blocks put their statements on new lines indented past `indent`.
+    fn expression(&mut self, expression: &Expression, indent: usize) {
+        let inferred_type = inferred_type(expression);
+        if let Some(ty) = inferred_type {
+            let _ = write!(self.output, "(the {} ", ty.name);
+        }
+
+        match expression {
+            Expression::BinaryOp(node) => {
+                let _ = write!(self.output, "({} ", node.operator.symbol());
+                self.expression(&node.left, indent);
+                self.output.push(' ');
+                self.expression(&node.right, indent);
+                self.output.push(')');
+            }
+            Expression::UnaryOp(node) => {
+                let _ = write!(self.output, "({} ", node.operator.symbol());
+                self.expression(&node.operand, indent);
+                self.output.push(')');
+            }
+            Expression::FunctionCall(node) => {
+                let _ = write!(self.output, "(call {}", node.function_name.name);
+                self.arguments(&node.arguments, indent);
+                self.output.push(')');
+            }
+            Expression::FieldAccess(node) => {
+                self.output.push_str("(field ");
+                self.expression(&node.object, indent);
+                let _ = write!(self.output, " {})", node.field.name);
+            }
+            Expression::MethodCall(node) => {
+                self.output.push_str("(method ");
+                self.expression(&node.receiver, indent);
+                let _ = write!(self.output, " {}", node.method.name);
+                self.arguments(&node.arguments, indent);
+                self.output.push(')');
+            }
+            Expression::BlockExpression(node) => self.block(node, indent),
+            Expression::IfExpression(node) => {
+                self.output.push_str("(if ");
+                self.expression(&node.condition, indent);
+                self.output.push(' ');
+                self.block(&node.then_branch, indent);
+                if let Some(else_branch) = &node.else_branch {
+                    self.output.push(' ');
+                    self.block(else_branch, indent);
+                }
+                self.output.push(')');
+            }
+            Expression::Identifier(Identifier { name, .. }) => self.output.push_str(name),
+            Expression::IntegerLiteral(node) => {
+                let _ = write!(self.output, "{}", node.value);
+                if let Some(suffix) = node.suffix {
+                    let _ = write!(self.output, "{suffix}");
+                }
+            }
+            Expression::FloatLiteral(node) => {
+                let _ = write!(self.output, "{:?}", node.value);
+                if let Some(suffix) = node.suffix {
+                    let _ = write!(self.output, "{suffix}");
+                }
+            }
+            Expression::StringLiteral(node) => {
+                let _ = write!(self.output, "{:?}", node.value);
+            }
+            Expression::CharLiteral(node) => {
+                let _ = write!(self.output, "{:?}", node.value);
+            }
+            Expression::BooleanLiteral(node) => {
+                let _ = write!(self.output, "{}", node.value);
+            }
+        }
+
+        if inferred_type.is_some() {
+            self.output.push(')');
+        }
+    }
+
+    fn arguments(&mut self, arguments: &[Expression], indent: usize) {
+        for argument in arguments {
+            self.output.push(' ');
+            self.expression(argument, indent);
+        }
+    }
+
+    fn block(&mut self, block: &BlockExpression, indent: usize) {
+        self.output.push_str("(block");
+        self.statements(&block.statements, indent + INDENT);
+        if let Some(expression) = &block.final_expression {
+            let indent = indent + INDENT;
+            let _ = write!(self.output, "\n{:indent$}", "");
+            self.expression(expression, indent);
+        }
+        self.output.push(')');
+    }
+}
+
+/// The type the type checker stored for an expression, if it has one and was checked.
+fn inferred_type(expression: &Expression) -> Option<&Identifier> {
+    match expression {
+        Expression::BinaryOp(node) => node.inferred_type.as_ref(),
+        Expression::UnaryOp(node) => node.inferred_type.as_ref(),
+        Expression::FunctionCall(node) => node.inferred_type.as_ref(),
+        Expression::FieldAccess(node) => node.inferred_type.as_ref(),
+        Expression::MethodCall(node) => node.inferred_type.as_ref(),
+        Expression::BlockExpression(node) => node.inferred_type.as_ref(),
+        Expression::IfExpression(node) => node.inferred_type.as_ref(),
+        Expression::IntegerLiteral(node) => node.inferred_type.as_ref(),
+        Expression::FloatLiteral(node) => node.inferred_type.as_ref(),
+        Expression::Identifier(_)
+        | Expression::StringLiteral(_)
+        | Expression::CharLiteral(_)
+        | Expression::BooleanLiteral(_) => None,
+    }
+}
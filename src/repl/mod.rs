@@ -0,0 +1,218 @@
+//! # REPL
+//!
+//! Reads scripts line by line and executes them in a single [`Runtime`], so declarations stay
+//! visible to later inputs. An input that is a single expression is evaluated and its value is
+//! printed, anything else is executed as a script.
+//!
+//! Inputs with unclosed braces or parentheses continue on the next line. Lines starting with `:`
+//! are commands, see [`HELP`].
+
+use std::{env, fs, io::Write, path::PathBuf};
+
+use logos::Logos;
+use rustyline::{DefaultEditor, error::ReadlineError};
+use termcolor::StandardStream;
+
+use rscript::{
+    Parser, Runtime, SourceMap,
+    core::{
+        diagnostic::{Diagnostic, DiagnosticRenderer, ToDiagnostic},
+        format::Format,
+    },
+    parser::lexer::Token,
+};
+
+const PROMPT: &str = ">> ";
+const CONTINUATION_PROMPT: &str = ".. ";
+
+/// The name of the inputs in diagnostics.
+const INPUT_NAME: &str = "<repl>";
+
+const HELP: &str = "\
+:type <expr>   show the type of an expression
+:ast <input>   show the syntax tree of an input
+:load <file>   execute a script
+:reset         forget all declarations
+:help          show this message
+:quit          exit the REPL";
+
+pub struct Repl {
+    runtime: Runtime,
+    editor: DefaultEditor,
+    /// Where the history is persisted between sessions, if a home directory is known.
+    history_path: Option<PathBuf>,
+    stdout: StandardStream,
+    stderr: StandardStream,
+}
+
+impl Repl {
+    pub fn new(stdout: StandardStream, stderr: StandardStream) -> rustyline::Result<Self> {
+        let mut editor = DefaultEditor::new()?;
+        let history_path =
+            env::var_os("HOME").map(|home| PathBuf::from(home).join(".rscript_history"));
+        if let Some(path) = &history_path {
+            // There is no history before the first session
+            let _ = editor.load_history(path);
+        }
+        Ok(Repl {
+            runtime: Runtime::new(),
+            editor,
+            history_path,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Reads and executes inputs until the end of input or `:quit`.
+    pub fn run(&mut self) -> anyhow::Result<()> {
+        while let Some(input) = self.read_input()? {
+            let _ = self.editor.add_history_entry(input.trim_end());
+            let keep_going = match input.trim().strip_prefix(':') {
+                Some(command) => self.command(command)?,
+                None => {
+                    self.evaluate(&input)?;
+                    true
+                }
+            };
+            if !keep_going {
+                break;
+            }
+        }
+
+        if let Some(path) = &self.history_path
+            && let Err(error) = self.editor.save_history(path)
+        {
+            warn!(
+                "Failed to save the history to {}: {}",
+                path.display(),
+                error
+            );
+        }
+        Ok(())
+    }
+
+    /// Reads lines until the input is complete. Returns `None` at the end of input.
+    fn read_input(&mut self) -> anyhow::Result<Option<String>> {
+        let mut input = String::new();
+        loop {
+            let prompt = if input.is_empty() {
+                PROMPT
+            } else {
+                CONTINUATION_PROMPT
+            };
+            match self.editor.readline(prompt) {
+                Ok(line) => {
+                    input.push_str(&line);
+                    input.push('\n');
+                    let is_command = input.trim_start().starts_with(':');
+                    if !input.trim().is_empty() && (is_command || !is_incomplete(&input)) {
+                        return Ok(Some(input));
+                    }
+                }
+                // Ctrl-C discards the current input
+                Err(ReadlineError::Interrupted) => input.clear(),
+                Err(ReadlineError::Eof) => return Ok(None),
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    /// Executes a command without its leading `:`. Returns `false` if the REPL should exit.
+    fn command(&mut self, command: &str) -> anyhow::Result<bool> {
+        let (name, argument) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+        match name {
+            "type" => match self.runtime.expression_type(INPUT_NAME, argument) {
+                Ok(ty) => writeln!(self.stdout, "{}", ty)?,
+                Err(error) => {
+                    let diagnostics = error.diagnostics();
+                    report(self.runtime.source_map(), &diagnostics, &mut self.stderr)?;
+                }
+            },
+            "ast" => self.ast(argument)?,
+            "load" => {
+                let result = fs::read_to_string(argument)
+                    .map_err(|error| anyhow::anyhow!("Failed to read {}: {}", argument, error))
+                    .map(|source| self.runtime.execute_named(argument, &source));
+                match result {
+                    Ok(Ok(())) => {}
+                    Ok(Err(error)) => {
+                        let diagnostics = error.diagnostics();
+                        report(self.runtime.source_map(), &diagnostics, &mut self.stderr)?;
+                    }
+                    Err(error) => writeln!(self.stderr, "error: {}", error)?,
+                }
+            }
+            "reset" => {
+                self.runtime = Runtime::new();
+                writeln!(self.stdout, "Cleared all declarations")?;
+            }
+            "help" => writeln!(self.stdout, "{}", HELP)?,
+            "quit" | "q" => return Ok(false),
+            _ => writeln!(
+                self.stderr,
+                "error: unknown command `:{}`, see `:help`",
+                name
+            )?,
+        }
+        Ok(true)
+    }
+
+    /// Prints the syntax tree of an expression, or of a script if it is not one.
+    fn ast(&mut self, source: &str) -> anyhow::Result<()> {
+        let mut source_map = SourceMap::new();
+        let file = source_map.add_file(INPUT_NAME, source);
+        if let Ok(expression) = Parser::with_file(source, file).parse_single_expression() {
+            expression.format(&mut self.stdout, 4, 0)?;
+            return Ok(());
+        }
+
+        let result = Parser::with_file(source, file).parse();
+        result.program.format(&mut self.stdout, 4, 0)?;
+        let diagnostics: Vec<_> = result
+            .errors
+            .iter()
+            .map(ToDiagnostic::to_diagnostic)
+            .collect();
+        report(&source_map, &diagnostics, &mut self.stderr)
+    }
+
+    fn evaluate(&mut self, input: &str) -> anyhow::Result<()> {
+        match self.runtime.evaluate_named(INPUT_NAME, input) {
+            Ok(Some(value)) => writeln!(self.stdout, "{}", value)?,
+            Ok(None) => {}
+            // The runtime undoes the declarations of failed inputs
+            Err(error) => {
+                let diagnostics = error.diagnostics();
+                report(self.runtime.source_map(), &diagnostics, &mut self.stderr)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `source` has more opening than closing braces or parentheses.
+fn is_incomplete(source: &str) -> bool {
+    let mut depth = 0i32;
+    for token in Token::lexer(source).flatten() {
+        match token {
+            Token::LBrace | Token::LParen => depth += 1,
+            Token::RBrace | Token::RParen => depth -= 1,
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+fn report(
+    source_map: &SourceMap,
+    diagnostics: &[Diagnostic],
+    stderr: &mut StandardStream,
+) -> anyhow::Result<()> {
+    let renderer = DiagnosticRenderer::new(source_map);
+    for diagnostic in diagnostics {
+        renderer.render(stderr, diagnostic)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,584 @@
+//! # Name Resolution
+//!
+//! Binds every name used in a [`Program`] to the declaration it refers to: variables and
+//! parameters in expressions, functions in calls and structs in type annotations. The result is
+//! a [`Resolution`], a side table from the span of every use to a [`Symbol`], which tools can
+//! query instead of looking names up by string.
+//!
+//! The scoping rules are those of the [`TypeChecker`](crate::typeck::TypeChecker):
+//! - functions are visible in the whole statement list declaring them, including the blocks
+//!   and function bodies nested in it, nested functions shadow those of the enclosing lists,
+//! - structs are global wherever they are declared,
+//! - variables are visible after their declaration until the end of their block, the initializer
+//!   of a `let` still sees the variable it shadows,
+//! - function bodies only see global variables and their own parameters.
+//!
+//! Names the host declares on the [`Resolver`] and builtin types resolve without a symbol.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use derive_more::{Display, Error};
+
+use crate::{
+    core::{
+        builtin::Builtin,
+        diagnostic::{Diagnostic, ToDiagnostic},
+        source_map::FileId,
+        span::{Span, Spanned},
+        types::Type,
+    },
+    parser::ast::{
+        BlockExpression, Expression, FunctionDeclaration, Identifier, Program, Statement,
+        StructDeclaration,
+    },
+};
+
+/// The kind of name a use refers to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Display)]
+pub enum Namespace {
+    #[display("value")]
+    Value,
+    #[display("function")]
+    Function,
+    #[display("type")]
+    Type,
+}
+
+#[derive(Debug, PartialEq, Clone, Display, Error)]
+pub enum ResolveError {
+    #[display("cannot find {namespace} `{name}` in this scope")]
+    Undefined {
+        name: String,
+        namespace: Namespace,
+        span: Span,
+    },
+    #[display("cannot use `{name}` before its declaration")]
+    UseBeforeDeclaration {
+        name: String,
+        span: Span,
+        declaration: Span,
+    },
+}
+
+impl Spanned for ResolveError {
+    fn span(&self) -> Span {
+        match self {
+            ResolveError::Undefined { span, .. }
+            | ResolveError::UseBeforeDeclaration { span, .. } => *span,
+        }
+    }
+}
+
+impl ToDiagnostic for ResolveError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string());
+        match self {
+            ResolveError::Undefined { span, .. } => {
+                diagnostic.with_primary_label(*span, "not found in this scope")
+            }
+            ResolveError::UseBeforeDeclaration {
+                name,
+                span,
+                declaration,
+            } => diagnostic
+                .with_primary_label(*span, "used here")
+                .with_secondary_label(*declaration, "declared here")
+                .with_help(format!("move the declaration of `{name}` before its use")),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Display)]
+pub enum ResolveWarning {
+    #[display("unused {kind} `{name}`")]
+    Unused {
+        name: String,
+        kind: SymbolKind,
+        span: Span,
+    },
+}
+
+impl Spanned for ResolveWarning {
+    fn span(&self) -> Span {
+        match self {
+            ResolveWarning::Unused { span, .. } => *span,
+        }
+    }
+}
+
+impl ToDiagnostic for ResolveWarning {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::warning(self.to_string());
+        match self {
+            ResolveWarning::Unused { name, span, .. } => diagnostic
+                .with_primary_label(*span, "never used")
+                .with_help(format!(
+                    "if this is intentional, prefix it with an underscore: `_{name}`"
+                )),
+        }
+    }
+}
+
+/// Identifies a [`Symbol`] of a [`Resolution`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct SymbolId(usize);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Display)]
+pub enum SymbolKind {
+    /// A variable declared at the top level of a program.
+    #[display("global variable")]
+    Global,
+    #[display("variable")]
+    Local,
+    #[display("parameter")]
+    Parameter,
+    #[display("function")]
+    Function,
+    #[display("struct")]
+    Struct,
+}
+
+/// A declared name.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The span of the name in the declaration.
+    pub span: Span,
+    /// The span of the whole declaration.
+    pub declaration: Span,
+}
+
+/// The symbols of a program and the uses bound to them, along with the problems found while
+/// resolving.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Resolution {
+    symbols: Vec<Symbol>,
+    /// The symbol every resolved use refers to, by the span of the use.
+    references: HashMap<Span, SymbolId>,
+    pub errors: Vec<ResolveError>,
+    pub warnings: Vec<ResolveWarning>,
+}
+
+impl Resolution {
+    pub fn symbol(&self, id: SymbolId) -> &Symbol {
+        &self.symbols[id.0]
+    }
+
+    /// Every declared symbol, in the order of their declarations.
+    pub fn symbols(&self) -> impl Iterator<Item = (SymbolId, &Symbol)> {
+        self.symbols
+            .iter()
+            .enumerate()
+            .map(|(index, symbol)| (SymbolId(index), symbol))
+    }
+
+    /// The symbol the name used at `span` refers to, `None` for names declared by the host,
+    /// builtin types and unresolved names.
+    pub fn resolve(&self, span: Span) -> Option<SymbolId> {
+        self.references.get(&span).copied()
+    }
+
+    /// The spans of every use of a symbol, in source order.
+    pub fn references(&self, id: SymbolId) -> Vec<Span> {
+        let mut references: Vec<_> = self
+            .references
+            .iter()
+            .filter(|(_, symbol)| **symbol == id)
+            .map(|(span, _)| *span)
+            .collect();
+        references.sort_by_key(|span| (span.file, span.start));
+        references
+    }
+
+    /// The symbol declared or used at a byte offset of a file, including the end of its name.
+    pub fn symbol_at(&self, file: FileId, offset: usize) -> Option<SymbolId> {
+        let contains =
+            |span: &Span| span.file == file && span.start <= offset && offset <= span.end;
+        self.references
+            .iter()
+            .find(|(span, _)| contains(span))
+            .map(|(_, id)| *id)
+            .or_else(|| {
+                self.symbols()
+                    .find(|(_, symbol)| contains(&symbol.span))
+                    .map(|(id, _)| id)
+            })
+    }
+
+    /// The errors followed by the warnings, as diagnostics.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.errors
+            .iter()
+            .map(ToDiagnostic::to_diagnostic)
+            .chain(self.warnings.iter().map(ToDiagnostic::to_diagnostic))
+            .collect()
+    }
+}
+
+/// # Resolver
+///
+/// Resolves the names of programs, see the [module documentation](self) for the rules.
+///
+/// Unlike the type checker, the resolver does not stop at the first problem: every unresolved
+/// name is reported, along with warnings for local variables, parameters and nested functions
+/// that are never used. Names starting with `_` are exempt, as are top level items, which the
+/// host may use.
+#[derive(Debug, Clone, Default)]
+pub struct Resolver {
+    globals: HashSet<String>,
+    functions: HashSet<String>,
+    types: HashSet<String>,
+}
+
+impl Resolver {
+    /// Makes a global variable of the host visible to the resolved programs.
+    pub fn declare_global(&mut self, name: &str) {
+        self.globals.insert(name.to_string());
+    }
+
+    /// Makes a function implemented by the host callable from the resolved programs.
+    pub fn declare_function(&mut self, name: &str) {
+        self.functions.insert(name.to_string());
+    }
+
+    /// Makes a type implemented by the host usable from the resolved programs.
+    pub fn declare_type(&mut self, name: &str) {
+        self.types.insert(name.to_string());
+    }
+
+    pub fn resolve_program(&self, program: &Program) -> Resolution {
+        trace!("Resolving names of program");
+        let mut walker = Walker {
+            host: self,
+            resolution: Resolution::default(),
+            globals: Scope::default(),
+            scopes: Vec::new(),
+            functions: HashMap::new(),
+            function_scopes: Vec::new(),
+            structs: HashMap::new(),
+            unused_candidates: Vec::new(),
+        };
+        walker.statements(&program.statements);
+        walker.finish()
+    }
+}
+
+/// The variables of a statement list.
+#[derive(Default)]
+struct Scope {
+    variables: HashMap<String, SymbolId>,
+    /// The variables declared further down the statement list, in order, to tell uses before
+    /// the declaration apart from undefined names.
+    upcoming: VecDeque<(String, Span)>,
+}
+
+struct Walker<'a> {
+    host: &'a Resolver,
+    resolution: Resolution,
+    globals: Scope,
+    /// Local scopes at the current position, innermost last.
+    scopes: Vec<Scope>,
+    functions: HashMap<String, SymbolId>,
+    /// The nested functions of the statement lists around the current position, innermost last.
+    /// Unlike variables, function bodies see the ones around their declaration.
+    function_scopes: Vec<HashMap<String, SymbolId>>,
+    structs: HashMap<String, SymbolId>,
+    /// The symbols to warn about if they are never used.
+    unused_candidates: Vec<SymbolId>,
+}
+
+impl Walker<'_> {
+    fn finish(mut self) -> Resolution {
+        let used: HashSet<_> = self.resolution.references.values().copied().collect();
+        for id in self.unused_candidates {
+            let symbol = &self.resolution.symbols[id.0];
+            if !used.contains(&id) && !symbol.name.starts_with('_') {
+                self.resolution.warnings.push(ResolveWarning::Unused {
+                    name: symbol.name.clone(),
+                    kind: symbol.kind,
+                    span: symbol.span,
+                });
+            }
+        }
+        self.resolution
+    }
+
+    fn declare(
+        &mut self,
+        identifier: &Identifier,
+        kind: SymbolKind,
+        declaration: Span,
+    ) -> SymbolId {
+        let id = SymbolId(self.resolution.symbols.len());
+        self.resolution.symbols.push(Symbol {
+            name: identifier.name.clone(),
+            kind,
+            span: identifier.span,
+            declaration,
+        });
+        id
+    }
+
+    fn bind(&mut self, identifier: &Identifier, id: SymbolId) {
+        self.resolution.references.insert(identifier.span, id);
+    }
+
+    fn error(&mut self, error: ResolveError) {
+        self.resolution.errors.push(error);
+    }
+
+    fn current_scope(&mut self) -> &mut Scope {
+        self.scopes.last_mut().unwrap_or(&mut self.globals)
+    }
+
+    // -- Statements --
+
+    /// Declares the items of a statement list, which are visible in the whole list, then
+    /// resolves its statements in order.
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            match statement {
+                Statement::FunctionDeclaration(declaration) => {
+                    let id = self.declare(
+                        &declaration.identifier,
+                        SymbolKind::Function,
+                        declaration.span,
+                    );
+                    let name = declaration.identifier.name.clone();
+                    match self.function_scopes.last_mut() {
+                        Some(scope) => {
+                            scope.insert(name, id);
+                            self.unused_candidates.push(id);
+                        }
+                        None => {
+                            self.functions.insert(name, id);
+                        }
+                    }
+                }
+                Statement::StructDeclaration(declaration) => {
+                    let identifier = declaration.identifier();
+                    let id = self.declare(identifier, SymbolKind::Struct, declaration.span());
+                    self.structs.insert(identifier.name.clone(), id);
+                }
+                Statement::VariableDeclaration(declaration) => {
+                    let identifier = &declaration.identifier;
+                    self.current_scope()
+                        .upcoming
+                        .push_back((identifier.name.clone(), identifier.span));
+                }
+                _ => {}
+            }
+        }
+
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::VariableDeclaration(declaration) => {
+                if let Some(declared_type) = &declaration.declared_type {
+                    self.type_name(declared_type);
+                }
+                // No longer upcoming, the initializer cannot refer to the variable itself
+                self.current_scope().upcoming.pop_front();
+                self.expression(&declaration.initializer);
+
+                let kind = if self.scopes.is_empty() {
+                    SymbolKind::Global
+                } else {
+                    SymbolKind::Local
+                };
+                let id = self.declare(&declaration.identifier, kind, declaration.span);
+                if kind == SymbolKind::Local {
+                    self.unused_candidates.push(id);
+                }
+                self.current_scope()
+                    .variables
+                    .insert(declaration.identifier.name.clone(), id);
+            }
+            Statement::FunctionDeclaration(declaration) => self.function(declaration),
+            Statement::StructDeclaration(declaration) => match declaration {
+                StructDeclaration::NamedStruct { fields, .. } => {
+                    for field in fields {
+                        self.type_name(&field.declared_type);
+                    }
+                }
+                StructDeclaration::TupleStruct { fields, .. } => {
+                    for field in fields {
+                        self.type_name(&field.declared_type);
+                    }
+                }
+                StructDeclaration::UnitStruct { .. } => {}
+            },
+            Statement::ExpressionStatement(statement) => self.expression(&statement.expression),
+            Statement::ReturnStatement(statement) => {
+                if let Some(value) = &statement.value {
+                    self.expression(value);
+                }
+            }
+            Statement::BreakStatement(statement) => {
+                if let Some(value) = &statement.value {
+                    self.expression(value);
+                }
+            }
+            // Imports are resolved by the module loader, errors were reported by the parser
+            Statement::UseDeclaration(_) | Statement::Error(_) => {}
+        }
+    }
+
+    fn function(&mut self, declaration: &FunctionDeclaration) {
+        let mut parameters = Scope::default();
+        for parameter in &declaration.parameters {
+            self.type_name(&parameter.declared_type);
+            let id = self.declare(&parameter.identifier, SymbolKind::Parameter, parameter.span);
+            self.unused_candidates.push(id);
+            parameters
+                .variables
+                .insert(parameter.identifier.name.clone(), id);
+        }
+        self.type_name(&declaration.return_type);
+
+        // Functions only see globals and their own parameters, not the locals around them
+        let enclosing = std::mem::replace(&mut self.scopes, vec![parameters]);
+        self.function_scopes.push(HashMap::new());
+        self.statements(&declaration.body);
+        self.function_scopes.pop();
+        self.scopes = enclosing;
+    }
+
+    // -- Expressions --
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::BinaryOp(node) => {
+                self.expression(&node.left);
+                self.expression(&node.right);
+            }
+            Expression::UnaryOp(node) => self.expression(&node.operand),
+            Expression::FunctionCall(node) => {
+                self.function_name(&node.function_name);
+                for argument in &node.arguments {
+                    self.expression(argument);
+                }
+            }
+            // Fields and methods depend on the type of the receiver, which is not known here
+            Expression::FieldAccess(node) => self.expression(&node.object),
+            Expression::MethodCall(node) => {
+                self.expression(&node.receiver);
+                for argument in &node.arguments {
+                    self.expression(argument);
+                }
+            }
+            Expression::BlockExpression(node) => self.block(node),
+            Expression::IfExpression(node) => {
+                self.expression(&node.condition);
+                self.block(&node.then_branch);
+                if let Some(else_branch) = &node.else_branch {
+                    self.block(else_branch);
+                }
+            }
+            Expression::Identifier(identifier) => self.variable(identifier),
+            Expression::IntegerLiteral(_)
+            | Expression::FloatLiteral(_)
+            | Expression::StringLiteral(_)
+            | Expression::CharLiteral(_)
+            | Expression::BooleanLiteral(_) => {}
+        }
+    }
+
+    fn block(&mut self, block: &BlockExpression) {
+        self.scopes.push(Scope::default());
+        self.function_scopes.push(HashMap::new());
+        self.statements(&block.statements);
+        if let Some(expression) = &block.final_expression {
+            self.expression(expression);
+        }
+        self.function_scopes.pop();
+        self.scopes.pop();
+    }
+
+    // -- Names --
+
+    fn variable(&mut self, identifier: &Identifier) {
+        let name = &identifier.name;
+        let found = self
+            .scopes
+            .iter()
+            .rev()
+            .chain(std::iter::once(&self.globals))
+            .find_map(|scope| scope.variables.get(name))
+            .copied();
+        if let Some(id) = found {
+            self.bind(identifier, id);
+            return;
+        }
+        if self.host.globals.contains(name) {
+            return;
+        }
+
+        let upcoming = self
+            .scopes
+            .iter()
+            .rev()
+            .chain(std::iter::once(&self.globals))
+            .find_map(|scope| {
+                scope
+                    .upcoming
+                    .iter()
+                    .find(|(upcoming, _)| upcoming == name)
+                    .map(|(_, span)| *span)
+            });
+        let error = match upcoming {
+            Some(declaration) => ResolveError::UseBeforeDeclaration {
+                name: name.clone(),
+                span: identifier.span,
+                declaration,
+            },
+            None => ResolveError::Undefined {
+                name: name.clone(),
+                namespace: Namespace::Value,
+                span: identifier.span,
+            },
+        };
+        self.error(error);
+    }
+
+    fn function_name(&mut self, identifier: &Identifier) {
+        let found = self
+            .function_scopes
+            .iter()
+            .rev()
+            .chain(std::iter::once(&self.functions))
+            .find_map(|scope| scope.get(&identifier.name))
+            .copied();
+        if let Some(id) = found {
+            self.bind(identifier, id);
+        } else if !self.host.functions.contains(&identifier.name)
+            && Builtin::from_name(&identifier.name).is_none()
+        {
+            self.error(ResolveError::Undefined {
+                name: identifier.name.clone(),
+                namespace: Namespace::Function,
+                span: identifier.span,
+            });
+        }
+    }
+
+    fn type_name(&mut self, identifier: &Identifier) {
+        if Type::from_name(&identifier.name).is_some() {
+            return;
+        }
+        if let Some(id) = self.structs.get(&identifier.name).copied() {
+            self.bind(identifier, id);
+        } else if !self.host.types.contains(&identifier.name) {
+            self.error(ResolveError::Undefined {
+                name: identifier.name.clone(),
+                namespace: Namespace::Type,
+                span: identifier.span,
+            });
+        }
+    }
+}
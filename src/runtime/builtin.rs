@@ -0,0 +1,132 @@
+//! Executes the calls of [`Builtin`]s, the same way on both engines.
+
+use super::{Runtime, RuntimeError, Value};
+use crate::core::{
+    builtin::{Builtin, FormatString},
+    span::Span,
+};
+
+impl Runtime {
+    /// Calls `builtin` with arguments the type checker accepted, `span` is the call reported when
+    /// it fails.
+    pub(super) fn call_builtin(
+        &mut self,
+        builtin: Builtin,
+        arguments: Vec<Value>,
+        span: Span,
+    ) -> Result<Value, RuntimeError> {
+        trace!("Calling builtin `{}`", builtin);
+        let failed = |message: String| RuntimeError::BuiltinFailed {
+            name: builtin.name(),
+            message,
+            span,
+        };
+
+        match builtin {
+            Builtin::Assert | Builtin::AssertEq | Builtin::AssertNe => {
+                check_assertion(builtin, arguments, span).map(|()| Value::Unit)
+            }
+            Builtin::Print | Builtin::Println | Builtin::Eprintln | Builtin::Format => {
+                let mut text = format_arguments(arguments).map_err(failed)?;
+                if builtin == Builtin::Format {
+                    return Ok(Value::String(text));
+                }
+                if builtin != Builtin::Print {
+                    text.push('\n');
+                }
+                let mut io = self.io.borrow_mut();
+                let result = if builtin == Builtin::Eprintln {
+                    io.write_stderr(&text)
+                } else {
+                    io.write_stdout(&text)
+                };
+                result.map_err(|error| failed(error.to_string()))?;
+                Ok(Value::Unit)
+            }
+            Builtin::ReadLine => {
+                let mut line = self
+                    .io
+                    .borrow_mut()
+                    .read_line()
+                    .map_err(|error| failed(error.to_string()))?;
+                let length = line.trim_end_matches(['\n', '\r']).len();
+                line.truncate(length);
+                Ok(Value::String(line))
+            }
+            Builtin::ReadFile => {
+                let [Value::String(path)] = arguments.as_slice() else {
+                    unreachable!("arguments are checked by the type checker");
+                };
+                self.io
+                    .borrow_mut()
+                    .read_file(path)
+                    .map(Value::String)
+                    .map_err(|error| failed(format!("could not read `{path}`: {error}")))
+            }
+            Builtin::WriteFile => {
+                let [Value::String(path), Value::String(contents)] = arguments.as_slice() else {
+                    unreachable!("arguments are checked by the type checker");
+                };
+                self.io
+                    .borrow_mut()
+                    .write_file(path, contents)
+                    .map(|()| Value::Unit)
+                    .map_err(|error| failed(format!("could not write `{path}`: {error}")))
+            }
+        }
+    }
+}
+
+fn check_assertion(
+    builtin: Builtin,
+    mut arguments: Vec<Value>,
+    span: Span,
+) -> Result<(), RuntimeError> {
+    let (required, _) = builtin.arity();
+    let message = if arguments.len() > required {
+        let Some(Value::String(message)) = arguments.pop() else {
+            unreachable!("messages are checked by the type checker");
+        };
+        Some(message)
+    } else {
+        None
+    };
+
+    let comparison = match (builtin, arguments.as_slice()) {
+        (Builtin::Assert, [condition]) if *condition == Value::Bool(true) => return Ok(()),
+        (Builtin::Assert, _) => None,
+        (Builtin::AssertEq, [left, right]) if left == right => return Ok(()),
+        (Builtin::AssertNe, [left, right]) if left != right => return Ok(()),
+        (Builtin::AssertEq | Builtin::AssertNe, [left, right]) => {
+            let operator = if builtin == Builtin::AssertEq {
+                "=="
+            } else {
+                "!="
+            };
+            Some(Box::new((operator, left.clone(), right.clone())))
+        }
+        _ => unreachable!("arguments are checked by the type checker"),
+    };
+    Err(RuntimeError::AssertionFailed {
+        comparison,
+        message,
+        span,
+    })
+}
+
+/// Formats the arguments after the format string into it. Bytecode files are not type checked
+/// when they are loaded, so the format string is checked again here.
+fn format_arguments(mut arguments: Vec<Value>) -> Result<String, String> {
+    let Value::String(format) = arguments.remove(0) else {
+        unreachable!("format strings are checked by the type checker");
+    };
+    let format = FormatString::parse(&format).map_err(|error| error.to_string())?;
+    if format.placeholders() != arguments.len() {
+        return Err(format!(
+            "the format string has {} placeholder(s) but {} argument(s) were supplied",
+            format.placeholders(),
+            arguments.len()
+        ));
+    }
+    Ok(format.format(&arguments))
+}
@@ -0,0 +1,436 @@
+use std::{collections::HashMap, iter, rc::Rc};
+
+use derive_more::From;
+
+use super::{
+    Function, Runtime, RuntimeError,
+    native::NativeError,
+    object::MemberError,
+    unresolved,
+    value::{OperationError, Value},
+};
+use crate::{
+    core::{
+        span::Span,
+        types::{FloatType, IntegerType},
+    },
+    parser::ast::{
+        BinaryOp, BinaryOperator, BlockExpression, Expression, FieldAccess, FloatLiteral,
+        FunctionCall, FunctionDeclaration, Identifier, IfExpression, IntegerLiteral, MethodCall,
+        Statement, UnaryOp, UnaryOperator,
+    },
+};
+
+/// Non-local control flow unwinding through expression evaluation.
+#[derive(Debug, From)]
+enum Interrupt {
+    /// A `return` statement, carrying the returned value up to the function call.
+    #[from(ignore)]
+    Return(Value),
+    Error(RuntimeError),
+}
+
+type Result<T> = std::result::Result<T, Interrupt>;
+
+impl Runtime {
+    /// Executes top level statements, where `return` is rejected by the type checker.
+    pub(super) fn execute_statements(
+        &mut self,
+        statements: &[Statement],
+    ) -> std::result::Result<(), RuntimeError> {
+        self.declare_functions(statements);
+        for statement in statements {
+            trace!("Executing statement: {:?}", statement);
+            match self.execute_statement(statement) {
+                Ok(()) => {}
+                Err(Interrupt::Error(error)) => return Err(error),
+                Err(Interrupt::Return(_)) => unreachable!("`return` outside of a function"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluates an expression outside of any function.
+    pub(super) fn evaluate_top_level(
+        &mut self,
+        expression: &Expression,
+    ) -> std::result::Result<Value, RuntimeError> {
+        match self.evaluate_expression(expression) {
+            Ok(value) => Ok(value),
+            Err(Interrupt::Error(error)) => Err(error),
+            Err(Interrupt::Return(_)) => unreachable!("`return` outside of a function"),
+        }
+    }
+
+    /// Makes the functions of a statement list callable before their declaration.
+    fn declare_functions(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            if let Statement::FunctionDeclaration(declaration) = statement {
+                self.declare_function(
+                    &declaration.identifier.name,
+                    Function::Script(Rc::new(declaration.clone())),
+                );
+            }
+        }
+    }
+
+    fn declare_variable(&mut self, name: &str, value: Value) {
+        trace!("Declaring variable `{}` = {}", name, value);
+        match self.scopes.last_mut() {
+            Some(scope) => scope.insert(name.to_string(), value),
+            None => self.globals.insert(name.to_string(), value),
+        };
+    }
+
+    fn lookup_variable(&self, identifier: &Identifier) -> Value {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&identifier.name))
+            .or_else(|| self.globals.get(&identifier.name))
+            .cloned()
+            .expect("variables are resolved by the type checker")
+    }
+
+    fn execute_statement(&mut self, statement: &Statement) -> Result<()> {
+        match statement {
+            Statement::VariableDeclaration(declaration) => {
+                let value = self.evaluate_expression(&declaration.initializer)?;
+                self.declare_variable(&declaration.identifier.name, value);
+            }
+            // Functions are registered by `declare_functions`, structs only exist for the
+            // type checker so far
+            Statement::FunctionDeclaration(_) | Statement::StructDeclaration(_) => {}
+            Statement::ExpressionStatement(statement) => {
+                self.evaluate_expression(&statement.expression)?;
+            }
+            Statement::ReturnStatement(statement) => {
+                let value = match &statement.value {
+                    Some(value) => self.evaluate_expression(value)?,
+                    None => Value::Unit,
+                };
+                return Err(Interrupt::Return(value));
+            }
+            Statement::BreakStatement(_) => unreachable!("`break` outside of a loop"),
+            Statement::UseDeclaration(_) => unreachable!("imports are resolved before checking"),
+            // Already reported by the parser, programs with syntax errors are not executed
+            Statement::Error(_) => {}
+        }
+        Ok(())
+    }
+
+    fn evaluate_expression(&mut self, expression: &Expression) -> Result<Value> {
+        match expression {
+            Expression::BinaryOp(node) => self.evaluate_binary_op(node),
+            Expression::UnaryOp(node) => self.evaluate_unary_op(node),
+            Expression::FunctionCall(node) => self.evaluate_function_call(node),
+            Expression::FieldAccess(node) => self.evaluate_field_access(node),
+            Expression::MethodCall(node) => self.evaluate_method_call(node),
+            Expression::BlockExpression(node) => self.evaluate_block_expression(node),
+            Expression::IfExpression(node) => self.evaluate_if_expression(node),
+            Expression::Identifier(node) => Ok(self.lookup_variable(node)),
+            Expression::IntegerLiteral(node) => Ok(integer_literal_value(node, false)),
+            Expression::FloatLiteral(node) => Ok(float_literal_value(node)),
+            Expression::StringLiteral(node) => Ok(Value::String(node.value.clone())),
+            Expression::CharLiteral(node) => Ok(Value::Char(node.value)),
+            Expression::BooleanLiteral(node) => Ok(Value::Bool(node.value)),
+        }
+    }
+
+    fn evaluate_binary_op(&mut self, node: &BinaryOp) -> Result<Value> {
+        use BinaryOperator::*;
+
+        // `&&` and `||` short circuit
+        if matches!(node.operator, And | Or) {
+            let left = self.evaluate_expression(&node.left)?;
+            return match (&node.operator, left) {
+                (And, Value::Bool(false)) => Ok(Value::Bool(false)),
+                (Or, Value::Bool(true)) => Ok(Value::Bool(true)),
+                _ => self.evaluate_expression(&node.right),
+            };
+        }
+
+        let left = self.evaluate_expression(&node.left)?;
+        let right = self.evaluate_expression(&node.right)?;
+        let result = left.binary_operation(&node.operator, &right);
+        Ok(result
+            .map_err(|error| operation_error(error, operation_name(&node.operator), node.span))?)
+    }
+
+    fn evaluate_unary_op(&mut self, node: &UnaryOp) -> Result<Value> {
+        match (&node.operator, node.operand.as_ref()) {
+            // Negated literals are built directly, `-128i8` must not overflow on `128i8`
+            (UnaryOperator::Negate, Expression::IntegerLiteral(literal)) => {
+                Ok(integer_literal_value(literal, true))
+            }
+            (UnaryOperator::Negate, operand) => {
+                let operand = self.evaluate_expression(operand)?;
+                Ok(operand
+                    .negate()
+                    .map_err(|error| operation_error(error, "negate", node.span))?)
+            }
+            (UnaryOperator::Not, operand) => {
+                let operand = self.evaluate_expression(operand)?;
+                Ok(operand
+                    .not()
+                    .map_err(|error| operation_error(error, "negate", node.span))?)
+            }
+        }
+    }
+
+    fn evaluate_function_call(&mut self, node: &FunctionCall) -> Result<Value> {
+        let function = self
+            .function(&node.function_name.name)
+            .expect("functions are resolved by the type checker");
+
+        let mut arguments = Vec::with_capacity(node.arguments.len());
+        for argument in &node.arguments {
+            arguments.push(self.evaluate_expression(argument)?);
+        }
+
+        self.call_scoped(&node.function_name.name, function, arguments, node.span)
+            .map_err(Interrupt::Error)
+    }
+
+    /// Calls a script, compiled, native or builtin function, `span` is the call reported by
+    /// conversion errors and failed assertions.
+    pub(super) fn invoke(
+        &mut self,
+        name: &str,
+        function: &Function,
+        arguments: Vec<Value>,
+        span: Span,
+    ) -> std::result::Result<Value, RuntimeError> {
+        match function {
+            Function::Script(function) => self.nested_call(name, span, |runtime| {
+                runtime.call_function(function, arguments)
+            }),
+            Function::Compiled(function) => self.nested_call(name, span, |runtime| {
+                runtime.call_compiled(function, arguments)
+            }),
+            Function::Native(function) => {
+                trace!("Calling native function `{}`", name);
+                function
+                    .call(arguments)
+                    .map_err(|error| native_error(name, error, span))
+            }
+            Function::Builtin(builtin) => self.call_builtin(*builtin, arguments, span),
+        }
+    }
+
+    /// Runs a call of a script or compiled function, which fails instead of overflowing the stack
+    /// of the host when too many of them are executing already.
+    fn nested_call(
+        &mut self,
+        name: &str,
+        span: Span,
+        call: impl FnOnce(&mut Self) -> std::result::Result<Value, RuntimeError>,
+    ) -> std::result::Result<Value, RuntimeError> {
+        if self.call_depth == self.max_call_depth {
+            return Err(RuntimeError::CallDepthExceeded {
+                name: name.to_string(),
+                limit: self.max_call_depth,
+                span,
+            });
+        }
+        self.call_depth += 1;
+        let result = call(self);
+        self.call_depth -= 1;
+        result
+    }
+
+    fn evaluate_field_access(&mut self, node: &FieldAccess) -> Result<Value> {
+        let Value::Object(object) = self.evaluate_expression(&node.object)? else {
+            unreachable!("fields are resolved by the type checker");
+        };
+        let name = format!("{}::{}", object.type_name(), node.field.name);
+        trace!("Reading field `{}`", name);
+        self.types
+            .get(object.type_name())
+            .and_then(|info| info.get(&object, &node.field.name))
+            .expect("fields are resolved by the type checker")
+            .map_err(|error| Interrupt::Error(member_error(&name, error, node.span)))
+    }
+
+    fn evaluate_method_call(&mut self, node: &MethodCall) -> Result<Value> {
+        let receiver = self.evaluate_expression(&node.receiver)?;
+        let mut arguments = Vec::with_capacity(node.arguments.len());
+        for argument in &node.arguments {
+            arguments.push(self.evaluate_expression(argument)?);
+        }
+        Ok(self.call_method(receiver, &node.method.name, arguments, node.span)?)
+    }
+
+    /// Calls a method of a host object or of a value of a builtin type, `span` is the call
+    /// reported by errors.
+    pub(super) fn call_method(
+        &self,
+        receiver: Value,
+        method: &str,
+        arguments: Vec<Value>,
+        span: Span,
+    ) -> std::result::Result<Value, RuntimeError> {
+        let ty = receiver.ty();
+        let name = format!("{}::{}", ty, method);
+        trace!("Calling method `{}`", name);
+        match receiver {
+            Value::Object(object) => self
+                .types
+                .get(object.type_name())
+                .and_then(|info| info.call(&object, method, arguments))
+                .ok_or_else(|| unresolved("method", &name))?
+                .map_err(|error| member_error(&name, error, span)),
+            receiver => self
+                .methods
+                .get(&ty)
+                .and_then(|methods| methods.get(method))
+                .ok_or_else(|| unresolved("method", &name))?
+                .call(iter::once(receiver).chain(arguments).collect())
+                .map_err(|error| native_error(&name, error, span)),
+        }
+    }
+
+    /// Calls a script function, the arguments must match its parameters.
+    pub(super) fn call_function(
+        &mut self,
+        function: &FunctionDeclaration,
+        arguments: Vec<Value>,
+    ) -> std::result::Result<Value, RuntimeError> {
+        trace!("Calling function `{}`", function.identifier.name);
+        let parameters = function
+            .parameters
+            .iter()
+            .map(|parameter| parameter.identifier.name.clone())
+            .zip(arguments)
+            .collect();
+
+        // Functions only see globals and their own parameters, not the locals of the caller
+        let caller_scopes = std::mem::replace(&mut self.scopes, vec![parameters]);
+        self.function_scopes.push(HashMap::new());
+        self.declare_functions(&function.body);
+        let result = function
+            .body
+            .iter()
+            .try_for_each(|statement| self.execute_statement(statement));
+        self.function_scopes.pop();
+        self.scopes = caller_scopes;
+
+        match result {
+            Err(Interrupt::Return(value)) => Ok(value),
+            Err(Interrupt::Error(error)) => Err(error),
+            Ok(()) if function.return_type.name == "()" => Ok(Value::Unit),
+            Ok(()) => Err(RuntimeError::MissingReturn {
+                name: function.identifier.name.clone(),
+                span: function.span,
+            }),
+        }
+    }
+
+    fn evaluate_block_expression(&mut self, node: &BlockExpression) -> Result<Value> {
+        self.scopes.push(HashMap::new());
+        self.function_scopes.push(HashMap::new());
+        let result = self.evaluate_block_contents(node);
+        self.function_scopes.pop();
+        self.scopes.pop();
+        result
+    }
+
+    fn evaluate_block_contents(&mut self, node: &BlockExpression) -> Result<Value> {
+        self.declare_functions(&node.statements);
+        for statement in &node.statements {
+            self.execute_statement(statement)?;
+        }
+        match &node.final_expression {
+            Some(expression) => self.evaluate_expression(expression),
+            None => Ok(Value::Unit),
+        }
+    }
+
+    fn evaluate_if_expression(&mut self, node: &IfExpression) -> Result<Value> {
+        let condition = self.evaluate_expression(&node.condition)?;
+        if condition == Value::Bool(true) {
+            self.evaluate_block_expression(&node.then_branch)
+        } else if let Some(else_branch) = &node.else_branch {
+            self.evaluate_block_expression(else_branch)
+        } else {
+            Ok(Value::Unit)
+        }
+    }
+}
+
+pub(super) fn native_error(name: &str, error: NativeError, span: Span) -> RuntimeError {
+    match error {
+        NativeError::Conversion { index, error } => RuntimeError::NativeArgument {
+            name: name.to_string(),
+            index,
+            error,
+            span,
+        },
+        NativeError::Failed(message) => RuntimeError::NativeFailed {
+            name: name.to_string(),
+            message,
+            span,
+        },
+    }
+}
+
+pub(super) fn member_error(name: &str, error: MemberError, span: Span) -> RuntimeError {
+    match error {
+        MemberError::Borrow(error) => RuntimeError::AlreadyBorrowed { error, span },
+        MemberError::Native(error) => native_error(name, error, span),
+    }
+}
+
+/// Builds the value of an integer literal with the type chosen by the type checker.
+pub(crate) fn integer_literal_value(node: &IntegerLiteral, negated: bool) -> Value {
+    let ty = node
+        .inferred_type
+        .as_ref()
+        .and_then(|ty| IntegerType::from_name(&ty.name))
+        .or(node.suffix)
+        .unwrap_or(IntegerType::I64);
+    let value = if negated {
+        -i128::from(node.value)
+    } else {
+        i128::from(node.value)
+    };
+    Value::from_integer(value, ty).expect("literal ranges are checked by the type checker")
+}
+
+/// Builds the value of a float literal with the type chosen by the type checker.
+pub(crate) fn float_literal_value(node: &FloatLiteral) -> Value {
+    let ty = node
+        .inferred_type
+        .as_ref()
+        .and_then(|ty| FloatType::from_name(&ty.name))
+        .or(node.suffix)
+        .unwrap_or(FloatType::F64);
+    Value::from_float(node.value, ty)
+}
+
+pub(super) fn operation_name(operator: &BinaryOperator) -> &'static str {
+    match operator {
+        BinaryOperator::Add => "add",
+        BinaryOperator::Subtract => "subtract",
+        BinaryOperator::Multiply => "multiply",
+        BinaryOperator::Divide => "divide",
+        BinaryOperator::Remainder => "calculate the remainder",
+        BinaryOperator::ShiftLeft => "shift left",
+        BinaryOperator::ShiftRight => "shift right",
+        _ => "compare",
+    }
+}
+
+pub(super) fn operation_error(
+    error: OperationError,
+    operation: &'static str,
+    span: Span,
+) -> RuntimeError {
+    match error {
+        OperationError::Overflow => RuntimeError::Overflow { operation, span },
+        OperationError::DivisionByZero => RuntimeError::DivisionByZero { span },
+        OperationError::InvalidOperands => {
+            unreachable!("operand types are checked by the type checker")
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Where the I/O builtins of scripts read from and write to.
+//!
+//! Every runtime starts out with [`StdIo`], the standard streams and file system of the process.
+//! Hosts replace it with [`Runtime::set_io`] to capture the output of scripts, feed them input or
+//! keep them away from the file system. The methods default to the behaviour of [`StdIo`], so
+//! an implementation only overrides what it wants to redirect:
+//!
+//! ```
+//! use std::{cell::RefCell, io, rc::Rc};
+//!
+//! use rscript::{Runtime, runtime::io::Io};
+//!
+//! struct Capture(Rc<RefCell<String>>);
+//!
+//! impl Io for Capture {
+//!     fn write_stdout(&mut self, text: &str) -> io::Result<()> {
+//!         self.0.borrow_mut().push_str(text);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let output = Rc::new(RefCell::new(String::new()));
+//! let mut runtime = Runtime::new();
+//! runtime.set_io(Capture(Rc::clone(&output)));
+//! runtime.execute("println(\"{} + {} = {}\", 1, 2, 1 + 2);")?;
+//! assert_eq!(*output.borrow(), "1 + 2 = 3\n");
+//! # Ok::<(), rscript::RuntimeError>(())
+//! ```
+//!
+//! [`Runtime::set_io`]: super::Runtime::set_io
+
+use std::{
+    cell::{RefCell, RefMut},
+    fmt, fs,
+    io::{self, Write},
+    rc::Rc,
+};
+
+/// The input and output of scripts.
+pub trait Io {
+    /// Writes text printed by `print` and `println`.
+    fn write_stdout(&mut self, text: &str) -> io::Result<()> {
+        // Flushed right away, so prompts printed without a line break show up before input
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(text.as_bytes())?;
+        stdout.flush()
+    }
+
+    /// Writes text printed by `eprintln`.
+    fn write_stderr(&mut self, text: &str) -> io::Result<()> {
+        io::stderr().lock().write_all(text.as_bytes())
+    }
+
+    /// Reads a line for `read_line`, including its line break. Returns an empty string at the
+    /// end of the input.
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        io::stdin().read_line(&mut line)?;
+        Ok(line)
+    }
+
+    fn read_file(&mut self, path: &str) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    /// Creates the file at `path` or replaces its contents.
+    fn write_file(&mut self, path: &str, contents: &str) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+}
+
+/// The standard streams and file system of the process.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdIo;
+
+impl Io for StdIo {}
+
+/// The [`Io`] of a runtime, shared with its clones.
+#[derive(Clone)]
+pub(super) struct SharedIo(Rc<RefCell<dyn Io>>);
+
+impl SharedIo {
+    pub(super) fn new(io: impl Io + 'static) -> Self {
+        SharedIo(Rc::new(RefCell::new(io)))
+    }
+
+    pub(super) fn borrow_mut(&self) -> RefMut<'_, dyn Io> {
+        self.0.borrow_mut()
+    }
+}
+
+impl Default for SharedIo {
+    fn default() -> Self {
+        SharedIo::new(StdIo)
+    }
+}
+
+impl fmt::Debug for SharedIo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedIo")
+    }
+}
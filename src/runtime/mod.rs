@@ -1,17 +1,210 @@
-use derive_more::From;
+use std::{collections::HashMap, iter, path::Path, rc::Rc};
+
+use derive_more::{Display, Error, From};
 use termcolor::{ColorChoice, StandardStream};
 
 use crate::{
-    core::format::Format,
+    core::{
+        builtin::Builtin,
+        diagnostic::{Diagnostic, ToDiagnostic},
+        format::Format,
+        source_map::{FileId, SourceMap},
+        span::Span,
+        types::Type,
+    },
+    module::{ModuleError, load_program},
+    optimizer::{OptimizerConfig, optimize_expression, optimize_program},
     parser::{
         Parser, ParserError,
-        ast::{Expression, Program, Statement},
+        ast::{FunctionDeclaration, Program, Statement},
     },
+    resolve::Resolver,
+    typeck::{TypeChecker, TypeError, TypeMembers},
 };
 
-#[derive(Debug, From)]
+pub(crate) use self::interpreter::{float_literal_value, integer_literal_value};
+use self::{
+    io::{Io, SharedIo},
+    object::NativeTypeInfo,
+    vm::{
+        BytecodeError, BytecodeFile,
+        chunk::{CompiledFunction, Instruction},
+    },
+};
+pub use self::{
+    native::{
+        ConversionError, FromValue, IntoNativeFunction, IntoValue, NativeFunction, NativeReturn,
+    },
+    object::{BorrowError, IntoMethod, NativeType, Object, Shared, TypeBuilder},
+    value::Value,
+};
+
+mod builtin;
+mod interpreter;
+pub mod io;
+pub mod native;
+pub mod object;
+pub mod stdlib;
+pub mod value;
+pub mod vm;
+
+#[derive(Debug, From, Display, Error)]
 pub enum RuntimeError {
-    ParserError(ParserError),
+    #[display("{}", display_parser_errors(_0))]
+    ParserErrors(#[error(not(source))] Vec<ParserError>),
+    #[display("{_0}")]
+    TypeError(TypeError),
+    #[display("{_0}")]
+    ModuleError(ModuleError),
+    #[display("attempt to {operation} with overflow")]
+    #[from(ignore)]
+    Overflow { operation: &'static str, span: Span },
+    #[display("attempt to divide by zero")]
+    #[from(ignore)]
+    DivisionByZero { span: Span },
+    #[display("function `{name}` ended without returning a value")]
+    #[from(ignore)]
+    MissingReturn { name: String, span: Span },
+    #[display("`main` function must not take parameters")]
+    #[from(ignore)]
+    InvalidMain { span: Span },
+    #[display("cannot find function `{name}`")]
+    #[from(ignore)]
+    UndefinedFunction { name: String },
+    #[display("{_0}")]
+    Bytecode(BytecodeError),
+    #[display("calling `{name}` exceeds the maximum call depth of {limit}")]
+    #[from(ignore)]
+    CallDepthExceeded {
+        name: String,
+        limit: usize,
+        span: Span,
+    },
+    #[display("function `{name}` takes {expected} argument(s) but {found} were supplied")]
+    #[from(ignore)]
+    ArgumentCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    #[display("mismatched types, expected: `{expected}`, found: `{found}`")]
+    #[from(ignore)]
+    ArgumentType {
+        name: String,
+        expected: Type,
+        found: Type,
+    },
+    #[display("invalid argument {} to `{name}`: {error}", index + 1)]
+    #[from(ignore)]
+    NativeArgument {
+        name: String,
+        index: usize,
+        error: ConversionError,
+        span: Span,
+    },
+    #[display("{error}")]
+    #[from(ignore)]
+    AlreadyBorrowed { error: BorrowError, span: Span },
+    #[display("`{name}` failed: {message}")]
+    #[from(ignore)]
+    NativeFailed {
+        name: String,
+        message: String,
+        span: Span,
+    },
+    #[display("{}", display_assertion(comparison, message))]
+    #[from(ignore)]
+    AssertionFailed {
+        /// The operator of `assert_eq` and `assert_ne` and the values it was applied to, boxed to
+        /// keep the error small.
+        comparison: Option<Box<(&'static str, Value, Value)>>,
+        /// The message passed to the assertion.
+        message: Option<String>,
+        span: Span,
+    },
+    #[display("`{name}` failed: {message}")]
+    #[from(ignore)]
+    BuiltinFailed {
+        name: &'static str,
+        message: String,
+        span: Span,
+    },
+}
+
+impl RuntimeError {
+    /// Converts the error into diagnostics, one per parser error or a single one otherwise.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            RuntimeError::ParserErrors(errors) => {
+                errors.iter().map(ToDiagnostic::to_diagnostic).collect()
+            }
+            RuntimeError::TypeError(error) => vec![error.to_diagnostic()],
+            RuntimeError::ModuleError(error) => error.diagnostics(),
+            RuntimeError::Overflow { operation, span } => vec![
+                Diagnostic::error(self.to_string())
+                    .with_primary_label(*span, format!("attempt to {operation} with overflow"))
+                    .with_note("the result does not fit into the type of the operands"),
+            ],
+            RuntimeError::DivisionByZero { span } => vec![
+                Diagnostic::error(self.to_string()).with_primary_label(*span, "division by zero"),
+            ],
+            RuntimeError::MissingReturn { span, .. } => vec![
+                Diagnostic::error(self.to_string())
+                    .with_primary_label(*span, "this function must return a value")
+                    .with_help("add a `return` statement at the end of the function"),
+            ],
+            RuntimeError::InvalidMain { span } => vec![
+                Diagnostic::error(self.to_string())
+                    .with_primary_label(*span, "`main` is called without arguments"),
+            ],
+            // Raised by calls from Rust, there is no source code to point at
+            RuntimeError::UndefinedFunction { .. } | RuntimeError::ArgumentCount { .. } => {
+                vec![Diagnostic::error(self.to_string())]
+            }
+            RuntimeError::Bytecode(_) => vec![
+                Diagnostic::error(self.to_string())
+                    .with_note("the file was compiled for a runtime with other declarations"),
+            ],
+            RuntimeError::CallDepthExceeded { span, .. } => vec![
+                Diagnostic::error(self.to_string())
+                    .with_primary_label(*span, "too many nested calls")
+                    .with_help("check the recursion for a missing or unreachable base case"),
+            ],
+            RuntimeError::ArgumentType { name, .. } => vec![
+                Diagnostic::error(self.to_string())
+                    .with_note(format!("in a call to `{name}` from the host")),
+            ],
+            RuntimeError::NativeArgument { error, span, .. } => vec![
+                Diagnostic::error(self.to_string())
+                    .with_primary_label(*span, format!("expected `{}`", error.expected)),
+            ],
+            RuntimeError::AlreadyBorrowed { span, .. } => vec![
+                Diagnostic::error(self.to_string())
+                    .with_primary_label(*span, "the object is in use")
+                    .with_note("the host or a method being called holds a conflicting borrow"),
+            ],
+            RuntimeError::NativeFailed { span, .. } => vec![
+                Diagnostic::error(self.to_string())
+                    .with_primary_label(*span, "error raised by the host function"),
+            ],
+            RuntimeError::AssertionFailed {
+                comparison, span, ..
+            } => {
+                let diagnostic = Diagnostic::error(self.to_string());
+                vec![match comparison.as_deref() {
+                    Some((operator, left, right)) => diagnostic
+                        .with_primary_label(*span, format!("`left {operator} right` is false"))
+                        .with_note(format!("left: {}", describe_value(left)))
+                        .with_note(format!("right: {}", describe_value(right))),
+                    None => diagnostic.with_primary_label(*span, "the condition is false"),
+                }]
+            }
+            RuntimeError::BuiltinFailed { span, .. } => vec![
+                Diagnostic::error(self.to_string())
+                    .with_primary_label(*span, "error raised by this call"),
+            ],
+        }
+    }
 }
 
 /// # Runtime
@@ -20,40 +213,649 @@ pub enum RuntimeError {
 /// This module is responsible for managing the execution context, including
 /// variables, functions, and the execution flow of the script.
 ///
+/// Declarations persist between calls, so a script can build on the ones executed before it.
+/// A script that fails leaves the runtime as it was before.
+///
+/// Scripts can call the builtins of the [prelude](crate::core::builtin), like `println`, whose
+/// input and output go through the standard streams unless replaced with [`Runtime::set_io`].
+///
 /// # Example
 /// ```
-/// use rscript::runtime::Runtime;
-/// let runtime = Runtime::new();
-/// runtime.execute("print('Hello, World!')");
+/// use rscript::{Runtime, Value};
+///
+/// let mut runtime = Runtime::new();
+/// runtime.execute("let greeting = \"Hello, World!\";")?;
+/// assert_eq!(
+///     runtime.global("greeting"),
+///     Some(&Value::String("Hello, World!".to_string()))
+/// );
+/// # Ok::<(), rscript::RuntimeError>(())
 /// ```
 #[derive(Debug, Clone)]
-pub struct Runtime {}
+pub struct Runtime {
+    /// Every script executed by this runtime, referenced by the spans in errors.
+    source_map: SourceMap,
+    checker: TypeChecker,
+    globals: HashMap<String, Value>,
+    /// Local scopes of the function currently executing, innermost last.
+    scopes: Vec<HashMap<String, Value>>,
+    functions: HashMap<String, Function>,
+    /// Nested functions of the statement lists enclosing the code currently executing, innermost
+    /// last. Top level functions are declared in `functions` instead.
+    function_scopes: Vec<HashMap<String, Function>>,
+    /// The `#[test]` functions declared at the top level of the executed scripts, in order.
+    tests: Vec<String>,
+    /// Members of the registered host types, by type name.
+    types: HashMap<String, Rc<NativeTypeInfo>>,
+    /// Methods of builtin types, by receiver type and name, called with the receiver first.
+    methods: HashMap<Type, HashMap<String, NativeFunction>>,
+    engine: Engine,
+    /// Passes run over every script after type checking it.
+    optimizer: OptimizerConfig,
+    /// Where the I/O builtins read from and write to.
+    io: SharedIo,
+    /// The number of script and compiled functions currently executing.
+    call_depth: usize,
+    /// How many of them may execute at once before calls fail.
+    max_call_depth: usize,
+}
+
+/// The default of [`Runtime::set_max_call_depth`].
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+/// How the runtime executes scripts.
+///
+/// Both engines produce the same results, including errors. Functions keep the engine of the
+/// script that declared them.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Engine {
+    /// Walks the syntax tree of the script.
+    #[default]
+    TreeWalker,
+    /// Compiles the script to bytecode first, see [`vm`].
+    Vm,
+}
+
+/// A function callable from scripts.
+#[derive(Debug, Clone)]
+enum Function {
+    Script(Rc<FunctionDeclaration>),
+    Compiled(Rc<CompiledFunction>),
+    Native(NativeFunction),
+    Builtin(Builtin),
+}
+
+impl Default for Runtime {
+    /// A runtime with the [standard library](stdlib) registered.
+    fn default() -> Self {
+        let mut runtime = Runtime {
+            source_map: SourceMap::default(),
+            checker: TypeChecker::default(),
+            globals: HashMap::new(),
+            scopes: Vec::new(),
+            functions: HashMap::new(),
+            function_scopes: Vec::new(),
+            tests: Vec::new(),
+            types: HashMap::new(),
+            methods: HashMap::new(),
+            engine: Engine::default(),
+            optimizer: OptimizerConfig::default(),
+            io: SharedIo::default(),
+            call_depth: 0,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+        };
+        stdlib::register(&mut runtime);
+        runtime
+    }
+}
 
 impl Runtime {
     /// Creates a new instance of the `Runtime`.
     pub fn new() -> Self {
-        Runtime {}
+        Self::default()
+    }
+
+    /// Creates a runtime executing scripts with the given engine.
+    pub fn with_engine(engine: Engine) -> Self {
+        Runtime {
+            engine,
+            ..Self::default()
+        }
+    }
+
+    pub fn engine(&self) -> Engine {
+        self.engine
+    }
+
+    /// Changes the engine executing the next scripts.
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+    }
+
+    pub fn optimizer(&self) -> &OptimizerConfig {
+        &self.optimizer
+    }
+
+    /// Changes the optimizations applied to the next scripts.
+    pub fn set_optimizer(&mut self, config: OptimizerConfig) {
+        self.optimizer = config;
+    }
+
+    pub fn max_call_depth(&self) -> usize {
+        self.max_call_depth
+    }
+
+    /// Changes how deeply script functions may call each other before the call fails with
+    /// [`RuntimeError::CallDepthExceeded`].
+    ///
+    /// Every call uses the stack of the host thread, a few kilobytes per call in release builds
+    /// and more in debug builds, so hosts running scripts on small stacks should lower the limit.
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    /// Routes the output and input of scripts through `io` instead of the standard streams and
+    /// file system of the process, see [`io`].
+    pub fn set_io(&mut self, io: impl Io + 'static) {
+        self.io = SharedIo::new(io);
+    }
+
+    /// The sources of all scripts executed so far, used to render diagnostics.
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
     }
 
     /// Executes a script in the runtime environment.
     pub fn execute(&mut self, source: &str) -> Result<(), RuntimeError> {
-        trace!("Executing script");
-        let parser = Parser::new(source);
-        let program = parser.parse()?;
+        self.execute_named("<script>", source)
+    }
+
+    /// Executes a script, `name` is shown as its file name in diagnostics.
+    pub fn execute_named(&mut self, name: &str, source: &str) -> Result<(), RuntimeError> {
+        trace!("Executing script `{}`", name);
+        let file = self.source_map.add_file(name, source);
+        let parser = Parser::with_file(source, file);
+        let program = parser.parse().into_result()?;
         self.execute_program(program)
     }
 
-    pub fn execute_program(&mut self, program: Program) -> Result<(), RuntimeError> {
-        trace!("Executing program");
-        if log::max_level() >= log::LevelFilter::Debug {
-            let mut stdout = StandardStream::stdout(ColorChoice::Auto);
-            program.format(&mut stdout, 4, 0);
-        }
+    /// Executes the script file at `path` whose content is `source`, together with the modules
+    /// it imports from the files next to it, see [`module`](crate::module).
+    pub fn execute_file(&mut self, path: &Path, source: &str) -> Result<(), RuntimeError> {
+        trace!("Executing script file `{}`", path.display());
+        let program = load_program(path, source, &mut self.source_map)?;
+        self.execute_program(program)
+    }
+
+    /// Type checks and executes an already parsed program.
+    ///
+    /// If the program fails, its declarations are undone, so the runtime can keep being used.
+    pub fn execute_program(&mut self, mut program: Program) -> Result<(), RuntimeError> {
+        self.transaction(|runtime| {
+            trace!("Type checking program");
+            runtime.checker.check_program(&mut program)?;
+            optimize_program(&mut program, &runtime.optimizer);
+
+            trace!("Executing program");
+            if log::max_level() >= log::LevelFilter::Debug {
+                let mut stdout = StandardStream::stdout(ColorChoice::Auto);
+                let _ = program.format(&mut stdout, 4, 0);
+            }
+
+            match runtime.engine {
+                Engine::TreeWalker => runtime.execute_statements(&program.statements)?,
+                Engine::Vm => {
+                    let chunk = vm::compile_program(&program);
+                    runtime.run_chunk(&chunk)?;
+                }
+            }
+
+            for statement in &program.statements {
+                if let Statement::FunctionDeclaration(declaration) = statement
+                    && declaration.is_test()
+                    && !runtime.tests.contains(&declaration.identifier.name)
+                {
+                    runtime.tests.push(declaration.identifier.name.clone());
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Evaluates `source` if it is a single expression and returns its value, otherwise executes
+    /// it as a script and returns `None`.
+    pub fn evaluate(&mut self, source: &str) -> Result<Option<Value>, RuntimeError> {
+        self.evaluate_named("<script>", source)
+    }
+
+    /// Evaluates `source` if it is a single expression and returns its value, otherwise executes
+    /// it as a script and returns `None`.
+    pub fn evaluate_named(
+        &mut self,
+        name: &str,
+        source: &str,
+    ) -> Result<Option<Value>, RuntimeError> {
+        trace!("Evaluating `{}`", name);
+        let file = self.source_map.add_file(name, source);
+        let Ok(mut expression) = Parser::with_file(source, file).parse_single_expression() else {
+            let program = Parser::with_file(source, file).parse().into_result()?;
+            return self.execute_program(program).map(|()| None);
+        };
+        self.transaction(|runtime| {
+            runtime.checker.check_expression(&mut expression, None)?;
+            optimize_expression(&mut expression, &runtime.optimizer);
+            let value = match runtime.engine {
+                Engine::TreeWalker => runtime.evaluate_top_level(&expression)?,
+                Engine::Vm => runtime.run_chunk(&vm::compile_expression(&expression))?,
+            };
+            Ok(Some(value))
+        })
+    }
+
+    /// Parses the script file at `path` whose content is `source` together with the modules it
+    /// imports, without checking or executing it. The parsed files are added to the source map.
+    pub fn load_file(&mut self, path: &Path, source: &str) -> Result<Program, RuntimeError> {
+        Ok(load_program(path, source, &mut self.source_map)?)
+    }
+
+    /// Loads the script file at `path` with the modules it imports and type checks it against
+    /// the declarations of this runtime, without executing it or declaring anything.
+    pub fn check_file(&mut self, path: &Path, source: &str) -> Result<Program, RuntimeError> {
+        trace!("Checking script file `{}`", path.display());
+        let mut program = self.load_file(path, source)?;
+        self.checker.clone().check_program(&mut program)?;
+        Ok(program)
+    }
+
+    /// Parses, type checks and compiles a script to bytecode without executing it.
+    ///
+    /// The script is checked against the declarations of this runtime, the compiled file can be
+    /// executed by any runtime providing the same host functions and types.
+    pub fn compile_named(
+        &mut self,
+        name: &str,
+        source: &str,
+    ) -> Result<BytecodeFile, RuntimeError> {
+        trace!("Compiling script `{}`", name);
+        let file = self.source_map.add_file(name, source);
+        let program = Parser::with_file(source, file).parse().into_result()?;
+        self.compile_program(program)
+    }
+
+    /// Like [`Runtime::compile_named`], for the script file at `path` and the modules it imports,
+    /// which are compiled into the same file.
+    pub fn compile_file(
+        &mut self,
+        path: &Path,
+        source: &str,
+    ) -> Result<BytecodeFile, RuntimeError> {
+        trace!("Compiling script file `{}`", path.display());
+        let program = self.load_file(path, source)?;
+        self.compile_program(program)
+    }
 
+    /// Compiles a program whose files were registered last, the script first.
+    fn compile_program(&mut self, mut program: Program) -> Result<BytecodeFile, RuntimeError> {
+        let mut checker = self.checker.clone();
+        checker.check_program(&mut program)?;
+        optimize_program(&mut program, &self.optimizer);
+
+        let mut structs = Vec::new();
+        let mut functions = Vec::new();
         for statement in &program.statements {
-            trace!("Executing statement: {:?}", statement);
+            match statement {
+                Statement::StructDeclaration(declaration) => {
+                    structs.push(declaration.identifier().name.clone())
+                }
+                Statement::FunctionDeclaration(declaration) => {
+                    let name = &declaration.identifier.name;
+                    let signature = checker
+                        .signature(name)
+                        .expect("functions are declared by the type checker");
+                    functions.push((name.clone(), signature.clone()));
+                }
+                _ => {}
+            }
         }
 
-        Ok(())
+        // Spans refer to the files of the program by index, counted from the script
+        let first = program.span.file.index();
+        let files: Vec<_> = (0..self.source_map.len())
+            .map(|index| FileId::from_index(index.saturating_sub(first)))
+            .collect();
+        let mut chunk = vm::compile_program(&program);
+        chunk.relocate(&files);
+
+        let mut sources = (first..self.source_map.len()).map(|index| {
+            let file = self
+                .source_map
+                .get(FileId::from_index(index))
+                .expect("files are registered in order");
+            (file.name().to_string(), file.source().to_string())
+        });
+        let (name, source) = sources.next().expect("the script is registered first");
+        Ok(BytecodeFile {
+            name,
+            source,
+            modules: sources.collect(),
+            structs,
+            functions,
+            chunk,
+        })
+    }
+
+    /// Executes a precompiled script on the VM, regardless of the engine of the runtime.
+    ///
+    /// Its declarations become visible to the scripts executed afterwards, like those of a
+    /// script executed from source.
+    pub fn execute_bytecode(&mut self, bytecode: &BytecodeFile) -> Result<(), RuntimeError> {
+        trace!("Executing bytecode of `{}`", bytecode.name);
+        let files: Vec<_> = iter::once((&bytecode.name, &bytecode.source))
+            .chain(bytecode.modules.iter().map(|(name, source)| (name, source)))
+            .map(|(name, source)| self.source_map.add_file(name.as_str(), source.as_str()))
+            .collect();
+        let mut chunk = bytecode.chunk.clone();
+        chunk.relocate(&files);
+
+        self.transaction(|runtime| {
+            for name in &bytecode.structs {
+                runtime.checker.declare_type(name, TypeMembers::default());
+            }
+            for (name, signature) in &bytecode.functions {
+                runtime.checker.declare_function(name, signature.clone());
+            }
+            runtime.check_names(&chunk)?;
+            runtime.run_chunk(&chunk)?;
+
+            for instruction in &chunk.code {
+                if let Instruction::DefineGlobal(index) = instruction {
+                    let name = &chunk.names[*index as usize];
+                    let ty = runtime.globals[name].ty();
+                    runtime.checker.declare_global(name, ty);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Determines the type of the expression in `source` without evaluating it.
+    pub fn expression_type(&mut self, name: &str, source: &str) -> Result<Type, RuntimeError> {
+        let file = self.source_map.add_file(name, source);
+        let mut expression = Parser::with_file(source, file).parse_single_expression()?;
+        // Checking declares nothing visible outside of the expression, but may leave partial
+        // state behind on errors
+        let mut checker = self.checker.clone();
+        Ok(checker.check_expression(&mut expression, None)?)
     }
+
+    /// Runs `f`, restoring the declared types, variables and functions if it fails. Otherwise
+    /// the type checker could know about declarations that were never executed.
+    fn transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, RuntimeError>,
+    ) -> Result<T, RuntimeError> {
+        let checker = self.checker.clone();
+        let globals = self.globals.clone();
+        let functions = self.functions.clone();
+        let result = f(self);
+        if result.is_err() {
+            trace!("Rolling back the runtime state");
+            self.checker = checker;
+            self.globals = globals;
+            self.functions = functions;
+            self.scopes.clear();
+            self.function_scopes.clear();
+        }
+        result
+    }
+
+    /// The function a script calls by `name` and the number of function scopes visible to it.
+    ///
+    /// Nested functions shadow the functions of the enclosing statement lists, declared functions
+    /// take precedence over builtins. A nested function sees the scopes up to the one declaring
+    /// it, the other functions none.
+    fn function(&self, name: &str) -> Option<(Function, usize)> {
+        self.function_scopes
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(index, scope)| Some((scope.get(name)?.clone(), index + 1)))
+            .or_else(|| Some((self.functions.get(name)?.clone(), 0)))
+            .or_else(|| Builtin::from_name(name).map(|builtin| (Function::Builtin(builtin), 0)))
+    }
+
+    /// Declares a function of a statement list in the innermost function scope, or at the top
+    /// level outside of any.
+    fn declare_function(&mut self, name: &str, function: Function) {
+        trace!("Declaring function `{}`", name);
+        match self.function_scopes.last_mut() {
+            Some(scope) => scope.insert(name.to_string(), function),
+            None => self.functions.insert(name.to_string(), function),
+        };
+    }
+
+    /// Calls a function found by [`Runtime::function`] with only the function scopes visible to
+    /// it, the scopes of the caller are restored afterwards.
+    fn call_scoped(
+        &mut self,
+        name: &str,
+        (function, depth): (Function, usize),
+        arguments: Vec<Value>,
+        span: Span,
+    ) -> Result<Value, RuntimeError> {
+        let caller_scopes = self.function_scopes.split_off(depth);
+        let result = self.invoke(name, &function, arguments, span);
+        // Returns and errors leave the scopes of the callee behind
+        self.function_scopes.truncate(depth);
+        self.function_scopes.extend(caller_scopes);
+        result
+    }
+
+    /// The names of the `#[test]` functions declared at the top level of the executed scripts,
+    /// in the order they were declared. Tests are called like any other function, see
+    /// [`Runtime::call`].
+    pub fn tests(&self) -> &[String] {
+        &self.tests
+    }
+
+    /// The value of a global variable.
+    pub fn global(&self, name: &str) -> Option<&Value> {
+        self.globals.get(name)
+    }
+
+    /// All global variables, in no particular order.
+    pub fn globals(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.globals
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Declares a global variable visible to the scripts executed afterwards, replacing any
+    /// previous one of the same name.
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        trace!("Setting global `{}` = {}", name, value);
+        self.checker.declare_global(name, value.ty());
+        self.globals.insert(name.to_string(), value);
+    }
+
+    /// Calls a function declared at the top level of an executed script.
+    ///
+    /// The arguments are checked against the parameter types, as scripts are checked before they
+    /// run.
+    pub fn call(&mut self, name: &str, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        let signature =
+            self.checker
+                .signature(name)
+                .ok_or_else(|| RuntimeError::UndefinedFunction {
+                    name: name.to_string(),
+                })?;
+        if signature.parameters.len() != arguments.len() {
+            return Err(RuntimeError::ArgumentCount {
+                name: name.to_string(),
+                expected: signature.parameters.len(),
+                found: arguments.len(),
+            });
+        }
+        for (expected, argument) in signature.parameters.iter().zip(&arguments) {
+            if *expected != argument.ty() {
+                return Err(RuntimeError::ArgumentType {
+                    name: name.to_string(),
+                    expected: expected.clone(),
+                    found: argument.ty(),
+                });
+            }
+        }
+
+        let function = self.functions[name].clone();
+        trace!("Calling `{}` from the host", name);
+        // The argument types were checked above, so there is no conversion error to point at
+        self.invoke(name, &function, arguments, Span::default())
+    }
+
+    /// Registers a Rust closure as a script function, replacing any function of the same name.
+    ///
+    /// Its signature is derived from the argument and return types of the closure, see
+    /// [`FromValue`] and [`IntoValue`].
+    pub fn register_function<F, Args>(&mut self, name: &str, function: F)
+    where
+        F: IntoNativeFunction<Args>,
+    {
+        let function = function.into_native_function();
+        trace!("Registering native function `{}`", name);
+        self.checker
+            .declare_function(name, function.signature().clone());
+        self.functions
+            .insert(name.to_string(), Function::Native(function));
+    }
+
+    /// Registers a Rust closure as a method of a builtin type, replacing any method of the same
+    /// name. The closure takes the receiver as its first argument:
+    ///
+    /// ```
+    /// use rscript::{Runtime, Value};
+    ///
+    /// let mut runtime = Runtime::new();
+    /// runtime.register_method("shout", |text: String| format!("{}!", text.to_uppercase()));
+    /// assert_eq!(
+    ///     runtime.evaluate("\"hey\".shout()")?,
+    ///     Some(Value::String("HEY!".to_string()))
+    /// );
+    /// # Ok::<(), rscript::RuntimeError>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the closure takes no receiver or the receiver is a host type, whose methods are
+    /// declared by [`NativeType::register`] instead.
+    pub fn register_method<F, Args>(&mut self, name: &str, method: F)
+    where
+        F: IntoNativeFunction<Args>,
+    {
+        let method = method.into_native_function();
+        let mut signature = method.signature().clone();
+        assert!(
+            !signature.parameters.is_empty(),
+            "method `{name}` must take its receiver as the first argument"
+        );
+        let receiver = signature.parameters.remove(0);
+        assert!(
+            !matches!(receiver, Type::Struct(_)),
+            "method `{name}` of host type `{receiver}` must be declared by the type"
+        );
+        trace!("Registering method `{}::{}`", receiver, name);
+        self.checker.declare_method(&receiver, name, signature);
+        self.methods
+            .entry(receiver)
+            .or_default()
+            .insert(name.to_string(), method);
+    }
+
+    /// Makes the fields and methods of a host type available to scripts, its values can then be
+    /// passed in as [`Shared`] handles.
+    pub fn register_type<T: NativeType>(&mut self) {
+        trace!("Registering native type `{}`", T::NAME);
+        let mut builder = TypeBuilder::<T>::new();
+        T::register(&mut builder);
+        let (members, info) = builder.finish();
+        self.checker.declare_type(T::NAME, members);
+        self.types.insert(T::NAME.to_string(), Rc::new(info));
+    }
+
+    /// A type checker knowing the globals, functions and types of this runtime, to check scripts
+    /// without executing them.
+    pub fn type_checker(&self) -> &TypeChecker {
+        &self.checker
+    }
+
+    /// A resolver knowing the globals, functions and types of this runtime, see
+    /// [`Runtime::type_checker`].
+    pub fn resolver(&self) -> Resolver {
+        let mut resolver = Resolver::default();
+        for name in self.globals.keys() {
+            resolver.declare_global(name);
+        }
+        for name in self.functions.keys() {
+            resolver.declare_function(name);
+        }
+        for name in self.types.keys() {
+            resolver.declare_type(name);
+        }
+        resolver
+    }
+
+    /// Calls the `main` function of the executed scripts, if one was declared.
+    ///
+    /// Its return value is used as the exit code of the script.
+    pub fn run_main(&mut self) -> Result<Option<Value>, RuntimeError> {
+        let (parameters, span) = match self.functions.get("main") {
+            Some(Function::Script(main)) => (main.parameters.len(), main.span),
+            Some(Function::Compiled(main)) => (main.parameters, main.span),
+            _ => return Ok(None),
+        };
+        if parameters != 0 {
+            return Err(RuntimeError::InvalidMain { span });
+        }
+        trace!("Calling `main`");
+        let main = self.functions["main"].clone();
+        self.invoke("main", &main, Vec::new(), span).map(Some)
+    }
+}
+
+fn display_assertion(
+    comparison: &Option<Box<(&'static str, Value, Value)>>,
+    message: &Option<String>,
+) -> String {
+    let assertion = match comparison.as_deref() {
+        Some((operator, ..)) => format!("assertion `left {operator} right` failed"),
+        None => "assertion failed".to_string(),
+    };
+    match message {
+        Some(message) => format!("{assertion}: {message}"),
+        None => assertion,
+    }
+}
+
+/// Shows a value the way it is written in source code, so strings are told apart from numbers.
+fn describe_value(value: &Value) -> String {
+    match value {
+        Value::String(string) => format!("{string:?}"),
+        Value::Char(char) => format!("{char:?}"),
+        value => value.to_string(),
+    }
+}
+
+/// A name the type checker would have rejected, found in bytecode compiled elsewhere.
+fn unresolved(kind: &'static str, name: &str) -> RuntimeError {
+    RuntimeError::Bytecode(BytecodeError::Unresolved {
+        kind,
+        name: name.to_string(),
+    })
+}
+
+fn display_parser_errors(errors: &[ParserError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
 }
@@ -0,0 +1,255 @@
+//! Rust functions callable from scripts.
+//!
+//! Arguments and return values are converted through [`FromValue`] and [`IntoValue`], which
+//! also provide the script types used to type check calls. Any closure taking up to six
+//! convertible arguments can be registered with [`Runtime::register_function`]:
+//!
+//! ```
+//! use rscript::{Runtime, Value};
+//!
+//! let mut runtime = Runtime::new();
+//! runtime.register_function("clamp", |value: i64, min: i64, max: i64| value.clamp(min, max));
+//! assert_eq!(runtime.evaluate("clamp(15, 0, 10)")?, Some(Value::I64(10)));
+//! # Ok::<(), rscript::RuntimeError>(())
+//! ```
+//!
+//! Functions returning a [`Result`] fail the script with their error message.
+//!
+//! [`Runtime::register_function`]: super::Runtime::register_function
+
+use std::{fmt, rc::Rc};
+
+use derive_more::{Display, Error};
+
+use super::Value;
+use crate::{
+    core::types::{FloatType, IntegerType, Type},
+    typeck::FunctionSignature,
+};
+
+/// A value did not have the type a native function expects.
+#[derive(Debug, PartialEq, Clone, Display, Error)]
+#[display("expected `{expected}`, found `{found}`")]
+pub struct ConversionError {
+    pub expected: Type,
+    pub found: Type,
+}
+
+/// A Rust type native functions can take as an argument.
+pub trait FromValue: Sized {
+    /// The script type the values are converted from.
+    fn ty() -> Type;
+
+    fn from_value(value: Value) -> Result<Self, ConversionError>;
+}
+
+/// A Rust type native functions can return.
+pub trait IntoValue {
+    /// The script type the values are converted into.
+    fn ty() -> Type;
+
+    fn into_value(self) -> Value;
+}
+
+/// Implements the conversions for a Rust type stored in the given variant of [`Value`].
+macro_rules! value_conversions {
+    ($($rust:ty => $variant:ident: $ty:expr),* $(,)?) => {
+        $(
+            impl FromValue for $rust {
+                fn ty() -> Type {
+                    $ty
+                }
+
+                fn from_value(value: Value) -> Result<Self, ConversionError> {
+                    match value {
+                        Value::$variant(value) => Ok(value),
+                        value => Err(ConversionError {
+                            expected: $ty,
+                            found: value.ty(),
+                        }),
+                    }
+                }
+            }
+
+            impl IntoValue for $rust {
+                fn ty() -> Type {
+                    $ty
+                }
+
+                fn into_value(self) -> Value {
+                    Value::$variant(self)
+                }
+            }
+        )*
+    };
+}
+
+value_conversions! {
+    i8 => I8: Type::Integer(IntegerType::I8),
+    i16 => I16: Type::Integer(IntegerType::I16),
+    i32 => I32: Type::Integer(IntegerType::I32),
+    i64 => I64: Type::Integer(IntegerType::I64),
+    u8 => U8: Type::Integer(IntegerType::U8),
+    u16 => U16: Type::Integer(IntegerType::U16),
+    u32 => U32: Type::Integer(IntegerType::U32),
+    u64 => U64: Type::Integer(IntegerType::U64),
+    f32 => F32: Type::Float(FloatType::F32),
+    f64 => F64: Type::Float(FloatType::F64),
+    bool => Bool: Type::Bool,
+    String => String: Type::String,
+    char => Char: Type::Char,
+}
+
+impl FromValue for () {
+    fn ty() -> Type {
+        Type::Unit
+    }
+
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::Unit => Ok(()),
+            value => Err(ConversionError {
+                expected: Type::Unit,
+                found: value.ty(),
+            }),
+        }
+    }
+}
+
+impl IntoValue for () {
+    fn ty() -> Type {
+        Type::Unit
+    }
+
+    fn into_value(self) -> Value {
+        Value::Unit
+    }
+}
+
+/// The return type of a native function, either a value or a [`Result`] whose error message
+/// aborts the script.
+pub trait NativeReturn {
+    fn ty() -> Type;
+
+    fn into_result(self) -> Result<Value, String>;
+}
+
+impl<T: IntoValue> NativeReturn for T {
+    fn ty() -> Type {
+        T::ty()
+    }
+
+    fn into_result(self) -> Result<Value, String> {
+        Ok(self.into_value())
+    }
+}
+
+impl<T: IntoValue, E: fmt::Display> NativeReturn for Result<T, E> {
+    fn ty() -> Type {
+        T::ty()
+    }
+
+    fn into_result(self) -> Result<Value, String> {
+        self.map(IntoValue::into_value)
+            .map_err(|error| error.to_string())
+    }
+}
+
+/// Reasons a call to a native function can fail.
+#[derive(Debug, PartialEq, Clone)]
+pub enum NativeError {
+    /// The argument at `index` could not be converted.
+    Conversion {
+        index: usize,
+        error: ConversionError,
+    },
+    /// The function itself returned an error.
+    Failed(String),
+}
+
+/// Converts the next of the enumerated arguments of a native call.
+pub(super) fn next_argument<T: FromValue>(
+    arguments: &mut impl Iterator<Item = (usize, Value)>,
+) -> Result<T, NativeError> {
+    let (index, value) = arguments
+        .next()
+        .expect("argument count is checked by the type checker");
+    T::from_value(value).map_err(|error| NativeError::Conversion { index, error })
+}
+
+type Callback = dyn Fn(Vec<Value>) -> Result<Value, NativeError>;
+
+/// A Rust function with the signature scripts see it with.
+#[derive(Clone)]
+pub struct NativeFunction {
+    signature: FunctionSignature,
+    callback: Rc<Callback>,
+}
+
+impl NativeFunction {
+    pub fn new<F, Args>(function: F) -> Self
+    where
+        F: IntoNativeFunction<Args>,
+    {
+        function.into_native_function()
+    }
+
+    pub fn signature(&self) -> &FunctionSignature {
+        &self.signature
+    }
+
+    /// Calls the function, the number of arguments must match its signature.
+    pub fn call(&self, arguments: Vec<Value>) -> Result<Value, NativeError> {
+        (self.callback)(arguments)
+    }
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NativeFunction")
+            .field("signature", &self.signature)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Closures that can be turned into a [`NativeFunction`], `Args` are their argument types.
+pub trait IntoNativeFunction<Args> {
+    fn into_native_function(self) -> NativeFunction;
+}
+
+/// Implements [`IntoNativeFunction`] for closures taking the given argument types.
+macro_rules! into_native_function {
+    ($($argument:ident),*) => {
+        impl<F, R, $($argument),*> IntoNativeFunction<($($argument,)*)> for F
+        where
+            F: Fn($($argument),*) -> R + 'static,
+            R: NativeReturn,
+            $($argument: FromValue,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn into_native_function(self) -> NativeFunction {
+                let signature = FunctionSignature {
+                    parameters: vec![$($argument::ty()),*],
+                    return_type: R::ty(),
+                };
+                let callback = move |arguments: Vec<Value>| {
+                    let mut arguments = arguments.into_iter().enumerate();
+                    $(let $argument = next_argument(&mut arguments)?;)*
+                    self($($argument),*).into_result().map_err(NativeError::Failed)
+                };
+                NativeFunction {
+                    signature,
+                    callback: Rc::new(callback),
+                }
+            }
+        }
+    };
+}
+
+into_native_function!();
+into_native_function!(A);
+into_native_function!(A, B);
+into_native_function!(A, B, C);
+into_native_function!(A, B, C, D);
+into_native_function!(A, B, C, D, E);
+into_native_function!(A, B, C, D, E, G);
@@ -0,0 +1,332 @@
+//! Rust types exposed to scripts.
+//!
+//! A type implementing [`NativeType`] declares the fields scripts can read and the methods they
+//! can call. Once registered with [`Runtime::register_type`], its values can be handed to
+//! scripts as [`Shared`] handles, which the host keeps to observe what the script changed:
+//!
+//! ```
+//! use rscript::{
+//!     Runtime, Value,
+//!     runtime::{NativeType, Shared, TypeBuilder},
+//! };
+//!
+//! struct Player {
+//!     health: i64,
+//! }
+//!
+//! impl NativeType for Player {
+//!     const NAME: &'static str = "Player";
+//!
+//!     fn register(builder: &mut TypeBuilder<Self>) {
+//!         builder
+//!             .field("health", |player: &Player| player.health)
+//!             .method("heal", |player: &mut Player, amount: i64| player.health += amount);
+//!     }
+//! }
+//!
+//! let mut runtime = Runtime::new();
+//! runtime.register_type::<Player>();
+//!
+//! let player = Shared::new(Player { health: 10 });
+//! runtime.set_global("player", player.clone().into());
+//! runtime.execute("player.heal(5);")?;
+//!
+//! assert_eq!(runtime.evaluate("player.health")?, Some(Value::I64(15)));
+//! assert_eq!(player.borrow().unwrap().health, 15);
+//! # Ok::<(), rscript::RuntimeError>(())
+//! ```
+//!
+//! Objects are shared between the host and scripts, so borrows are checked at runtime: a method
+//! cannot be called on an object the host is currently borrowing, this is reported as a
+//! [`RuntimeError::AlreadyBorrowed`](super::RuntimeError::AlreadyBorrowed) instead of a panic.
+
+use std::{
+    any::Any,
+    cell::{Ref, RefCell, RefMut},
+    collections::HashMap,
+    fmt,
+    marker::PhantomData,
+    rc::Rc,
+};
+
+use derive_more::{Display, Error, From};
+
+use super::{
+    Value,
+    native::{ConversionError, FromValue, IntoValue, NativeError, NativeReturn, next_argument},
+};
+use crate::{
+    core::types::Type,
+    typeck::{FunctionSignature, TypeMembers},
+};
+
+/// A Rust type scripts can use.
+pub trait NativeType: Any + Sized {
+    /// The name of the type in scripts, it must not collide with other types.
+    const NAME: &'static str;
+
+    /// Declares the fields and methods scripts can access.
+    fn register(builder: &mut TypeBuilder<Self>);
+}
+
+/// An object was borrowed in a way that conflicts with an existing borrow.
+#[derive(Debug, PartialEq, Clone, Copy, Display, Error)]
+#[display("`{type_name}` is already borrowed")]
+pub struct BorrowError {
+    pub type_name: &'static str,
+}
+
+/// A handle to a host object that can be passed to scripts.
+///
+/// Cloning the handle does not clone the object, all clones and the scripts share it.
+pub struct Shared<T>(Rc<RefCell<T>>);
+
+impl<T: NativeType> Shared<T> {
+    pub fn new(value: T) -> Self {
+        Shared(Rc::new(RefCell::new(value)))
+    }
+
+    /// Borrows the object, failing if it is currently borrowed mutably.
+    pub fn borrow(&self) -> Result<Ref<'_, T>, BorrowError> {
+        self.0
+            .try_borrow()
+            .map_err(|_| BorrowError { type_name: T::NAME })
+    }
+
+    /// Borrows the object mutably, failing if it is currently borrowed.
+    pub fn borrow_mut(&self) -> Result<RefMut<'_, T>, BorrowError> {
+        self.0
+            .try_borrow_mut()
+            .map_err(|_| BorrowError { type_name: T::NAME })
+    }
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Shared(self.0.clone())
+    }
+}
+
+impl<T: NativeType> From<Shared<T>> for Value {
+    fn from(shared: Shared<T>) -> Self {
+        shared.into_value()
+    }
+}
+
+impl<T: NativeType> FromValue for Shared<T> {
+    fn ty() -> Type {
+        Type::Struct(T::NAME.to_string())
+    }
+
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        match &value {
+            Value::Object(object) => object.downcast(),
+            _ => None,
+        }
+        .ok_or_else(|| ConversionError {
+            expected: <Self as FromValue>::ty(),
+            found: value.ty(),
+        })
+    }
+}
+
+impl<T: NativeType> IntoValue for Shared<T> {
+    fn ty() -> Type {
+        Type::Struct(T::NAME.to_string())
+    }
+
+    fn into_value(self) -> Value {
+        Value::Object(Object {
+            type_name: T::NAME,
+            data: self.0,
+        })
+    }
+}
+
+/// A host object stored in a [`Value`], with its Rust type erased.
+///
+/// Objects are equal if they refer to the same host object.
+#[derive(Clone)]
+pub struct Object {
+    type_name: &'static str,
+    /// A `RefCell<T>` of the [`NativeType`] named `type_name`.
+    data: Rc<dyn Any>,
+}
+
+impl Object {
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// A handle to the object if it is a `T`.
+    pub fn downcast<T: NativeType>(&self) -> Option<Shared<T>> {
+        self.data.clone().downcast::<RefCell<T>>().ok().map(Shared)
+    }
+}
+
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.data, &other.data)
+    }
+}
+
+impl fmt::Debug for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Object")
+            .field("type_name", &self.type_name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}>", self.type_name)
+    }
+}
+
+/// Reasons accessing a member of an [`Object`] can fail.
+#[derive(Debug, PartialEq, Clone, From)]
+pub enum MemberError {
+    Borrow(BorrowError),
+    Native(NativeError),
+}
+
+type Getter = dyn Fn(&Object) -> Result<Value, MemberError>;
+type Method = dyn Fn(&Object, Vec<Value>) -> Result<Value, MemberError>;
+
+/// The members of a registered [`NativeType`], as seen by the runtime.
+#[derive(Clone, Default)]
+pub(super) struct NativeTypeInfo {
+    fields: HashMap<String, Rc<Getter>>,
+    methods: HashMap<String, Rc<Method>>,
+}
+
+impl NativeTypeInfo {
+    pub(super) fn get(&self, object: &Object, field: &str) -> Option<Result<Value, MemberError>> {
+        self.fields.get(field).map(|getter| getter(object))
+    }
+
+    pub(super) fn has_field(&self, field: &str) -> bool {
+        self.fields.contains_key(field)
+    }
+
+    pub(super) fn has_method(&self, method: &str) -> bool {
+        self.methods.contains_key(method)
+    }
+
+    pub(super) fn call(
+        &self,
+        object: &Object,
+        method: &str,
+        arguments: Vec<Value>,
+    ) -> Option<Result<Value, MemberError>> {
+        self.methods
+            .get(method)
+            .map(|method| method(object, arguments))
+    }
+}
+
+impl fmt::Debug for NativeTypeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NativeTypeInfo")
+            .field("fields", &self.fields.keys())
+            .field("methods", &self.methods.keys())
+            .finish()
+    }
+}
+
+/// Collects the members of a [`NativeType`] in [`NativeType::register`].
+pub struct TypeBuilder<T> {
+    members: TypeMembers,
+    info: NativeTypeInfo,
+    marker: PhantomData<T>,
+}
+
+impl<T: NativeType> TypeBuilder<T> {
+    pub(super) fn new() -> Self {
+        TypeBuilder {
+            members: TypeMembers::default(),
+            info: NativeTypeInfo::default(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Declares a field scripts can read through `getter`.
+    pub fn field<R, F>(&mut self, name: &str, getter: F) -> &mut Self
+    where
+        R: IntoValue,
+        F: Fn(&T) -> R + 'static,
+    {
+        let getter = move |object: &Object| {
+            let shared = downcast::<T>(object);
+            let value = getter(&*shared.borrow()?);
+            Ok(value.into_value())
+        };
+        self.members.fields.insert(name.to_string(), R::ty());
+        self.info.fields.insert(name.to_string(), Rc::new(getter));
+        self
+    }
+
+    /// Declares a method, a closure taking the object as `&mut T` followed by the arguments.
+    pub fn method<F, Args>(&mut self, name: &str, method: F) -> &mut Self
+    where
+        F: IntoMethod<T, Args>,
+    {
+        let (signature, method) = method.into_method();
+        self.members.methods.insert(name.to_string(), signature);
+        self.info.methods.insert(name.to_string(), method);
+        self
+    }
+
+    pub(super) fn finish(self) -> (TypeMembers, NativeTypeInfo) {
+        (self.members, self.info)
+    }
+}
+
+fn downcast<T: NativeType>(object: &Object) -> Shared<T> {
+    object
+        .downcast()
+        .expect("members are looked up by the type name of the object")
+}
+
+/// Closures that can be methods of `T`, `Args` are their argument types after the receiver.
+pub trait IntoMethod<T, Args> {
+    #[doc(hidden)]
+    fn into_method(self) -> (FunctionSignature, Rc<Method>);
+}
+
+/// Implements [`IntoMethod`] for closures taking the given argument types.
+macro_rules! into_method {
+    ($($argument:ident),*) => {
+        impl<F, T, R, $($argument),*> IntoMethod<T, ($($argument,)*)> for F
+        where
+            F: Fn(&mut T, $($argument),*) -> R + 'static,
+            T: NativeType,
+            R: NativeReturn,
+            $($argument: FromValue,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn into_method(self) -> (FunctionSignature, Rc<Method>) {
+                let signature = FunctionSignature {
+                    parameters: vec![$($argument::ty()),*],
+                    return_type: R::ty(),
+                };
+                let method = move |object: &Object, arguments: Vec<Value>| {
+                    let mut arguments = arguments.into_iter().enumerate();
+                    $(let $argument = next_argument(&mut arguments)?;)*
+                    let shared = downcast::<T>(object);
+                    let result = self(&mut *shared.borrow_mut()?, $($argument),*);
+                    Ok(result.into_result().map_err(NativeError::Failed)?)
+                };
+                (signature, Rc::new(method))
+            }
+        }
+    };
+}
+
+into_method!();
+into_method!(A);
+into_method!(A, B);
+into_method!(A, B, C);
+into_method!(A, B, C, D);
+into_method!(A, B, C, D, E);
@@ -0,0 +1,163 @@
+//! Numeric functions, as methods of the number types, and the constants `PI`, `E` and `TAU`.
+//!
+//! Every integer and float type has its own methods, so they keep the type of their receiver:
+//! `x.max(y)` on two `u8` values is a `u8` again. Operations that can not produce a result,
+//! like `pow` overflowing or `clamp` with a minimum above its maximum, abort the script with an
+//! error, the same as the arithmetic operators do.
+//!
+//! ```
+//! use rscript::{Runtime, Value};
+//!
+//! let mut runtime = Runtime::new();
+//! runtime.execute(
+//!     "let radius = 2.0;
+//!      let area = (PI * radius.pow(2.0)).round();
+//!      let level = 250u8.saturating_add(10);
+//!      let steps = 7.max(3).pow(2).to_float().sqrt();",
+//! )?;
+//! assert_eq!(runtime.global("area"), Some(&Value::F64(13.0)));
+//! assert_eq!(runtime.global("level"), Some(&Value::U8(255)));
+//! assert_eq!(runtime.global("steps"), Some(&Value::F64(7.0)));
+//! # Ok::<(), rscript::RuntimeError>(())
+//! ```
+//!
+//! | Method | Types | Returns |
+//! |---|---|---|
+//! | `abs()` | signed integers, floats | the absolute value |
+//! | `min(other)`, `max(other)`, `clamp(min, max)` | all | the smaller, larger or bounded value |
+//! | `pow(exponent)` | all | the value raised to a `u32` power, or a float power for floats |
+//! | `checked_add(other, fallback)`, also `_sub`, `_mul` and `_div` | integers | the result, or `fallback` if there is none |
+//! | `wrapping_add(other)`, also `_sub` and `_mul` | integers | the result wrapped around at the bounds of the type |
+//! | `saturating_add(other)`, also `_sub` and `_mul` | integers | the result limited to the bounds of the type |
+//! | `sqrt()`, `exp()`, `ln()`, `log10()` | floats | roots and logarithms |
+//! | `sin()`, `cos()`, `tan()`, `asin()`, `acos()`, `atan()`, `atan2(x)` | floats | trigonometry in radians |
+//! | `floor()`, `ceil()`, `round()`, `trunc()` | floats | the value rounded to a whole number |
+//! | `to_int()`, `to_float()` | all | the value as `i64`, truncated towards zero, or as `f64` |
+//!
+//! Scripts have no `Option` type, so the checked operations take the value to return on
+//! overflow or division by zero.
+
+use std::{cmp::Ordering, f64::consts, fmt::Display};
+
+use super::super::{Runtime, Value};
+
+/// Registers the methods of integer types.
+macro_rules! register_integer {
+    ($runtime:expr, $($ty:ty),*) => {$(
+        register_common!($runtime, $ty);
+        $runtime.register_method("pow", |value: $ty, exponent: u32| {
+            value
+                .checked_pow(exponent)
+                .ok_or_else(|| format!("{value} to the power of {exponent} overflows"))
+        });
+        $runtime.register_method("checked_add", |value: $ty, other: $ty, fallback: $ty| {
+            value.checked_add(other).unwrap_or(fallback)
+        });
+        $runtime.register_method("checked_sub", |value: $ty, other: $ty, fallback: $ty| {
+            value.checked_sub(other).unwrap_or(fallback)
+        });
+        $runtime.register_method("checked_mul", |value: $ty, other: $ty, fallback: $ty| {
+            value.checked_mul(other).unwrap_or(fallback)
+        });
+        $runtime.register_method("checked_div", |value: $ty, other: $ty, fallback: $ty| {
+            value.checked_div(other).unwrap_or(fallback)
+        });
+        $runtime.register_method("wrapping_add", |value: $ty, other: $ty| value.wrapping_add(other));
+        $runtime.register_method("wrapping_sub", |value: $ty, other: $ty| value.wrapping_sub(other));
+        $runtime.register_method("wrapping_mul", |value: $ty, other: $ty| value.wrapping_mul(other));
+        $runtime.register_method("saturating_add", |value: $ty, other: $ty| {
+            value.saturating_add(other)
+        });
+        $runtime.register_method("saturating_sub", |value: $ty, other: $ty| {
+            value.saturating_sub(other)
+        });
+        $runtime.register_method("saturating_mul", |value: $ty, other: $ty| {
+            value.saturating_mul(other)
+        });
+        $runtime.register_method("to_int", |value: $ty| {
+            i64::try_from(value).map_err(|_| format!("{value} does not fit into `i64`"))
+        });
+        $runtime.register_method("to_float", |value: $ty| value as f64);
+    )*};
+}
+
+/// Registers `abs` on signed integer types.
+macro_rules! register_signed {
+    ($runtime:expr, $($ty:ty),*) => {$(
+        $runtime.register_method("abs", |value: $ty| {
+            value
+                .checked_abs()
+                .ok_or_else(|| format!("the absolute value of {value} overflows"))
+        });
+    )*};
+}
+
+/// Registers the methods of float types.
+macro_rules! register_float {
+    ($runtime:expr, $($ty:ty),*) => {$(
+        register_common!($runtime, $ty);
+        $runtime.register_method("abs", |value: $ty| value.abs());
+        $runtime.register_method("pow", |value: $ty, exponent: $ty| value.powf(exponent));
+        $runtime.register_method("atan2", |value: $ty, x: $ty| value.atan2(x));
+        register_float!(@unary $runtime, $ty, sqrt, exp, ln, log10, sin, cos, tan, asin, acos, atan, floor, ceil, round, trunc);
+        $runtime.register_method("to_int", |value: $ty| float_to_int(f64::from(value)));
+        $runtime.register_method("to_float", |value: $ty| f64::from(value));
+    )*};
+    (@unary $runtime:expr, $ty:ty, $($method:ident),*) => {
+        $($runtime.register_method(stringify!($method), |value: $ty| value.$method());)*
+    };
+}
+
+/// Registers the methods every number type has.
+macro_rules! register_common {
+    ($runtime:expr, $ty:ty) => {
+        $runtime.register_method(
+            "min",
+            |value: $ty, other: $ty| {
+                if other < value { other } else { value }
+            },
+        );
+        $runtime.register_method(
+            "max",
+            |value: $ty, other: $ty| {
+                if other > value { other } else { value }
+            },
+        );
+        $runtime.register_method("clamp", |value: $ty, min: $ty, max: $ty| {
+            clamp(value, min, max)
+        });
+    };
+}
+
+pub(super) fn register(runtime: &mut Runtime) {
+    register_integer!(runtime, i8, i16, i32, i64, u8, u16, u32, u64);
+    register_signed!(runtime, i8, i16, i32, i64);
+    register_float!(runtime, f32, f64);
+
+    runtime.set_global("PI", Value::F64(consts::PI));
+    runtime.set_global("E", Value::F64(consts::E));
+    runtime.set_global("TAU", Value::F64(consts::TAU));
+}
+
+/// Like `Ord::clamp`, which panics if `min` is greater than `max`, for floats too.
+fn clamp<T: PartialOrd + Display>(value: T, min: T, max: T) -> Result<T, String> {
+    match min.partial_cmp(&max) {
+        Some(Ordering::Less | Ordering::Equal) if value < min => Ok(min),
+        Some(Ordering::Less | Ordering::Equal) if value > max => Ok(max),
+        Some(Ordering::Less | Ordering::Equal) => Ok(value),
+        _ => Err(format!(
+            "the minimum {min} is greater than the maximum {max}"
+        )),
+    }
+}
+
+/// Truncates towards zero, failing for values outside of the range of `i64` and NaN.
+fn float_to_int(value: f64) -> Result<i64, String> {
+    let truncated = value.trunc();
+    // `i64::MAX` is rounded up to 2^63 as a float, which is out of range itself
+    if truncated >= i64::MIN as f64 && truncated < i64::MAX as f64 {
+        Ok(truncated as i64)
+    } else {
+        Err(format!("{value} does not fit into `i64`"))
+    }
+}
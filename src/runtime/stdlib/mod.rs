@@ -0,0 +1,17 @@
+//! The standard library, registered in every [`Runtime`].
+//!
+//! Unlike [builtins](crate::core::builtin), its functions have fixed signatures, so they are
+//! registered like host functions and the host can replace them the same way.
+//!
+//! - [`string`]: methods of strings and numbers for processing and formatting text.
+//! - [`math`]: methods of numbers for arithmetic, rounding and trigonometry, and constants.
+
+use super::Runtime;
+
+pub mod math;
+pub mod string;
+
+pub(super) fn register(runtime: &mut Runtime) {
+    string::register(runtime);
+    math::register(runtime);
+}
@@ -0,0 +1,176 @@
+//! Text processing, as methods of `String` values, and the formatting of numbers.
+//!
+//! Lengths and indices count chars rather than bytes, except for `byte_len`. Methods that can
+//! fail, like `parse_int` or slicing out of bounds, abort the script with an error.
+//!
+//! ```
+//! use rscript::{Runtime, Value};
+//!
+//! let mut runtime = Runtime::new();
+//! runtime.execute(
+//!     "let words = \"  grüße, world  \".trim().split(\", \");
+//!      let title = words.get(0).to_upper().slice(0, 3);
+//!      let total = \"40\".parse_int() + 2;
+//!      let price = 4.5.to_fixed(2).pad_start(7, ' ');",
+//! )?;
+//! assert_eq!(runtime.global("title"), Some(&Value::String("GRÜ".to_string())));
+//! assert_eq!(runtime.global("total"), Some(&Value::I64(42)));
+//! assert_eq!(runtime.global("price"), Some(&Value::String("   4.50".to_string())));
+//! # Ok::<(), rscript::RuntimeError>(())
+//! ```
+//!
+//! | Method | Returns |
+//! |---|---|
+//! | `len()`, `byte_len()` | the number of chars or bytes |
+//! | `slice(start, end)`, `char_at(index)` | the chars in `start..end` or at `index` |
+//! | `split(separator)` | a [`StringList`] of the parts between the separators |
+//! | `trim()`, `trim_start()`, `trim_end()` | the string without surrounding whitespace |
+//! | `replace(from, to)` | the string with every `from` replaced by `to` |
+//! | `contains(text)`, `starts_with(text)`, `ends_with(text)` | whether `text` is found there |
+//! | `to_upper()`, `to_lower()` | the string in upper or lower case |
+//! | `parse_int()`, `parse_float()` | the `i64` or `f64` written in the string |
+//! | `pad_start(width, fill)`, `pad_end(width, fill)` | the string filled up to `width` chars |
+//! | `to_string()` on numbers, `bool` and `char` | the value as text |
+//! | `to_fixed(digits)` on floats | the value with `digits` decimal places |
+
+use super::super::{NativeType, Runtime, Shared, TypeBuilder};
+
+/// The parts of a string, returned by `split`.
+///
+/// Scripts read them with `len()`, `get(index)` and `join(separator)`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct StringList(pub Vec<String>);
+
+impl NativeType for StringList {
+    const NAME: &'static str = "StringList";
+
+    fn register(builder: &mut TypeBuilder<Self>) {
+        builder
+            .method("len", |list: &mut StringList| list.0.len() as i64)
+            .method("get", |list: &mut StringList, index: i64| {
+                usize::try_from(index)
+                    .ok()
+                    .and_then(|index| list.0.get(index))
+                    .cloned()
+                    .ok_or_else(|| {
+                        format!(
+                            "index {index} is out of bounds for a list of {} strings",
+                            list.0.len()
+                        )
+                    })
+            })
+            .method("join", |list: &mut StringList, separator: String| {
+                list.0.join(&separator)
+            });
+    }
+}
+
+/// Registers a method on every type whose values can be shown as text.
+macro_rules! register_to_string {
+    ($runtime:expr, $($ty:ty),*) => {
+        $($runtime.register_method("to_string", |value: $ty| value.to_string());)*
+    };
+}
+
+/// Registers `to_fixed` on the float types, for at most `u16::MAX` digits like `format!`.
+macro_rules! register_to_fixed {
+    ($runtime:expr, $($ty:ty),*) => {
+        $($runtime.register_method("to_fixed", |value: $ty, digits: i64| {
+            u16::try_from(digits)
+                .map(|digits| format!("{value:.0$}", usize::from(digits)))
+                .map_err(|_| format!("cannot show {digits} decimal places"))
+        });)*
+    };
+}
+
+pub(super) fn register(runtime: &mut Runtime) {
+    runtime.register_type::<StringList>();
+
+    runtime.register_method("len", |text: String| text.chars().count() as i64);
+    runtime.register_method("byte_len", |text: String| text.len() as i64);
+    runtime.register_method("slice", slice);
+    runtime.register_method("char_at", |text: String, index: i64| {
+        let length = text.chars().count();
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| text.chars().nth(index))
+            .ok_or_else(|| out_of_bounds(&index.to_string(), length))
+    });
+    runtime.register_method("split", |text: String, separator: String| {
+        if separator.is_empty() {
+            return Err("the separator must not be empty");
+        }
+        let parts = text.split(&separator).map(str::to_string).collect();
+        Ok(Shared::new(StringList(parts)))
+    });
+    runtime.register_method("trim", |text: String| text.trim().to_string());
+    runtime.register_method("trim_start", |text: String| text.trim_start().to_string());
+    runtime.register_method("trim_end", |text: String| text.trim_end().to_string());
+    runtime.register_method("replace", |text: String, from: String, to: String| {
+        text.replace(&from, &to)
+    });
+    runtime.register_method("contains", |text: String, part: String| {
+        text.contains(&part)
+    });
+    runtime.register_method("starts_with", |text: String, prefix: String| {
+        text.starts_with(&prefix)
+    });
+    runtime.register_method("ends_with", |text: String, suffix: String| {
+        text.ends_with(&suffix)
+    });
+    runtime.register_method("to_upper", |text: String| text.to_uppercase());
+    runtime.register_method("to_lower", |text: String| text.to_lowercase());
+    runtime.register_method("parse_int", |text: String| {
+        text.parse::<i64>()
+            .map_err(|error| format!("cannot parse `{text}` as an integer: {error}"))
+    });
+    runtime.register_method("parse_float", |text: String| {
+        text.parse::<f64>()
+            .map_err(|error| format!("cannot parse `{text}` as a float: {error}"))
+    });
+    runtime.register_method("pad_start", |text: String, width: i64, fill: char| {
+        let padding = padding(&text, width, fill)?;
+        Ok::<_, String>(padding + &text)
+    });
+    runtime.register_method("pad_end", |text: String, width: i64, fill: char| {
+        let padding = padding(&text, width, fill)?;
+        Ok::<_, String>(text + &padding)
+    });
+
+    register_to_string!(
+        runtime, i8, i16, i32, i64, u8, u16, u32, u64, f32, f64, bool, char
+    );
+    register_to_fixed!(runtime, f32, f64);
+}
+
+/// The chars in `start..end`.
+fn slice(text: String, start: i64, end: i64) -> Result<String, String> {
+    let length = text.chars().count();
+    match (usize::try_from(start), usize::try_from(end)) {
+        (Ok(start), Ok(end)) if start <= end && end <= length => {
+            Ok(text.chars().skip(start).take(end - start).collect())
+        }
+        _ => Err(out_of_bounds(&format!("{start}..{end}"), length)),
+    }
+}
+
+fn out_of_bounds(index: &str, length: usize) -> String {
+    format!("{index} is out of bounds for a string of {length} chars")
+}
+
+/// The widest string padding is allowed to produce, wider ones would rather exhaust the memory
+/// of the host than be intended.
+const MAX_WIDTH: i64 = 1 << 20;
+
+/// The `fill` chars that make `text` `width` chars long, none if it is as long already.
+fn padding(text: &str, width: i64, fill: char) -> Result<String, String> {
+    if width > MAX_WIDTH {
+        return Err(format!(
+            "cannot pad to {width} chars, the maximum is {MAX_WIDTH}"
+        ));
+    }
+    let missing = usize::try_from(width)
+        .unwrap_or(0)
+        .saturating_sub(text.chars().count());
+    Ok(std::iter::repeat_n(fill, missing).collect())
+}
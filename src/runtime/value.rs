@@ -0,0 +1,337 @@
+use std::cmp::Ordering;
+
+use derive_more::{Display, From};
+
+use super::object::Object;
+use crate::{
+    core::types::{FloatType, IntegerType, Type},
+    parser::ast::BinaryOperator,
+};
+
+/// A value produced while executing a script.
+///
+/// Every numeric type has its own variant, so arithmetic is carried out at the width of the
+/// operands and overflows are detected according to that width. Values can be created from the
+/// corresponding Rust types, e.g. `Value::from(5u8)`.
+#[derive(Debug, PartialEq, Clone, Display, From)]
+pub enum Value {
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Bool(bool),
+    String(String),
+    Char(char),
+    #[display("()")]
+    Unit,
+    /// A host object, see [`NativeType`](super::NativeType).
+    Object(Object),
+}
+
+/// Reasons an operation on [`Value`]s can fail.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum OperationError {
+    Overflow,
+    DivisionByZero,
+    /// The operands do not have matching types, normally ruled out by the type checker.
+    InvalidOperands,
+}
+
+/// Applies `$method` to two integer values of the same width, yielding `None` on overflow.
+macro_rules! integer_operation {
+    ($left:expr, $right:expr, $method:ident) => {
+        match ($left, $right) {
+            (Value::I8(a), Value::I8(b)) => a.$method(*b).map(Value::I8),
+            (Value::I16(a), Value::I16(b)) => a.$method(*b).map(Value::I16),
+            (Value::I32(a), Value::I32(b)) => a.$method(*b).map(Value::I32),
+            (Value::I64(a), Value::I64(b)) => a.$method(*b).map(Value::I64),
+            (Value::U8(a), Value::U8(b)) => a.$method(*b).map(Value::U8),
+            (Value::U16(a), Value::U16(b)) => a.$method(*b).map(Value::U16),
+            (Value::U32(a), Value::U32(b)) => a.$method(*b).map(Value::U32),
+            (Value::U64(a), Value::U64(b)) => a.$method(*b).map(Value::U64),
+            _ => return Err(OperationError::InvalidOperands),
+        }
+    };
+}
+
+/// Applies the non-overflowing operator `$op` to two integer values of the same width.
+macro_rules! bitwise_operation {
+    ($left:expr, $right:expr, $op:tt) => {
+        match ($left, $right) {
+            (Value::I8(a), Value::I8(b)) => Value::I8(a $op b),
+            (Value::I16(a), Value::I16(b)) => Value::I16(a $op b),
+            (Value::I32(a), Value::I32(b)) => Value::I32(a $op b),
+            (Value::I64(a), Value::I64(b)) => Value::I64(a $op b),
+            (Value::U8(a), Value::U8(b)) => Value::U8(a $op b),
+            (Value::U16(a), Value::U16(b)) => Value::U16(a $op b),
+            (Value::U32(a), Value::U32(b)) => Value::U32(a $op b),
+            (Value::U64(a), Value::U64(b)) => Value::U64(a $op b),
+            _ => return Err(OperationError::InvalidOperands),
+        }
+    };
+}
+
+/// Shifts an integer value by `$amount` bits, yielding `None` if the amount exceeds its width.
+macro_rules! shift_operation {
+    ($value:expr, $amount:expr, $method:ident) => {
+        match $value {
+            Value::I8(a) => a.$method($amount).map(Value::I8),
+            Value::I16(a) => a.$method($amount).map(Value::I16),
+            Value::I32(a) => a.$method($amount).map(Value::I32),
+            Value::I64(a) => a.$method($amount).map(Value::I64),
+            Value::U8(a) => a.$method($amount).map(Value::U8),
+            Value::U16(a) => a.$method($amount).map(Value::U16),
+            Value::U32(a) => a.$method($amount).map(Value::U32),
+            Value::U64(a) => a.$method($amount).map(Value::U64),
+            _ => return Err(OperationError::InvalidOperands),
+        }
+    };
+}
+
+/// Applies the operator `$op` to two float values of the same width.
+macro_rules! float_operation {
+    ($left:expr, $right:expr, $op:tt) => {
+        match ($left, $right) {
+            (Value::F32(a), Value::F32(b)) => Value::F32(a $op b),
+            (Value::F64(a), Value::F64(b)) => Value::F64(a $op b),
+            _ => return Err(OperationError::InvalidOperands),
+        }
+    };
+}
+
+impl Value {
+    /// Creates an integer value of the given type, or `None` if `value` does not fit.
+    pub fn from_integer(value: i128, ty: IntegerType) -> Option<Value> {
+        let value = match ty {
+            IntegerType::I8 => Value::I8(value.try_into().ok()?),
+            IntegerType::I16 => Value::I16(value.try_into().ok()?),
+            IntegerType::I32 => Value::I32(value.try_into().ok()?),
+            IntegerType::I64 => Value::I64(value.try_into().ok()?),
+            IntegerType::U8 => Value::U8(value.try_into().ok()?),
+            IntegerType::U16 => Value::U16(value.try_into().ok()?),
+            IntegerType::U32 => Value::U32(value.try_into().ok()?),
+            IntegerType::U64 => Value::U64(value.try_into().ok()?),
+        };
+        Some(value)
+    }
+
+    /// Creates a float value of the given type, rounding to `f32` precision if needed.
+    pub fn from_float(value: f64, ty: FloatType) -> Value {
+        match ty {
+            FloatType::F32 => Value::F32(value as f32),
+            FloatType::F64 => Value::F64(value),
+        }
+    }
+
+    /// The [`Type`] of this value.
+    pub fn ty(&self) -> Type {
+        match self {
+            Value::I8(_) => Type::Integer(IntegerType::I8),
+            Value::I16(_) => Type::Integer(IntegerType::I16),
+            Value::I32(_) => Type::Integer(IntegerType::I32),
+            Value::I64(_) => Type::Integer(IntegerType::I64),
+            Value::U8(_) => Type::Integer(IntegerType::U8),
+            Value::U16(_) => Type::Integer(IntegerType::U16),
+            Value::U32(_) => Type::Integer(IntegerType::U32),
+            Value::U64(_) => Type::Integer(IntegerType::U64),
+            Value::F32(_) => Type::Float(FloatType::F32),
+            Value::F64(_) => Type::Float(FloatType::F64),
+            Value::Bool(_) => Type::Bool,
+            Value::String(_) => Type::String,
+            Value::Char(_) => Type::Char,
+            Value::Unit => Type::Unit,
+            Value::Object(object) => Type::Struct(object.type_name().to_string()),
+        }
+    }
+
+    /// The value of an integer as `i128`, which can hold every integer type losslessly.
+    pub fn as_i128(&self) -> Option<i128> {
+        match *self {
+            Value::I8(v) => Some(v.into()),
+            Value::I16(v) => Some(v.into()),
+            Value::I32(v) => Some(v.into()),
+            Value::I64(v) => Some(v.into()),
+            Value::U8(v) => Some(v.into()),
+            Value::U16(v) => Some(v.into()),
+            Value::U32(v) => Some(v.into()),
+            Value::U64(v) => Some(v.into()),
+            _ => None,
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        matches!(
+            self,
+            Value::I8(0)
+                | Value::I16(0)
+                | Value::I32(0)
+                | Value::I64(0)
+                | Value::U8(0)
+                | Value::U16(0)
+                | Value::U32(0)
+                | Value::U64(0)
+        )
+    }
+
+    pub fn add(&self, other: &Value) -> Result<Value, OperationError> {
+        match (self, other) {
+            (Value::F32(_) | Value::F64(_), _) => Ok(float_operation!(self, other, +)),
+            (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{a}{b}"))),
+            _ => integer_operation!(self, other, checked_add).ok_or(OperationError::Overflow),
+        }
+    }
+
+    pub fn subtract(&self, other: &Value) -> Result<Value, OperationError> {
+        match self {
+            Value::F32(_) | Value::F64(_) => Ok(float_operation!(self, other, -)),
+            _ => integer_operation!(self, other, checked_sub).ok_or(OperationError::Overflow),
+        }
+    }
+
+    pub fn multiply(&self, other: &Value) -> Result<Value, OperationError> {
+        match self {
+            Value::F32(_) | Value::F64(_) => Ok(float_operation!(self, other, *)),
+            _ => integer_operation!(self, other, checked_mul).ok_or(OperationError::Overflow),
+        }
+    }
+
+    pub fn divide(&self, other: &Value) -> Result<Value, OperationError> {
+        match self {
+            Value::F32(_) | Value::F64(_) => Ok(float_operation!(self, other, /)),
+            _ if other.is_zero() => Err(OperationError::DivisionByZero),
+            // `MIN / -1` is the only overflowing integer division
+            _ => integer_operation!(self, other, checked_div).ok_or(OperationError::Overflow),
+        }
+    }
+
+    pub fn remainder(&self, other: &Value) -> Result<Value, OperationError> {
+        match self {
+            Value::F32(_) | Value::F64(_) => Ok(float_operation!(self, other, %)),
+            _ if other.is_zero() => Err(OperationError::DivisionByZero),
+            _ => integer_operation!(self, other, checked_rem).ok_or(OperationError::Overflow),
+        }
+    }
+
+    pub fn bitwise_and(&self, other: &Value) -> Result<Value, OperationError> {
+        Ok(bitwise_operation!(self, other, &))
+    }
+
+    pub fn bitwise_or(&self, other: &Value) -> Result<Value, OperationError> {
+        Ok(bitwise_operation!(self, other, |))
+    }
+
+    pub fn bitwise_xor(&self, other: &Value) -> Result<Value, OperationError> {
+        Ok(bitwise_operation!(self, other, ^))
+    }
+
+    /// Shifts left by `amount` bits, which overflows if it is negative or not smaller than the
+    /// width of the value.
+    pub fn shift_left(&self, amount: &Value) -> Result<Value, OperationError> {
+        let amount = shift_amount(amount)?;
+        shift_operation!(self, amount, checked_shl).ok_or(OperationError::Overflow)
+    }
+
+    /// Shifts right by `amount` bits, arithmetic for signed and logical for unsigned integers.
+    pub fn shift_right(&self, amount: &Value) -> Result<Value, OperationError> {
+        let amount = shift_amount(amount)?;
+        shift_operation!(self, amount, checked_shr).ok_or(OperationError::Overflow)
+    }
+
+    /// Logical not for booleans, bitwise not for integers.
+    pub fn not(&self) -> Result<Value, OperationError> {
+        let value = match *self {
+            Value::Bool(v) => Value::Bool(!v),
+            Value::I8(v) => Value::I8(!v),
+            Value::I16(v) => Value::I16(!v),
+            Value::I32(v) => Value::I32(!v),
+            Value::I64(v) => Value::I64(!v),
+            Value::U8(v) => Value::U8(!v),
+            Value::U16(v) => Value::U16(!v),
+            Value::U32(v) => Value::U32(!v),
+            Value::U64(v) => Value::U64(!v),
+            _ => return Err(OperationError::InvalidOperands),
+        };
+        Ok(value)
+    }
+
+    pub fn negate(&self) -> Result<Value, OperationError> {
+        let value = match self {
+            Value::I8(v) => v.checked_neg().map(Value::I8),
+            Value::I16(v) => v.checked_neg().map(Value::I16),
+            Value::I32(v) => v.checked_neg().map(Value::I32),
+            Value::I64(v) => v.checked_neg().map(Value::I64),
+            Value::F32(v) => Some(Value::F32(-v)),
+            Value::F64(v) => Some(Value::F64(-v)),
+            _ => return Err(OperationError::InvalidOperands),
+        };
+        value.ok_or(OperationError::Overflow)
+    }
+
+    /// Applies a binary operator other than the short circuiting `&&` and `||`.
+    pub fn binary_operation(
+        &self,
+        operator: &BinaryOperator,
+        other: &Value,
+    ) -> Result<Value, OperationError> {
+        use BinaryOperator::*;
+
+        let ordering = || self.compare(other);
+        match operator {
+            Add => self.add(other),
+            Subtract => self.subtract(other),
+            Multiply => self.multiply(other),
+            Divide => self.divide(other),
+            Remainder => self.remainder(other),
+            BitwiseAnd => self.bitwise_and(other),
+            BitwiseOr => self.bitwise_or(other),
+            BitwiseXor => self.bitwise_xor(other),
+            ShiftLeft => self.shift_left(other),
+            ShiftRight => self.shift_right(other),
+            Equals => Ok(Value::Bool(self == other)),
+            NotEquals => Ok(Value::Bool(self != other)),
+            LessThan => Ok(Value::Bool(ordering() == Some(Ordering::Less))),
+            GreaterThan => Ok(Value::Bool(ordering() == Some(Ordering::Greater))),
+            LessThanOrEqual => Ok(Value::Bool(matches!(
+                ordering(),
+                Some(Ordering::Less | Ordering::Equal)
+            ))),
+            GreaterThanOrEqual => Ok(Value::Bool(matches!(
+                ordering(),
+                Some(Ordering::Greater | Ordering::Equal)
+            ))),
+            And | Or => Err(OperationError::InvalidOperands),
+        }
+    }
+
+    /// Compares two values of the same type, `None` if they are not comparable.
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::I8(a), Value::I8(b)) => a.partial_cmp(b),
+            (Value::I16(a), Value::I16(b)) => a.partial_cmp(b),
+            (Value::I32(a), Value::I32(b)) => a.partial_cmp(b),
+            (Value::I64(a), Value::I64(b)) => a.partial_cmp(b),
+            (Value::U8(a), Value::U8(b)) => a.partial_cmp(b),
+            (Value::U16(a), Value::U16(b)) => a.partial_cmp(b),
+            (Value::U32(a), Value::U32(b)) => a.partial_cmp(b),
+            (Value::U64(a), Value::U64(b)) => a.partial_cmp(b),
+            (Value::F32(a), Value::F32(b)) => a.partial_cmp(b),
+            (Value::F64(a), Value::F64(b)) => a.partial_cmp(b),
+            (Value::Bool(a), Value::Bool(b)) => a.partial_cmp(b),
+            (Value::String(a), Value::String(b)) => a.partial_cmp(b),
+            (Value::Char(a), Value::Char(b)) => a.partial_cmp(b),
+            (Value::Unit, Value::Unit) => Some(Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+fn shift_amount(amount: &Value) -> Result<u32, OperationError> {
+    let amount = amount.as_i128().ok_or(OperationError::InvalidOperands)?;
+    u32::try_from(amount).map_err(|_| OperationError::Overflow)
+}
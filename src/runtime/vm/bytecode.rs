@@ -0,0 +1,633 @@
+//! The on-disk format of precompiled scripts (`.rsc` files).
+//!
+//! A file starts with the magic bytes `RSC\0`, the format version as a little endian `u16` and a
+//! checksum of the rest of the file, followed by the compiled program. The sources of the script
+//! and of the modules it imports are stored as well, so errors raised by precompiled code are
+//! rendered like any other.
+//!
+//! All integers are little endian, strings and lists are prefixed with their length as a `u32`.
+
+use derive_more::{Display, Error};
+
+use super::chunk::{Chunk, CompiledFunction, Instruction};
+use crate::{
+    core::{source_map::FileId, span::Span, types::Type},
+    runtime::Value,
+    typeck::FunctionSignature,
+};
+
+pub const MAGIC: [u8; 4] = *b"RSC\0";
+
+/// Incremented whenever the encoding or the instruction set changes.
+pub const FORMAT_VERSION: u16 = 3;
+
+/// The most local variable slots a chunk may use, all of them are allocated on every call.
+pub const MAX_LOCALS: usize = 1 << 16;
+
+/// How deep functions may be nested in each other, the decoder recurses into every level.
+pub const MAX_NESTING: usize = 256;
+
+/// Reasons a bytecode file is rejected.
+#[derive(Debug, PartialEq, Clone, Display, Error)]
+pub enum BytecodeError {
+    #[display("not a bytecode file")]
+    InvalidMagic,
+    #[display("unsupported bytecode version {found}, expected version {FORMAT_VERSION}")]
+    UnsupportedVersion { found: u16 },
+    #[display("the bytecode file is corrupted")]
+    ChecksumMismatch,
+    #[display("the bytecode file ends unexpectedly")]
+    UnexpectedEnd,
+    #[display("invalid bytecode: {reason}")]
+    Invalid { reason: String },
+    /// The file uses a function, global or member that neither it nor the runtime loading it
+    /// declares, e.g. a native of the runtime it was compiled with.
+    #[display("cannot find {kind} `{name}` used by the bytecode")]
+    Unresolved { kind: &'static str, name: String },
+}
+
+/// A program compiled ahead of time, with the declarations the type checker needs to check
+/// scripts using it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BytecodeFile {
+    /// The file name of the script, shown in diagnostics.
+    pub name: String,
+    pub source: String,
+    /// The file names and sources of the modules the script imports, see
+    /// [`module`](crate::module). Spans in the file of index `n + 1` refer to the `n`th module.
+    pub modules: Vec<(String, String)>,
+    /// Structs declared at the top level of the script.
+    pub structs: Vec<String>,
+    /// Signatures of the functions declared at the top level of the script.
+    pub functions: Vec<(String, FunctionSignature)>,
+    /// The top level statements, the spans of file index `0` refer to `source`.
+    pub chunk: Chunk,
+}
+
+impl BytecodeFile {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut payload = Writer::default();
+        payload.string(&self.name);
+        payload.string(&self.source);
+        payload.list(&self.modules, |writer, (name, source)| {
+            writer.string(name);
+            writer.string(source);
+        });
+        payload.list(&self.structs, |writer, name| writer.string(name));
+        payload.list(&self.functions, |writer, (name, signature)| {
+            writer.string(name);
+            writer.list(&signature.parameters, Writer::ty);
+            writer.ty(&signature.return_type);
+        });
+        payload.chunk(&self.chunk);
+
+        let mut bytes = Vec::with_capacity(payload.0.len() + 14);
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&checksum(&payload.0).to_le_bytes());
+        bytes.extend_from_slice(&payload.0);
+        bytes
+    }
+
+    /// Decodes and validates a bytecode file. Spans in the returned chunk refer to files by their
+    /// index, the script first, until the file is executed, which registers its sources.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BytecodeError> {
+        if !bytes.starts_with(&MAGIC) {
+            return Err(BytecodeError::InvalidMagic);
+        }
+        let mut header = Reader::new(&bytes[MAGIC.len()..]);
+        let version = header.u16()?;
+        if version != FORMAT_VERSION {
+            return Err(BytecodeError::UnsupportedVersion { found: version });
+        }
+        let expected = header.u64()?;
+        let payload = header.rest();
+        if checksum(payload) != expected {
+            return Err(BytecodeError::ChecksumMismatch);
+        }
+
+        let mut reader = Reader::new(payload);
+        let name = reader.string()?;
+        let source = reader.string()?;
+        let modules = reader.list(|reader| Ok((reader.string()?, reader.string()?)))?;
+        reader.source_lengths = std::iter::once(&source)
+            .chain(modules.iter().map(|(_, source)| source))
+            .map(String::len)
+            .collect();
+        let structs = reader.list(Reader::string)?;
+        let functions = reader.list(|reader| {
+            let name = reader.string()?;
+            let parameters = reader.list(Reader::ty)?;
+            let return_type = reader.ty()?;
+            Ok((
+                name,
+                FunctionSignature {
+                    parameters,
+                    return_type,
+                },
+            ))
+        })?;
+        let chunk = reader.chunk()?;
+        if !reader.rest().is_empty() {
+            return Err(invalid("trailing bytes after the program"));
+        }
+
+        Ok(BytecodeFile {
+            name,
+            source,
+            modules,
+            structs,
+            functions,
+            chunk,
+        })
+    }
+}
+
+/// 64 bit FNV-1a, enough to detect accidental corruption.
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+fn invalid(reason: impl Into<String>) -> BytecodeError {
+    BytecodeError::Invalid {
+        reason: reason.into(),
+    }
+}
+
+// -- Encoding --
+
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn length(&mut self, length: usize) {
+        self.u32(u32::try_from(length).expect("bytecode sections are smaller than 4 GiB"));
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.length(bytes.len());
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.bytes(value.as_bytes());
+    }
+
+    fn list<T>(&mut self, items: &[T], mut write: impl FnMut(&mut Self, &T)) {
+        self.length(items.len());
+        for item in items {
+            write(self, item);
+        }
+    }
+
+    fn ty(&mut self, ty: &Type) {
+        self.string(&ty.to_string());
+    }
+
+    fn span(&mut self, span: &Span) {
+        self.length(span.file.index());
+        self.length(span.start);
+        self.length(span.end);
+    }
+
+    fn value(&mut self, value: &Value) {
+        match value {
+            Value::I8(value) => self.tagged(0, &value.to_le_bytes()),
+            Value::I16(value) => self.tagged(1, &value.to_le_bytes()),
+            Value::I32(value) => self.tagged(2, &value.to_le_bytes()),
+            Value::I64(value) => self.tagged(3, &value.to_le_bytes()),
+            Value::U8(value) => self.tagged(4, &value.to_le_bytes()),
+            Value::U16(value) => self.tagged(5, &value.to_le_bytes()),
+            Value::U32(value) => self.tagged(6, &value.to_le_bytes()),
+            Value::U64(value) => self.tagged(7, &value.to_le_bytes()),
+            Value::F32(value) => self.tagged(8, &value.to_le_bytes()),
+            Value::F64(value) => self.tagged(9, &value.to_le_bytes()),
+            Value::Bool(value) => self.tagged(10, &[u8::from(*value)]),
+            Value::String(value) => {
+                self.u8(11);
+                self.string(value);
+            }
+            Value::Char(value) => self.tagged(12, &u32::from(*value).to_le_bytes()),
+            Value::Unit => self.u8(13),
+            Value::Object(_) => unreachable!("objects are never constants"),
+        }
+    }
+
+    fn tagged(&mut self, tag: u8, bytes: &[u8]) {
+        self.u8(tag);
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn chunk(&mut self, chunk: &Chunk) {
+        self.length(chunk.locals);
+        self.list(&chunk.constants, Writer::value);
+        self.list(&chunk.names, |writer, name| writer.string(name));
+        self.list(&chunk.functions, |writer, function| {
+            writer.string(&function.name);
+            writer.length(function.parameters);
+            writer.span(&function.span);
+            writer.chunk(&function.chunk);
+        });
+        self.length(chunk.code.len());
+        for (instruction, span) in chunk.code.iter().zip(&chunk.spans) {
+            self.instruction(instruction);
+            self.span(span);
+        }
+    }
+
+    fn instruction(&mut self, instruction: &Instruction) {
+        let (opcode, operands): (u8, &[u32]) = match instruction {
+            Instruction::Constant(index) => (0, &[*index]),
+            Instruction::Unit => (1, &[]),
+            Instruction::Pop => (2, &[]),
+            Instruction::Duplicate => (3, &[]),
+            Instruction::GetLocal(slot) => (4, &[*slot]),
+            Instruction::SetLocal(slot) => (5, &[*slot]),
+            Instruction::GetGlobal(name) => (6, &[*name]),
+            Instruction::DefineGlobal(name) => (7, &[*name]),
+            Instruction::Add => (8, &[]),
+            Instruction::Subtract => (9, &[]),
+            Instruction::Multiply => (10, &[]),
+            Instruction::Divide => (11, &[]),
+            Instruction::Remainder => (12, &[]),
+            Instruction::BitwiseAnd => (13, &[]),
+            Instruction::BitwiseOr => (14, &[]),
+            Instruction::BitwiseXor => (15, &[]),
+            Instruction::ShiftLeft => (16, &[]),
+            Instruction::ShiftRight => (17, &[]),
+            Instruction::Equals => (18, &[]),
+            Instruction::NotEquals => (19, &[]),
+            Instruction::LessThan => (20, &[]),
+            Instruction::GreaterThan => (21, &[]),
+            Instruction::LessThanOrEqual => (22, &[]),
+            Instruction::GreaterThanOrEqual => (23, &[]),
+            Instruction::Negate => (24, &[]),
+            Instruction::Not => (25, &[]),
+            Instruction::Jump(target) => (26, &[*target]),
+            Instruction::JumpIfFalse(target) => (27, &[*target]),
+            Instruction::JumpIfTrue(target) => (28, &[*target]),
+            Instruction::Call { name, arguments } => (29, &[*name, *arguments]),
+            Instruction::GetField(name) => (30, &[*name]),
+            Instruction::CallMethod { name, arguments } => (31, &[*name, *arguments]),
+            Instruction::DeclareFunction(index) => (32, &[*index]),
+            Instruction::Return => (33, &[]),
+            Instruction::MissingReturn(name) => (34, &[*name]),
+            Instruction::EnterScope => (35, &[]),
+            Instruction::ExitScope => (36, &[]),
+        };
+        self.u8(opcode);
+        for operand in operands {
+            self.u32(*operand);
+        }
+    }
+}
+
+// -- Decoding --
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    /// The length of the source of every file, decoded spans must lie within theirs.
+    source_lengths: Vec<usize>,
+    /// The number of functions the chunk being decoded is nested in.
+    nesting: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader {
+            bytes,
+            position: 0,
+            source_lengths: Vec::new(),
+            nesting: 0,
+        }
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.bytes[self.position..]
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], BytecodeError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + N)
+            .ok_or(BytecodeError::UnexpectedEnd)?;
+        self.position += N;
+        Ok(bytes.try_into().expect("slice has length N"))
+    }
+
+    fn u8(&mut self) -> Result<u8, BytecodeError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, BytecodeError> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, BytecodeError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, BytecodeError> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    fn length(&mut self) -> Result<usize, BytecodeError> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn string(&mut self) -> Result<String, BytecodeError> {
+        let length = self.length()?;
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + length)
+            .ok_or(BytecodeError::UnexpectedEnd)?;
+        self.position += length;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid("string is not valid UTF-8"))
+    }
+
+    fn list<T>(
+        &mut self,
+        mut read: impl FnMut(&mut Self) -> Result<T, BytecodeError>,
+    ) -> Result<Vec<T>, BytecodeError> {
+        let length = self.length()?;
+        // Every item takes at least one byte, this keeps corrupted lengths from allocating
+        if length > self.rest().len() {
+            return Err(BytecodeError::UnexpectedEnd);
+        }
+        (0..length).map(|_| read(self)).collect()
+    }
+
+    fn ty(&mut self) -> Result<Type, BytecodeError> {
+        let name = self.string()?;
+        Ok(Type::from_name(&name).unwrap_or(Type::Struct(name)))
+    }
+
+    fn span(&mut self) -> Result<Span, BytecodeError> {
+        let file = self.length()?;
+        let start = self.length()?;
+        let end = self.length()?;
+        let Some(&length) = self.source_lengths.get(file) else {
+            return Err(invalid(format!("span refers to unknown file {file}")));
+        };
+        if start > end || end > length {
+            return Err(invalid(format!(
+                "span {start}-{end} is outside of the source"
+            )));
+        }
+        Ok(Span::new(FileId::from_index(file), start..end))
+    }
+
+    fn value(&mut self) -> Result<Value, BytecodeError> {
+        Ok(match self.u8()? {
+            0 => Value::I8(i8::from_le_bytes(self.take()?)),
+            1 => Value::I16(i16::from_le_bytes(self.take()?)),
+            2 => Value::I32(i32::from_le_bytes(self.take()?)),
+            3 => Value::I64(i64::from_le_bytes(self.take()?)),
+            4 => Value::U8(u8::from_le_bytes(self.take()?)),
+            5 => Value::U16(u16::from_le_bytes(self.take()?)),
+            6 => Value::U32(u32::from_le_bytes(self.take()?)),
+            7 => Value::U64(u64::from_le_bytes(self.take()?)),
+            8 => Value::F32(f32::from_le_bytes(self.take()?)),
+            9 => Value::F64(f64::from_le_bytes(self.take()?)),
+            10 => match self.u8()? {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                _ => return Err(invalid("invalid boolean")),
+            },
+            11 => Value::String(self.string()?),
+            12 => Value::Char(
+                char::from_u32(self.u32()?).ok_or_else(|| invalid("invalid character"))?,
+            ),
+            13 => Value::Unit,
+            tag => return Err(invalid(format!("unknown constant tag {tag}"))),
+        })
+    }
+
+    fn chunk(&mut self) -> Result<Chunk, BytecodeError> {
+        let locals = self.length()?;
+        if locals > MAX_LOCALS {
+            return Err(invalid(format!(
+                "{locals} local slots exceed the maximum of {MAX_LOCALS}"
+            )));
+        }
+        let constants = self.list(Reader::value)?;
+        let names = self.list(Reader::string)?;
+        let functions = self.list(|reader| {
+            let name = reader.string()?;
+            let parameters = reader.length()?;
+            let span = reader.span()?;
+            if reader.nesting == MAX_NESTING {
+                return Err(invalid(format!(
+                    "functions are nested deeper than {MAX_NESTING} levels"
+                )));
+            }
+            reader.nesting += 1;
+            let chunk = reader.chunk();
+            reader.nesting -= 1;
+            let chunk = chunk?;
+            if parameters > chunk.locals {
+                return Err(invalid(format!(
+                    "function `{name}` has fewer local slots than parameters"
+                )));
+            }
+            Ok(CompiledFunction {
+                name,
+                parameters,
+                chunk,
+                span,
+            }
+            .into())
+        })?;
+        let (code, spans) = self
+            .list(|reader| Ok((reader.instruction()?, reader.span()?)))?
+            .into_iter()
+            .unzip();
+
+        let chunk = Chunk {
+            code,
+            spans,
+            constants,
+            names,
+            functions,
+            locals,
+        };
+        validate(&chunk)?;
+        Ok(chunk)
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, BytecodeError> {
+        Ok(match self.u8()? {
+            0 => Instruction::Constant(self.u32()?),
+            1 => Instruction::Unit,
+            2 => Instruction::Pop,
+            3 => Instruction::Duplicate,
+            4 => Instruction::GetLocal(self.u32()?),
+            5 => Instruction::SetLocal(self.u32()?),
+            6 => Instruction::GetGlobal(self.u32()?),
+            7 => Instruction::DefineGlobal(self.u32()?),
+            8 => Instruction::Add,
+            9 => Instruction::Subtract,
+            10 => Instruction::Multiply,
+            11 => Instruction::Divide,
+            12 => Instruction::Remainder,
+            13 => Instruction::BitwiseAnd,
+            14 => Instruction::BitwiseOr,
+            15 => Instruction::BitwiseXor,
+            16 => Instruction::ShiftLeft,
+            17 => Instruction::ShiftRight,
+            18 => Instruction::Equals,
+            19 => Instruction::NotEquals,
+            20 => Instruction::LessThan,
+            21 => Instruction::GreaterThan,
+            22 => Instruction::LessThanOrEqual,
+            23 => Instruction::GreaterThanOrEqual,
+            24 => Instruction::Negate,
+            25 => Instruction::Not,
+            26 => Instruction::Jump(self.u32()?),
+            27 => Instruction::JumpIfFalse(self.u32()?),
+            28 => Instruction::JumpIfTrue(self.u32()?),
+            29 => Instruction::Call {
+                name: self.u32()?,
+                arguments: self.u32()?,
+            },
+            30 => Instruction::GetField(self.u32()?),
+            31 => Instruction::CallMethod {
+                name: self.u32()?,
+                arguments: self.u32()?,
+            },
+            32 => Instruction::DeclareFunction(self.u32()?),
+            33 => Instruction::Return,
+            34 => Instruction::MissingReturn(self.u32()?),
+            35 => Instruction::EnterScope,
+            36 => Instruction::ExitScope,
+            opcode => return Err(invalid(format!("unknown opcode {opcode}"))),
+        })
+    }
+}
+
+/// Checks that every operand of the chunk refers to an existing entry, that no instruction pops
+/// more values than the stack holds and that execution cannot run past the end.
+fn validate(chunk: &Chunk) -> Result<(), BytecodeError> {
+    let check = |index: u32, length: usize, what: &str| {
+        if (index as usize) < length {
+            Ok(())
+        } else {
+            Err(invalid(format!("{what} {index} is out of range")))
+        }
+    };
+
+    for instruction in &chunk.code {
+        match *instruction {
+            Instruction::Constant(index) => check(index, chunk.constants.len(), "constant")?,
+            Instruction::GetLocal(slot) | Instruction::SetLocal(slot) => {
+                check(slot, chunk.locals, "local slot")?
+            }
+            Instruction::GetGlobal(name)
+            | Instruction::DefineGlobal(name)
+            | Instruction::Call { name, .. }
+            | Instruction::GetField(name)
+            | Instruction::CallMethod { name, .. }
+            | Instruction::MissingReturn(name) => check(name, chunk.names.len(), "name")?,
+            Instruction::Jump(target)
+            | Instruction::JumpIfFalse(target)
+            | Instruction::JumpIfTrue(target) => check(target, chunk.code.len(), "jump target")?,
+            Instruction::DeclareFunction(index) => check(index, chunk.functions.len(), "function")?,
+            _ => {}
+        }
+    }
+
+    validate_stack(chunk)
+}
+
+/// Follows every path through the chunk, tracking the depth of the stack. Paths joining at an
+/// instruction must agree on the depth, as they do for compiled branches, so the depth at every
+/// instruction is known.
+fn validate_stack(chunk: &Chunk) -> Result<(), BytecodeError> {
+    let mut depths: Vec<Option<usize>> = vec![None; chunk.code.len()];
+    let mut pending = vec![(0, 0)];
+    while let Some((ip, depth)) = pending.pop() {
+        let Some(known) = depths.get_mut(ip) else {
+            return Err(invalid("execution runs past the end of the code"));
+        };
+        match *known {
+            Some(known) if known == depth => continue,
+            Some(known) => {
+                return Err(invalid(format!(
+                    "instruction {ip} is reached with {known} and with {depth} values on the stack"
+                )));
+            }
+            None => *known = Some(depth),
+        }
+
+        let instruction = chunk.code[ip];
+        let (pops, pushes) = stack_effect(instruction);
+        let Some(depth) = depth.checked_sub(pops) else {
+            return Err(invalid(format!(
+                "instruction {ip} pops {pops} values from a stack of {depth}"
+            )));
+        };
+        let depth = depth + pushes;
+        match instruction {
+            Instruction::Return | Instruction::MissingReturn(_) => {}
+            Instruction::Jump(target) => pending.push((target as usize, depth)),
+            Instruction::JumpIfFalse(target) | Instruction::JumpIfTrue(target) => {
+                pending.push((target as usize, depth));
+                pending.push((ip + 1, depth));
+            }
+            _ => pending.push((ip + 1, depth)),
+        }
+    }
+    Ok(())
+}
+
+/// The number of values an instruction pops and pushes.
+fn stack_effect(instruction: Instruction) -> (usize, usize) {
+    match instruction {
+        Instruction::Constant(_)
+        | Instruction::Unit
+        | Instruction::GetLocal(_)
+        | Instruction::GetGlobal(_) => (0, 1),
+        Instruction::Pop
+        | Instruction::SetLocal(_)
+        | Instruction::DefineGlobal(_)
+        | Instruction::JumpIfFalse(_)
+        | Instruction::JumpIfTrue(_)
+        | Instruction::Return => (1, 0),
+        Instruction::Duplicate => (1, 2),
+        Instruction::Add
+        | Instruction::Subtract
+        | Instruction::Multiply
+        | Instruction::Divide
+        | Instruction::Remainder
+        | Instruction::BitwiseAnd
+        | Instruction::BitwiseOr
+        | Instruction::BitwiseXor
+        | Instruction::ShiftLeft
+        | Instruction::ShiftRight
+        | Instruction::Equals
+        | Instruction::NotEquals
+        | Instruction::LessThan
+        | Instruction::GreaterThan
+        | Instruction::LessThanOrEqual
+        | Instruction::GreaterThanOrEqual => (2, 1),
+        Instruction::Negate | Instruction::Not | Instruction::GetField(_) => (1, 1),
+        Instruction::Call { arguments, .. } => (arguments as usize, 1),
+        Instruction::CallMethod { arguments, .. } => (arguments as usize + 1, 1),
+        Instruction::Jump(_)
+        | Instruction::DeclareFunction(_)
+        | Instruction::EnterScope
+        | Instruction::ExitScope
+        | Instruction::MissingReturn(_) => (0, 0),
+    }
+}
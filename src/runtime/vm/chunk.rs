@@ -0,0 +1,154 @@
+use std::rc::Rc;
+
+use crate::{
+    core::{source_map::FileId, span::Span},
+    runtime::Value,
+};
+
+/// A single bytecode instruction.
+///
+/// Operands index into the pools of the [`Chunk`] containing the instruction, jump targets are
+/// instruction indices.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Instruction {
+    /// Pushes `constants[index]`.
+    Constant(u32),
+    /// Pushes `()`.
+    Unit,
+    Pop,
+    /// Pushes a copy of the top of the stack.
+    Duplicate,
+    /// Pushes the local variable in the slot.
+    GetLocal(u32),
+    /// Pops a value into the local variable slot.
+    SetLocal(u32),
+    /// Pushes the global variable named `names[index]`.
+    GetGlobal(u32),
+    /// Pops a value into the global variable named `names[index]`.
+    DefineGlobal(u32),
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    ShiftLeft,
+    ShiftRight,
+    Equals,
+    NotEquals,
+    LessThan,
+    GreaterThan,
+    LessThanOrEqual,
+    GreaterThanOrEqual,
+    Negate,
+    Not,
+    Jump(u32),
+    /// Pops a `bool` and jumps if it is `false`.
+    JumpIfFalse(u32),
+    /// Pops a `bool` and jumps if it is `true`.
+    JumpIfTrue(u32),
+    /// Pops the arguments and calls the function named `names[name]`.
+    Call {
+        name: u32,
+        arguments: u32,
+    },
+    /// Pops an object and pushes its field named `names[index]`.
+    GetField(u32),
+    /// Pops the arguments and the receiver and calls its method named `names[name]`.
+    CallMethod {
+        name: u32,
+        arguments: u32,
+    },
+    /// Makes `functions[index]` callable by its name in the innermost function scope, or at the
+    /// top level outside of any.
+    DeclareFunction(u32),
+    /// Opens a function scope for the nested functions of a block.
+    EnterScope,
+    /// Closes the innermost function scope.
+    ExitScope,
+    /// Pops the result and returns it to the caller.
+    Return,
+    /// Reached the end of the function named `names[index]`, which must return a value.
+    MissingReturn(u32),
+}
+
+/// Compiled code with the pools its instructions refer to.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Chunk {
+    pub code: Vec<Instruction>,
+    /// Source location of every instruction, used to report errors.
+    pub spans: Vec<Span>,
+    pub constants: Vec<Value>,
+    /// Names of variables, functions, fields and methods.
+    pub names: Vec<String>,
+    /// Functions declared in this chunk.
+    pub functions: Vec<Rc<CompiledFunction>>,
+    /// Number of local variable slots.
+    pub locals: usize,
+}
+
+impl Chunk {
+    pub(super) fn push(&mut self, instruction: Instruction, span: Span) -> usize {
+        self.code.push(instruction);
+        self.spans.push(span);
+        self.code.len() - 1
+    }
+
+    pub(super) fn add_constant(&mut self, value: Value) -> u32 {
+        let existing = self
+            .constants
+            .iter()
+            .position(|constant| is_same_constant(constant, &value));
+        match existing {
+            Some(index) => index as u32,
+            None => {
+                self.constants.push(value);
+                (self.constants.len() - 1) as u32
+            }
+        }
+    }
+
+    pub(super) fn add_name(&mut self, name: &str) -> u32 {
+        match self.names.iter().position(|existing| existing == name) {
+            Some(index) => index as u32,
+            None => {
+                self.names.push(name.to_string());
+                (self.names.len() - 1) as u32
+            }
+        }
+    }
+
+    /// Moves the spans of the chunk and its functions from file index `n` to `files[n]`.
+    pub(crate) fn relocate(&mut self, files: &[FileId]) {
+        for span in &mut self.spans {
+            span.file = files[span.file.index()];
+        }
+        for function in &mut self.functions {
+            let function = Rc::make_mut(function);
+            function.span.file = files[function.span.file.index()];
+            function.chunk.relocate(files);
+        }
+    }
+}
+
+/// Floats are compared by their bits, `0.0 == -0.0` must not merge the two constants.
+fn is_same_constant(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::F32(a), Value::F32(b)) => a.to_bits() == b.to_bits(),
+        (Value::F64(a), Value::F64(b)) => a.to_bits() == b.to_bits(),
+        _ => a == b,
+    }
+}
+
+/// A script function compiled to bytecode.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CompiledFunction {
+    pub name: String,
+    /// The parameters occupy the first local variable slots.
+    pub parameters: usize,
+    pub chunk: Chunk,
+    /// The declaration of the function, reported if it ends without returning.
+    pub span: Span,
+}
@@ -0,0 +1,334 @@
+use std::rc::Rc;
+
+use super::chunk::{Chunk, CompiledFunction, Instruction};
+use crate::{
+    core::span::{Span, Spanned},
+    parser::ast::{
+        BinaryOp, BinaryOperator, BlockExpression, Expression, FunctionDeclaration, IfExpression,
+        Program, Statement, UnaryOp, UnaryOperator,
+    },
+    runtime::{
+        Value,
+        interpreter::{float_literal_value, integer_literal_value},
+    },
+};
+
+/// Compiles a type checked program into the chunk executing its top level statements.
+pub fn compile_program(program: &Program) -> Chunk {
+    let mut compiler = Compiler::default();
+    compiler.statements(&program.statements);
+    compiler.emit(Instruction::Unit, program.span);
+    compiler.emit(Instruction::Return, program.span);
+    compiler.finish()
+}
+
+/// Compiles a type checked expression into a chunk returning its value.
+pub fn compile_expression(expression: &Expression) -> Chunk {
+    let mut compiler = Compiler::default();
+    compiler.expression(expression);
+    compiler.emit(Instruction::Return, expression.span());
+    compiler.finish()
+}
+
+/// Compiles a type checked function declaration.
+pub fn compile_function(declaration: &FunctionDeclaration) -> CompiledFunction {
+    let mut compiler = Compiler::default();
+    compiler.scopes.push(Vec::new());
+    for parameter in &declaration.parameters {
+        compiler.declare_local(&parameter.identifier.name);
+    }
+
+    compiler.statements(&declaration.body);
+    if declaration.return_type.name == "()" {
+        compiler.emit(Instruction::Unit, declaration.span);
+        compiler.emit(Instruction::Return, declaration.span);
+    } else {
+        let name = compiler.chunk.add_name(&declaration.identifier.name);
+        compiler.emit(Instruction::MissingReturn(name), declaration.span);
+    }
+
+    CompiledFunction {
+        name: declaration.identifier.name.clone(),
+        parameters: declaration.parameters.len(),
+        chunk: compiler.finish(),
+        span: declaration.span,
+    }
+}
+
+#[derive(Debug, Default)]
+struct Compiler {
+    chunk: Chunk,
+    /// Local variables by slot, innermost scope last. Variables outside of any scope are globals.
+    scopes: Vec<Vec<(String, u32)>>,
+    /// The first free slot, slots of a scope are reused once it ends.
+    next_slot: u32,
+}
+
+impl Compiler {
+    fn finish(self) -> Chunk {
+        self.chunk
+    }
+
+    fn emit(&mut self, instruction: Instruction, span: Span) -> usize {
+        self.chunk.push(instruction, span)
+    }
+
+    fn emit_constant(&mut self, value: Value, span: Span) {
+        let index = self.chunk.add_constant(value);
+        self.emit(Instruction::Constant(index), span);
+    }
+
+    /// Points the jump at `index` to the next instruction.
+    fn patch_jump(&mut self, index: usize) {
+        let target = self.chunk.code.len() as u32;
+        match &mut self.chunk.code[index] {
+            Instruction::Jump(offset)
+            | Instruction::JumpIfFalse(offset)
+            | Instruction::JumpIfTrue(offset) => *offset = target,
+            instruction => unreachable!("patching {instruction:?}, which is not a jump"),
+        }
+    }
+
+    fn declare_local(&mut self, name: &str) -> u32 {
+        let slot = self.next_slot;
+        self.next_slot += 1;
+        self.chunk.locals = self.chunk.locals.max(self.next_slot as usize);
+        self.scopes
+            .last_mut()
+            .expect("locals are declared inside a scope")
+            .push((name.to_string(), slot));
+        slot
+    }
+
+    fn resolve_local(&self, name: &str) -> Option<u32> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(local, _)| local == name)
+            .map(|(_, slot)| *slot)
+    }
+
+    // -- Statements --
+
+    /// Compiles a statement list, its functions are declared before any statement runs.
+    ///
+    /// Nested functions are declared in a function scope of their own, which blocks close again
+    /// and function calls discard on return.
+    fn statements(&mut self, statements: &[Statement]) {
+        if !self.scopes.is_empty() && declares_functions(statements) {
+            let span = statements[0].span();
+            self.emit(Instruction::EnterScope, span);
+        }
+        for statement in statements {
+            if let Statement::FunctionDeclaration(declaration) = statement {
+                trace!("Compiling function `{}`", declaration.identifier.name);
+                self.chunk
+                    .functions
+                    .push(Rc::new(compile_function(declaration)));
+                let index = (self.chunk.functions.len() - 1) as u32;
+                self.emit(Instruction::DeclareFunction(index), declaration.span);
+            }
+        }
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::VariableDeclaration(declaration) => {
+                self.expression(&declaration.initializer);
+                // Declared after the initializer, which still sees a shadowed variable
+                let name = &declaration.identifier.name;
+                if self.scopes.is_empty() {
+                    let index = self.chunk.add_name(name);
+                    self.emit(Instruction::DefineGlobal(index), declaration.span);
+                } else {
+                    let slot = self.declare_local(name);
+                    self.emit(Instruction::SetLocal(slot), declaration.span);
+                }
+            }
+            Statement::FunctionDeclaration(_) | Statement::StructDeclaration(_) => {}
+            Statement::ExpressionStatement(statement) => {
+                self.expression(&statement.expression);
+                self.emit(Instruction::Pop, statement.span);
+            }
+            Statement::ReturnStatement(statement) => {
+                match &statement.value {
+                    Some(value) => self.expression(value),
+                    None => {
+                        self.emit(Instruction::Unit, statement.span);
+                    }
+                }
+                self.emit(Instruction::Return, statement.span);
+            }
+            Statement::BreakStatement(_) => unreachable!("`break` outside of a loop"),
+            Statement::UseDeclaration(_) => unreachable!("imports are resolved before checking"),
+            // Already reported by the parser, programs with syntax errors are not compiled
+            Statement::Error(_) => {}
+        }
+    }
+
+    // -- Expressions --
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::BinaryOp(node) => self.binary_op(node),
+            Expression::UnaryOp(node) => self.unary_op(node),
+            Expression::FunctionCall(node) => {
+                for argument in &node.arguments {
+                    self.expression(argument);
+                }
+                let name = self.chunk.add_name(&node.function_name.name);
+                self.emit(
+                    Instruction::Call {
+                        name,
+                        arguments: node.arguments.len() as u32,
+                    },
+                    node.span,
+                );
+            }
+            Expression::FieldAccess(node) => {
+                self.expression(&node.object);
+                let name = self.chunk.add_name(&node.field.name);
+                self.emit(Instruction::GetField(name), node.span);
+            }
+            Expression::MethodCall(node) => {
+                self.expression(&node.receiver);
+                for argument in &node.arguments {
+                    self.expression(argument);
+                }
+                let name = self.chunk.add_name(&node.method.name);
+                self.emit(
+                    Instruction::CallMethod {
+                        name,
+                        arguments: node.arguments.len() as u32,
+                    },
+                    node.span,
+                );
+            }
+            Expression::BlockExpression(node) => self.block(node),
+            Expression::IfExpression(node) => self.if_expression(node),
+            Expression::Identifier(node) => match self.resolve_local(&node.name) {
+                Some(slot) => {
+                    self.emit(Instruction::GetLocal(slot), node.span);
+                }
+                None => {
+                    let index = self.chunk.add_name(&node.name);
+                    self.emit(Instruction::GetGlobal(index), node.span);
+                }
+            },
+            Expression::IntegerLiteral(node) => {
+                self.emit_constant(integer_literal_value(node, false), node.span)
+            }
+            Expression::FloatLiteral(node) => {
+                self.emit_constant(float_literal_value(node), node.span)
+            }
+            Expression::StringLiteral(node) => {
+                self.emit_constant(Value::String(node.value.clone()), node.span)
+            }
+            Expression::CharLiteral(node) => self.emit_constant(Value::Char(node.value), node.span),
+            Expression::BooleanLiteral(node) => {
+                self.emit_constant(Value::Bool(node.value), node.span)
+            }
+        }
+    }
+
+    fn binary_op(&mut self, node: &BinaryOp) {
+        use BinaryOperator::*;
+
+        // `&&` and `||` short circuit, leaving the left operand as the result
+        if matches!(node.operator, And | Or) {
+            self.expression(&node.left);
+            self.emit(Instruction::Duplicate, node.span);
+            let jump = match node.operator {
+                And => Instruction::JumpIfFalse(0),
+                _ => Instruction::JumpIfTrue(0),
+            };
+            let jump = self.emit(jump, node.span);
+            self.emit(Instruction::Pop, node.span);
+            self.expression(&node.right);
+            self.patch_jump(jump);
+            return;
+        }
+
+        self.expression(&node.left);
+        self.expression(&node.right);
+        let instruction = match node.operator {
+            Add => Instruction::Add,
+            Subtract => Instruction::Subtract,
+            Multiply => Instruction::Multiply,
+            Divide => Instruction::Divide,
+            Remainder => Instruction::Remainder,
+            BitwiseAnd => Instruction::BitwiseAnd,
+            BitwiseOr => Instruction::BitwiseOr,
+            BitwiseXor => Instruction::BitwiseXor,
+            ShiftLeft => Instruction::ShiftLeft,
+            ShiftRight => Instruction::ShiftRight,
+            Equals => Instruction::Equals,
+            NotEquals => Instruction::NotEquals,
+            LessThan => Instruction::LessThan,
+            GreaterThan => Instruction::GreaterThan,
+            LessThanOrEqual => Instruction::LessThanOrEqual,
+            GreaterThanOrEqual => Instruction::GreaterThanOrEqual,
+            And | Or => unreachable!(),
+        };
+        self.emit(instruction, node.span);
+    }
+
+    fn unary_op(&mut self, node: &UnaryOp) {
+        match (&node.operator, node.operand.as_ref()) {
+            // Negated literals are built directly, `-128i8` must not overflow on `128i8`
+            (UnaryOperator::Negate, Expression::IntegerLiteral(literal)) => {
+                self.emit_constant(integer_literal_value(literal, true), node.span);
+            }
+            (UnaryOperator::Negate, operand) => {
+                self.expression(operand);
+                self.emit(Instruction::Negate, node.span);
+            }
+            (UnaryOperator::Not, operand) => {
+                self.expression(operand);
+                self.emit(Instruction::Not, node.span);
+            }
+        }
+    }
+
+    fn block(&mut self, node: &BlockExpression) {
+        let first_slot = self.next_slot;
+        self.scopes.push(Vec::new());
+        self.statements(&node.statements);
+        match &node.final_expression {
+            Some(expression) => self.expression(expression),
+            None => {
+                self.emit(Instruction::Unit, node.span);
+            }
+        }
+        if declares_functions(&node.statements) {
+            self.emit(Instruction::ExitScope, node.span);
+        }
+        self.scopes.pop();
+        self.next_slot = first_slot;
+    }
+
+    fn if_expression(&mut self, node: &IfExpression) {
+        self.expression(&node.condition);
+        let to_else = self.emit(Instruction::JumpIfFalse(0), node.span);
+        self.block(&node.then_branch);
+        let to_end = self.emit(Instruction::Jump(0), node.span);
+        self.patch_jump(to_else);
+        match &node.else_branch {
+            Some(else_branch) => self.block(else_branch),
+            None => {
+                self.emit(Instruction::Unit, node.span);
+            }
+        }
+        self.patch_jump(to_end);
+    }
+}
+
+fn declares_functions(statements: &[Statement]) -> bool {
+    statements
+        .iter()
+        .any(|statement| matches!(statement, Statement::FunctionDeclaration(_)))
+}
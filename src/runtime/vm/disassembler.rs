@@ -0,0 +1,114 @@
+use std::fmt::Write;
+
+use super::chunk::{Chunk, Instruction};
+use crate::{core::source_map::SourceMap, runtime::Value};
+
+/// Renders a chunk and the functions declared in it as text, one instruction per line.
+///
+/// Instructions are grouped under the source lines they were compiled from, operands referring
+/// to a pool of the chunk are followed by the entry they refer to.
+pub fn disassemble(chunk: &Chunk, name: &str, source_map: &SourceMap) -> String {
+    let mut output = String::new();
+    let header = format!("{} ({} locals)", name, chunk.locals);
+    disassemble_chunk(&mut output, chunk, &header, source_map);
+    output
+}
+
+fn disassemble_chunk(output: &mut String, chunk: &Chunk, header: &str, source_map: &SourceMap) {
+    let _ = writeln!(output, "== {header} ==");
+
+    let mut current_line = None;
+    for (index, (instruction, span)) in chunk.code.iter().zip(&chunk.spans).enumerate() {
+        if let Some(file) = source_map.get(span.file) {
+            let (line, _) = file.line_column(span.start);
+            if current_line != Some(line) {
+                current_line = Some(line);
+                let _ = writeln!(output, "{:>9} | {}", line, file.line_text(line).trim());
+            }
+        }
+
+        let (mnemonic, operands, comment) = describe(chunk, instruction);
+        let line = format!("{index:04}  {mnemonic:<16} {operands}");
+        let _ = match comment {
+            Some(comment) => writeln!(output, "{line:<32} ; {comment}"),
+            None => writeln!(output, "{}", line.trim_end()),
+        };
+    }
+
+    for function in &chunk.functions {
+        output.push('\n');
+        let header = format!(
+            "fn {} ({} parameters, {} locals)",
+            function.name, function.parameters, function.chunk.locals
+        );
+        disassemble_chunk(output, &function.chunk, &header, source_map);
+    }
+}
+
+/// The mnemonic, the operands and what the operands refer to.
+fn describe(chunk: &Chunk, instruction: &Instruction) -> (&'static str, String, Option<String>) {
+    let name = |index: u32| format!("`{}`", chunk.names[index as usize]);
+    match *instruction {
+        Instruction::Constant(index) => {
+            let constant = &chunk.constants[index as usize];
+            let constant = match constant {
+                Value::String(value) => format!("{value:?}"),
+                Value::Char(value) => format!("{value:?}"),
+                constant => format!("{constant}: {}", constant.ty()),
+            };
+            ("constant", index.to_string(), Some(constant))
+        }
+        Instruction::Unit => ("unit", String::new(), None),
+        Instruction::Pop => ("pop", String::new(), None),
+        Instruction::Duplicate => ("duplicate", String::new(), None),
+        Instruction::GetLocal(slot) => ("get_local", slot.to_string(), None),
+        Instruction::SetLocal(slot) => ("set_local", slot.to_string(), None),
+        Instruction::GetGlobal(index) => ("get_global", index.to_string(), Some(name(index))),
+        Instruction::DefineGlobal(index) => ("define_global", index.to_string(), Some(name(index))),
+        Instruction::Add => ("add", String::new(), None),
+        Instruction::Subtract => ("subtract", String::new(), None),
+        Instruction::Multiply => ("multiply", String::new(), None),
+        Instruction::Divide => ("divide", String::new(), None),
+        Instruction::Remainder => ("remainder", String::new(), None),
+        Instruction::BitwiseAnd => ("bitwise_and", String::new(), None),
+        Instruction::BitwiseOr => ("bitwise_or", String::new(), None),
+        Instruction::BitwiseXor => ("bitwise_xor", String::new(), None),
+        Instruction::ShiftLeft => ("shift_left", String::new(), None),
+        Instruction::ShiftRight => ("shift_right", String::new(), None),
+        Instruction::Equals => ("equals", String::new(), None),
+        Instruction::NotEquals => ("not_equals", String::new(), None),
+        Instruction::LessThan => ("less_than", String::new(), None),
+        Instruction::GreaterThan => ("greater_than", String::new(), None),
+        Instruction::LessThanOrEqual => ("less_equal", String::new(), None),
+        Instruction::GreaterThanOrEqual => ("greater_equal", String::new(), None),
+        Instruction::Negate => ("negate", String::new(), None),
+        Instruction::Not => ("not", String::new(), None),
+        Instruction::Jump(target) => ("jump", format!("{target:04}"), None),
+        Instruction::JumpIfFalse(target) => ("jump_if_false", format!("{target:04}"), None),
+        Instruction::JumpIfTrue(target) => ("jump_if_true", format!("{target:04}"), None),
+        Instruction::Call {
+            name: index,
+            arguments,
+        } => ("call", format!("{index} {arguments}"), Some(name(index))),
+        Instruction::GetField(index) => ("get_field", index.to_string(), Some(name(index))),
+        Instruction::CallMethod {
+            name: index,
+            arguments,
+        } => (
+            "call_method",
+            format!("{index} {arguments}"),
+            Some(name(index)),
+        ),
+        Instruction::DeclareFunction(index) => (
+            "declare_function",
+            index.to_string(),
+            Some(format!("`{}`", chunk.functions[index as usize].name)),
+        ),
+        Instruction::EnterScope => ("enter_scope", String::new(), None),
+        Instruction::ExitScope => ("exit_scope", String::new(), None),
+        Instruction::Return => ("return", String::new(), None),
+        Instruction::MissingReturn(index) => {
+            ("missing_return", index.to_string(), Some(name(index)))
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! Bytecode execution.
+//!
+//! Type checked programs are compiled into [`Chunk`]s, flat instruction lists operating on a
+//! value stack, with local variables stored in numbered slots instead of named scopes. Each
+//! function call runs its own chunk with a fresh stack and slots.
+
+use std::{collections::HashMap, rc::Rc};
+
+use self::chunk::{Chunk, CompiledFunction, Instruction};
+pub use self::{
+    bytecode::{BytecodeError, BytecodeFile},
+    compiler::{compile_expression, compile_function, compile_program},
+    disassembler::disassemble,
+};
+use super::{
+    Function, Runtime, RuntimeError, Value,
+    interpreter::{member_error, operation_error, operation_name},
+    unresolved,
+};
+use crate::parser::ast::BinaryOperator;
+
+pub mod bytecode;
+pub mod chunk;
+mod compiler;
+mod disassembler;
+
+impl Runtime {
+    /// Runs a top level chunk, whose locals are the variables declared in its blocks.
+    pub(super) fn run_chunk(&mut self, chunk: &Chunk) -> Result<Value, RuntimeError> {
+        let locals = vec![Value::Unit; chunk.locals];
+        let depth = self.function_scopes.len();
+        let result = self.execute_chunk(chunk, locals);
+        self.function_scopes.truncate(depth);
+        result
+    }
+
+    /// Checks that every function, global and member `chunk` uses is declared by the chunk
+    /// itself or by this runtime. Bytecode is type checked against the runtime that compiled it,
+    /// which may provide natives the loading runtime does not.
+    pub(super) fn check_names(&self, chunk: &Chunk) -> Result<(), RuntimeError> {
+        let mut functions = Vec::new();
+        let mut globals = Vec::new();
+        collect_declarations(chunk, &mut functions, &mut globals);
+
+        let has_member = |name: &str, field: bool| {
+            self.types.values().any(|info| {
+                if field {
+                    info.has_field(name)
+                } else {
+                    info.has_method(name)
+                }
+            }) || (!field
+                && self
+                    .methods
+                    .values()
+                    .any(|methods| methods.contains_key(name)))
+        };
+        check_chunk_names(chunk, &mut |kind, name| {
+            let declared = match kind {
+                "function" => functions.contains(&name) || self.function(name).is_some(),
+                "global" => globals.contains(&name) || self.globals.contains_key(name),
+                "field" => has_member(name, true),
+                _ => has_member(name, false),
+            };
+            if declared {
+                Ok(())
+            } else {
+                Err(unresolved(kind, name))
+            }
+        })
+    }
+
+    /// Calls a compiled function, the arguments must match its parameters.
+    pub(super) fn call_compiled(
+        &mut self,
+        function: &CompiledFunction,
+        mut arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        trace!("Calling compiled function `{}`", function.name);
+        arguments.resize(function.chunk.locals.max(function.parameters), Value::Unit);
+        self.execute_chunk(&function.chunk, arguments)
+    }
+
+    fn execute_chunk(
+        &mut self,
+        chunk: &Chunk,
+        mut locals: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let mut stack: Vec<Value> = Vec::new();
+        let mut ip = 0;
+
+        loop {
+            let instruction = chunk.code[ip];
+            let span = chunk.spans[ip];
+            trace!("{:04} {:?}", ip, instruction);
+            ip += 1;
+
+            let binary = |stack: &mut Vec<Value>, operator: BinaryOperator| {
+                let right = stack.pop().expect("stack underflow");
+                let left = stack.pop().expect("stack underflow");
+                let value = left
+                    .binary_operation(&operator, &right)
+                    .map_err(|error| operation_error(error, operation_name(&operator), span))?;
+                stack.push(value);
+                Ok::<(), RuntimeError>(())
+            };
+
+            match instruction {
+                Instruction::Constant(index) => stack.push(chunk.constants[index as usize].clone()),
+                Instruction::Unit => stack.push(Value::Unit),
+                Instruction::Pop => {
+                    stack.pop();
+                }
+                Instruction::Duplicate => {
+                    let top = stack.last().cloned().expect("stack underflow");
+                    stack.push(top);
+                }
+                Instruction::GetLocal(slot) => stack.push(locals[slot as usize].clone()),
+                Instruction::SetLocal(slot) => {
+                    locals[slot as usize] = stack.pop().expect("stack underflow");
+                }
+                Instruction::GetGlobal(index) => {
+                    let value = self
+                        .globals
+                        .get(&chunk.names[index as usize])
+                        .cloned()
+                        .ok_or_else(|| unresolved("global", &chunk.names[index as usize]))?;
+                    stack.push(value);
+                }
+                Instruction::DefineGlobal(index) => {
+                    let value = stack.pop().expect("stack underflow");
+                    let name = &chunk.names[index as usize];
+                    trace!("Declaring variable `{}` = {}", name, value);
+                    self.globals.insert(name.clone(), value);
+                }
+                Instruction::Add => binary(&mut stack, BinaryOperator::Add)?,
+                Instruction::Subtract => binary(&mut stack, BinaryOperator::Subtract)?,
+                Instruction::Multiply => binary(&mut stack, BinaryOperator::Multiply)?,
+                Instruction::Divide => binary(&mut stack, BinaryOperator::Divide)?,
+                Instruction::Remainder => binary(&mut stack, BinaryOperator::Remainder)?,
+                Instruction::BitwiseAnd => binary(&mut stack, BinaryOperator::BitwiseAnd)?,
+                Instruction::BitwiseOr => binary(&mut stack, BinaryOperator::BitwiseOr)?,
+                Instruction::BitwiseXor => binary(&mut stack, BinaryOperator::BitwiseXor)?,
+                Instruction::ShiftLeft => binary(&mut stack, BinaryOperator::ShiftLeft)?,
+                Instruction::ShiftRight => binary(&mut stack, BinaryOperator::ShiftRight)?,
+                Instruction::Equals => binary(&mut stack, BinaryOperator::Equals)?,
+                Instruction::NotEquals => binary(&mut stack, BinaryOperator::NotEquals)?,
+                Instruction::LessThan => binary(&mut stack, BinaryOperator::LessThan)?,
+                Instruction::GreaterThan => binary(&mut stack, BinaryOperator::GreaterThan)?,
+                Instruction::LessThanOrEqual => {
+                    binary(&mut stack, BinaryOperator::LessThanOrEqual)?
+                }
+                Instruction::GreaterThanOrEqual => {
+                    binary(&mut stack, BinaryOperator::GreaterThanOrEqual)?
+                }
+                Instruction::Negate => {
+                    let operand = stack.pop().expect("stack underflow");
+                    let value = operand
+                        .negate()
+                        .map_err(|error| operation_error(error, "negate", span))?;
+                    stack.push(value);
+                }
+                Instruction::Not => {
+                    let operand = stack.pop().expect("stack underflow");
+                    let value = operand
+                        .not()
+                        .map_err(|error| operation_error(error, "negate", span))?;
+                    stack.push(value);
+                }
+                Instruction::Jump(target) => ip = target as usize,
+                Instruction::JumpIfFalse(target) => {
+                    if stack.pop() != Some(Value::Bool(true)) {
+                        ip = target as usize;
+                    }
+                }
+                Instruction::JumpIfTrue(target) => {
+                    if stack.pop() == Some(Value::Bool(true)) {
+                        ip = target as usize;
+                    }
+                }
+                Instruction::Call { name, arguments } => {
+                    let name = &chunk.names[name as usize];
+                    let arguments = stack.split_off(stack.len() - arguments as usize);
+                    let function = self
+                        .function(name)
+                        .ok_or_else(|| RuntimeError::UndefinedFunction { name: name.clone() })?;
+                    stack.push(self.call_scoped(name, function, arguments, span)?);
+                }
+                Instruction::GetField(field) => {
+                    let field = &chunk.names[field as usize];
+                    let Some(Value::Object(object)) = stack.pop() else {
+                        return Err(unresolved("field", field));
+                    };
+                    let name = format!("{}::{}", object.type_name(), field);
+                    trace!("Reading field `{}`", name);
+                    let value = self
+                        .types
+                        .get(object.type_name())
+                        .and_then(|info| info.get(&object, field))
+                        .ok_or_else(|| unresolved("field", &name))?
+                        .map_err(|error| member_error(&name, error, span))?;
+                    stack.push(value);
+                }
+                Instruction::CallMethod { name, arguments } => {
+                    let method = &chunk.names[name as usize];
+                    let arguments = stack.split_off(stack.len() - arguments as usize);
+                    let receiver = stack.pop().expect("stack underflow");
+                    stack.push(self.call_method(receiver, method, arguments, span)?);
+                }
+                Instruction::DeclareFunction(index) => {
+                    let function = &chunk.functions[index as usize];
+                    self.declare_function(&function.name, Function::Compiled(Rc::clone(function)));
+                }
+                Instruction::EnterScope => self.function_scopes.push(HashMap::new()),
+                Instruction::ExitScope => {
+                    self.function_scopes.pop();
+                }
+                Instruction::Return => return Ok(stack.pop().expect("stack underflow")),
+                Instruction::MissingReturn(name) => {
+                    return Err(RuntimeError::MissingReturn {
+                        name: chunk.names[name as usize].clone(),
+                        span,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Collects the functions and globals declared anywhere in `chunk` and its functions.
+fn collect_declarations<'a>(
+    chunk: &'a Chunk,
+    functions: &mut Vec<&'a str>,
+    globals: &mut Vec<&'a str>,
+) {
+    for instruction in &chunk.code {
+        if let Instruction::DefineGlobal(index) = instruction {
+            globals.push(&chunk.names[*index as usize]);
+        }
+    }
+    for function in &chunk.functions {
+        functions.push(&function.name);
+        collect_declarations(&function.chunk, functions, globals);
+    }
+}
+
+/// Calls `check` with the kind and name of everything `chunk` and its functions look up by name.
+fn check_chunk_names<'a>(
+    chunk: &'a Chunk,
+    check: &mut impl FnMut(&'static str, &'a str) -> Result<(), RuntimeError>,
+) -> Result<(), RuntimeError> {
+    for instruction in &chunk.code {
+        let (kind, index) = match *instruction {
+            Instruction::Call { name, .. } => ("function", name),
+            Instruction::GetGlobal(index) => ("global", index),
+            Instruction::GetField(index) => ("field", index),
+            Instruction::CallMethod { name, .. } => ("method", name),
+            _ => continue,
+        };
+        check(kind, &chunk.names[index as usize])?;
+    }
+    chunk
+        .functions
+        .iter()
+        .try_for_each(|function| check_chunk_names(&function.chunk, check))
+}
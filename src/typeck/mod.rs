@@ -0,0 +1,1023 @@
+use std::collections::{HashMap, HashSet};
+
+use derive_more::{Display, Error};
+
+use crate::{
+    core::{
+        builtin::{Builtin, FormatError, FormatString},
+        diagnostic::{Diagnostic, ToDiagnostic},
+        span::{Span, Spanned},
+        types::{FloatType, IntegerType, Type},
+    },
+    parser::ast::{
+        BinaryOp, BinaryOperator, BlockExpression, Expression, FieldAccess, FloatLiteral,
+        FunctionCall, FunctionDeclaration, Identifier, IfExpression, IntegerLiteral, MethodCall,
+        Program, Statement, StringLiteral, StructDeclaration, TEST_ATTRIBUTE, UnaryOp,
+        UnaryOperator,
+    },
+};
+
+#[derive(Debug, PartialEq, Clone, Display, Error)]
+pub enum TypeError {
+    #[display("unknown type `{name}`")]
+    UnknownType { name: String, span: Span },
+    #[display("mismatched types, expected: `{expected}`, found: `{found}`")]
+    MismatchedTypes {
+        expected: Type,
+        found: Type,
+        span: Span,
+        /// Where the expectation comes from, e.g. a type annotation.
+        expected_span: Option<Span>,
+    },
+    #[display("literal `{value}` out of range for `{ty}`")]
+    LiteralOutOfRange { value: String, ty: Type, span: Span },
+    #[display("cannot apply `{}` to `{left}` and `{right}`", operator.symbol())]
+    InvalidOperands {
+        operator: BinaryOperator,
+        left: Type,
+        right: Type,
+        span: Span,
+    },
+    #[display("cannot apply unary `{}` to `{operand}`", operator.symbol())]
+    InvalidOperand {
+        operator: UnaryOperator,
+        operand: Type,
+        span: Span,
+    },
+    #[display("cannot find value `{name}` in this scope")]
+    UndefinedVariable { name: String, span: Span },
+    #[display("cannot find function `{name}` in this scope")]
+    UndefinedFunction { name: String, span: Span },
+    #[display("function `{name}` takes {expected} argument(s) but {found} were supplied")]
+    ArgumentCount {
+        name: String,
+        expected: usize,
+        found: usize,
+        span: Span,
+    },
+    #[display("no field `{field}` on type `{ty}`")]
+    UnknownField { ty: Type, field: String, span: Span },
+    #[display("no method named `{method}` found for `{ty}`")]
+    UnknownMethod {
+        ty: Type,
+        method: String,
+        span: Span,
+    },
+    #[display("`return` outside of a function")]
+    ReturnOutsideFunction { span: Span },
+    #[display("`break` outside of a loop")]
+    BreakOutsideLoop { span: Span },
+    #[display("unresolved import")]
+    UnresolvedImport { span: Span },
+    #[display("cannot find attribute `{name}`")]
+    UnknownAttribute { name: String, span: Span },
+    #[display("test function `{name}` must not take parameters or return a value")]
+    InvalidTest { name: String, span: Span },
+    #[display("test function `{name}` must be declared at the top level")]
+    NestedTest { name: String, span: Span },
+    #[display("{error}")]
+    InvalidFormat {
+        #[error(not(source))]
+        error: FormatError,
+        span: Span,
+    },
+    #[display(
+        "{placeholders} placeholder(s) in the format string but {found} argument(s) were supplied"
+    )]
+    FormatArgumentCount {
+        placeholders: usize,
+        found: usize,
+        span: Span,
+    },
+    #[display("format string must be a string literal")]
+    NonLiteralFormat { span: Span },
+}
+
+impl Spanned for TypeError {
+    fn span(&self) -> Span {
+        match self {
+            TypeError::UnknownType { span, .. }
+            | TypeError::MismatchedTypes { span, .. }
+            | TypeError::LiteralOutOfRange { span, .. }
+            | TypeError::InvalidOperands { span, .. }
+            | TypeError::InvalidOperand { span, .. }
+            | TypeError::UndefinedVariable { span, .. }
+            | TypeError::UndefinedFunction { span, .. }
+            | TypeError::ArgumentCount { span, .. }
+            | TypeError::UnknownField { span, .. }
+            | TypeError::UnknownMethod { span, .. }
+            | TypeError::ReturnOutsideFunction { span }
+            | TypeError::BreakOutsideLoop { span }
+            | TypeError::UnresolvedImport { span }
+            | TypeError::UnknownAttribute { span, .. }
+            | TypeError::InvalidTest { span, .. }
+            | TypeError::NestedTest { span, .. }
+            | TypeError::InvalidFormat { span, .. }
+            | TypeError::FormatArgumentCount { span, .. }
+            | TypeError::NonLiteralFormat { span } => *span,
+        }
+    }
+}
+
+impl ToDiagnostic for TypeError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string());
+        match self {
+            TypeError::UnknownType { span, .. } => {
+                diagnostic.with_primary_label(*span, "not found in this scope")
+            }
+            TypeError::MismatchedTypes {
+                expected,
+                found,
+                span,
+                expected_span,
+            } => {
+                let mut diagnostic = Diagnostic::error("mismatched types")
+                    .with_primary_label(*span, format!("expected `{expected}`, found `{found}`"));
+                if let Some(expected_span) = expected_span {
+                    diagnostic =
+                        diagnostic.with_secondary_label(*expected_span, "expected due to this");
+                }
+                if expected.is_numeric() && found.is_numeric() {
+                    diagnostic = diagnostic.with_help(format!(
+                        "numeric types are never converted implicitly, use a `{expected}` value instead"
+                    ));
+                }
+                diagnostic
+            }
+            TypeError::LiteralOutOfRange { ty, span, .. } => {
+                let diagnostic = diagnostic.with_primary_label(*span, "does not fit");
+                match ty {
+                    Type::Integer(integer) => diagnostic.with_note(format!(
+                        "the range of `{}` is `{}..={}`",
+                        integer,
+                        integer.min(),
+                        integer.max()
+                    )),
+                    _ => diagnostic,
+                }
+            }
+            TypeError::InvalidOperands {
+                operator,
+                left,
+                right,
+                span,
+            } => {
+                let diagnostic = diagnostic
+                    .with_primary_label(*span, format!("`{left}` {} `{right}`", operator.symbol()));
+                if left != right && left.is_numeric() && right.is_numeric() {
+                    diagnostic.with_help("both operands must have the same type")
+                } else {
+                    diagnostic
+                }
+            }
+            TypeError::InvalidOperand { operand, span, .. } => {
+                diagnostic.with_primary_label(*span, format!("cannot be applied to `{operand}`"))
+            }
+            TypeError::UndefinedVariable { span, .. }
+            | TypeError::UndefinedFunction { span, .. } => {
+                diagnostic.with_primary_label(*span, "not found in this scope")
+            }
+            TypeError::ArgumentCount { expected, span, .. } => {
+                diagnostic.with_primary_label(*span, format!("expected {expected} argument(s)"))
+            }
+            TypeError::UnknownField { span, .. } => {
+                diagnostic.with_primary_label(*span, "unknown field")
+            }
+            TypeError::UnknownMethod { span, .. } => {
+                diagnostic.with_primary_label(*span, "method not found")
+            }
+            TypeError::ReturnOutsideFunction { span } => {
+                diagnostic.with_primary_label(*span, "cannot `return` here")
+            }
+            TypeError::BreakOutsideLoop { span } => {
+                diagnostic.with_primary_label(*span, "cannot `break` here")
+            }
+            TypeError::UnresolvedImport { span } => diagnostic
+                .with_primary_label(*span, "cannot import here")
+                .with_note("imports are resolved at the top level of scripts executed from a file"),
+            TypeError::UnknownAttribute { span, .. } => diagnostic
+                .with_primary_label(*span, "unknown attribute")
+                .with_note(format!("the only attribute is `#[{TEST_ATTRIBUTE}]`")),
+            TypeError::InvalidTest { name, span } => {
+                diagnostic.with_primary_label(*span, format!("expected `fn {name}()`"))
+            }
+            TypeError::NestedTest { span, .. } => diagnostic
+                .with_primary_label(*span, "declared inside of another item")
+                .with_note("tests are only looked for at the top level of scripts"),
+            TypeError::InvalidFormat { span, .. } => diagnostic
+                .with_primary_label(*span, "invalid format string")
+                .with_help("write `{{` and `}}` for literal braces"),
+            TypeError::FormatArgumentCount {
+                placeholders, span, ..
+            } => diagnostic.with_primary_label(
+                *span,
+                format!("expects {placeholders} argument(s) after it"),
+            ),
+            TypeError::NonLiteralFormat { span } => diagnostic
+                .with_primary_label(*span, "not a string literal")
+                .with_help("write `\"{}\"` as the format string and pass this value after it"),
+        }
+    }
+}
+
+/// The parameter and return types of a declared function.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FunctionSignature {
+    pub parameters: Vec<Type>,
+    pub return_type: Type,
+}
+
+/// The fields and methods of a type implemented by the host, methods take the receiver as an
+/// implicit first argument.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TypeMembers {
+    pub fields: HashMap<String, Type>,
+    pub methods: HashMap<String, FunctionSignature>,
+}
+
+/// # TypeChecker
+///
+/// Resolves the type of every expression in a [`Program`] and stores it in the
+/// `inferred_type` fields of the AST, which the runtime relies on to pick the width of
+/// numeric values.
+///
+/// Unsuffixed numeric literals take their type from the surrounding context
+/// (`let x: u8 = 5;`, the other operand of a binary operation, a parameter type, ...)
+/// and default to `i64` and `f64` respectively.
+///
+/// The checker keeps its global state between calls to [`TypeChecker::check_program`],
+/// so declarations from earlier programs stay visible.
+#[derive(Debug, Clone, Default)]
+pub struct TypeChecker {
+    globals: HashMap<String, Type>,
+    /// Local scopes of the function currently being checked, innermost last.
+    scopes: Vec<HashMap<String, Type>>,
+    functions: HashMap<String, FunctionSignature>,
+    /// Nested functions of the statement lists around the checked code, innermost last. They
+    /// shadow the functions of the enclosing lists and are visible in the bodies declared there.
+    function_scopes: Vec<HashMap<String, FunctionSignature>>,
+    structs: HashSet<String>,
+    /// Members of the types implemented by the host and the methods of builtin types, by type
+    /// name.
+    members: HashMap<String, TypeMembers>,
+    /// Return type of the function currently being checked and where it was declared.
+    return_type: Option<(Type, Span)>,
+}
+
+impl TypeChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn check_program(&mut self, program: &mut Program) -> Result<(), TypeError> {
+        trace!("Type checking program");
+        self.declare_items(&program.statements)?;
+        for statement in &mut program.statements {
+            self.check_statement(statement)?;
+        }
+        Ok(())
+    }
+
+    /// Makes a global variable of type `ty` visible to the programs checked afterwards.
+    pub fn declare_global(&mut self, name: &str, ty: Type) {
+        self.globals.insert(name.to_string(), ty);
+    }
+
+    /// Makes a function implemented outside of the checked programs callable from them.
+    pub fn declare_function(&mut self, name: &str, signature: FunctionSignature) {
+        self.functions.insert(name.to_string(), signature);
+    }
+
+    /// Makes a type implemented outside of the checked programs usable from them.
+    pub fn declare_type(&mut self, name: &str, members: TypeMembers) {
+        self.structs.insert(name.to_string());
+        self.members.insert(name.to_string(), members);
+    }
+
+    /// Makes a method implemented outside of the checked programs callable on the values of a
+    /// builtin type, like `"text".len()`.
+    pub fn declare_method(&mut self, receiver: &Type, name: &str, signature: FunctionSignature) {
+        self.members
+            .entry(receiver.to_string())
+            .or_default()
+            .methods
+            .insert(name.to_string(), signature);
+    }
+
+    /// The signature of a function declared at the top level of a checked program.
+    pub fn signature(&self, name: &str) -> Option<&FunctionSignature> {
+        self.functions.get(name)
+    }
+
+    /// Resolves a type name written in source code.
+    pub fn resolve_type(&self, identifier: &Identifier) -> Result<Type, TypeError> {
+        if let Some(ty) = Type::from_name(&identifier.name) {
+            Ok(ty)
+        } else if self.structs.contains(&identifier.name) {
+            Ok(Type::Struct(identifier.name.clone()))
+        } else {
+            Err(TypeError::UnknownType {
+                name: identifier.name.clone(),
+                span: identifier.span,
+            })
+        }
+    }
+
+    /// Registers the structs and function signatures of a statement list up front, so they
+    /// can be used before their declaration.
+    fn declare_items(&mut self, statements: &[Statement]) -> Result<(), TypeError> {
+        for statement in statements {
+            if let Statement::StructDeclaration(declaration) = statement {
+                self.structs.insert(declaration.identifier().name.clone());
+            }
+        }
+
+        for statement in statements {
+            if let Statement::FunctionDeclaration(declaration) = statement {
+                let signature = self.function_signature(declaration)?;
+                let name = declaration.identifier.name.clone();
+                match self.function_scopes.last_mut() {
+                    Some(scope) => scope.insert(name, signature),
+                    None => self.functions.insert(name, signature),
+                };
+            }
+        }
+
+        Ok(())
+    }
+
+    fn function_signature(
+        &self,
+        declaration: &FunctionDeclaration,
+    ) -> Result<FunctionSignature, TypeError> {
+        let parameters = declaration
+            .parameters
+            .iter()
+            .map(|parameter| self.resolve_type(&parameter.declared_type))
+            .collect::<Result<_, _>>()?;
+        let return_type = self.resolve_type(&declaration.return_type)?;
+        Ok(FunctionSignature {
+            parameters,
+            return_type,
+        })
+    }
+
+    fn declare_variable(&mut self, name: &str, ty: Type) {
+        match self.scopes.last_mut() {
+            Some(scope) => scope.insert(name.to_string(), ty),
+            None => self.globals.insert(name.to_string(), ty),
+        };
+    }
+
+    fn lookup_variable(&self, identifier: &Identifier) -> Result<Type, TypeError> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&identifier.name))
+            .or_else(|| self.globals.get(&identifier.name))
+            .cloned()
+            .ok_or_else(|| TypeError::UndefinedVariable {
+                name: identifier.name.clone(),
+                span: identifier.span,
+            })
+    }
+
+    fn check_statement(&mut self, statement: &mut Statement) -> Result<(), TypeError> {
+        match statement {
+            Statement::VariableDeclaration(declaration) => {
+                let declared_type = declaration
+                    .declared_type
+                    .as_ref()
+                    .map(|declared_type| self.resolve_type(declared_type))
+                    .transpose()?;
+                let ty =
+                    self.check_expression(&mut declaration.initializer, declared_type.as_ref())?;
+                if let (Some(declared_type), Some(annotation)) =
+                    (declared_type, &declaration.declared_type)
+                {
+                    expect_type(
+                        &declared_type,
+                        &ty,
+                        declaration.initializer.span(),
+                        Some(annotation.span),
+                    )?;
+                }
+                trace!(
+                    "Variable `{}` has type `{}`",
+                    declaration.identifier.name, ty
+                );
+                self.declare_variable(&declaration.identifier.name, ty);
+            }
+            Statement::FunctionDeclaration(declaration) => {
+                self.check_function_declaration(declaration)?;
+            }
+            Statement::StructDeclaration(declaration) => match declaration {
+                StructDeclaration::NamedStruct { fields, .. } => {
+                    for field in fields {
+                        self.resolve_type(&field.declared_type)?;
+                    }
+                }
+                StructDeclaration::TupleStruct { fields, .. } => {
+                    for field in fields {
+                        self.resolve_type(&field.declared_type)?;
+                    }
+                }
+                StructDeclaration::UnitStruct { .. } => {}
+            },
+            Statement::ExpressionStatement(statement) => {
+                self.check_expression(&mut statement.expression, None)?;
+            }
+            Statement::ReturnStatement(statement) => {
+                let (return_type, return_type_span) =
+                    self.return_type
+                        .clone()
+                        .ok_or(TypeError::ReturnOutsideFunction {
+                            span: statement.span,
+                        })?;
+                match &mut statement.value {
+                    Some(value) => {
+                        let ty = self.check_expression(value, Some(&return_type))?;
+                        expect_type(&return_type, &ty, value.span(), Some(return_type_span))?;
+                    }
+                    None => expect_type(
+                        &return_type,
+                        &Type::Unit,
+                        statement.span,
+                        Some(return_type_span),
+                    )?,
+                }
+            }
+            Statement::BreakStatement(statement) => {
+                return Err(TypeError::BreakOutsideLoop {
+                    span: statement.span,
+                });
+            }
+            // Top level imports of files are resolved and removed by the module loader
+            Statement::UseDeclaration(declaration) => {
+                return Err(TypeError::UnresolvedImport {
+                    span: declaration.span,
+                });
+            }
+            // Already reported by the parser
+            Statement::Error(_) => {}
+        }
+        Ok(())
+    }
+
+    fn check_function_declaration(
+        &mut self,
+        declaration: &mut FunctionDeclaration,
+    ) -> Result<(), TypeError> {
+        let signature = self.function_signature(declaration)?;
+        self.check_attributes(declaration, &signature)?;
+
+        // Functions only see globals and their own parameters, not the locals of the caller
+        let parameters = declaration
+            .parameters
+            .iter()
+            .zip(signature.parameters)
+            .map(|(parameter, ty)| (parameter.identifier.name.clone(), ty))
+            .collect();
+        let outer_scopes = std::mem::replace(&mut self.scopes, vec![parameters]);
+        let outer_return_type = self
+            .return_type
+            .replace((signature.return_type, declaration.return_type.span));
+
+        self.function_scopes.push(HashMap::new());
+
+        let result = self.declare_items(&declaration.body).and_then(|_| {
+            declaration
+                .body
+                .iter_mut()
+                .try_for_each(|statement| self.check_statement(statement))
+        });
+
+        self.function_scopes.pop();
+        self.scopes = outer_scopes;
+        self.return_type = outer_return_type;
+        result
+    }
+
+    /// Rejects unknown attributes and tests the test runner could not call.
+    fn check_attributes(
+        &self,
+        declaration: &FunctionDeclaration,
+        signature: &FunctionSignature,
+    ) -> Result<(), TypeError> {
+        if let Some(attribute) = declaration
+            .attributes
+            .iter()
+            .find(|attribute| attribute.name.name != TEST_ATTRIBUTE)
+        {
+            return Err(TypeError::UnknownAttribute {
+                name: attribute.name.name.clone(),
+                span: attribute.name.span,
+            });
+        }
+        if !declaration.is_test() {
+            return Ok(());
+        }
+
+        let name = declaration.identifier.name.clone();
+        let span = declaration.identifier.span;
+        // Top level statements are checked without a scope
+        if !self.scopes.is_empty() {
+            return Err(TypeError::NestedTest { name, span });
+        }
+        if !signature.parameters.is_empty() || signature.return_type != Type::Unit {
+            return Err(TypeError::InvalidTest { name, span });
+        }
+        Ok(())
+    }
+
+    /// Determines the type of `expression`, storing it in the AST.
+    ///
+    /// `expected` is a hint from the surrounding context used to type unsuffixed literals, it
+    /// is not enforced here.
+    pub fn check_expression(
+        &mut self,
+        expression: &mut Expression,
+        expected: Option<&Type>,
+    ) -> Result<Type, TypeError> {
+        match expression {
+            Expression::BinaryOp(node) => self.check_binary_op(node, expected),
+            Expression::UnaryOp(node) => self.check_unary_op(node, expected),
+            Expression::FunctionCall(node) => self.check_function_call(node),
+            Expression::FieldAccess(node) => self.check_field_access(node),
+            Expression::MethodCall(node) => self.check_method_call(node),
+            Expression::BlockExpression(node) => self.check_block_expression(node, expected),
+            Expression::IfExpression(node) => self.check_if_expression(node, expected),
+            Expression::Identifier(node) => self.lookup_variable(node),
+            Expression::IntegerLiteral(node) => check_integer_literal(node, expected, false),
+            Expression::FloatLiteral(node) => check_float_literal(node, expected),
+            Expression::StringLiteral(_) => Ok(Type::String),
+            Expression::CharLiteral(_) => Ok(Type::Char),
+            Expression::BooleanLiteral(_) => Ok(Type::Bool),
+        }
+    }
+
+    fn check_binary_op(
+        &mut self,
+        node: &mut BinaryOp,
+        expected: Option<&Type>,
+    ) -> Result<Type, TypeError> {
+        use BinaryOperator::*;
+
+        // Only arithmetic passes the expected type on to its operands
+        let operand_hint = match node.operator {
+            Add | Subtract | Multiply | Divide | Remainder | BitwiseAnd | BitwiseOr
+            | BitwiseXor | ShiftLeft | ShiftRight => expected,
+            _ => None,
+        };
+
+        let (left, right) = if matches!(node.operator, ShiftLeft | ShiftRight) {
+            // The shift amount may be of any integer type, independent of the shifted value
+            let left = self.check_expression(&mut node.left, operand_hint)?;
+            let right = self.check_expression(&mut node.right, None)?;
+            (left, right)
+        } else if is_untyped_literal(&node.left) && !is_untyped_literal(&node.right) {
+            // An unsuffixed literal adopts the type of the other operand: `x + 1`, `1 + x`
+            let right = self.check_expression(&mut node.right, operand_hint)?;
+            let left = self.check_expression(&mut node.left, Some(&right))?;
+            (left, right)
+        } else {
+            let left = self.check_expression(&mut node.left, operand_hint)?;
+            let right = self.check_expression(&mut node.right, Some(&left))?;
+            (left, right)
+        };
+
+        let is_ordered = |ty: &Type| ty.is_numeric() || matches!(ty, Type::String | Type::Char);
+
+        let result = match node.operator {
+            Add if left == right && (left.is_numeric() || left == Type::String) => {
+                Some(left.clone())
+            }
+            Subtract | Multiply | Divide | Remainder if left == right && left.is_numeric() => {
+                Some(left.clone())
+            }
+            BitwiseAnd | BitwiseOr | BitwiseXor if left == right && left.is_integer() => {
+                Some(left.clone())
+            }
+            ShiftLeft | ShiftRight if left.is_integer() && right.is_integer() => Some(left.clone()),
+            Equals | NotEquals if left == right => Some(Type::Bool),
+            LessThan | GreaterThan | LessThanOrEqual | GreaterThanOrEqual
+                if left == right && is_ordered(&left) =>
+            {
+                Some(Type::Bool)
+            }
+            And | Or if left == Type::Bool && right == Type::Bool => Some(Type::Bool),
+            _ => None,
+        };
+
+        let ty = result.ok_or_else(|| TypeError::InvalidOperands {
+            operator: node.operator.clone(),
+            left,
+            right,
+            span: node.span,
+        })?;
+        node.inferred_type = Some(type_identifier(&ty, node.span));
+        Ok(ty)
+    }
+
+    fn check_unary_op(
+        &mut self,
+        node: &mut UnaryOp,
+        expected: Option<&Type>,
+    ) -> Result<Type, TypeError> {
+        let operand = match (&node.operator, node.operand.as_mut()) {
+            // `-128i8` is valid even though `128i8` is not
+            (UnaryOperator::Negate, Expression::IntegerLiteral(literal)) => {
+                check_integer_literal(literal, expected, true)?
+            }
+            (_, operand) => self.check_expression(operand, expected)?,
+        };
+
+        let valid = match node.operator {
+            UnaryOperator::Negate => match &operand {
+                Type::Integer(ty) => ty.is_signed(),
+                Type::Float(_) => true,
+                _ => false,
+            },
+            UnaryOperator::Not => operand == Type::Bool || operand.is_integer(),
+        };
+
+        if !valid {
+            return Err(TypeError::InvalidOperand {
+                operator: node.operator.clone(),
+                operand,
+                span: node.span,
+            });
+        }
+
+        node.inferred_type = Some(type_identifier(&operand, node.span));
+        Ok(operand)
+    }
+
+    fn check_function_call(&mut self, node: &mut FunctionCall) -> Result<Type, TypeError> {
+        let name = &node.function_name.name;
+        let signature = self
+            .function_scopes
+            .iter()
+            .rev()
+            .chain(std::iter::once(&self.functions))
+            .find_map(|scope| scope.get(name))
+            .cloned();
+        let Some(signature) = signature else {
+            return match Builtin::from_name(&node.function_name.name) {
+                Some(builtin) => self.check_builtin_call(builtin, node),
+                None => Err(TypeError::UndefinedFunction {
+                    name: node.function_name.name.clone(),
+                    span: node.function_name.span,
+                }),
+            };
+        };
+
+        if signature.parameters.len() != node.arguments.len() {
+            return Err(TypeError::ArgumentCount {
+                name: node.function_name.name.clone(),
+                expected: signature.parameters.len(),
+                found: node.arguments.len(),
+                span: node.span,
+            });
+        }
+
+        for (argument, parameter) in node.arguments.iter_mut().zip(&signature.parameters) {
+            let ty = self.check_expression(argument, Some(parameter))?;
+            expect_type(parameter, &ty, argument.span(), None)?;
+        }
+
+        node.inferred_type = Some(type_identifier(&signature.return_type, node.span));
+        Ok(signature.return_type)
+    }
+
+    /// Builtins are checked by hand, as their parameters are not of a single type.
+    fn check_builtin_call(
+        &mut self,
+        builtin: Builtin,
+        node: &mut FunctionCall,
+    ) -> Result<Type, TypeError> {
+        let (required, maximum) = builtin.arity();
+        let found = node.arguments.len();
+        if found < required || found > maximum {
+            return Err(TypeError::ArgumentCount {
+                name: node.function_name.name.clone(),
+                expected: if found < required { required } else { maximum },
+                found,
+                span: node.span,
+            });
+        }
+
+        match builtin {
+            Builtin::Assert | Builtin::AssertEq | Builtin::AssertNe => {
+                self.check_assertion(builtin, &mut node.arguments)?
+            }
+            Builtin::Print | Builtin::Println | Builtin::Eprintln | Builtin::Format => {
+                self.check_format_arguments(&mut node.arguments)?
+            }
+            Builtin::ReadLine | Builtin::ReadFile | Builtin::WriteFile => {
+                for argument in &mut node.arguments {
+                    self.check_argument(argument, &Type::String)?;
+                }
+            }
+        }
+
+        let ty = builtin.return_type();
+        node.inferred_type = Some(type_identifier(&ty, node.span));
+        Ok(ty)
+    }
+
+    fn check_assertion(
+        &mut self,
+        builtin: Builtin,
+        arguments: &mut [Expression],
+    ) -> Result<(), TypeError> {
+        let (required, _) = builtin.arity();
+        let (values, message) = arguments.split_at_mut(required);
+        match (builtin, values) {
+            (Builtin::Assert, [condition]) => self.check_argument(condition, &Type::Bool)?,
+            (Builtin::AssertEq | Builtin::AssertNe, [left, right]) => {
+                // Both values must be comparable with `==`, so the same rules apply as for its
+                // operands
+                let (left_type, right_type) =
+                    if is_untyped_literal(left) && !is_untyped_literal(right) {
+                        let right_type = self.check_expression(right, None)?;
+                        (self.check_expression(left, Some(&right_type))?, right_type)
+                    } else {
+                        let left_type = self.check_expression(left, None)?;
+                        let right_type = self.check_expression(right, Some(&left_type))?;
+                        (left_type, right_type)
+                    };
+                expect_type(&left_type, &right_type, right.span(), Some(left.span()))?;
+            }
+            _ => unreachable!("the number of arguments was checked by the caller"),
+        }
+        if let Some(message) = message.first_mut() {
+            self.check_argument(message, &Type::String)?;
+        }
+        Ok(())
+    }
+
+    /// Checks a format string and the values formatted into it, which can have any type.
+    ///
+    /// Format strings must be literals. A single argument that is not one is written as it is, so
+    /// the call is rewritten to format it with `"{}"`.
+    fn check_format_arguments(&mut self, arguments: &mut Vec<Expression>) -> Result<(), TypeError> {
+        self.check_argument(&mut arguments[0], &Type::String)?;
+        let (placeholders, span) = match &arguments[0] {
+            Expression::StringLiteral(literal) => {
+                let format = FormatString::parse(&literal.value).map_err(|error| {
+                    TypeError::InvalidFormat {
+                        error,
+                        span: literal.span,
+                    }
+                })?;
+                (format.placeholders(), literal.span)
+            }
+            value if arguments.len() == 1 => {
+                let span = value.span();
+                arguments.insert(
+                    0,
+                    StringLiteral {
+                        value: "{}".to_string(),
+                        span,
+                    }
+                    .into(),
+                );
+                return Ok(());
+            }
+            format => {
+                return Err(TypeError::NonLiteralFormat {
+                    span: format.span(),
+                });
+            }
+        };
+        let values = &mut arguments[1..];
+        if placeholders != values.len() {
+            return Err(TypeError::FormatArgumentCount {
+                placeholders,
+                found: values.len(),
+                span,
+            });
+        }
+        for value in values {
+            self.check_expression(value, None)?;
+        }
+        Ok(())
+    }
+
+    /// Checks an argument that must have type `expected`.
+    fn check_argument(
+        &mut self,
+        argument: &mut Expression,
+        expected: &Type,
+    ) -> Result<(), TypeError> {
+        let ty = self.check_expression(argument, Some(expected))?;
+        expect_type(expected, &ty, argument.span(), None)
+    }
+
+    fn check_field_access(&mut self, node: &mut FieldAccess) -> Result<Type, TypeError> {
+        let object = self.check_expression(&mut node.object, None)?;
+        let ty = self
+            .type_members(&object)
+            .and_then(|members| members.fields.get(&node.field.name))
+            .cloned()
+            .ok_or_else(|| TypeError::UnknownField {
+                ty: object,
+                field: node.field.name.clone(),
+                span: node.field.span,
+            })?;
+        node.inferred_type = Some(type_identifier(&ty, node.span));
+        Ok(ty)
+    }
+
+    fn check_method_call(&mut self, node: &mut MethodCall) -> Result<Type, TypeError> {
+        let receiver = self.check_expression(&mut node.receiver, None)?;
+        let signature = self
+            .type_members(&receiver)
+            .and_then(|members| members.methods.get(&node.method.name))
+            .cloned()
+            .ok_or_else(|| TypeError::UnknownMethod {
+                ty: receiver,
+                method: node.method.name.clone(),
+                span: node.method.span,
+            })?;
+
+        if signature.parameters.len() != node.arguments.len() {
+            return Err(TypeError::ArgumentCount {
+                name: node.method.name.clone(),
+                expected: signature.parameters.len(),
+                found: node.arguments.len(),
+                span: node.span,
+            });
+        }
+
+        for (argument, parameter) in node.arguments.iter_mut().zip(&signature.parameters) {
+            let ty = self.check_expression(argument, Some(parameter))?;
+            expect_type(parameter, &ty, argument.span(), None)?;
+        }
+
+        node.inferred_type = Some(type_identifier(&signature.return_type, node.span));
+        Ok(signature.return_type)
+    }
+
+    /// Scripts cannot declare members yet, so only host types and the builtin types the host
+    /// declared methods for have any.
+    pub fn type_members(&self, ty: &Type) -> Option<&TypeMembers> {
+        self.members.get(&ty.to_string())
+    }
+
+    fn check_block_expression(
+        &mut self,
+        node: &mut BlockExpression,
+        expected: Option<&Type>,
+    ) -> Result<Type, TypeError> {
+        self.scopes.push(HashMap::new());
+        self.function_scopes.push(HashMap::new());
+
+        let result = self.declare_items(&node.statements).and_then(|_| {
+            for statement in &mut node.statements {
+                self.check_statement(statement)?;
+            }
+            match &mut node.final_expression {
+                Some(expression) => self.check_expression(expression, expected),
+                None => Ok(Type::Unit),
+            }
+        });
+
+        self.function_scopes.pop();
+        self.scopes.pop();
+        let ty = result?;
+        node.inferred_type = Some(type_identifier(&ty, node.span));
+        Ok(ty)
+    }
+
+    fn check_if_expression(
+        &mut self,
+        node: &mut IfExpression,
+        expected: Option<&Type>,
+    ) -> Result<Type, TypeError> {
+        let condition = self.check_expression(&mut node.condition, Some(&Type::Bool))?;
+        expect_type(&Type::Bool, &condition, node.condition.span(), None)?;
+
+        let then_type = self.check_block_expression(&mut node.then_branch, expected)?;
+        let ty = match &mut node.else_branch {
+            Some(else_branch) => {
+                let else_type = self.check_block_expression(else_branch, Some(&then_type))?;
+                let then_span = node
+                    .then_branch
+                    .final_expression
+                    .as_ref()
+                    .map_or(node.then_branch.span, |expression| expression.span());
+                let else_span = else_branch
+                    .final_expression
+                    .as_ref()
+                    .map_or(else_branch.span, |expression| expression.span());
+                expect_type(&then_type, &else_type, else_span, Some(then_span))?;
+                then_type
+            }
+            None => Type::Unit,
+        };
+
+        node.inferred_type = Some(type_identifier(&ty, node.span));
+        Ok(ty)
+    }
+}
+
+fn check_integer_literal(
+    node: &mut IntegerLiteral,
+    expected: Option<&Type>,
+    negated: bool,
+) -> Result<Type, TypeError> {
+    let integer_type = match (node.suffix, expected) {
+        (Some(suffix), _) => suffix,
+        (None, Some(Type::Integer(expected))) => *expected,
+        (None, _) => IntegerType::I64,
+    };
+
+    let value = if negated {
+        -i128::from(node.value)
+    } else {
+        i128::from(node.value)
+    };
+
+    let ty = Type::Integer(integer_type);
+    if !integer_type.contains(value) {
+        return Err(TypeError::LiteralOutOfRange {
+            value: value.to_string(),
+            ty,
+            span: node.span,
+        });
+    }
+
+    node.inferred_type = Some(type_identifier(&ty, node.span));
+    Ok(ty)
+}
+
+fn check_float_literal(
+    node: &mut FloatLiteral,
+    expected: Option<&Type>,
+) -> Result<Type, TypeError> {
+    let float_type = match (node.suffix, expected) {
+        (Some(suffix), _) => suffix,
+        (None, Some(Type::Float(expected))) => *expected,
+        (None, _) => FloatType::F64,
+    };
+
+    let ty = Type::Float(float_type);
+    let in_range = match float_type {
+        FloatType::F32 => (node.value as f32).is_finite(),
+        FloatType::F64 => node.value.is_finite(),
+    };
+    if !in_range {
+        return Err(TypeError::LiteralOutOfRange {
+            value: node.value.to_string(),
+            ty,
+            span: node.span,
+        });
+    }
+
+    node.inferred_type = Some(type_identifier(&ty, node.span));
+    Ok(ty)
+}
+
+/// Whether the expression is a numeric literal without a type suffix, which can adopt the
+/// type of its context.
+fn is_untyped_literal(expression: &Expression) -> bool {
+    match expression {
+        Expression::IntegerLiteral(literal) => literal.suffix.is_none(),
+        Expression::FloatLiteral(literal) => literal.suffix.is_none(),
+        Expression::UnaryOp(UnaryOp {
+            operator: UnaryOperator::Negate,
+            operand,
+            ..
+        }) => is_untyped_literal(operand),
+        _ => false,
+    }
+}
+
+fn expect_type(
+    expected: &Type,
+    found: &Type,
+    span: Span,
+    expected_span: Option<Span>,
+) -> Result<(), TypeError> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(TypeError::MismatchedTypes {
+            expected: expected.clone(),
+            found: found.clone(),
+            span,
+            expected_span,
+        })
+    }
+}
+
+/// Converts a [`Type`] into the `Identifier` representation used by the AST.
+fn type_identifier(ty: &Type, span: Span) -> Identifier {
+    Identifier {
+        name: ty.to_string(),
+        span,
+    }
+}
@@ -0,0 +1,177 @@
+//! Compiles scripts to `.rsc` files and loads them into other runtimes.
+
+use std::rc::Rc;
+
+use rscript::{
+    Runtime, RuntimeError, Value,
+    runtime::vm::{
+        BytecodeError, BytecodeFile,
+        bytecode::{MAX_LOCALS, MAX_NESTING},
+        chunk::Instruction,
+    },
+};
+
+fn compile(runtime: &mut Runtime, source: &str) -> Vec<u8> {
+    runtime
+        .compile_named("script.rscript", source)
+        .unwrap()
+        .to_bytes()
+}
+
+fn double(x: i64) -> i64 {
+    x * 2
+}
+
+#[test]
+fn compiled_files_run_in_other_runtimes() {
+    let bytes = compile(
+        &mut Runtime::new(),
+        "fn square(x: i64) -> i64 { return x * x; } let squared = square(4);",
+    );
+    let mut runtime = Runtime::new();
+    runtime
+        .execute_bytecode(&BytecodeFile::from_bytes(&bytes).unwrap())
+        .unwrap();
+    assert_eq!(runtime.global("squared"), Some(&Value::I64(16)));
+    runtime.execute("let cubed = square(2) * 2;").unwrap();
+    assert_eq!(runtime.global("cubed"), Some(&Value::I64(8)));
+}
+
+#[test]
+fn files_of_other_versions_are_rejected() {
+    let mut bytes = compile(&mut Runtime::new(), "let x = 1;");
+    bytes[4..6].copy_from_slice(&99u16.to_le_bytes());
+    assert_eq!(
+        BytecodeFile::from_bytes(&bytes),
+        Err(BytecodeError::UnsupportedVersion { found: 99 })
+    );
+}
+
+#[test]
+fn corrupted_files_are_rejected() {
+    let mut bytes = compile(&mut Runtime::new(), "let x = 1;");
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    assert_eq!(
+        BytecodeFile::from_bytes(&bytes),
+        Err(BytecodeError::ChecksumMismatch)
+    );
+    assert_eq!(
+        BytecodeFile::from_bytes(&bytes[..8]),
+        Err(BytecodeError::UnexpectedEnd)
+    );
+}
+
+#[test]
+fn files_using_missing_natives_are_rejected_before_running() {
+    let mut compiler = Runtime::new();
+    compiler.register_function("double", double);
+    let bytes = compile(&mut compiler, "let before = 1; let doubled = double(2);");
+    let bytecode = BytecodeFile::from_bytes(&bytes).unwrap();
+
+    let mut runtime = Runtime::new();
+    let error = runtime.execute_bytecode(&bytecode).unwrap_err();
+    assert!(
+        matches!(
+            &error,
+            RuntimeError::Bytecode(BytecodeError::Unresolved { kind: "function", name })
+                if name == "double"
+        ),
+        "{error:?}"
+    );
+    assert_eq!(runtime.global("before"), None);
+
+    runtime.register_function("double", double);
+    runtime.execute_bytecode(&bytecode).unwrap();
+    assert_eq!(runtime.global("doubled"), Some(&Value::I64(4)));
+}
+
+/// Compiles `let x = 1;`, replaces the code of the top level chunk and encodes the result.
+fn crafted(code: Vec<Instruction>, locals: usize) -> Vec<u8> {
+    let mut bytecode = Runtime::new()
+        .compile_named("script.rscript", "let x = 1;")
+        .unwrap();
+    let span = bytecode.chunk.spans[0];
+    bytecode.chunk.spans = vec![span; code.len()];
+    bytecode.chunk.code = code;
+    bytecode.chunk.locals = locals;
+    bytecode.to_bytes()
+}
+
+fn assert_invalid(bytes: &[u8], reason: &str) {
+    match BytecodeFile::from_bytes(bytes) {
+        Err(BytecodeError::Invalid { reason: found }) => {
+            assert!(found.contains(reason), "{found}")
+        }
+        other => panic!("expected an invalid file, got {other:?}"),
+    }
+}
+
+#[test]
+fn crafted_files_cannot_underflow_the_stack() {
+    assert_invalid(
+        &crafted(vec![Instruction::Add, Instruction::Return], 0),
+        "pops 2 values from a stack of 0",
+    );
+    assert_invalid(
+        &crafted(
+            vec![
+                Instruction::Unit,
+                Instruction::Call {
+                    name: 0,
+                    arguments: 5,
+                },
+                Instruction::Return,
+            ],
+            0,
+        ),
+        "pops 5 values from a stack of 1",
+    );
+    assert_invalid(
+        &crafted(
+            vec![
+                Instruction::Unit,
+                Instruction::JumpIfFalse(3),
+                Instruction::Unit,
+                Instruction::Return,
+            ],
+            0,
+        ),
+        "values on the stack",
+    );
+    assert_invalid(
+        &crafted(vec![Instruction::Unit, Instruction::Pop], 0),
+        "runs past the end",
+    );
+}
+
+#[test]
+fn crafted_files_cannot_allocate_unbounded_locals() {
+    assert_invalid(
+        &crafted(
+            vec![Instruction::Unit, Instruction::Return],
+            u32::MAX as usize,
+        ),
+        "local slots exceed the maximum",
+    );
+    let bytes = crafted(vec![Instruction::Unit, Instruction::Return], MAX_LOCALS);
+    let mut runtime = Runtime::new();
+    runtime
+        .execute_bytecode(&BytecodeFile::from_bytes(&bytes).unwrap())
+        .unwrap();
+}
+
+#[test]
+fn crafted_files_cannot_nest_functions_without_bound() {
+    let mut bytecode = Runtime::new()
+        .compile_named("script.rscript", "fn f() {} f();")
+        .unwrap();
+    let mut function = (*bytecode.chunk.functions[0]).clone();
+    for _ in 0..MAX_NESTING {
+        let mut nested = function.clone();
+        nested.chunk.functions = vec![Rc::new(function)];
+        function = nested;
+    }
+    bytecode.chunk.functions = vec![Rc::new(function)];
+    assert_invalid(&bytecode.to_bytes(), "nested deeper than");
+}
@@ -0,0 +1,153 @@
+//! Runs the `rscript` binary and checks its output and exit codes.
+
+use std::{
+    env, fs,
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+/// Runs `rscript` with `arguments`, feeding `input` to its standard input.
+fn rscript(arguments: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rscript"))
+        .args(arguments)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn main_sets_the_exit_code() {
+    for (code, expected) in [(0, 0), (42, 42), (255, 255)] {
+        let output = rscript(
+            &["run", "-"],
+            &format!("fn main() -> i64 {{ return {code}; }}"),
+        );
+        assert_eq!(output.status.code(), Some(expected));
+    }
+}
+
+#[test]
+fn exit_codes_outside_of_a_byte_are_errors() {
+    for code in ["300", "-1"] {
+        let output = rscript(
+            &["run", "-"],
+            &format!("fn main() -> i64 {{ return {code}; }}"),
+        );
+        assert_eq!(output.status.code(), Some(1), "for {code}");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains(&format!(
+                "`main` returned {code}, which is not a valid exit code"
+            )),
+            "{stderr}"
+        );
+    }
+}
+
+#[test]
+fn tests_do_not_see_what_other_tests_changed() {
+    // The nested `value` only shadows the global one inside `shadows`
+    let source = "fn value() -> i64 { return 1; }\n\
+                  #[test]\nfn shadows() { fn value() -> i64 { return 2; } assert_eq(value(), 2); }\n\
+                  #[test]\nfn sees_the_global() { assert_eq(value(), 1); }\n";
+    let path = env::temp_dir().join(format!("rscript-isolation-{}.rscript", std::process::id()));
+    fs::write(&path, source).unwrap();
+
+    for engine in ["tree", "vm"] {
+        for filter in [None, Some("sees")] {
+            let mut arguments = vec!["test", path.to_str().unwrap(), "--engine", engine];
+            if let Some(filter) = filter {
+                arguments.extend(["--filter", filter]);
+            }
+            let output = rscript(&arguments, "");
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(output.status.success(), "on {engine}: {stdout}");
+            assert!(stdout.contains("test sees_the_global ... ok"), "{stdout}");
+        }
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn runaway_recursion_is_an_error() {
+    for engine in ["tree", "vm"] {
+        let output = rscript(
+            &["run", "-", "--engine", engine],
+            "fn forever(n: i64) -> i64 { return forever(n + 1) + 1; }\nfn main() { forever(0); }",
+        );
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(1), "on {engine}: {stderr}");
+        assert!(
+            stderr.contains("calling `forever` exceeds the maximum call depth of 1000"),
+            "on {engine}: {stderr}"
+        );
+    }
+}
+
+#[test]
+fn deeply_nested_code_is_reported() {
+    let source = format!("let x = {}1{};", "(".repeat(1000), ")".repeat(1000));
+    for command in ["check", "run", "ast"] {
+        let output = rscript(&[command, "-"], &source);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(1), "for {command}: {stderr}");
+        assert!(
+            stderr.contains("code is nested more than 64 levels deep"),
+            "for {command}: {stderr}"
+        );
+    }
+}
+
+#[test]
+fn closed_output_pipes_end_commands_quietly() {
+    for format in ["tree", "json", "sexpr"] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rscript"))
+            .args(["ast", "-", "--format", format])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // The reader is gone before anything was written
+        drop(child.stdout.take());
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all("let x = 1 + 2;\n".repeat(1000).as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "for {format}: {stderr}");
+        assert!(stderr.is_empty(), "for {format}: {stderr}");
+    }
+}
+
+#[test]
+fn closed_output_pipes_end_test_runs_quietly() {
+    let source = "#[test]\nfn passes() { assert(true); }\n";
+    let path = env::temp_dir().join(format!("rscript-pipe-{}.rscript", std::process::id()));
+    fs::write(&path, source).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rscript"))
+        .args(["test", path.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    drop(child.stdout.take());
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(stderr.is_empty(), "{stderr}");
+    fs::remove_file(path).unwrap();
+}
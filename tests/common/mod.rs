@@ -0,0 +1,127 @@
+//! Helpers shared by the integration tests comparing different ways of executing scripts.
+
+// Every test crate uses a part of the helpers only
+#![allow(dead_code)]
+
+use std::{
+    cell::RefCell,
+    fs, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use rscript::{
+    Engine, Runtime, Value,
+    core::diagnostic::Diagnostic,
+    runtime::{NativeType, Shared, TypeBuilder, io::Io},
+};
+
+pub struct Counter {
+    pub value: i64,
+}
+
+impl NativeType for Counter {
+    const NAME: &'static str = "Counter";
+
+    fn register(builder: &mut TypeBuilder<Self>) {
+        builder
+            .field("value", |counter: &Counter| counter.value)
+            .method("increment", |counter: &mut Counter, amount: i64| {
+                counter.value += amount;
+                counter.value
+            });
+    }
+}
+
+/// Collects everything a script prints, to stdout and stderr alike, and gives it no input.
+#[derive(Clone, Default)]
+pub struct Capture(pub Rc<RefCell<String>>);
+
+impl Io for Capture {
+    fn write_stdout(&mut self, text: &str) -> io::Result<()> {
+        self.0.borrow_mut().push_str(text);
+        Ok(())
+    }
+
+    fn write_stderr(&mut self, text: &str) -> io::Result<()> {
+        self.write_stdout(text)
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        Ok(String::new())
+    }
+}
+
+/// Everything observable about running a script.
+#[derive(Debug, PartialEq)]
+pub struct Outcome {
+    /// What the script printed.
+    pub output: String,
+    pub globals: Vec<(String, Value)>,
+    pub result: Result<Option<Value>, Vec<Diagnostic>>,
+}
+
+/// A runtime with the natives, types and globals the scripts use.
+pub fn runtime(engine: Engine) -> Runtime {
+    let mut runtime = Runtime::with_engine(engine);
+    runtime.register_function("clamp", |value: i64, min: i64, max: i64| {
+        value.clamp(min, max)
+    });
+    runtime.register_function("checked_divide", |a: i64, b: i64| {
+        a.checked_div(b).ok_or("division by zero")
+    });
+    runtime.register_type::<Counter>();
+    runtime.set_global("counter", Shared::new(Counter { value: 1 }).into());
+    runtime
+}
+
+/// Executes a script and its `main` function, if any, in `runtime`.
+pub fn run(mut runtime: Runtime, name: &str, source: &str) -> Outcome {
+    let capture = Capture::default();
+    runtime.set_io(capture.clone());
+    let result = runtime
+        .execute_named(name, source)
+        .and_then(|()| runtime.run_main())
+        .map_err(|error| error.diagnostics());
+
+    // Only the globals of the script, not the constants of the standard library
+    let standard = Runtime::new();
+    let mut globals: Vec<_> = runtime
+        .globals()
+        .filter(|(name, value)| {
+            !matches!(value, Value::Object(_)) && standard.global(name).is_none()
+        })
+        .map(|(name, value)| (name.to_string(), value.clone()))
+        .collect();
+    globals.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let output = capture.0.take();
+    Outcome {
+        output,
+        globals,
+        result,
+    }
+}
+
+/// The scripts in `tests/scripts` and the examples at the root of the repository.
+pub fn scripts() -> Vec<PathBuf> {
+    let mut paths = scripts_in("tests/scripts");
+    paths.extend(["example.rscript", "simple.rscript"].map(|path| Path::new(path).to_path_buf()));
+    paths.sort();
+    paths
+}
+
+/// The `.rscript` files in `directory`, sorted.
+pub fn scripts_in(directory: impl AsRef<Path>) -> Vec<PathBuf> {
+    let directory = directory.as_ref();
+    let mut paths: Vec<_> = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "rscript")
+        })
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no scripts in {}", directory.display());
+    paths
+}
@@ -0,0 +1,184 @@
+//! Error recovery of the parser, the source map and how diagnostics are rendered.
+
+use rscript::{
+    Parser, Runtime, SourceMap, Span,
+    ast::Statement,
+    core::diagnostic::{Diagnostic, DiagnosticRenderer},
+};
+use termcolor::{Ansi, NoColor};
+
+fn render(source_map: &SourceMap, diagnostic: &Diagnostic) -> String {
+    let mut output = NoColor::new(Vec::new());
+    DiagnosticRenderer::new(source_map)
+        .render(&mut output, diagnostic)
+        .unwrap();
+    String::from_utf8(output.into_inner()).unwrap()
+}
+
+#[test]
+fn the_parser_reports_every_error_and_keeps_the_valid_statements() {
+    let source = "let = 1;\nlet first = 2;\nfn f( { return 1; }\nlet x = 1 +;\nlet second = 3;";
+    let result = Parser::new(source).parse();
+    assert_eq!(result.errors.len(), 3, "{:?}", result.errors);
+
+    let declared: Vec<_> = result
+        .program
+        .statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::VariableDeclaration(declaration) => {
+                Some(declaration.identifier.name.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(declared, ["first", "second"]);
+    let errors = result
+        .program
+        .statements
+        .iter()
+        .filter(|statement| matches!(statement, Statement::Error(_)))
+        .count();
+    assert_eq!(errors, 3);
+
+    // The runtime reports all of them at once
+    let diagnostics = Runtime::new().execute(source).unwrap_err().diagnostics();
+    assert_eq!(diagnostics.len(), 3);
+}
+
+#[test]
+fn the_parser_does_not_panic_on_malformed_input() {
+    let sources = [
+        "",
+        "}",
+        "{",
+        "(",
+        ")",
+        ";;;",
+        "fn",
+        "fn (",
+        "fn f(x: ) -> {",
+        "struct",
+        "struct S {",
+        "let x = if { } else",
+        "#[test",
+        "use ::;",
+        "pub pub fn",
+        "1 +",
+        "let x = 'ab';",
+        "let s = \"open",
+        "a.b.",
+        "f(1,",
+        "-",
+        "!",
+        "return",
+        "let x: = 1;",
+    ];
+    for source in sources {
+        let result = Parser::new(source).parse();
+        if !source.is_empty() {
+            assert!(
+                !result.errors.is_empty(),
+                "`{source}` parsed without errors"
+            );
+        }
+        let _ = Parser::new(source).parse_single_expression();
+    }
+}
+
+#[test]
+fn snippets_show_labels_notes_and_help() {
+    let mut source_map = SourceMap::new();
+    let source = "let total = price +\n    tax;\nlet x = 1;";
+    let file = source_map.add_file("shop.rscript", source);
+    let diagnostic = Diagnostic::error("cannot add `String` to `i64`")
+        .with_primary_label(Span::new(file, 24..27), "this is a `String`")
+        .with_secondary_label(Span::new(file, 12..17), "this is an `i64`")
+        .with_note("both operands must have the same type")
+        .with_help("convert `tax` with `parse_int`");
+    assert_eq!(
+        render(&source_map, &diagnostic),
+        "\
+error: cannot add `String` to `i64`
+ --> shop.rscript:2:5
+  |
+1 | let total = price +
+  |             ----- this is an `i64`
+2 |     tax;
+  |     ^^^ this is a `String`
+  |
+  = note: both operands must have the same type
+  = help: convert `tax` with `parse_int`
+
+"
+    );
+}
+
+#[test]
+fn long_labels_are_elided_and_tabs_expanded() {
+    let mut source_map = SourceMap::new();
+    let source = "fn f() {\n\ta;\n\tb;\n\tc;\n\td;\n}";
+    let file = source_map.add_file("tabs.rscript", source);
+    let diagnostic = Diagnostic::warning("unused function")
+        .with_primary_label(Span::new(file, 0..source.len()), "never called");
+    let rendered = render(&source_map, &diagnostic);
+    assert!(
+        rendered.starts_with("warning: unused function\n"),
+        "{rendered}"
+    );
+    assert!(rendered.contains("..."), "{rendered}");
+    assert!(rendered.contains("6 | }\n  | ^ never called"), "{rendered}");
+    assert!(!rendered.contains('\t'), "{rendered}");
+}
+
+#[test]
+fn colors_are_only_written_when_requested() {
+    let mut source_map = SourceMap::new();
+    let file = source_map.add_file("a.rscript", "let x = y;");
+    let diagnostic = Diagnostic::error("cannot find value `y`")
+        .with_primary_label(Span::new(file, 8..9), "not found");
+
+    let mut colored = Ansi::new(Vec::new());
+    DiagnosticRenderer::new(&source_map)
+        .render(&mut colored, &diagnostic)
+        .unwrap();
+    let colored = String::from_utf8(colored.into_inner()).unwrap();
+    assert!(colored.contains("\x1b["), "{colored:?}");
+    assert!(!render(&source_map, &diagnostic).contains('\x1b'));
+}
+
+#[test]
+fn the_source_map_converts_offsets_of_every_file() {
+    let mut source_map = SourceMap::new();
+    let first = source_map.add_file("first.rscript", "let a = 1;\r\nlet b = 2;");
+    let first_line = "let ü = \"grüße\";\n";
+    let second = source_map.add_file("second.rscript", format!("{first_line}let c = 3;"));
+    assert_ne!(first, second);
+    assert_eq!(source_map.len(), 2);
+
+    let file = source_map.get(first).unwrap();
+    assert_eq!(file.line_count(), 2);
+    assert_eq!(file.line_text(1), "let a = 1;");
+    assert_eq!(file.line_column(16), (2, 5));
+
+    // Columns count characters, not bytes
+    let file = source_map.get(second).unwrap();
+    assert_eq!(file.line_column("let ü = \"grüße\"".len()), (1, 16));
+    assert_eq!(
+        source_map.location(Span::new(second, first_line.len()..first_line.len() + 3)),
+        "second.rscript:2:1"
+    );
+}
+
+#[test]
+fn errors_point_into_the_script_they_come_from() {
+    let mut runtime = Runtime::new();
+    runtime
+        .execute_named("first.rscript", "let a = 1;")
+        .unwrap();
+    let error = runtime
+        .execute_named("second.rscript", "let b = a;\nlet c = missing;")
+        .unwrap_err();
+    let span = error.diagnostics()[0].primary_span().unwrap();
+    assert_eq!(runtime.source_map().location(span), "second.rscript:2:9");
+}
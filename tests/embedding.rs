@@ -0,0 +1,201 @@
+//! The library API a host application embeds scripts with: globals, calls in both directions and
+//! host objects shared with scripts.
+
+use rscript::{
+    Engine, Runtime, RuntimeError, TypeError, Value,
+    runtime::{BorrowError, Shared},
+};
+
+mod common;
+
+use common::Counter;
+
+fn runtimes() -> [Runtime; 2] {
+    [Engine::TreeWalker, Engine::Vm].map(Runtime::with_engine)
+}
+
+#[test]
+fn hosts_exchange_values_with_scripts() {
+    for mut runtime in runtimes() {
+        runtime.set_global("base", Value::I64(40));
+        runtime
+            .execute("fn add(a: i64, b: i64) -> i64 { return a + b; } let total = add(base, 2);")
+            .unwrap();
+        assert_eq!(runtime.global("total"), Some(&Value::I64(42)));
+        assert_eq!(
+            runtime.evaluate("add(total, 1)").unwrap(),
+            Some(Value::I64(43))
+        );
+        assert_eq!(runtime.evaluate("let unit = 1;").unwrap(), None);
+        assert_eq!(
+            runtime
+                .call("add", vec![Value::I64(1), Value::I64(2)])
+                .unwrap(),
+            Value::I64(3)
+        );
+    }
+}
+
+#[test]
+fn calls_from_the_host_are_checked() {
+    for mut runtime in runtimes() {
+        runtime
+            .execute("fn add(a: i64, b: i64) -> i64 { return a + b; }")
+            .unwrap();
+        assert!(matches!(
+            runtime.call("missing", Vec::new()),
+            Err(RuntimeError::UndefinedFunction { name }) if name == "missing"
+        ));
+        assert!(matches!(
+            runtime.call("add", vec![Value::I64(1)]),
+            Err(RuntimeError::ArgumentCount {
+                expected: 2,
+                found: 1,
+                ..
+            })
+        ));
+        assert!(matches!(
+            runtime.call("add", vec![Value::I64(1), Value::Bool(true)]),
+            Err(RuntimeError::ArgumentType { .. })
+        ));
+    }
+}
+
+#[test]
+fn failed_scripts_leave_no_declarations_behind() {
+    for mut runtime in runtimes() {
+        let error = runtime
+            .execute("let kept = 1; fn helper() -> i64 { return 1; } let broken = 1 / 0;")
+            .unwrap_err();
+        assert!(matches!(error, RuntimeError::DivisionByZero { .. }));
+        assert_eq!(runtime.global("kept"), None);
+        assert!(runtime.evaluate("helper()").is_err());
+    }
+}
+
+#[test]
+fn native_functions_are_type_checked_like_script_functions() {
+    for mut runtime in runtimes() {
+        runtime.register_function("repeat", |text: String, times: i64| {
+            text.repeat(times as usize)
+        });
+        runtime.register_function("checked_sqrt", |value: f64| {
+            if value < 0.0 {
+                Err(format!("{value} is negative"))
+            } else {
+                Ok(value.sqrt())
+            }
+        });
+        assert_eq!(
+            runtime.evaluate("repeat(\"ab\", 2)").unwrap(),
+            Some(Value::String("abab".to_string()))
+        );
+
+        let error = runtime.execute("let x = repeat(2, \"ab\");").unwrap_err();
+        assert!(
+            matches!(
+                error,
+                RuntimeError::TypeError(TypeError::MismatchedTypes { .. })
+            ),
+            "{error:?}"
+        );
+        let error = runtime.execute("let x = checked_sqrt(-4.0);").unwrap_err();
+        assert_eq!(
+            error.diagnostics()[0].message,
+            "`checked_sqrt` failed: -4 is negative"
+        );
+    }
+}
+
+#[test]
+fn conversion_errors_point_at_the_call() {
+    for mut runtime in runtimes() {
+        runtime.register_function("describe", |value: i64| value.to_string());
+        runtime
+            .execute_named(
+                "caller.rscript",
+                "fn caller() -> String {\n    return describe(7);\n}",
+            )
+            .unwrap();
+
+        // The host replaces the function after `caller` was checked against the old signature
+        runtime.register_function("describe", |value: bool| value.to_string());
+        let error = runtime.call("caller", Vec::new()).unwrap_err();
+        let RuntimeError::NativeArgument {
+            name, index, span, ..
+        } = &error
+        else {
+            panic!("expected a conversion error, got {error:?}");
+        };
+        assert_eq!((name.as_str(), *index), ("describe", 0));
+        assert_eq!(runtime.source_map().location(*span), "caller.rscript:2:12");
+        assert_eq!(
+            error.to_string(),
+            "invalid argument 1 to `describe`: expected `bool`, found `i64`"
+        );
+    }
+}
+
+#[test]
+fn objects_borrowed_by_the_host_cannot_be_changed_by_scripts() {
+    for mut runtime in runtimes() {
+        runtime.register_type::<Counter>();
+        let counter = Shared::new(Counter { value: 1 });
+        runtime.set_global("counter", counter.clone().into());
+
+        {
+            let _reading = counter.borrow().unwrap();
+            let error = runtime.execute("counter.increment(1);").unwrap_err();
+            assert!(
+                matches!(
+                    error,
+                    RuntimeError::AlreadyBorrowed {
+                        error: BorrowError {
+                            type_name: "Counter"
+                        },
+                        ..
+                    }
+                ),
+                "{error:?}"
+            );
+            // Reading while the host reads too is fine
+            assert_eq!(
+                runtime.evaluate("counter.value").unwrap(),
+                Some(Value::I64(1))
+            );
+        }
+        {
+            let _writing = counter.borrow_mut().unwrap();
+            let error = runtime.evaluate("counter.value").unwrap_err();
+            assert!(
+                matches!(error, RuntimeError::AlreadyBorrowed { .. }),
+                "{error:?}"
+            );
+            assert_eq!(
+                error.diagnostics()[0].message,
+                "`Counter` is already borrowed"
+            );
+        }
+
+        assert_eq!(
+            runtime.evaluate("counter.increment(1)").unwrap(),
+            Some(Value::I64(2))
+        );
+        assert_eq!(counter.borrow().unwrap().value, 2);
+    }
+}
+
+#[test]
+fn scripts_see_what_the_host_changed_in_shared_objects() {
+    for mut runtime in runtimes() {
+        runtime.register_type::<Counter>();
+        let counter = Shared::new(Counter { value: 1 });
+        runtime.set_global("counter", counter.clone().into());
+        counter.borrow_mut().unwrap().value = 10;
+        assert_eq!(
+            runtime.evaluate("counter.increment(5)").unwrap(),
+            Some(Value::I64(15))
+        );
+        assert_eq!(counter.borrow().unwrap().value, 15);
+    }
+}
@@ -0,0 +1,131 @@
+//! Runs every script through the tree-walking interpreter and the bytecode VM and checks that
+//! both engines produce the same globals, `main` results and errors.
+
+use std::fs;
+
+use rscript::{Engine, Runtime, RuntimeError, TypeError, Value};
+
+mod common;
+
+use common::{run, runtime, scripts};
+
+fn assert_same_outcome(name: &str, source: &str) {
+    let tree = run(runtime(Engine::TreeWalker), name, source);
+    let vm = run(runtime(Engine::Vm), name, source);
+    assert_eq!(tree, vm, "engines disagree on `{name}`");
+}
+
+#[test]
+fn scripts_behave_the_same_on_both_engines() {
+    for path in scripts() {
+        let source = fs::read_to_string(&path).unwrap();
+        assert_same_outcome(&path.display().to_string(), &source);
+    }
+}
+
+#[test]
+fn expressions_evaluate_the_same_on_both_engines() {
+    let expressions = [
+        "1 + 2 * 3",
+        "-128i8",
+        "{ let x = 2; x * x }",
+        "if 1 < 2 { \"yes\" } else { \"no\" }",
+        "false || !false && true",
+        "clamp(-1, 0, 5)",
+        "counter.increment(2) + counter.value",
+        "255u8 + 1u8",
+        "1 % 0",
+        "checked_divide(1, 0)",
+    ];
+    for expression in expressions {
+        let evaluate = |engine| {
+            let mut runtime = runtime(engine);
+            runtime
+                .evaluate(expression)
+                .map_err(|error| error.diagnostics())
+        };
+        assert_eq!(
+            evaluate(Engine::TreeWalker),
+            evaluate(Engine::Vm),
+            "engines disagree on `{expression}`"
+        );
+    }
+}
+
+#[test]
+fn declarations_persist_across_engines() {
+    let mut runtime = Runtime::with_engine(Engine::Vm);
+    runtime
+        .execute("fn square(x: i64) -> i64 { return x * x; } let base = 3;")
+        .unwrap();
+    runtime.set_engine(Engine::TreeWalker);
+    runtime.execute("let squared = square(base);").unwrap();
+    assert_eq!(runtime.global("squared"), Some(&Value::I64(9)));
+
+    runtime.set_engine(Engine::Vm);
+    assert_eq!(
+        runtime.call("square", vec![Value::I64(4)]).unwrap(),
+        Value::I64(16)
+    );
+}
+
+#[test]
+fn failed_scripts_are_rolled_back_on_the_vm() {
+    let mut runtime = Runtime::with_engine(Engine::Vm);
+    runtime.execute("let kept = 1;").unwrap();
+    assert!(
+        runtime
+            .execute("let dropped = 2; let broken = 1 / 0;")
+            .is_err()
+    );
+    assert_eq!(runtime.global("kept"), Some(&Value::I64(1)));
+    assert_eq!(runtime.global("dropped"), None);
+}
+
+#[test]
+fn nested_functions_are_typed_in_their_own_scope() {
+    let source = "fn a() -> i64 { fn helper() -> i64 { return 1; } return helper(); }
+fn b() -> bool { fn helper() -> bool { return false; } return !helper(); }
+let from_a = a();
+let from_b = b();";
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let mut runtime = Runtime::with_engine(engine);
+        runtime.execute(source).unwrap();
+        assert_eq!(runtime.global("from_a"), Some(&Value::I64(1)));
+        assert_eq!(runtime.global("from_b"), Some(&Value::Bool(true)));
+
+        // Neither `helper` is visible outside of its function, whichever ran last
+        let error = runtime
+            .execute("let w = helper(); let x = !w;")
+            .unwrap_err();
+        assert!(
+            matches!(
+                &error,
+                RuntimeError::TypeError(TypeError::UndefinedFunction { name, .. }) if name == "helper"
+            ),
+            "{error:?} on {engine:?}"
+        );
+    }
+}
+
+#[test]
+fn deep_recursion_fails_instead_of_overflowing_the_stack() {
+    let source = "fn down(n: i64) -> i64 { if n == 0 { return 0; } return down(n - 1) + 1; }";
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let mut runtime = Runtime::with_engine(engine);
+        runtime.set_max_call_depth(50);
+        runtime.execute(source).unwrap();
+        assert_eq!(runtime.evaluate("down(49)").unwrap(), Some(Value::I64(49)));
+
+        let error = runtime.execute("let x = down(1000000);").unwrap_err();
+        assert!(
+            matches!(
+                &error,
+                RuntimeError::CallDepthExceeded { name, limit: 50, .. } if name == "down"
+            ),
+            "{error:?} on {engine:?}"
+        );
+        // The failed call leaves no frames behind
+        assert_eq!(runtime.evaluate("down(49)").unwrap(), Some(Value::I64(49)));
+    }
+}
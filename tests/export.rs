@@ -0,0 +1,66 @@
+//! Exports parsed and checked programs as JSON and S-expressions.
+
+use rscript::{
+    Parser, TypeChecker,
+    ast::Program,
+    parser::sexpr::{expression_to_sexpr, program_to_sexpr},
+};
+use serde_json::json;
+
+fn parse(source: &str) -> Program {
+    Parser::new(source).parse().into_result().unwrap()
+}
+
+#[test]
+fn sexpr_shows_the_structure_of_programs() {
+    let program = parse(
+        "pub struct Point { x: i64, y: i64 }\nstruct Pair(u8, u8);\nfn f(p: Point) -> i64 {\n    let d = { let a = -p.x; a * 2 };\n    return if d > 0 { d } else { p.len(\"y\", 'c') };\n}",
+    );
+    assert_eq!(
+        program_to_sexpr(&program),
+        "(program
+  (pub struct Point (x i64) (y i64))
+  (struct Pair (u8 u8))
+  (fn f ((p Point)) i64
+    (let d (block
+      (let a (- (field p x)))
+      (* a 2)))
+    (return (if (> d 0) (block
+      d) (block
+      (method p len \"y\" 'c'))))))
+"
+    );
+}
+
+#[test]
+fn sexpr_shows_inferred_types_after_checking() {
+    let mut program = parse("let x: u8 = 1 + 2;");
+    TypeChecker::default().check_program(&mut program).unwrap();
+    let rscript::ast::Statement::VariableDeclaration(declaration) = &program.statements[0] else {
+        panic!("expected a variable declaration");
+    };
+    assert_eq!(
+        expression_to_sexpr(&declaration.initializer),
+        "(the u8 (+ (the u8 1) (the u8 2)))"
+    );
+}
+
+#[test]
+fn json_tags_nodes_with_their_kind_and_keeps_spans() {
+    let mut program = parse("let x = 2u8;");
+    TypeChecker::default().check_program(&mut program).unwrap();
+    let json = serde_json::to_value(&program).unwrap();
+
+    let declaration = &json["statements"][0];
+    assert_eq!(declaration["kind"], "VariableDeclaration");
+    assert_eq!(declaration["identifier"]["name"], "x");
+    assert_eq!(
+        declaration["span"],
+        json!({ "file": 0, "start": 0, "end": 12 })
+    );
+    let initializer = &declaration["initializer"];
+    assert_eq!(initializer["kind"], "IntegerLiteral");
+    assert_eq!(initializer["value"], 2);
+    assert_eq!(initializer["suffix"], "u8");
+    assert_eq!(initializer["inferred_type"]["name"], "u8");
+}
@@ -0,0 +1,207 @@
+//! Golden-file tests: every script in a directory of `tests/golden` is run through one stage of
+//! the pipeline and the output is compared to the `.expected` file next to the script.
+//!
+//! - `lexer`: the tokens with their positions,
+//! - `parser`: the program as S-expressions, followed by the syntax errors,
+//! - `format`: the tree printed by the [`Format`] implementation of the AST,
+//! - `fmt`: the script as written by the formatter, which must not change it again,
+//! - `runtime`: what the script printed, the result of `main` and the globals, or the errors, on
+//!   both engines.
+//!
+//! Set `RSCRIPT_BLESS=1` to write the current output to the `.expected` files instead, e.g.
+//! after adding a script or changing the output on purpose, and review the diff.
+
+use std::{env, fs, path::Path};
+
+use logos::Logos;
+use rscript::{
+    Engine, Parser, SourceMap, Span,
+    core::{
+        diagnostic::{Diagnostic, DiagnosticRenderer, ToDiagnostic},
+        format::Format,
+    },
+    formatter::{FormatterConfig, format_source},
+    parser::{lexer::Token, sexpr::program_to_sexpr},
+};
+use termcolor::NoColor;
+
+mod common;
+
+use common::{Outcome, run, runtime, scripts_in};
+
+/// The environment variable that turns comparisons into updates of the expected output.
+const BLESS: &str = "RSCRIPT_BLESS";
+
+/// Renders the output of every script of a stage and compares it to the expected output,
+/// reporting every mismatch at once.
+fn check_stage(stage: &str, render: impl Fn(&str, &str) -> String) {
+    let bless = env::var_os(BLESS).is_some_and(|value| value != "0");
+    let mut failures = Vec::new();
+    for path in scripts_in(Path::new("tests/golden").join(stage)) {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let source = fs::read_to_string(&path).unwrap();
+        let actual = render(&name, &source);
+        let expected_path = path.with_extension("expected");
+
+        if bless {
+            if fs::read_to_string(&expected_path).ok().as_deref() != Some(actual.as_str()) {
+                fs::write(&expected_path, &actual).unwrap();
+                eprintln!("blessed {}", expected_path.display());
+            }
+            continue;
+        }
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{} differs from the expected output:\n{}",
+                path.display(),
+                diff(&expected, &actual)
+            )),
+            Err(_) => failures.push(format!("{} is missing", expected_path.display())),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{}\n\nrun with `{BLESS}=1` to accept the new output",
+        failures.join("\n\n")
+    );
+}
+
+/// The lines that differ between two outputs, marked with `-` for expected and `+` for actual.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<_> = expected.lines().collect();
+    let actual: Vec<_> = actual.lines().collect();
+    let mut lines = Vec::new();
+    for index in 0..expected.len().max(actual.len()) {
+        let (old, new) = (expected.get(index), actual.get(index));
+        if old != new {
+            if let Some(old) = old {
+                lines.push(format!("{:>4} - {old}", index + 1));
+            }
+            if let Some(new) = new {
+                lines.push(format!("{:>4} + {new}", index + 1));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+fn render_diagnostics(source_map: &SourceMap, diagnostics: &[Diagnostic]) -> String {
+    let renderer = DiagnosticRenderer::new(source_map);
+    let mut output = NoColor::new(Vec::new());
+    for diagnostic in diagnostics {
+        renderer.render(&mut output, diagnostic).unwrap();
+    }
+    String::from_utf8(output.into_inner()).unwrap()
+}
+
+// -- Stages --
+
+#[test]
+fn lexer() {
+    check_stage("lexer", |name, source| {
+        let mut source_map = SourceMap::new();
+        let file = source_map.add_file(name, source);
+        let source_file = source_map.get(file).unwrap();
+
+        let mut output = String::new();
+        let mut diagnostics = Vec::new();
+        let mut lexer = Token::lexer(source);
+        while let Some(token) = lexer.next() {
+            let span = lexer.span();
+            let (line, column) = source_file.line_column(span.start);
+            match token {
+                Ok(token) => output.push_str(&format!(
+                    "{line}:{column}\t{token:?}\t{:?}\n",
+                    lexer.slice()
+                )),
+                Err(error) => {
+                    output.push_str(&format!("{line}:{column}\tError\t{:?}\n", lexer.slice()));
+                    diagnostics.push(
+                        Diagnostic::error(error.to_string())
+                            .with_primary_label(Span::new(file, span), "invalid token"),
+                    );
+                }
+            }
+        }
+        output + &render_diagnostics(&source_map, &diagnostics)
+    });
+}
+
+#[test]
+fn parser() {
+    check_stage("parser", |name, source| {
+        let mut source_map = SourceMap::new();
+        let file = source_map.add_file(name, source);
+        let result = Parser::with_file(source, file).parse();
+        let diagnostics: Vec<_> = result
+            .errors
+            .iter()
+            .map(ToDiagnostic::to_diagnostic)
+            .collect();
+        program_to_sexpr(&result.program) + &render_diagnostics(&source_map, &diagnostics)
+    });
+}
+
+#[test]
+fn format() {
+    check_stage("format", |name, source| {
+        let mut source_map = SourceMap::new();
+        let file = source_map.add_file(name, source);
+        let program = Parser::with_file(source, file)
+            .parse()
+            .into_result()
+            .unwrap_or_else(|errors| panic!("`{name}` does not parse: {errors:?}"));
+        let mut output = NoColor::new(Vec::new());
+        program.format(&mut output, 4, 0).unwrap();
+        String::from_utf8(output.into_inner()).unwrap()
+    });
+}
+
+#[test]
+fn fmt() {
+    check_stage("fmt", |name, source| {
+        let config = FormatterConfig::default();
+        let formatted = format_source(source, &config)
+            .unwrap_or_else(|errors| panic!("`{name}` does not parse: {errors:?}"));
+        let again = format_source(&formatted, &config).unwrap();
+        assert_eq!(again, formatted, "formatting `{name}` again changes it");
+        formatted
+    });
+}
+
+#[test]
+fn runtime_stage() {
+    check_stage("runtime", |name, source| {
+        let tree = run(runtime(Engine::TreeWalker), name, source);
+        let vm = run(runtime(Engine::Vm), name, source);
+        assert_eq!(tree, vm, "engines disagree on `{name}`");
+
+        let Outcome {
+            output: printed,
+            globals,
+            result,
+        } = tree;
+        let mut output = String::new();
+        if !printed.is_empty() {
+            output.push_str("output:\n");
+            for line in printed.lines() {
+                output.push_str(&format!("| {line}\n"));
+            }
+        }
+        match result {
+            Ok(Some(value)) => output.push_str(&format!("main: {value:?}\n")),
+            Ok(None) => output.push_str("main: none\n"),
+            Err(diagnostics) => {
+                // The script is the only file of the runtime, so its spans point into this map too
+                let mut source_map = SourceMap::new();
+                source_map.add_file(name, source);
+                output.push_str(&render_diagnostics(&source_map, &diagnostics));
+            }
+        }
+        for (name, value) in globals {
+            output.push_str(&format!("{name} = {value:?}\n"));
+        }
+        output
+    });
+}
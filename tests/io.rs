@@ -0,0 +1,111 @@
+//! The I/O builtins, routed through a host-provided [`Io`].
+
+use std::{cell::RefCell, collections::HashMap, io, rc::Rc};
+
+use rscript::{Engine, Runtime, Value, runtime::io::Io};
+
+/// Lines to read and an in-memory file system, shared with the test.
+#[derive(Default)]
+struct Memory {
+    input: Vec<String>,
+    files: Rc<RefCell<HashMap<String, String>>>,
+}
+
+impl Io for Memory {
+    fn read_line(&mut self) -> io::Result<String> {
+        Ok(if self.input.is_empty() {
+            String::new()
+        } else {
+            self.input.remove(0)
+        })
+    }
+
+    fn read_file(&mut self, path: &str) -> io::Result<String> {
+        self.files
+            .borrow()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not found"))
+    }
+
+    fn write_file(&mut self, path: &str, contents: &str) -> io::Result<()> {
+        self.files
+            .borrow_mut()
+            .insert(path.to_string(), contents.to_string());
+        Ok(())
+    }
+}
+
+fn runtimes() -> [Runtime; 2] {
+    [Engine::TreeWalker, Engine::Vm].map(Runtime::with_engine)
+}
+
+#[test]
+fn lines_are_read_without_their_line_break() {
+    for mut runtime in runtimes() {
+        runtime.set_io(Memory {
+            input: vec!["first\r\n".to_string(), "second".to_string()],
+            ..Memory::default()
+        });
+        runtime
+            .execute("let a = read_line(); let b = read_line(); let c = read_line();")
+            .unwrap();
+        for (name, line) in [("a", "first"), ("b", "second"), ("c", "")] {
+            assert_eq!(runtime.global(name), Some(&Value::String(line.to_string())));
+        }
+    }
+}
+
+#[test]
+fn files_go_through_the_host() {
+    for mut runtime in runtimes() {
+        let files = Rc::new(RefCell::new(HashMap::new()));
+        runtime.set_io(Memory {
+            files: Rc::clone(&files),
+            ..Memory::default()
+        });
+        runtime
+            .execute("write_file(\"notes.txt\", format(\"{} notes\", 3)); let notes = read_file(\"notes.txt\");")
+            .unwrap();
+        assert_eq!(files.borrow()["notes.txt"], "3 notes");
+        assert_eq!(
+            runtime.global("notes"),
+            Some(&Value::String("3 notes".to_string()))
+        );
+
+        let diagnostics = runtime
+            .execute("let missing = read_file(\"missing.txt\");")
+            .unwrap_err()
+            .diagnostics();
+        assert_eq!(
+            diagnostics[0].message,
+            "`read_file` failed: could not read `missing.txt`: not found"
+        );
+    }
+}
+
+#[test]
+fn format_strings_are_checked() {
+    let cases = [
+        (
+            "println(\"{} and {}\", 1);",
+            "2 placeholder(s) in the format string but 1 argument(s) were supplied",
+        ),
+        (
+            "print(\"{name}\", 1);",
+            "invalid placeholder `{name}`, only `{}` is supported",
+        ),
+        ("eprintln(\"}\");", "unmatched `}` in format string"),
+        ("let x = format(1);", "mismatched types"),
+        (
+            "let f = \"{}\"; let x = format(f, 1);",
+            "format string must be a string literal",
+        ),
+    ];
+    for (source, message) in cases {
+        for mut runtime in runtimes() {
+            let diagnostics = runtime.execute(source).unwrap_err().diagnostics();
+            assert_eq!(diagnostics[0].message, message, "for `{source}`");
+        }
+    }
+}
@@ -0,0 +1,423 @@
+//! Drives `rscript lsp` with scripted JSON-RPC sessions and checks the responses.
+
+use std::{
+    io::{Read, Write},
+    process::{Command, Stdio},
+};
+
+use serde_json::{Value, json};
+
+const URI: &str = "file:///test.rscript";
+
+/// A session sent to the server all at once, the server handles messages in order.
+#[derive(Default)]
+struct Session {
+    messages: Vec<Value>,
+    next_id: i64,
+    /// The document requests are about.
+    uri: String,
+}
+
+impl Session {
+    /// A session with an initialized server and `text` open as [`URI`].
+    fn with_document(text: &str) -> Self {
+        Session::with_document_at(URI, text)
+    }
+
+    fn with_document_at(uri: &str, text: &str) -> Self {
+        let mut session = Session {
+            uri: uri.to_string(),
+            ..Session::default()
+        };
+        session.request("initialize", json!({ "capabilities": {} }));
+        session.notify("initialized", json!({}));
+        session.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": uri, "languageId": "rscript", "version": 1, "text": text }
+            }),
+        );
+        session
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> i64 {
+        self.next_id += 1;
+        self.messages.push(
+            json!({ "jsonrpc": "2.0", "id": self.next_id, "method": method, "params": params }),
+        );
+        self.next_id
+    }
+
+    /// A request about the position of the first occurrence of `needle` in `text`, plus `shift`.
+    fn request_at(&mut self, method: &str, text: &str, needle: &str, shift: usize) -> i64 {
+        let offset = text.find(needle).expect("needle is in the text") + shift;
+        let line = text[..offset].matches('\n').count();
+        let character = offset - text[..offset].rfind('\n').map_or(0, |index| index + 1);
+        self.request(
+            method,
+            json!({
+                "textDocument": { "uri": self.uri },
+                "position": { "line": line, "character": character },
+                "context": { "includeDeclaration": true },
+            }),
+        )
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.messages
+            .push(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    /// Shuts the server down and returns its messages and whether it exited successfully.
+    fn run(mut self) -> (Vec<Value>, bool) {
+        self.request("shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rscript"))
+            .arg("lsp")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdin = child.stdin.take().unwrap();
+        for message in &self.messages {
+            let body = message.to_string();
+            write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        }
+        drop(stdin);
+
+        let mut output = String::new();
+        child
+            .stdout
+            .take()
+            .unwrap()
+            .read_to_string(&mut output)
+            .unwrap();
+        let status = child.wait().unwrap();
+
+        let mut messages = Vec::new();
+        let mut rest = output.as_str();
+        while let Some((header, body)) = rest.split_once("\r\n\r\n") {
+            let length: usize = header
+                .strip_prefix("Content-Length: ")
+                .unwrap()
+                .parse()
+                .unwrap();
+            messages.push(serde_json::from_str(&body[..length]).unwrap());
+            rest = &body[length..];
+        }
+        (messages, status.success())
+    }
+}
+
+fn response(messages: &[Value], id: i64) -> &Value {
+    messages
+        .iter()
+        .find(|message| message["id"] == id)
+        .unwrap_or_else(|| panic!("no response to request {id}"))
+}
+
+fn result(messages: &[Value], id: i64) -> &Value {
+    let response = response(messages, id);
+    assert!(response.get("error").is_none(), "{response}");
+    &response["result"]
+}
+
+/// The diagnostics of every `publishDiagnostics` notification, in order.
+fn published_diagnostics(messages: &[Value]) -> Vec<&Vec<Value>> {
+    messages
+        .iter()
+        .filter(|message| message["method"] == "textDocument/publishDiagnostics")
+        .map(|message| message["params"]["diagnostics"].as_array().unwrap())
+        .collect()
+}
+
+fn range(start: (u64, u64), end: (u64, u64)) -> Value {
+    json!({
+        "start": { "line": start.0, "character": start.1 },
+        "end": { "line": end.0, "character": end.1 },
+    })
+}
+
+#[test]
+fn diagnostics_follow_the_document() {
+    let mut session = Session::with_document("let x: u8 = \"text\";");
+    let change = |text: &str| json!({ "textDocument": { "uri": URI, "version": 2 }, "contentChanges": [{ "text": text }] });
+    session.notify(
+        "textDocument/didChange",
+        change("let x = {\n    let unused = 1;\n    2\n};"),
+    );
+    session.notify("textDocument/didChange", change("let x = 1;"));
+    session.notify(
+        "textDocument/didClose",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    let (messages, success) = session.run();
+    assert!(success);
+
+    let published = published_diagnostics(&messages);
+    assert_eq!(published.len(), 4);
+
+    let [error] = published[0].as_slice() else {
+        panic!("expected a single diagnostic, found {:?}", published[0]);
+    };
+    assert_eq!(error["severity"], 1);
+    assert_eq!(error["range"], range((0, 12), (0, 18)));
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("mismatched types")
+    );
+    assert_eq!(
+        error["relatedInformation"][0]["location"]["range"],
+        range((0, 7), (0, 9))
+    );
+
+    let [warning] = published[1].as_slice() else {
+        panic!("expected a single diagnostic, found {:?}", published[1]);
+    };
+    assert_eq!(warning["severity"], 2);
+    assert_eq!(warning["range"], range((1, 8), (1, 14)));
+
+    assert!(published[2].is_empty());
+    assert!(published[3].is_empty());
+}
+
+#[test]
+fn hover_shows_declarations_and_inferred_types() {
+    let text = "let x = 2u8;\nfn add(a: u8, b: u8) -> u8 { return a + b; }\nlet y = add(x, 3);";
+    let mut session = Session::with_document(text);
+    let variable = session.request_at("textDocument/hover", text, "x, 3", 0);
+    let function = session.request_at("textDocument/hover", text, "add(x", 1);
+    let literal = session.request_at("textDocument/hover", text, "3)", 0);
+    let parameter = session.request_at("textDocument/hover", text, "b;", 0);
+    let nothing = session.request_at("textDocument/hover", text, "fn", 0);
+    let (messages, _) = session.run();
+
+    let contents = |id| result(&messages, id)["contents"]["value"].clone();
+    assert_eq!(contents(variable), "```rscript\nlet x: u8\n```");
+    assert_eq!(
+        result(&messages, variable)["range"],
+        range((2, 12), (2, 13))
+    );
+    assert_eq!(
+        contents(function),
+        "```rscript\nfn add(a: u8, b: u8) -> u8\n```"
+    );
+    assert_eq!(contents(literal), "```rscript\nu8\n```");
+    assert_eq!(contents(parameter), "```rscript\nb: u8\n```");
+    assert_eq!(*result(&messages, nothing), Value::Null);
+}
+
+#[test]
+fn definitions_and_references_come_from_name_resolution() {
+    let text = "let x = 1;\nfn f(x: i64) -> i64 { return x + x; }\nlet y = x + f(x);";
+    let mut session = Session::with_document(text);
+    let definition = session.request_at("textDocument/definition", text, "x + f", 0);
+    let global = session.request_at("textDocument/references", text, "x = 1", 0);
+    let parameter = session.request_at("textDocument/references", text, "x: i64", 0);
+    let (messages, _) = session.run();
+
+    assert_eq!(
+        *result(&messages, definition),
+        json!({ "uri": URI, "range": range((0, 4), (0, 5)) })
+    );
+    let ranges = |id| -> Vec<Value> {
+        result(&messages, id)
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|location| location["range"].clone())
+            .collect()
+    };
+    // The declaration comes first, the function body only sees its parameter
+    assert_eq!(
+        ranges(global),
+        vec![
+            range((0, 4), (0, 5)),
+            range((2, 8), (2, 9)),
+            range((2, 14), (2, 15))
+        ]
+    );
+    assert_eq!(
+        ranges(parameter),
+        vec![
+            range((1, 5), (1, 6)),
+            range((1, 29), (1, 30)),
+            range((1, 33), (1, 34))
+        ]
+    );
+}
+
+#[test]
+fn document_symbols_list_items_with_their_children() {
+    let text = "struct Point { x: i64, y: i64 }\nfn outer() -> i64 {\n    fn inner() -> i64 { return 1; }\n    return inner();\n}\nlet origin = 0;";
+    let mut session = Session::with_document(text);
+    let id = session.request(
+        "textDocument/documentSymbol",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    let (messages, _) = session.run();
+
+    let symbols = result(&messages, id).as_array().unwrap();
+    let names: Vec<_> = symbols
+        .iter()
+        .map(|symbol| {
+            (
+                symbol["name"].as_str().unwrap(),
+                symbol["kind"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(names, vec![("Point", 23), ("outer", 12), ("origin", 13)]);
+    assert_eq!(symbols[0]["children"][1]["name"], "y");
+    assert_eq!(symbols[0]["children"][1]["detail"], "i64");
+    assert_eq!(symbols[1]["children"][0]["name"], "inner");
+    assert_eq!(symbols[1]["selectionRange"], range((1, 3), (1, 8)));
+}
+
+#[test]
+fn completion_offers_names_in_scope_and_members() {
+    let text = "struct Point { x: i64, y: i64 }\nfn norm(point: Point) -> i64 {\n    return point.x;\n}\nlet name = \"a\";\nlet size = name.len();\nlet later = 1;";
+    let mut session = Session::with_document(text);
+    let fields = session.request_at("textDocument/completion", text, "x;", 0);
+    let methods = session.request_at("textDocument/completion", text, "len()", 0);
+    let names = session.request_at("textDocument/completion", text, "point.", 2);
+    let (messages, _) = session.run();
+
+    let labels = |id| -> Vec<String> {
+        result(&messages, id)
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["label"].as_str().unwrap().to_string())
+            .collect()
+    };
+    // Scripts cannot access the fields of their structs yet, the type checker rejects `point.x`
+    assert_eq!(labels(fields), Vec::<String>::new());
+    let methods = labels(methods);
+    for expected in ["byte_len", "len", "to_upper"] {
+        assert!(methods.contains(&expected.to_string()), "{methods:?}");
+    }
+    let names = labels(names);
+    for expected in ["Point", "norm", "point", "return", "let"] {
+        assert!(names.contains(&expected.to_string()), "{names:?}");
+    }
+    // Declared after the position
+    assert!(!names.contains(&"later".to_string()), "{names:?}");
+}
+
+#[test]
+fn formatting_replaces_the_whole_document() {
+    let mut session = Session::with_document("fn f()->i64{return 1;}\n");
+    let formatted = session.request(
+        "textDocument/formatting",
+        json!({ "textDocument": { "uri": URI }, "options": { "tabSize": 2, "insertSpaces": true } }),
+    );
+    let (messages, _) = session.run();
+
+    assert_eq!(
+        *result(&messages, formatted),
+        json!([{ "range": range((0, 0), (1, 0)), "newText": "fn f() -> i64 {\n  return 1;\n}\n" }])
+    );
+}
+
+#[test]
+fn oversized_messages_and_tab_sizes_are_rejected() {
+    let mut session = Session::with_document("fn f()->i64{return 1;}\n");
+    // 16 MiB is the largest message the server reads
+    session.request("workspace/symbol", json!({ "query": "x".repeat(16 << 20) }));
+    let wide = session.request(
+        "textDocument/formatting",
+        json!({ "textDocument": { "uri": URI }, "options": { "tabSize": u64::MAX } }),
+    );
+    let formatted = session.request(
+        "textDocument/formatting",
+        json!({ "textDocument": { "uri": URI }, "options": { "tabSize": 16 } }),
+    );
+    let (messages, success) = session.run();
+    assert!(success);
+
+    let oversized = messages
+        .iter()
+        .find(|message| message["id"].is_null() && message.get("error").is_some())
+        .expect("the oversized message is answered");
+    assert_eq!(oversized["error"]["code"], -32600);
+    let error = &response(&messages, wide)["error"];
+    assert_eq!(error["code"], -32602);
+    assert_eq!(
+        error["message"],
+        format!("tab size {} exceeds the maximum of 16", u64::MAX)
+    );
+    assert_eq!(
+        result(&messages, formatted)[0]["newText"],
+        format!("fn f() -> i64 {{\n{}return 1;\n}}\n", " ".repeat(16))
+    );
+}
+
+#[test]
+fn protocol_errors_are_reported() {
+    let mut session = Session::default();
+    let early = session.request("textDocument/hover", json!({}));
+    session.request("initialize", json!({ "capabilities": {} }));
+    let unknown = session.request("textDocument/rename", json!({}));
+    let closed = session.request(
+        "textDocument/documentSymbol",
+        json!({ "textDocument": { "uri": "file:///closed.rscript" } }),
+    );
+    let (messages, success) = session.run();
+    assert!(success);
+
+    let code = |id| response(&messages, id)["error"]["code"].as_i64().unwrap();
+    assert_eq!(code(early), -32002);
+    assert_eq!(code(unknown), -32601);
+    assert_eq!(code(closed), -32602);
+}
+
+#[test]
+fn exiting_without_shutdown_fails() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rscript"))
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    let exit = json!({ "jsonrpc": "2.0", "method": "exit" }).to_string();
+    write!(
+        child.stdin.take().unwrap(),
+        "Content-Length: {}\r\n\r\n{}",
+        exit.len(),
+        exit
+    )
+    .unwrap();
+    assert!(!child.wait().unwrap().success());
+}
+
+#[test]
+fn imported_modules_are_loaded_from_the_directory_of_the_document() {
+    let path = format!("{}/tests/modules/main.rscript", env!("CARGO_MANIFEST_DIR"));
+    let text = std::fs::read_to_string(&path).unwrap();
+    let mut session = Session::with_document_at(&format!("file://{path}"), &text);
+    let definition = session.request_at("textDocument/definition", &text, "length_squared(3", 0);
+    let names = session.request_at("textDocument/completion", &text, "offset + unscaled", 0);
+    let (messages, _) = session.run();
+
+    assert_eq!(published_diagnostics(&messages), vec![&Vec::<Value>::new()]);
+    let definition = result(&messages, definition);
+    let uri = definition["uri"].as_str().unwrap();
+    assert!(uri.ends_with("/tests/modules/math/vec.rscript"), "{uri}");
+    assert_eq!(definition["range"], range((11, 7), (11, 21)));
+
+    let names: Vec<_> = result(&messages, names)
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    for expected in ["Vector2D", "length_squared", "scale", "identity"] {
+        assert!(names.contains(&expected), "{names:?}");
+    }
+    // Items of modules are only visible through their imports
+    assert!(!names.iter().any(|name| name.contains("::")), "{names:?}");
+}
@@ -0,0 +1,203 @@
+//! Executes scripts importing the modules in `tests/modules` and checks how imports resolve.
+
+use std::{
+    env, fs,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use rscript::{Engine, Runtime, RuntimeError, Value, module::ModuleError};
+
+fn execute(engine: Engine, script: &str) -> (Runtime, Result<Option<Value>, RuntimeError>) {
+    let path = Path::new("tests/modules").join(script);
+    let source = fs::read_to_string(&path).unwrap();
+    let mut runtime = Runtime::with_engine(engine);
+    let result = runtime
+        .execute_file(&path, &source)
+        .and_then(|()| runtime.run_main());
+    (runtime, result)
+}
+
+fn module_error(script: &str) -> ModuleError {
+    match execute(Engine::TreeWalker, script).1 {
+        Err(RuntimeError::ModuleError(error)) => error,
+        result => panic!("expected a module error for `{script}`, found {result:?}"),
+    }
+}
+
+#[test]
+fn imported_items_are_renamed_to_their_module() {
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let (runtime, result) = execute(engine, "main.rscript");
+        assert_eq!(result.unwrap(), Some(Value::I64(72)));
+        // Items of the entry script keep their names, private globals of modules cannot clash
+        assert_eq!(runtime.global("offset"), Some(&Value::I64(5)));
+        assert_eq!(runtime.global("math::vec::offset"), Some(&Value::I64(1)));
+        assert_eq!(runtime.global("math::factor"), Some(&Value::I64(10)));
+    }
+}
+
+#[test]
+fn private_items_cannot_be_imported() {
+    assert!(matches!(
+        module_error("private.rscript"),
+        ModuleError::PrivateItem { name, module, .. } if name == "square" && module == "math::vec"
+    ));
+}
+
+#[test]
+fn unresolved_imports_point_at_the_importing_file() {
+    let (runtime, result) = execute(Engine::TreeWalker, "missing_item.rscript");
+    let Err(RuntimeError::ModuleError(ModuleError::UnresolvedImport { name, span, .. })) = result
+    else {
+        panic!("expected an unresolved import, found {result:?}");
+    };
+    assert_eq!(name, "cross");
+    let file = runtime.source_map().get(span.file).unwrap();
+    assert_eq!(file.name(), "tests/modules/missing_item.rscript");
+    assert_eq!(&file.source()[span.start..span.end], "cross");
+
+    assert!(matches!(
+        module_error("missing_module.rscript"),
+        ModuleError::UnresolvedModule { module, .. } if module == "geometry"
+    ));
+}
+
+#[test]
+fn import_cycles_are_detected() {
+    let ModuleError::CyclicImport { cycle, .. } = module_error("cycle.rscript") else {
+        panic!("expected a cyclic import");
+    };
+    assert_eq!(cycle, ["cycle::a", "cycle::b", "cycle::a"]);
+}
+
+#[test]
+fn imports_cannot_shadow_declarations() {
+    assert!(matches!(
+        module_error("conflict.rscript"),
+        ModuleError::NameConflict { name, .. } if name == "scale"
+    ));
+}
+
+#[test]
+fn imports_require_a_script_file() {
+    let mut runtime = Runtime::new();
+    assert!(runtime.execute("use math::scale;").is_err());
+}
+
+fn rscript(arguments: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_rscript"))
+        .args(arguments)
+        .output()
+        .unwrap()
+}
+
+/// Runs the command line in `tests/modules` with `input` as the script read from stdin.
+fn rscript_stdin(arguments: &[&str], input: &str) -> std::process::Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rscript"))
+        .args(arguments)
+        .current_dir("tests/modules")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn scripts_from_stdin_import_modules_from_the_working_directory() {
+    let script = "use math::scale;\n\
+                  #[test]\n\
+                  fn scales() { assert_eq(scale(2), 40); }\n\
+                  fn main() -> i64 { return scale(2); }";
+    let output_path = env::temp_dir().join(format!("rscript-stdin-{}.rsc", std::process::id()));
+    let output_path = output_path.to_str().unwrap();
+    let commands: [&[&str]; 8] = [
+        &["run", "-"],
+        &["run", "-", "--engine", "vm"],
+        &["check", "-"],
+        &["compile", "-", "--output", output_path],
+        &["disasm", "-"],
+        &["ast", "-", "--typed"],
+        &["test", "-"],
+        &["run", output_path],
+    ];
+    for arguments in commands {
+        let output = rscript_stdin(arguments, script);
+        let code = if arguments[0] == "run" { 40 } else { 0 };
+        assert_eq!(
+            output.status.code(),
+            Some(code),
+            "`{}`: {}",
+            arguments.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    fs::remove_file(output_path).unwrap();
+
+    // Modules missing from the working directory are reported alike by every command
+    for command in ["run", "check", "compile", "disasm", "test"] {
+        let output = rscript_stdin(&[command, "-"], "use missing::item;");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success(), "`{command} -` succeeded");
+        assert!(stderr.contains("<stdin>:1:"), "`{command} -`: {stderr}");
+    }
+}
+
+#[test]
+fn check_loads_imported_modules() {
+    let output = rscript(&["check", "tests/modules/main.rscript"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = rscript(&["check", "tests/modules/missing_item.rscript"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn compiled_files_include_imported_modules() {
+    let output_path = env::temp_dir().join(format!("rscript-modules-{}.rsc", std::process::id()));
+    let output_path = output_path.to_str().unwrap();
+    let output = rscript(&[
+        "compile",
+        "tests/modules/main.rscript",
+        "--output",
+        output_path,
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = rscript(&["run", output_path]);
+    fs::remove_file(output_path).unwrap();
+    assert_eq!(output.status.code(), Some(72));
+}
+
+#[test]
+fn compiled_files_keep_the_sources_of_modules() {
+    let path = Path::new("tests/modules/main.rscript");
+    let source = fs::read_to_string(path).unwrap();
+    let bytecode = Runtime::new().compile_file(path, &source).unwrap();
+    assert_eq!(bytecode.modules.len(), 2);
+
+    let decoded = rscript::runtime::vm::BytecodeFile::from_bytes(&bytecode.to_bytes()).unwrap();
+    assert_eq!(decoded, bytecode);
+    let mut runtime = Runtime::new();
+    runtime.execute_bytecode(&decoded).unwrap();
+    let function = &decoded.chunk.functions[0];
+    let file = runtime.source_map().get(function.span.file).unwrap();
+    assert!(file.name().ends_with("vec.rscript"), "{}", file.name());
+}
@@ -0,0 +1,149 @@
+//! Numeric and char literals, the sized numeric types and the operators on them, on both engines.
+
+use rscript::{Engine, Runtime, Value};
+
+fn runtimes() -> [Runtime; 2] {
+    [Engine::TreeWalker, Engine::Vm].map(Runtime::with_engine)
+}
+
+/// Evaluates each expression on both engines and compares the result to the expected value.
+fn assert_evaluates(cases: &[(&str, Value)]) {
+    for mut runtime in runtimes() {
+        for (expression, expected) in cases {
+            assert_eq!(
+                runtime.evaluate(expression).unwrap(),
+                Some(expected.clone()),
+                "for `{expression}` on {:?}",
+                runtime.engine()
+            );
+        }
+    }
+}
+
+/// Evaluates each expression on both engines and compares the message of the error.
+fn assert_fails(cases: &[(&str, &str)]) {
+    for mut runtime in runtimes() {
+        for (expression, message) in cases {
+            let diagnostics = runtime.evaluate(expression).unwrap_err().diagnostics();
+            assert_eq!(
+                diagnostics[0].message,
+                *message,
+                "for `{expression}` on {:?}",
+                runtime.engine()
+            );
+        }
+    }
+}
+
+#[test]
+fn integer_literals_have_a_base_separators_and_a_suffix() {
+    assert_evaluates(&[
+        ("0xFF", Value::I64(255)),
+        ("0o17", Value::I64(15)),
+        ("0b1010", Value::I64(10)),
+        ("1_000_000", Value::I64(1_000_000)),
+        ("0x_FF_u8", Value::U8(255)),
+        ("5u8", Value::U8(5)),
+        ("-5i16", Value::I16(-5)),
+        ("4_294_967_295u32", Value::U32(u32::MAX)),
+        ("7u64", Value::U64(7)),
+        ("-128i8", Value::I8(i8::MIN)),
+    ]);
+    assert_fails(&[
+        ("300u8", "literal `300` out of range for `u8`"),
+        ("let x: u8 = 256;", "literal `256` out of range for `u8`"),
+    ]);
+}
+
+#[test]
+fn float_literals_have_exponents_and_a_suffix() {
+    assert_evaluates(&[
+        ("1.5", Value::F64(1.5)),
+        ("1e3", Value::F64(1000.0)),
+        ("2.5E-1", Value::F64(0.25)),
+        ("1.5e3f32", Value::F32(1500.0)),
+        ("1.0f32 / 3.0f32", Value::F32(1.0 / 3.0)),
+        ("1_000.5", Value::F64(1000.5)),
+    ]);
+}
+
+#[test]
+fn unsuffixed_literals_take_the_type_of_the_other_operand() {
+    assert_evaluates(&[
+        ("250u8 + 5", Value::U8(255)),
+        ("2 * 3i32", Value::I32(6)),
+        ("0.5 + 1.0f32", Value::F32(1.5)),
+        ("{ let x: u16 = 65535; x }", Value::U16(u16::MAX)),
+    ]);
+}
+
+#[test]
+fn arithmetic_overflows_according_to_the_width_of_the_type() {
+    assert_evaluates(&[
+        ("127i8 + 0i8", Value::I8(127)),
+        ("65535u16 - 1u16", Value::U16(65534)),
+        ("2147483647 + 1", Value::I64(2_147_483_648)),
+    ]);
+    assert_fails(&[
+        ("255u8 + 1u8", "attempt to add with overflow"),
+        ("127i8 + 1i8", "attempt to add with overflow"),
+        ("0u32 - 1u32", "attempt to subtract with overflow"),
+        ("200u8 * 2u8", "attempt to multiply with overflow"),
+        ("2147483647i32 + 1i32", "attempt to add with overflow"),
+        ("-(-128i8)", "attempt to negate with overflow"),
+        ("1 / 0", "attempt to divide by zero"),
+        ("1u8 % 0u8", "attempt to divide by zero"),
+    ]);
+}
+
+#[test]
+fn operands_of_different_types_are_type_errors() {
+    assert_fails(&[
+        ("1i32 + 1i64", "cannot apply `+` to `i32` and `i64`"),
+        ("1.0f32 + 2.0f64", "cannot apply `+` to `f32` and `f64`"),
+        ("1u8 < 1u16", "cannot apply `<` to `u8` and `u16`"),
+    ]);
+}
+
+#[test]
+fn chars_are_literals_of_their_own_type() {
+    assert_evaluates(&[
+        ("'a'", Value::Char('a')),
+        ("'\\n'", Value::Char('\n')),
+        ("'ß'", Value::Char('ß')),
+        ("'a' < 'b'", Value::Bool(true)),
+        ("'a' == 'a'", Value::Bool(true)),
+    ]);
+    assert_fails(&[("'a' + 'b'", "cannot apply `+` to `char` and `char`")]);
+}
+
+#[test]
+fn remainder_and_comparisons() {
+    assert_evaluates(&[
+        ("7 % 3", Value::I64(1)),
+        ("-7 % 3", Value::I64(-1)),
+        ("7.5 % 2.0", Value::F64(1.5)),
+        ("2 <= 2", Value::Bool(true)),
+        ("3 >= 4", Value::Bool(false)),
+        ("1.5 > 1.0", Value::Bool(true)),
+        ("\"a\" != \"b\"", Value::Bool(true)),
+    ]);
+}
+
+#[test]
+fn bitwise_operators_and_shifts_take_integers_only() {
+    assert_evaluates(&[
+        ("0xF0 & 0x3C", Value::I64(0x30)),
+        ("0xF0 | 0x0F", Value::I64(0xFF)),
+        ("0xFF ^ 0x0F", Value::I64(0xF0)),
+        ("1 << 3", Value::I64(8)),
+        ("-16 >> 2", Value::I64(-4)),
+        ("0b1000_0000u8 >> 7u8", Value::U8(1)),
+        ("1 + 2 << 1", Value::I64(6)),
+    ]);
+    assert_fails(&[
+        ("1.0 & 2.0", "cannot apply `&` to `f64` and `f64`"),
+        ("true | false", "cannot apply `|` to `bool` and `bool`"),
+        ("1u8 << 8u8", "attempt to shift left with overflow"),
+    ]);
+}
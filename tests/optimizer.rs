@@ -0,0 +1,214 @@
+//! Runs every script with each optimization pass on its own and with all of them, and checks
+//! that the optimized scripts behave exactly like the unoptimized ones on both engines.
+
+use std::fs;
+
+use rscript::{
+    Engine, Parser, TypeChecker,
+    ast::{Expression, Program, Statement},
+    optimizer::{OptimizerConfig, optimize_program},
+};
+
+mod common;
+
+use common::{run, runtime, scripts};
+
+/// Every pass on its own, the default passes and all of them together.
+fn configs() -> Vec<(&'static str, OptimizerConfig)> {
+    let none = OptimizerConfig::none();
+    vec![
+        (
+            "constant folding",
+            OptimizerConfig {
+                constant_folding: true,
+                ..none
+            },
+        ),
+        (
+            "dead branches",
+            OptimizerConfig {
+                dead_branches: true,
+                ..none
+            },
+        ),
+        (
+            "unreachable code",
+            OptimizerConfig {
+                unreachable_code: true,
+                ..none
+            },
+        ),
+        (
+            "inlining",
+            OptimizerConfig {
+                inlining: true,
+                ..none
+            },
+        ),
+        ("default", OptimizerConfig::default()),
+        ("all", OptimizerConfig::all()),
+    ]
+}
+
+fn optimized(engine: Engine, config: OptimizerConfig) -> rscript::Runtime {
+    let mut runtime = runtime(engine);
+    runtime.set_optimizer(config);
+    runtime
+}
+
+#[test]
+fn optimized_scripts_behave_like_unoptimized_ones() {
+    for path in scripts() {
+        let name = path.display().to_string();
+        let source = fs::read_to_string(&path).unwrap();
+        for engine in [Engine::TreeWalker, Engine::Vm] {
+            let expected = run(optimized(engine, OptimizerConfig::none()), &name, &source);
+            for (pass, config) in configs() {
+                let actual = run(optimized(engine, config), &name, &source);
+                assert_eq!(
+                    expected, actual,
+                    "`{name}` behaves differently with {pass} on {engine:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn optimized_expressions_evaluate_like_unoptimized_ones() {
+    let expressions = [
+        "1 + 2 * 3",
+        "-128i8",
+        "-(-128i8)",
+        "2.5 * 4.0 - 0.5",
+        "!true || 1 < 2 && 3 != 4",
+        "if 1 > 2 { 1 } else { 2 }",
+        "if false { 1; }",
+        "{ let x = 2; x * 3 + 1 }",
+        "255u8 + 1u8",
+        "1 / 0",
+        "7 % -0",
+        "1i32 << 40",
+    ];
+    for expression in expressions {
+        for engine in [Engine::TreeWalker, Engine::Vm] {
+            let evaluate = |config| {
+                optimized(engine, config)
+                    .evaluate(expression)
+                    .map_err(|error| error.diagnostics())
+            };
+            let expected = evaluate(OptimizerConfig::none());
+            for (pass, config) in configs() {
+                assert_eq!(
+                    expected,
+                    evaluate(config),
+                    "`{expression}` evaluates differently with {pass} on {engine:?}"
+                );
+            }
+        }
+    }
+}
+
+// -- Rewrites --
+
+fn optimize(source: &str, config: OptimizerConfig) -> Program {
+    let mut program = Parser::new(source).parse().into_result().unwrap();
+    TypeChecker::new().check_program(&mut program).unwrap();
+    optimize_program(&mut program, &config);
+    program
+}
+
+fn initializer(program: &Program, index: usize) -> &Expression {
+    match &program.statements[index] {
+        Statement::VariableDeclaration(declaration) => &declaration.initializer,
+        statement => panic!("expected a variable declaration, found {statement:?}"),
+    }
+}
+
+#[test]
+fn constant_expressions_are_folded_into_literals() {
+    let program = optimize("let x = 2 * 3 + 4;", OptimizerConfig::default());
+    assert!(matches!(
+        initializer(&program, 0),
+        Expression::IntegerLiteral(literal) if literal.value == 10
+    ));
+
+    // Overflowing operations must still fail at runtime
+    let program = optimize("let x = 200u8 + 100u8;", OptimizerConfig::default());
+    assert!(matches!(initializer(&program, 0), Expression::BinaryOp(_)));
+}
+
+#[test]
+fn constant_conditions_keep_only_the_taken_branch() {
+    let program = optimize(
+        "let x = if 1 < 2 { 1 } else { 2 };",
+        OptimizerConfig::default(),
+    );
+    let Expression::BlockExpression(block) = initializer(&program, 0) else {
+        panic!("the `if` was not removed");
+    };
+    assert!(matches!(
+        block.final_expression.as_deref(),
+        Some(Expression::IntegerLiteral(literal)) if literal.value == 1
+    ));
+
+    let program = optimize(
+        "let x = if 1 < 2 { 1 } else { 2 };",
+        OptimizerConfig {
+            dead_branches: false,
+            ..OptimizerConfig::default()
+        },
+    );
+    assert!(matches!(
+        initializer(&program, 0),
+        Expression::IfExpression(_)
+    ));
+}
+
+#[test]
+fn statements_after_return_are_removed() {
+    let program = optimize(
+        "fn f() -> i64 { return 1; let x = 2; fn g() {} }",
+        OptimizerConfig::default(),
+    );
+    let Statement::FunctionDeclaration(function) = &program.statements[0] else {
+        panic!("expected a function declaration");
+    };
+    assert_eq!(function.body.len(), 2);
+    assert!(matches!(function.body[0], Statement::ReturnStatement(_)));
+    assert!(matches!(
+        function.body[1],
+        Statement::FunctionDeclaration(_)
+    ));
+}
+
+#[test]
+fn tiny_functions_are_inlined() {
+    let source = "fn double(x: i64) -> i64 { return x * 2; } let y = double(21);";
+    let program = optimize(source, OptimizerConfig::all());
+    assert!(matches!(
+        initializer(&program, 1),
+        Expression::IntegerLiteral(literal) if literal.value == 42
+    ));
+
+    let program = optimize(source, OptimizerConfig::default());
+    assert!(matches!(
+        initializer(&program, 1),
+        Expression::FunctionCall(_)
+    ));
+}
+
+#[test]
+fn inlining_does_not_capture_locals() {
+    let program = optimize(
+        "let y = 1; fn offset(x: i64) -> i64 { return x + y; } let z = { let y = 2; offset(y) };",
+        OptimizerConfig::all(),
+    );
+    let Expression::BlockExpression(block) = initializer(&program, 2) else {
+        panic!("expected a block");
+    };
+    assert!(matches!(
+        block.final_expression.as_deref(),
+        Some(Expression::FunctionCall(_))
+    ));
+}
@@ -0,0 +1,96 @@
+//! Feeds inputs to `rscript repl` and checks what it prints.
+
+use std::{
+    env,
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+/// Runs the REPL on `input` without a terminal, so no prompts are printed, and without a home
+/// directory to keep the history out of the user's.
+fn repl(input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rscript"))
+        .arg("repl")
+        .env_remove("HOME")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{output:?}");
+    output
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn declarations_persist_between_inputs() {
+    let output = repl("let x = 40;\nfn add(a: i64, b: i64) -> i64 { return a + b; }\nadd(x, 2)\n");
+    assert_eq!(stdout(&output), "42\n");
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn unclosed_braces_continue_on_the_next_line() {
+    let output = repl("fn add(a: i64,\n       b: i64) -> i64 {\n    return a + b;\n}\nadd(1, 2)\n");
+    assert_eq!(stdout(&output), "3\n");
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn errors_are_reported_without_losing_state() {
+    let output = repl("let x = 1;\nlet y = missing;\ny\nx\n");
+    assert_eq!(stdout(&output), "1\n");
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("error: cannot find value `missing` in this scope\n --> <repl>:1:9"),
+        "{stderr}"
+    );
+    // The failed input declared nothing
+    assert!(
+        stderr.contains("error: cannot find value `y` in this scope"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn commands_inspect_and_reset_the_session() {
+    let output = repl(":type 1u8 + 2\n:ast 1 + 2\nlet x = 1;\n:reset\nx\n:nope\n:quit\n1\n");
+    let stdout = stdout(&output);
+    assert!(stdout.starts_with("u8\n"), "{stdout}");
+    assert!(stdout.contains("BinaryOp"), "{stdout}");
+    assert!(stdout.ends_with("Cleared all declarations\n"), "{stdout}");
+
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("error: cannot find value `x` in this scope"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("error: unknown command `:nope`, see `:help`"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn scripts_are_loaded_into_the_session() {
+    let path = env::temp_dir().join(format!("rscript-repl-{}.rscript", std::process::id()));
+    std::fs::write(&path, "fn triple(x: i64) -> i64 { return x * 3; }").unwrap();
+    let output = repl(&format!(":load {}\ntriple(14)\n", path.display()));
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(stdout(&output), "42\n");
+    assert_eq!(stderr(&output), "");
+}
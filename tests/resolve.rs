@@ -0,0 +1,217 @@
+//! Resolves the names of small programs and checks the bindings and problems found.
+
+use rscript::{
+    Engine, Parser, Resolution, Resolver, Runtime, SourceMap, Value,
+    resolve::{Namespace, ResolveError, ResolveWarning, SymbolKind},
+};
+
+fn resolve(source: &str) -> Resolution {
+    let mut source_map = SourceMap::new();
+    let file = source_map.add_file("test.rscript", source);
+    let program = Parser::with_file(source, file)
+        .parse()
+        .into_result()
+        .unwrap();
+    Resolver::default().resolve_program(&program)
+}
+
+/// The kinds and declaration offsets of the symbols the uses of `name` refer to, in source
+/// order.
+fn uses(resolution: &Resolution, source: &str, name: &str) -> Vec<(SymbolKind, usize)> {
+    let mut uses: Vec<_> = resolution
+        .symbols()
+        .flat_map(|(id, symbol)| {
+            resolution
+                .references(id)
+                .into_iter()
+                .map(move |span| (span.start, symbol.kind, symbol.span.start))
+        })
+        .filter(|(start, _, _)| source[*start..].starts_with(name))
+        .collect();
+    uses.sort_by_key(|(start, _, _)| *start);
+    uses.into_iter()
+        .map(|(_, kind, declaration)| (kind, declaration))
+        .collect()
+}
+
+#[test]
+fn uses_bind_to_the_innermost_declaration() {
+    let source = "let x = 1;\nfn f(x: i64) -> i64 { return x; }\nlet y = { let x = x + 1; x };";
+    let resolution = resolve(source);
+    assert!(resolution.errors.is_empty(), "{:?}", resolution.errors);
+
+    let global = source.find("x").unwrap();
+    let parameter = source.find("x: i64").unwrap();
+    let local = source.find("let x = x").unwrap() + 4;
+    assert_eq!(
+        uses(&resolution, source, "x"),
+        vec![
+            (SymbolKind::Parameter, parameter),
+            // The initializer still sees the shadowed global
+            (SymbolKind::Global, global),
+            (SymbolKind::Local, local),
+        ]
+    );
+}
+
+#[test]
+fn items_are_visible_in_the_whole_statement_list() {
+    let source = "fn a() -> Point { return b(); }\nfn b() -> Point { return a(); }\nstruct Point;";
+    let resolution = resolve(source);
+    assert!(resolution.errors.is_empty(), "{:?}", resolution.errors);
+
+    let symbol = resolution
+        .symbol_at(Default::default(), source.find("b()").unwrap())
+        .unwrap();
+    assert_eq!(resolution.symbol(symbol).kind, SymbolKind::Function);
+    assert_eq!(resolution.references(symbol).len(), 1);
+    assert_eq!(uses(&resolution, source, "Point").len(), 2);
+}
+
+#[test]
+fn undefined_names_are_reported_per_namespace() {
+    let resolution = resolve("fn f(a: Missing) -> i64 { return g(a) + y; }");
+    let undefined: Vec<_> = resolution
+        .errors
+        .iter()
+        .map(|error| match error {
+            ResolveError::Undefined {
+                name, namespace, ..
+            } => (name.as_str(), *namespace),
+            error => panic!("unexpected error {error:?}"),
+        })
+        .collect();
+    assert_eq!(
+        undefined,
+        vec![
+            ("Missing", Namespace::Type),
+            ("g", Namespace::Function),
+            ("y", Namespace::Value),
+        ]
+    );
+}
+
+#[test]
+fn uses_before_the_declaration_are_reported() {
+    // Function bodies only see the globals declared before them
+    let source =
+        "fn f() -> i64 { return later; }\nlet later = 1;\nlet z = { let a = b; let b = 2; a };";
+    let resolution = resolve(source);
+    let names: Vec<_> = resolution
+        .errors
+        .iter()
+        .map(|error| match error {
+            ResolveError::UseBeforeDeclaration {
+                name, declaration, ..
+            } => (name.as_str(), declaration.start),
+            error => panic!("unexpected error {error:?}"),
+        })
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            ("later", source.find("later =").unwrap()),
+            ("b", source.find("b = 2").unwrap()),
+        ]
+    );
+}
+
+#[test]
+fn unused_locals_parameters_and_nested_functions_are_reported() {
+    let source = "let global = 1;\nfn top(used: i64, unused: i64, _ignored: i64) -> i64 {\n    fn helper() -> i64 { return 1; }\n    let local = 2;\n    return used;\n}";
+    let resolution = resolve(source);
+    assert!(resolution.errors.is_empty(), "{:?}", resolution.errors);
+    let unused: Vec<_> = resolution
+        .warnings
+        .iter()
+        .map(|ResolveWarning::Unused { name, kind, .. }| (name.as_str(), *kind))
+        .collect();
+    assert_eq!(
+        unused,
+        vec![
+            ("unused", SymbolKind::Parameter),
+            ("helper", SymbolKind::Function),
+            ("local", SymbolKind::Local),
+        ]
+    );
+}
+
+#[test]
+fn the_runtime_binds_uses_like_the_resolver() {
+    let source = "let x = 1;
+fn value() -> i64 { return 100; }
+fn parameter(x: i64) -> i64 { return x; }
+fn enclosing() -> i64 {
+    let x = 3;
+    fn inner() -> i64 { return x; }
+    return inner() + x;
+}
+fn shadowing() -> i64 {
+    fn value() -> i64 { return 200 + offset(); }
+    fn offset() -> i64 { return 5; }
+    return value();
+}
+let from_parameter = parameter(2);
+let from_initializer = { let x = x + 10; x * 2 };
+let from_enclosing = enclosing();
+let from_shadowing = shadowing();
+let after_shadowing = value();
+let from_block = { fn value() -> i64 { return 300; } value() };
+let after_block = value();
+let from_global = x;";
+    let resolution = resolve(source);
+    assert!(resolution.errors.is_empty(), "{:?}", resolution.errors);
+
+    let global = source.find("x = 1").unwrap();
+    let parameter = source.find("x: i64").unwrap();
+    let enclosing_local = source.find("x = 3").unwrap();
+    let block_local = source.find("x = x").unwrap();
+    assert_eq!(
+        uses(&resolution, source, "x"),
+        vec![
+            (SymbolKind::Parameter, parameter),
+            // Function bodies do not capture the locals of the enclosing function
+            (SymbolKind::Global, global),
+            (SymbolKind::Local, enclosing_local),
+            (SymbolKind::Global, global),
+            (SymbolKind::Local, block_local),
+            (SymbolKind::Global, global),
+        ]
+    );
+    // Nested functions shadow the top level one only in the statement list declaring them
+    let top_level_value = source.find("value() -> i64 { return 100").unwrap();
+    let nested_value = source.find("value() -> i64 { return 200").unwrap();
+    let block_value = source.find("value() -> i64 { return 300").unwrap();
+    assert_eq!(
+        uses(&resolution, source, "value"),
+        vec![
+            (SymbolKind::Function, nested_value),
+            (SymbolKind::Function, top_level_value),
+            (SymbolKind::Function, block_value),
+            (SymbolKind::Function, top_level_value),
+        ]
+    );
+
+    // The values the runtime computes show which declarations it bound the uses to
+    let expected = [
+        ("from_parameter", 2),
+        ("from_initializer", 22),
+        ("from_enclosing", 1 + 3),
+        ("from_shadowing", 205),
+        ("after_shadowing", 100),
+        ("from_block", 300),
+        ("after_block", 100),
+        ("from_global", 1),
+    ];
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let mut runtime = Runtime::with_engine(engine);
+        runtime.execute(source).unwrap();
+        for (name, value) in expected {
+            assert_eq!(
+                runtime.global(name),
+                Some(&Value::I64(value)),
+                "for `{name}` on {engine:?}"
+            );
+        }
+    }
+}
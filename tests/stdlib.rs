@@ -0,0 +1,215 @@
+//! The standard library registered in every runtime, on both engines.
+
+use rscript::{Engine, Parser, Runtime, Value};
+
+fn runtimes() -> [Runtime; 2] {
+    [Engine::TreeWalker, Engine::Vm].map(Runtime::with_engine)
+}
+
+/// Evaluates each expression on both engines and compares the result to the expected value.
+fn assert_evaluates(cases: &[(&str, Value)]) {
+    for mut runtime in runtimes() {
+        for (expression, expected) in cases {
+            assert_eq!(
+                runtime.evaluate(expression).unwrap(),
+                Some(expected.clone()),
+                "for `{expression}` on {:?}",
+                runtime.engine()
+            );
+        }
+    }
+}
+
+fn string(text: &str) -> Value {
+    Value::String(text.to_string())
+}
+
+#[test]
+fn string_methods_count_chars() {
+    assert_evaluates(&[
+        ("\"grüße\".len()", Value::I64(5)),
+        ("\"grüße\".byte_len()", Value::I64(7)),
+        ("\"grüße\".slice(1, 4)", string("rüß")),
+        ("\"grüße\".char_at(2)", Value::Char('ü')),
+        ("\"ab\".pad_start(4, '.')", string("..ab")),
+        ("\"ab\".pad_end(1, '.')", string("ab")),
+        ("\"ab\".pad_start(-3, '.')", string("ab")),
+        ("\"\".pad_end(1048576, '.').len()", Value::I64(1048576)),
+    ]);
+}
+
+#[test]
+fn string_methods_search_and_transform() {
+    assert_evaluates(&[
+        ("\"  a b  \".trim()", string("a b")),
+        ("\"  a b  \".trim_start()", string("a b  ")),
+        ("\"a-b-c\".replace(\"-\", \"+\")", string("a+b+c")),
+        ("\"script\".contains(\"rip\")", Value::Bool(true)),
+        ("\"script\".starts_with(\"sc\")", Value::Bool(true)),
+        ("\"script\".ends_with(\"sc\")", Value::Bool(false)),
+        ("\"MiXeD\".to_upper()", string("MIXED")),
+        ("\"MiXeD\".to_lower()", string("mixed")),
+        ("\"a,b,,c\".split(\",\").len()", Value::I64(4)),
+        ("\"a,b,,c\".split(\",\").get(1)", string("b")),
+        ("\"a,b,,c\".split(\",\").join(\"/\")", string("a/b//c")),
+    ]);
+}
+
+#[test]
+fn strings_are_parsed_and_numbers_formatted() {
+    assert_evaluates(&[
+        ("\"-17\".parse_int() + 1", Value::I64(-16)),
+        ("\"2.5\".parse_float() * 2.0", Value::F64(5.0)),
+        ("{ let x = 255u8; x.to_string() }", string("255")),
+        ("{ let x = 2.0 / 3.0; x.to_fixed(3) }", string("0.667")),
+        ("1.5.to_fixed(65535).len()", Value::I64(65537)),
+        ("{ let flag = true; flag.to_string() }", string("true")),
+    ]);
+}
+
+#[test]
+fn failing_methods_abort_the_script() {
+    let cases = [
+        (
+            "\"12a\".parse_int()",
+            "`String::parse_int` failed: cannot parse `12a` as an integer: invalid digit found in string",
+        ),
+        (
+            "\"abc\".slice(2, 5)",
+            "`String::slice` failed: 2..5 is out of bounds for a string of 3 chars",
+        ),
+        (
+            "\"a b\".split(\" \").get(-1)",
+            "`StringList::get` failed: index -1 is out of bounds for a list of 2 strings",
+        ),
+        (
+            "\"abc\".split(\"\")",
+            "`String::split` failed: the separator must not be empty",
+        ),
+        (
+            "1.5.to_fixed(65536)",
+            "`f64::to_fixed` failed: cannot show 65536 decimal places",
+        ),
+        (
+            "1.5.to_fixed(-1)",
+            "`f64::to_fixed` failed: cannot show -1 decimal places",
+        ),
+        (
+            "\"a\".pad_start(9223372036854775807, ' ')",
+            "`String::pad_start` failed: cannot pad to 9223372036854775807 chars, the maximum is 1048576",
+        ),
+        (
+            "\"a\".pad_end(1048577, ' ')",
+            "`String::pad_end` failed: cannot pad to 1048577 chars, the maximum is 1048576",
+        ),
+    ];
+    for mut runtime in runtimes() {
+        for (expression, message) in cases {
+            let diagnostics = runtime.evaluate(expression).unwrap_err().diagnostics();
+            assert_eq!(diagnostics[0].message, message, "for `{expression}`");
+        }
+    }
+}
+
+#[test]
+fn hosts_can_add_and_replace_methods() {
+    let mut runtime = Runtime::new();
+    runtime.register_method("len", |_: String| -1i64);
+    runtime.register_method("twice", |value: i64| value * 2);
+    assert_eq!(
+        runtime.evaluate("\"abc\".len()").unwrap(),
+        Some(Value::I64(-1))
+    );
+    assert_eq!(
+        runtime.evaluate("{ let x = 21; x.twice() }").unwrap(),
+        Some(Value::I64(42))
+    );
+}
+
+#[test]
+fn scripts_are_checked_against_the_standard_library() {
+    let source =
+        "let parts: StringList = \"a b\".split(\" \");\nlet n = parts.len() + \"x\".len();";
+    let mut program = Parser::new(source).parse().into_result().unwrap();
+    let runtime = Runtime::new();
+    assert!(
+        runtime
+            .resolver()
+            .resolve_program(&program)
+            .errors
+            .is_empty()
+    );
+    runtime
+        .type_checker()
+        .clone()
+        .check_program(&mut program)
+        .unwrap();
+}
+
+#[test]
+fn math_methods_keep_the_type_of_their_receiver() {
+    assert_evaluates(&[
+        ("(-5i8).abs()", Value::I8(5)),
+        ("3u16.max(7)", Value::U16(7)),
+        ("{ let x = -2.5; x.min(1.0) }", Value::F64(-2.5)),
+        ("12i32.clamp(0, 10)", Value::I32(10)),
+        ("2u64.pow(10)", Value::U64(1024)),
+        ("250u8.checked_add(10, 0)", Value::U8(0)),
+        ("7i64.checked_div(0, -1)", Value::I64(-1)),
+        ("250u8.wrapping_add(10)", Value::U8(4)),
+        ("(-100i8).saturating_mul(2)", Value::I8(-128)),
+        ("{ let x = 2.0f32; x.pow(3.0) }", Value::F32(8.0)),
+    ]);
+}
+
+#[test]
+fn float_functions_and_constants() {
+    assert_evaluates(&[
+        ("{ let x = 16.0; x.sqrt() }", Value::F64(4.0)),
+        ("{ let x = -2.5; x.floor() }", Value::F64(-3.0)),
+        ("{ let x = -2.5; x.ceil() }", Value::F64(-2.0)),
+        ("{ let x = 2.5; x.round() }", Value::F64(3.0)),
+        ("{ let x = 0.0; x.cos() }", Value::F64(1.0)),
+        (
+            "{ let y = 1.0; y.atan2(1.0) * 4.0 }",
+            Value::F64(std::f64::consts::PI),
+        ),
+        ("TAU / PI", Value::F64(2.0)),
+        ("E.ln()", Value::F64(1.0)),
+        ("{ let x = -7.9; x.to_int() }", Value::I64(-7)),
+        ("3u32.to_float() / 2.0", Value::F64(1.5)),
+        ("200u8.to_int()", Value::I64(200)),
+    ]);
+}
+
+#[test]
+fn failing_math_aborts_the_script() {
+    let cases = [
+        (
+            "10i64.pow(20)",
+            "`i64::pow` failed: 10 to the power of 20 overflows",
+        ),
+        (
+            "(-128i8).abs()",
+            "`i8::abs` failed: the absolute value of -128 overflows",
+        ),
+        (
+            "5.clamp(3, 1)",
+            "`i64::clamp` failed: the minimum 3 is greater than the maximum 1",
+        ),
+        (
+            "{ let x = 0.0 / 0.0; x.to_int() }",
+            "`f64::to_int` failed: NaN does not fit into `i64`",
+        ),
+        (
+            "18446744073709551615u64.to_int()",
+            "`u64::to_int` failed: 18446744073709551615 does not fit into `i64`",
+        ),
+    ];
+    for mut runtime in runtimes() {
+        for (expression, message) in cases {
+            let diagnostics = runtime.evaluate(expression).unwrap_err().diagnostics();
+            assert_eq!(diagnostics[0].message, message, "for `{expression}`");
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Script-level unit tests: `#[test]` functions, the assertion builtins and their failures.
+
+use rscript::{Engine, Runtime, Value, formatter::format_source};
+
+fn runtimes() -> [Runtime; 2] {
+    [Engine::TreeWalker, Engine::Vm].map(Runtime::with_engine)
+}
+
+#[test]
+fn test_functions_are_discovered_in_declaration_order() {
+    let source = "fn helper() {}\n#[test]\nfn second() {}\n#[test]\nfn first() {}";
+    for mut runtime in runtimes() {
+        runtime.execute(source).unwrap();
+        assert_eq!(runtime.tests(), ["second", "first"]);
+    }
+}
+
+#[test]
+fn passing_assertions_return_unit() {
+    let source = "fn add(a: i64, b: i64) -> i64 { return a + b; }\n\
+                  #[test]\n\
+                  fn adds() { assert(add(1, 1) == 2); assert_eq(add(1, 2), 3); assert_ne(\"a\", \"b\", \"different\"); }";
+    for mut runtime in runtimes() {
+        runtime.execute(source).unwrap();
+        assert_eq!(runtime.call("adds", Vec::new()).unwrap(), Value::Unit);
+    }
+}
+
+#[test]
+fn failed_assertions_report_both_values() {
+    let source = "#[test]\nfn fails() { assert_eq(\"left\", \"right\", \"strings differ\"); }";
+    for mut runtime in runtimes() {
+        runtime.execute(source).unwrap();
+        let diagnostics = runtime.call("fails", Vec::new()).unwrap_err().diagnostics();
+        let [diagnostic] = diagnostics.as_slice() else {
+            panic!("expected one diagnostic, found {diagnostics:?}");
+        };
+        assert_eq!(
+            diagnostic.message,
+            "assertion `left == right` failed: strings differ"
+        );
+        assert_eq!(diagnostic.notes, ["left: \"left\"", "right: \"right\""]);
+        let span = diagnostic.primary_span().unwrap();
+        let call = source.find("assert_eq").unwrap()..source.rfind(';').unwrap();
+        assert_eq!(span.start..span.end, call);
+    }
+}
+
+#[test]
+fn invalid_tests_are_type_errors() {
+    let cases = [
+        ("#[bench]\nfn a() {}", "cannot find attribute `bench`"),
+        (
+            "#[test]\nfn a(x: i64) {}",
+            "test function `a` must not take parameters or return a value",
+        ),
+        (
+            "fn a() { #[test]\nfn b() {} }",
+            "test function `b` must be declared at the top level",
+        ),
+        ("fn a() { assert(1); }", "mismatched types"),
+        (
+            "fn a() { assert_eq(1); }",
+            "function `assert_eq` takes 2 argument(s) but 1 were supplied",
+        ),
+    ];
+    for (source, message) in cases {
+        let mut runtime = Runtime::new();
+        let diagnostics = runtime.execute(source).unwrap_err().diagnostics();
+        assert_eq!(diagnostics[0].message, message, "for `{source}`");
+    }
+}
+
+#[test]
+fn declared_functions_shadow_builtins() {
+    let source = "fn assert(value: i64) -> i64 { return value; }\nlet x = assert(7);";
+    for mut runtime in runtimes() {
+        runtime.execute(source).unwrap();
+        assert_eq!(runtime.global("x"), Some(&Value::I64(7)));
+    }
+}
+
+#[test]
+fn attributes_survive_formatting() {
+    let source = "#[test]   fn adds( ) { assert( true ); }\n";
+    let formatted = format_source(source, &Default::default()).unwrap();
+    assert_eq!(formatted, "#[test]\nfn adds() {\n    assert(true);\n}\n");
+}